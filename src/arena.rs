@@ -0,0 +1,224 @@
+use futures::StreamExt;
+use ratatui::{prelude::*, widgets::*};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, path::PathBuf, sync::Arc, time::Duration};
+use tokio::sync::mpsc;
+
+use crate::{
+    endpoint,
+    limiter::Limiter,
+    ndjson::{NdjsonDecoder, StreamDecoding},
+    trace, AppError, AppEvent, OllamaGenerateChunk, OllamaGenerateRequest,
+};
+
+// --- Arena: Blind A/B Voting ---
+// Two random models answer the same prompt under the names "A" and "B";
+// the vote updates an Elo scoreboard kept in <data dir>/ollama-tui/arena.json.
+const INITIAL_RATING: f64 = 1000.0;
+const K_FACTOR: f64 = 32.0;
+
+#[derive(Clone, Copy, Debug)]
+pub enum Vote {
+    A,
+    B,
+    Tie,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Rating {
+    pub elo: f64,
+    pub wins: u32,
+    pub losses: u32,
+    pub ties: u32,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Rating { elo: INITIAL_RATING, wins: 0, losses: 0, ties: 0 }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Scoreboard {
+    ratings: HashMap<String, Rating>,
+}
+
+fn scoreboard_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("ollama-tui").join("arena.json"))
+}
+
+impl Scoreboard {
+    // A missing file is an empty scoreboard
+    pub fn load() -> Result<Self, AppError> {
+        let Some(path) = scoreboard_path() else {
+            return Ok(Scoreboard::default());
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Scoreboard::default()),
+            Err(e) => Err(AppError::Io(e)),
+        }
+    }
+
+    fn save(&self) -> Result<(), AppError> {
+        let path = scoreboard_path().ok_or_else(|| AppError::Session("No data directory available".to_string()))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    // Applies a standard Elo update for one match and saves the result
+    pub fn record(&mut self, model_a: &str, model_b: &str, vote: Vote) -> Result<(), AppError> {
+        let rating_a = self.ratings.get(model_a).map_or(INITIAL_RATING, |r| r.elo);
+        let rating_b = self.ratings.get(model_b).map_or(INITIAL_RATING, |r| r.elo);
+        let expected_a = 1.0 / (1.0 + 10f64.powf((rating_b - rating_a) / 400.0));
+        let score_a = match vote {
+            Vote::A => 1.0,
+            Vote::B => 0.0,
+            Vote::Tie => 0.5,
+        };
+
+        for (model, score, expected) in [(model_a, score_a, expected_a), (model_b, 1.0 - score_a, 1.0 - expected_a)] {
+            let rating = self.ratings.entry(model.to_string()).or_default();
+            rating.elo += K_FACTOR * (score - expected);
+            match score {
+                s if s > 0.5 => rating.wins += 1,
+                s if s < 0.5 => rating.losses += 1,
+                _ => rating.ties += 1,
+            }
+        }
+        self.save()
+    }
+
+    fn ranked(&self) -> Vec<(&String, &Rating)> {
+        let mut ranked: Vec<_> = self.ratings.iter().collect();
+        ranked.sort_by(|a, b| b.1.elo.total_cmp(&a.1.elo));
+        ranked
+    }
+}
+
+pub struct ArenaMatch {
+    pub prompt: String,
+    pub models: [String; 2],
+    pub replies: [String; 2],
+    pub finished: [bool; 2],
+    // Set once voted, which also reveals the models
+    pub vote: Option<Vote>,
+}
+
+impl ArenaMatch {
+    // Picks two different models at random
+    pub fn new(prompt: String, models: &[String]) -> Option<Self> {
+        if models.len() < 2 {
+            return None;
+        }
+        let picks = rand::seq::index::sample(&mut rand::rng(), models.len(), 2);
+        Some(ArenaMatch {
+            prompt,
+            models: [models[picks.index(0)].clone(), models[picks.index(1)].clone()],
+            replies: [String::new(), String::new()],
+            finished: [false, false],
+            vote: None,
+        })
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished.iter().all(|done| *done)
+    }
+}
+
+// Streams one side's reply as ArenaChunk events
+pub async fn stream_side(
+    client: Client,
+    decoding: StreamDecoding,
+    limiter: Arc<Limiter>,
+    model: String,
+    prompt: String,
+    side: usize,
+    event_sender: mpsc::Sender<AppEvent>,
+) {
+    let _slot = limiter.acquire().await;
+    let result = async {
+        let (trace, response) = trace::send(
+            client
+                .post(format!("{}/api/generate", endpoint::base()))
+                .json(&OllamaGenerateRequest { model, prompt, format: None, options: None, think: None, stream: true, keep_alive: endpoint::keep_alive() })
+                .timeout(Duration::from_secs(300)),
+        )
+        .await?;
+        if !response.status().is_success() {
+            return Err(trace.tag(AppError::ApiResponse(format!("Status {}", response.status()))));
+        }
+
+        let mut stream = response.bytes_stream();
+        let mut decoder = NdjsonDecoder::<OllamaGenerateChunk>::new(decoding);
+        while let Some(bytes) = stream.next().await {
+            for chunk in decoder.push(&bytes.map_err(|e| trace.tag(e.into()))?) {
+                let chunk = chunk.map_err(|e| trace.tag(AppError::ApiResponse(e)))?;
+                let _ = event_sender.send(AppEvent::ArenaChunk { side, text: chunk.response }).await;
+                if chunk.done {
+                    trace.record("done");
+                    return Ok(());
+                }
+            }
+        }
+        Err(trace.tag(AppError::Interrupted("Connection closed before the response finished".to_string())))
+    }
+    .await;
+    let error = result.err().map(|e| e.to_string());
+    let _ = event_sender.send(AppEvent::ArenaDone { side, error }).await;
+}
+
+pub fn render(f: &mut Frame, area: Rect, arena: Option<&ArenaMatch>, scoreboard: &Scoreboard) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(5), Constraint::Length(8)])
+        .split(area);
+
+    match arena {
+        None => {
+            let help = "No match yet.\n\n/arena <prompt>   two random models answer blind; vote with a, b or t (tie)";
+            let block = Block::default().borders(Borders::ALL).title(" Arena (Tab: Next view) ");
+            f.render_widget(Paragraph::new(help).block(block), chunks[0]);
+        }
+        Some(arena) => {
+            let sides = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(chunks[0]);
+            for (side, label) in ["A", "B"].iter().enumerate() {
+                let mut title = match arena.vote {
+                    Some(_) => format!(" {}: {} ", label, arena.models[side]),
+                    None => format!(" Model {} ", label),
+                };
+                if !arena.finished[side] {
+                    title.push_str("(answering...) ");
+                }
+                let won = matches!(
+                    (arena.vote, side),
+                    (Some(Vote::A), 0) | (Some(Vote::B), 1) | (Some(Vote::Tie), _)
+                );
+                let border_style = if won { Style::default().fg(Color::Green) } else { Style::default() };
+                let block = Block::default().borders(Borders::ALL).title(title).border_style(border_style);
+                let reply = Paragraph::new(arena.replies[side].as_str()).block(block).wrap(Wrap { trim: false });
+                f.render_widget(reply, sides[side]);
+            }
+        }
+    }
+
+    let rows = scoreboard.ranked().into_iter().map(|(model, rating)| {
+        Row::new(vec![
+            model.clone(),
+            format!("{:.0}", rating.elo),
+            format!("{}/{}/{}", rating.wins, rating.losses, rating.ties),
+        ])
+    });
+    let widths = [Constraint::Min(20), Constraint::Length(8), Constraint::Length(12)];
+    let table = Table::new(rows, widths)
+        .header(Row::new(["Model", "Elo", "W/L/T"]).style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)))
+        .block(Block::default().borders(Borders::ALL).title(" Scoreboard "));
+    f.render_widget(table, chunks[1]);
+}
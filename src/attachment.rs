@@ -0,0 +1,104 @@
+use std::{
+    env, fs,
+    io::Read,
+    path::Path,
+};
+
+use crate::AppError;
+
+// Files larger than this are truncated before being inserted into the prompt
+pub const MAX_ATTACHMENT_BYTES: usize = 64 * 1024;
+
+pub struct Attachment {
+    pub label: String,
+    pub block: String,
+    pub truncated: bool,
+}
+
+// Reads a text file and wraps it in a fenced code block labelled with its path
+pub fn attach_file(path: &Path) -> Result<Attachment, AppError> {
+    let label = display_path(path);
+    if path.is_dir() {
+        return Err(AppError::Attachment(format!("{} is a directory", label)));
+    }
+
+    // One byte past the limit is enough to tell the file was cut short
+    let mut bytes = Vec::new();
+    fs::File::open(path)?.take(MAX_ATTACHMENT_BYTES as u64 + 1).read_to_end(&mut bytes)?;
+    let truncated = bytes.len() > MAX_ATTACHMENT_BYTES;
+    let bytes = if truncated { &bytes[..MAX_ATTACHMENT_BYTES] } else { &bytes[..] };
+
+    let text = match std::str::from_utf8(bytes) {
+        Ok(text) => text,
+        // Truncation may cut a multi-byte character in half; keep everything before it
+        Err(e) if truncated && e.error_len().is_none() => {
+            std::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap_or_default()
+        }
+        Err(_) => return Err(AppError::Attachment(format!("{} is not a UTF-8 text file", label))),
+    };
+    if text.contains('\0') {
+        return Err(AppError::Attachment(format!("{} looks like a binary file", label)));
+    }
+
+    let language = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+    let mut block = format!("File: {}\n{}", label, fenced_block(language, text));
+    if truncated {
+        block.push_str(&truncation_note());
+    }
+
+    Ok(Attachment { label, block, truncated })
+}
+
+fn truncation_note() -> String {
+    format!("(truncated to the first {} KiB)\n", MAX_ATTACHMENT_BYTES / 1024)
+}
+
+// Fences in-memory text (e.g. a diff), truncating it to the attachment limit
+pub fn fenced_text(language: &str, text: &str) -> String {
+    let (text, truncated) = truncate(text);
+    let mut block = fenced_block(language, text);
+    if truncated {
+        block.push_str(&truncation_note());
+    }
+    block
+}
+
+// Cuts text down to the attachment limit on a character boundary
+fn truncate(text: &str) -> (&str, bool) {
+    if text.len() <= MAX_ATTACHMENT_BYTES {
+        return (text, false);
+    }
+    let mut end = MAX_ATTACHMENT_BYTES;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    (&text[..end], true)
+}
+
+// Wraps text in a code fence long enough not to clash with backticks inside it
+pub fn fenced_block(language: &str, text: &str) -> String {
+    let longest_run = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+
+    let mut block = format!("{}{}\n{}", fence, language, text);
+    if !text.ends_with('\n') {
+        block.push('\n');
+    }
+    block.push_str(&fence);
+    block.push('\n');
+    block
+}
+
+// Shows paths relative to the working directory when possible
+pub fn display_path(path: &Path) -> String {
+    env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok().map(Path::to_path_buf))
+        .unwrap_or_else(|| path.to_path_buf())
+        .display()
+        .to_string()
+}
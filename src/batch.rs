@@ -0,0 +1,277 @@
+use ratatui::{backend::CrosstermBackend, prelude::*, widgets::*, TerminalOptions, Viewport};
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, IsTerminal, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use tokio::sync::mpsc;
+
+use crate::{
+    config::{Config, Route},
+    dispatch_prompt,
+    stats::Generation,
+    AppError, AppEvent, PromptRequest, ReplyEvents, ReplyShape,
+};
+
+// --- Batch Runs (`ollama-tui batch`) ---
+// Every line of a text file, or every record of a .jsonl file, goes to the
+// model as a prompt of its own, one after another, through the same streaming
+// client as the conversation. Results are appended to the output file as they
+// finish, so an interrupted run keeps what it has done.
+const USAGE: &str = "Usage: ollama-tui batch <prompts.txt|prompts.jsonl> --model <model> --out <results.jsonl>";
+const PROGRESS_HEIGHT: u16 = 5;
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+pub struct BatchArgs {
+    prompts: PathBuf,
+    model: String,
+    out: PathBuf,
+}
+
+pub fn parse_args(args: &[String]) -> Result<BatchArgs, String> {
+    let (mut prompts, mut model, mut out) = (None, None, None);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--model" | "-m" => model = args.next().cloned(),
+            "--out" | "-o" => out = args.next().map(PathBuf::from),
+            flag if flag.starts_with('-') => return Err(format!("Unknown option {}\n{}", flag, USAGE)),
+            path if prompts.is_none() => prompts = Some(PathBuf::from(path)),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    match (prompts, model, out) {
+        (Some(prompts), Some(model), Some(out)) => Ok(BatchArgs { prompts, model, out }),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+// `id` is copied over from a JSONL record, to match results to inputs
+struct Record {
+    line: usize,
+    id: Option<Value>,
+    prompt: String,
+}
+
+// Text files have one prompt per non-empty line; JSONL records need a
+// "prompt" string
+fn load_records(path: &Path) -> Result<Vec<Record>, AppError> {
+    let contents = fs::read_to_string(path)?;
+    let jsonl = path.extension().is_some_and(|ext| ext == "jsonl");
+    let mut records = Vec::new();
+    for (index, line) in contents.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let bad = |reason: String| AppError::Batch(format!("{} line {}: {}", path.display(), index + 1, reason));
+        if !jsonl {
+            records.push(Record { line: index + 1, id: None, prompt: line.trim().to_string() });
+            continue;
+        }
+        let value: Value = serde_json::from_str(line).map_err(|e| bad(e.to_string()))?;
+        let Some(prompt) = value.get("prompt").and_then(Value::as_str) else {
+            return Err(bad("no \"prompt\" string".to_string()));
+        };
+        records.push(Record { line: index + 1, id: value.get("id").cloned(), prompt: prompt.to_string() });
+    }
+    if records.is_empty() {
+        return Err(AppError::Batch(format!("{} contains no prompts", path.display())));
+    }
+    Ok(records)
+}
+
+// One line of the output file
+#[derive(Serialize)]
+struct BatchResult<'a> {
+    line: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<&'a Value>,
+    prompt: &'a str,
+    // The model that answered, which is a fallback if the routing config has one
+    model: String,
+    response: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    eval_count: u64,
+    duration_ms: u64,
+}
+
+// Inline below the command line on a terminal; one line per prompt otherwise
+struct Progress {
+    terminal: Option<Terminal<CrosstermBackend<io::Stderr>>>,
+    title: String,
+    total: usize,
+    done: usize,
+    failed: usize,
+    generation: Generation,
+    current: String,
+    chunks: usize,
+    started: Instant,
+    drawn: Option<Instant>,
+    // Where the inline viewport ended up, to leave the cursor below it
+    area: Rect,
+}
+
+impl Progress {
+    fn new(args: &BatchArgs, total: usize) -> Self {
+        let terminal = io::stderr().is_terminal().then(|| {
+            let options = TerminalOptions { viewport: Viewport::Inline(PROGRESS_HEIGHT) };
+            Terminal::with_options(CrosstermBackend::new(io::stderr()), options).ok()
+        });
+        Progress {
+            terminal: terminal.flatten(),
+            title: format!(" Batch: {} → {} ", args.model, args.out.display()),
+            total,
+            done: 0,
+            failed: 0,
+            generation: Generation::default(),
+            current: String::new(),
+            chunks: 0,
+            started: Instant::now(),
+            drawn: None,
+            area: Rect::default(),
+        }
+    }
+
+    fn start(&mut self, prompt: &str) {
+        self.current = prompt.lines().next().unwrap_or("").to_string();
+        self.chunks = 0;
+        self.draw(true);
+    }
+
+    fn chunk(&mut self) {
+        self.chunks += 1;
+        self.draw(false);
+    }
+
+    fn finish(&mut self, result: &BatchResult) {
+        self.done += 1;
+        self.generation.eval_count += result.eval_count;
+        match &result.error {
+            Some(error) => {
+                self.failed += 1;
+                if self.terminal.is_none() {
+                    eprintln!("[{}/{}] line {}: {}", self.done, self.total, result.line, error);
+                }
+            }
+            None if self.terminal.is_none() => {
+                eprintln!("[{}/{}] line {}: {} tokens in {}ms", self.done, self.total, result.line, result.eval_count, result.duration_ms);
+            }
+            None => {}
+        }
+        self.draw(true);
+    }
+
+    fn draw(&mut self, force: bool) {
+        if !force && self.drawn.is_some_and(|at| at.elapsed() < REDRAW_INTERVAL) {
+            return;
+        }
+        self.drawn = Some(Instant::now());
+        let Some(terminal) = self.terminal.as_mut() else { return };
+        let ratio = self.done as f64 / self.total as f64;
+        let summary = format!(
+            "{} failed, {} tokens, {}s elapsed",
+            self.failed,
+            self.generation.eval_count,
+            self.started.elapsed().as_secs()
+        );
+        let current = if self.done < self.total {
+            format!("Now: {} ({} chunks)", self.current, self.chunks)
+        } else {
+            "Finished.".to_string()
+        };
+        let title = self.title.as_str();
+        let (done, total) = (self.done, self.total);
+        let area = &mut self.area;
+        let _ = terminal.draw(|f| {
+            *area = f.size();
+            let block = Block::default().borders(Borders::ALL).title(title);
+            let inner = block.inner(f.size());
+            f.render_widget(block, f.size());
+            let rows = Layout::vertical([Constraint::Length(1); 3]).split(inner);
+            let gauge = Gauge::default()
+                .gauge_style(Style::default().fg(Color::Green))
+                .ratio(ratio)
+                .label(format!("{}/{}", done, total));
+            f.render_widget(gauge, rows[0]);
+            f.render_widget(Paragraph::new(summary), rows[1]);
+            f.render_widget(Paragraph::new(current).style(Style::default().fg(Color::DarkGray)), rows[2]);
+        });
+    }
+}
+
+// Streams one prompt, collecting the reply from the events the conversation
+// would get
+async fn run_one<'a>(client: &Client, config: &Config, model: &str, record: &'a Record, progress: &mut Progress) -> BatchResult<'a> {
+    let started = Instant::now();
+    let (event_sender, mut events) = mpsc::channel(100);
+    let routes = vec![Route { model: model.to_string(), server: None }];
+    tokio::spawn(dispatch_prompt(
+        client.clone(),
+        config.hooks.clone(),
+        config.stream_decoding,
+        routes,
+        record.prompt.clone(),
+        PromptRequest::Generate { shape: ReplyShape { format: None, num_predict: config.num_predict, think: config.think } },
+        // One request per channel, so the id doesn't matter
+        ReplyEvents { request: 0, sender: event_sender },
+    ));
+
+    let mut result = BatchResult {
+        line: record.line,
+        id: record.id.as_ref(),
+        prompt: &record.prompt,
+        model: model.to_string(),
+        response: String::new(),
+        error: None,
+        eval_count: 0,
+        duration_ms: 0,
+    };
+    // The channel closes once the request is over, however it ended
+    while let Some(event) = events.recv().await {
+        match event {
+            AppEvent::OllamaChunk { text, .. } => {
+                result.response.push_str(&text);
+                progress.chunk();
+            }
+            AppEvent::Generated { generation, .. } => result.eval_count = generation.eval_count,
+            AppEvent::Rerouted { route, .. } => result.model = route.model,
+            AppEvent::OllamaError { report, .. } | AppEvent::StreamDecodeError { report, .. } => {
+                result.error.get_or_insert(report.message);
+            }
+            AppEvent::OllamaInterrupted { message: error, .. } => {
+                result.error.get_or_insert(error);
+            }
+            _ => {}
+        }
+    }
+    result.duration_ms = started.elapsed().as_millis() as u64;
+    result
+}
+
+pub async fn run(args: BatchArgs, config: Config, client: Client) -> Result<(), AppError> {
+    let records = load_records(&args.prompts)?;
+    let mut out = BufWriter::new(File::create(&args.out)?);
+    let mut progress = Progress::new(&args, records.len());
+    for record in &records {
+        progress.start(&record.prompt);
+        let result = run_one(&client, &config, &args.model, record, &mut progress).await;
+        serde_json::to_writer(&mut out, &result)?;
+        writeln!(out)?;
+        out.flush()?;
+        progress.finish(&result);
+    }
+    if let Some(terminal) = progress.terminal.as_mut() {
+        let _ = terminal.set_cursor(0, progress.area.bottom().saturating_sub(1));
+        let _ = terminal.show_cursor();
+        eprintln!();
+    }
+    eprintln!(
+        "{} prompts, {} failed; results written to {}",
+        progress.done,
+        progress.failed,
+        args.out.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use ratatui::{prelude::*, widgets::*};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    fs,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::mpsc;
+
+use crate::{endpoint, limiter::Limiter, trace, AppError, AppEvent};
+
+// --- Prompt Benchmarks ---
+// Every prompt is run against every model, one request at a time so the
+// runs don't compete for the GPU.
+#[derive(Serialize, Clone, Debug)]
+pub struct BenchResult {
+    pub model: String,
+    pub prompt_index: usize,
+    // Wall-clock time for the whole request, including model load
+    pub latency_secs: f64,
+    pub output_tokens: u64,
+    pub tokens_per_sec: f64,
+    pub output_chars: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GenerateStats {
+    #[serde(default)]
+    response: String,
+    #[serde(default)]
+    eval_count: u64,
+    // Nanoseconds spent generating the output tokens
+    #[serde(default)]
+    eval_duration: u64,
+}
+
+pub struct BenchRun {
+    pub models: Vec<String>,
+    pub prompts: Vec<String>,
+    pub results: Vec<BenchResult>,
+    pub finished: bool,
+}
+
+impl BenchRun {
+    fn total(&self) -> usize {
+        self.models.len() * self.prompts.len()
+    }
+}
+
+// "@path" reads one prompt per non-empty line; anything else is a single prompt
+pub fn load_prompts(arg: &str) -> Result<Vec<String>, AppError> {
+    let Some(path) = arg.strip_prefix('@') else {
+        return Ok(vec![arg.to_string()]);
+    };
+    let prompts: Vec<String> = fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect();
+    if prompts.is_empty() {
+        return Err(AppError::Attachment(format!("{} contains no prompts", path)));
+    }
+    Ok(prompts)
+}
+
+async fn run_one(client: &Client, model: &str, prompt: &str) -> Result<(GenerateStats, Duration), AppError> {
+    let started = Instant::now();
+    let (trace, response) = trace::send(
+        client
+            .post(format!("{}/api/generate", endpoint::base()))
+            .json(&endpoint::with_keep_alive(json!({ "model": model, "prompt": prompt, "stream": false })))
+            .timeout(Duration::from_secs(600)),
+    )
+    .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let err_text = response.text().await.unwrap_or_default();
+        return Err(trace.tag(AppError::ApiResponse(format!("Status {} - {}", status, err_text))));
+    }
+    let stats: GenerateStats = response.json().await.map_err(|e| trace.tag(e.into()))?;
+    Ok((stats, started.elapsed()))
+}
+
+pub async fn run(
+    client: Client,
+    limiter: Arc<Limiter>,
+    models: Vec<String>,
+    prompts: Vec<String>,
+    event_sender: mpsc::Sender<AppEvent>,
+) {
+    for (prompt_index, prompt) in prompts.iter().enumerate() {
+        for model in &models {
+            let mut result = BenchResult {
+                model: model.clone(),
+                prompt_index,
+                latency_secs: 0.0,
+                output_tokens: 0,
+                tokens_per_sec: 0.0,
+                output_chars: 0,
+                error: None,
+            };
+            let slot = limiter.acquire().await;
+            let outcome = run_one(&client, model, prompt).await;
+            drop(slot);
+            match outcome {
+                Ok((stats, elapsed)) => {
+                    result.latency_secs = elapsed.as_secs_f64();
+                    result.output_tokens = stats.eval_count;
+                    if stats.eval_duration > 0 {
+                        result.tokens_per_sec = stats.eval_count as f64 / (stats.eval_duration as f64 / 1e9);
+                    }
+                    result.output_chars = stats.response.chars().count();
+                }
+                Err(e) => result.error = Some(e.to_string()),
+            }
+            if event_sender.send(AppEvent::BenchResult(result)).await.is_err() {
+                return;
+            }
+        }
+    }
+    let _ = event_sender.send(AppEvent::BenchFinished).await;
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+// Writes CSV for a .csv path, JSON otherwise
+pub fn export(run: &BenchRun, path: &Path) -> Result<(), AppError> {
+    let is_csv = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+    let contents = if is_csv {
+        let mut csv = String::from("model,prompt,latency_secs,output_tokens,tokens_per_sec,output_chars,error\n");
+        for result in &run.results {
+            csv.push_str(&format!(
+                "{},{},{:.3},{},{:.2},{},{}\n",
+                csv_field(&result.model),
+                csv_field(&run.prompts[result.prompt_index]),
+                result.latency_secs,
+                result.output_tokens,
+                result.tokens_per_sec,
+                result.output_chars,
+                csv_field(result.error.as_deref().unwrap_or("")),
+            ));
+        }
+        csv
+    } else {
+        serde_json::to_string_pretty(&json!({ "prompts": run.prompts, "results": run.results }))?
+    };
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+pub fn render(f: &mut Frame, area: Rect, run: Option<&BenchRun>) {
+    let Some(run) = run else {
+        let help = "No benchmark yet.\n\n\
+                    /bench <model,model,...|all> <prompt>     run one prompt\n\
+                    /bench <model,model,...|all> @prompts.txt  one prompt per line\n\
+                    /bench export <results.csv|results.json>";
+        let block = Block::default().borders(Borders::ALL).title(" Benchmark (Tab: Next view) ");
+        f.render_widget(Paragraph::new(help).block(block), area);
+        return;
+    };
+
+    let progress = if run.finished {
+        format!("done, {} runs", run.results.len())
+    } else {
+        format!("running {}/{}", run.results.len() + 1, run.total())
+    };
+    let title = format!(" Benchmark: {} models x {} prompts, {} (Tab: Next view) ", run.models.len(), run.prompts.len(), progress);
+
+    let header = Row::new(["Model", "Prompt", "Latency", "Tokens", "Tok/s", "Chars"])
+        .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD));
+    let rows = run.results.iter().map(|result| {
+        let prompt = format!("#{}", result.prompt_index + 1);
+        match &result.error {
+            // The full error is in the status bar and the export
+            Some(_) => Row::new(vec![result.model.clone(), prompt, "failed".to_string()])
+                .style(Style::default().fg(Color::Red)),
+            None => Row::new(vec![
+                result.model.clone(),
+                prompt,
+                format!("{:.2}s", result.latency_secs),
+                result.output_tokens.to_string(),
+                format!("{:.1}", result.tokens_per_sec),
+                result.output_chars.to_string(),
+            ]),
+        }
+    });
+    let widths = [
+        Constraint::Min(20),
+        Constraint::Length(7),
+        Constraint::Length(10),
+        Constraint::Length(8),
+        Constraint::Length(8),
+        Constraint::Length(8),
+    ];
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(table, area);
+}
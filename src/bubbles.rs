@@ -0,0 +1,104 @@
+use ratatui::{prelude::*, text::{Line, Span}};
+use serde::Deserialize;
+
+use crate::layout;
+
+// --- Message Layout ---
+// "labels" (the default) puts every turn on the left under its sender's
+// name; "bubbles" draws your prompts as right-aligned blocks on a tinted
+// background, like a messaging app, with replies on the left as before.
+// /layout switches between them at runtime.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageLayout {
+    #[default]
+    Labels,
+    Bubbles,
+}
+
+impl MessageLayout {
+    pub fn name(self) -> &'static str {
+        match self {
+            MessageLayout::Labels => "labels",
+            MessageLayout::Bubbles => "bubbles",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [MessageLayout::Labels, MessageLayout::Bubbles].into_iter().find(|layout| layout.name() == name)
+    }
+
+    pub fn other(self) -> Self {
+        match self {
+            MessageLayout::Labels => MessageLayout::Bubbles,
+            MessageLayout::Bubbles => MessageLayout::Labels,
+        }
+    }
+}
+
+const BUBBLE_BACKGROUND: Color = Color::Rgb(35, 50, 70);
+// Share of the pane a bubble's text may take, and the least it gets
+const BUBBLE_PERCENT: usize = 75;
+const MIN_BUBBLE_WIDTH: usize = 20;
+
+// A turn laid out as a bubble, ready for the conversation pane
+pub struct Bubble<'a> {
+    pub rows: Vec<Line<'a>>,
+    // Per row: whether it was wrapped onto from the row before
+    pub continued: Vec<bool>,
+    // Per row: columns of alignment and padding before and after the text,
+    // which selections skip
+    pub margins: Vec<(usize, usize)>,
+    // The row each of the given lines starts on, headings included
+    pub starts: Vec<usize>,
+}
+
+// `header` right-aligned over `body` in a right-aligned bubble, in a pane
+// `width` columns wide
+pub fn right<'a>(header: Line<'a>, body: Vec<Line<'a>>, width: u16) -> Bubble<'a> {
+    let width = width as usize;
+    let inner = (width * BUBBLE_PERCENT / 100).max(MIN_BUBBLE_WIDTH).min(width.saturating_sub(4)).max(1);
+    let mut bubble = Bubble { rows: Vec::new(), continued: Vec::new(), margins: Vec::new(), starts: Vec::new() };
+
+    // The name, flush with the bubble's right edge
+    bubble.starts.push(0);
+    for (index, row) in layout::wrap_line(header, width as u16).into_iter().enumerate() {
+        let indent = width.saturating_sub(row.width());
+        bubble.push(pad(indent, Style::default()).into_iter().chain(row.spans).collect(), index > 0, (indent, 0));
+    }
+
+    // Every row as wide as the widest, so the background forms a block
+    let mut body_rows = Vec::new();
+    for line in body {
+        bubble.starts.push(bubble.rows.len() + body_rows.len());
+        let rows = layout::wrap_line(line, inner as u16);
+        body_rows.extend(rows.into_iter().enumerate().map(|(index, row)| (row, index > 0)));
+    }
+    let text_width = body_rows.iter().map(|(row, _)| row.width()).max().unwrap_or(0);
+    let indent = width.saturating_sub(text_width + 2);
+    let background = Style::default().bg(BUBBLE_BACKGROUND);
+    for (row, continued) in body_rows {
+        let fill = text_width.saturating_sub(row.width()) + 1;
+        let mut spans = pad(indent, Style::default());
+        spans.extend(pad(1, background));
+        spans.extend(row.patch_style(background).spans);
+        spans.extend(pad(fill, background));
+        bubble.push(spans, continued, (indent + 1, fill));
+    }
+    bubble
+}
+
+impl<'a> Bubble<'a> {
+    fn push(&mut self, spans: Vec<Span<'a>>, continued: bool, margins: (usize, usize)) {
+        self.rows.push(Line::from(spans));
+        self.continued.push(continued);
+        self.margins.push(margins);
+    }
+}
+
+fn pad<'a>(columns: usize, style: Style) -> Vec<Span<'a>> {
+    if columns == 0 {
+        return Vec::new();
+    }
+    vec![Span::styled(" ".repeat(columns), style)]
+}
@@ -0,0 +1,121 @@
+use ratatui::{prelude::*, text::Span};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+
+use crate::{endpoint, trace, AppError};
+
+// --- Model Capability Badges ---
+// What a model can do, from /api/show. Newer servers list capabilities
+// outright; for older ones they are guessed from the model's families,
+// template and name.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Capability {
+    Vision,
+    Tools,
+    Embed,
+    Code,
+}
+
+impl Capability {
+    pub fn badge(self) -> &'static str {
+        match self {
+            Capability::Vision => "[vision]",
+            Capability::Tools => "[tools]",
+            Capability::Embed => "[embed]",
+            Capability::Code => "[code]",
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            Capability::Vision => Color::Magenta,
+            Capability::Tools => Color::Yellow,
+            Capability::Embed => Color::Blue,
+            Capability::Code => Color::Green,
+        }
+    }
+}
+
+// Families with an image encoder
+const VISION_FAMILIES: &[&str] = &["clip", "mllama", "llava", "gemma3", "qwen25vl", "mistral3"];
+const EMBED_FAMILIES: &[&str] = &["bert", "nomic-bert", "jina-bert"];
+// Name fragments of code-tuned models
+const CODE_NAMES: &[&str] = &["code", "coder", "starcoder", "codestral", "devstral", "granite-code"];
+
+#[derive(Deserialize, Default)]
+struct ShowResponse {
+    #[serde(default)]
+    details: ShowDetails,
+    #[serde(default)]
+    template: String,
+    // Only on servers that report capabilities themselves
+    capabilities: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Default)]
+struct ShowDetails {
+    #[serde(default)]
+    family: String,
+    #[serde(default)]
+    families: Option<Vec<String>>,
+}
+
+pub async fn fetch(client: Client, model: &str) -> Result<Vec<Capability>, AppError> {
+    let (trace, response) = trace::send(
+        client
+            .post(format!("{}/api/show", endpoint::base()))
+            .json(&json!({ "model": model }))
+            .timeout(Duration::from_secs(15)),
+    )
+    .await?;
+    if !response.status().is_success() {
+        return Err(trace.tag(AppError::ApiResponse(format!("Status {} from /api/show", response.status()))));
+    }
+    let show: ShowResponse = response.json().await.map_err(|e| trace.tag(e.into()))?;
+    Ok(detect(model, &show))
+}
+
+fn detect(model: &str, show: &ShowResponse) -> Vec<Capability> {
+    let mut found = Vec::new();
+    match &show.capabilities {
+        Some(reported) => {
+            for (name, capability) in [("vision", Capability::Vision), ("tools", Capability::Tools), ("embedding", Capability::Embed)] {
+                if reported.iter().any(|reported| reported == name) {
+                    found.push(capability);
+                }
+            }
+        }
+        None => {
+            let families: Vec<&str> = std::iter::once(show.details.family.as_str())
+                .chain(show.details.families.iter().flatten().map(String::as_str))
+                .collect();
+            if families.iter().any(|family| VISION_FAMILIES.contains(family)) {
+                found.push(Capability::Vision);
+            }
+            // Tool-capable templates render the tool list
+            if show.template.contains(".Tools") {
+                found.push(Capability::Tools);
+            }
+            if families.iter().any(|family| EMBED_FAMILIES.contains(family)) || model.contains("embed") {
+                found.push(Capability::Embed);
+            }
+        }
+    }
+    // No server reports this one
+    let name = model.split(':').next().unwrap_or(model).to_lowercase();
+    if CODE_NAMES.iter().any(|fragment| name.contains(fragment)) {
+        found.push(Capability::Code);
+    }
+    found
+}
+
+// The badges, each preceded by a space, for the models list
+pub fn badges(capabilities: &[Capability]) -> Vec<Span<'static>> {
+    capabilities
+        .iter()
+        .map(|capability| Span::styled(format!(" {}", capability.badge()), Style::default().fg(capability.color())))
+        .collect()
+}
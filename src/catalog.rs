@@ -0,0 +1,101 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use crate::{capabilities::Capability, endpoint, installed::ModelDetails, AppError};
+
+// --- Offline Model Catalog ---
+// The last model list (/api/tags) and capabilities (/api/show) the server
+// gave, kept in <data dir>/ollama-tui/catalog.json. When the server can't be
+// reached the models come from here, marked as cached in the models list and
+// the Models tab, so the app still starts, models can be browsed and old
+// sessions read; the list is fetched again until the server answers.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CachedModel {
+    pub name: String,
+    pub size: u64,
+    #[serde(default)]
+    pub details: ModelDetails,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Catalog {
+    // The server the models are on; another one's cache is not used
+    server: String,
+    saved_at: Option<DateTime<Local>>,
+    models: Vec<CachedModel>,
+    capabilities: HashMap<String, Vec<Capability>>,
+}
+
+fn catalog_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("ollama-tui").join("catalog.json"))
+}
+
+impl Catalog {
+    // A missing file means nothing was cached yet
+    pub fn load() -> Result<Self, AppError> {
+        let Some(path) = catalog_path() else {
+            return Ok(Catalog::default());
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Catalog::default()),
+            Err(e) => Err(AppError::Io(e)),
+        }
+    }
+
+    fn save(&self) -> Result<(), AppError> {
+        let path = catalog_path().ok_or_else(|| AppError::Session("No data directory available".to_string()))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    // The cached models of the current server, and when they were cached
+    pub fn models(&self) -> Option<(&[CachedModel], DateTime<Local>)> {
+        let saved_at = self.saved_at?;
+        (self.server == endpoint::base() && !self.models.is_empty()).then_some((self.models.as_slice(), saved_at))
+    }
+
+    pub fn capabilities(&self) -> &HashMap<String, Vec<Capability>> {
+        &self.capabilities
+    }
+
+    // A fresh list from the server; capabilities of models no longer
+    // installed are dropped
+    pub fn store_models(&mut self, models: Vec<CachedModel>) -> Result<(), AppError> {
+        if self.server != endpoint::base() {
+            self.capabilities.clear();
+        }
+        self.server = endpoint::base().to_string();
+        self.saved_at = Some(Local::now());
+        self.capabilities.retain(|name, _| models.iter().any(|model| model.name == *name));
+        self.models = models;
+        self.save()
+    }
+
+    // Saved with the next model list, or on quit
+    pub fn store_capabilities(&mut self, model: &str, capabilities: &[Capability]) {
+        self.capabilities.insert(model.to_string(), capabilities.to_vec());
+    }
+
+    pub fn save_capabilities(&self) -> Result<(), AppError> {
+        if self.saved_at.is_none() {
+            return Ok(());
+        }
+        self.save()
+    }
+}
+
+// "5 minutes ago", "3 hours ago", "2 days ago"
+pub fn age(saved_at: DateTime<Local>) -> String {
+    let minutes = (Local::now() - saved_at).num_minutes().max(0);
+    match minutes {
+        0 => "just now".to_string(),
+        1..=59 => format!("{} min ago", minutes),
+        60..=1439 => format!("{} h ago", minutes / 60),
+        _ => format!("{} days ago", minutes / 1440),
+    }
+}
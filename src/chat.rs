@@ -0,0 +1,240 @@
+use futures::StreamExt;
+use reqwest::{Client, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    config::Route,
+    endpoint,
+    is_retryable_status,
+    mcp::McpManager,
+    ndjson::{NdjsonDecoder, StreamDecoding},
+    notifications::ErrorReport,
+    stats::Generation,
+    trace::{self, Trace},
+    AppError, AppEvent, ErrorCategory, ReplyEvents, ReplyShape,
+};
+
+// --- Chat Endpoint (/api/chat) with Tool Calling ---
+// Stop a model that keeps calling tools instead of answering
+const MAX_TOOL_ROUNDS: usize = 8;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ChatMessage {
+    pub role: String,
+    #[serde(default)]
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+}
+
+impl ChatMessage {
+    pub fn new(role: &str, content: String) -> Self {
+        ChatMessage { role: role.to_string(), content, tool_calls: Vec::new() }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ToolCall {
+    pub function: ToolCallFunction,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ToolCallFunction {
+    pub name: String,
+    #[serde(default)]
+    pub arguments: Value,
+}
+
+#[derive(Serialize)]
+struct OllamaChatRequest<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage],
+    #[serde(skip_serializing_if = "<[Value]>::is_empty")]
+    tools: &'a [Value],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<&'a Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    think: Option<bool>,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<Value>,
+}
+
+#[derive(Deserialize, Debug)]
+struct OllamaChatChunk {
+    #[serde(default)]
+    message: Option<ChatMessage>,
+    #[serde(default)]
+    done: bool,
+    // Why it stopped, on the final chunk: "stop", "length" or "load"
+    #[serde(default)]
+    done_reason: Option<String>,
+    #[serde(flatten)]
+    generation: Generation,
+}
+
+async fn report_error(events: &ReplyEvents, error: &AppError) {
+    let _ = events.sender.send(AppEvent::OllamaError { request: events.request, report: ErrorReport::from(error) }).await;
+    let _ = events.sender.send(AppEvent::OllamaDone { request: events.request }).await;
+}
+
+// Streams the reply, running any tool calls through MCP and feeding the
+// results back to the model until it produces a final answer.
+pub async fn stream_chat_response(
+    client: Client,
+    route: &Route,
+    decoding: StreamDecoding,
+    mut messages: Vec<ChatMessage>,
+    mcp: Option<Arc<McpManager>>,
+    shape: ReplyShape,
+    events: ReplyEvents,
+) -> Result<(), AppError> {
+    let mut tools = mcp.as_ref().map(|mcp| mcp.ollama_tools()).unwrap_or_default();
+
+    for round in 0..MAX_TOOL_ROUNDS {
+        let reply = match stream_chat_round(&client, route, decoding, &messages, &mut tools, &shape, &events).await {
+            Ok(reply) => reply,
+            // Nothing was streamed yet, so the caller may try another route
+            Err(e @ AppError::Unavailable(..)) if round == 0 => return Err(e),
+            Err(AppError::Interrupted(msg)) => {
+                // Keep the partial text and let the user resume it
+                let _ = events.sender.send(AppEvent::OllamaInterrupted { request: events.request, message: msg.clone() }).await;
+                return Err(AppError::Interrupted(msg));
+            }
+            Err(e) => {
+                report_error(&events, &e).await;
+                return Err(e);
+            }
+        };
+
+        let Some(mcp) = mcp.as_ref().filter(|_| !reply.tool_calls.is_empty()) else {
+            events.sender.send(AppEvent::OllamaDone { request: events.request }).await
+                .map_err(|e| AppError::ChannelSend(format!("Failed to send done signal: {}", e)))?;
+            return Ok(());
+        };
+
+        let calls = reply.tool_calls.clone();
+        messages.push(reply);
+        for call in calls {
+            let ToolCallFunction { name, arguments } = call.function;
+            let _ = events.sender.send(AppEvent::ToolCall { request: events.request, name: name.clone(), arguments: arguments.clone() }).await;
+            let (output, is_error) = match mcp.call_tool(&name, arguments).await {
+                Ok(output) => (output, false),
+                Err(e) => (e.to_string(), true),
+            };
+            let _ = events.sender.send(AppEvent::ToolResult { request: events.request, name, output: output.clone(), is_error }).await;
+            messages.push(ChatMessage::new("tool", output));
+        }
+    }
+
+    let error = AppError::ApiResponse(format!("Stopped after {} rounds of tool calls", MAX_TOOL_ROUNDS));
+    report_error(&events, &error).await;
+    Err(error)
+}
+
+// Sends the request, retrying without tools if the model can't use them
+async fn send_chat_request(
+    client: &Client,
+    route: &Route,
+    messages: &[ChatMessage],
+    tools: &mut Vec<Value>,
+    shape: &ReplyShape,
+) -> Result<(Trace, Response), AppError> {
+    loop {
+        let url = format!("{}/api/chat", route.base_url());
+        let request_body = OllamaChatRequest {
+            model: &route.model,
+            messages,
+            tools,
+            format: shape.format.as_ref(),
+            options: shape.options(),
+            think: shape.think,
+            stream: true,
+            keep_alive: endpoint::keep_alive(),
+        };
+        let (trace, response) = trace::send(client.post(&url)
+            .json(&request_body)
+            .timeout(Duration::from_secs(300))) // Long timeout for generation
+            .await
+            .map_err(AppError::unavailable)?;
+
+        if response.status().is_success() {
+            return Ok((trace, response));
+        }
+
+        let status = response.status();
+        let err_text = response.text().await.unwrap_or_else(|_| "Failed to read error body".to_string());
+        if status == StatusCode::BAD_REQUEST && err_text.contains("does not support tools") && !tools.is_empty() {
+            tools.clear();
+            continue;
+        }
+        let message = trace.tag_message(format!("Status {} - {}", status, err_text));
+        if is_retryable_status(status) {
+            return Err(AppError::Unavailable(message, ErrorCategory::Server));
+        }
+        return Err(AppError::ApiResponse(message));
+    }
+}
+
+// Streams one assistant message, forwarding its text as it arrives
+async fn stream_chat_round(
+    client: &Client,
+    route: &Route,
+    decoding: StreamDecoding,
+    messages: &[ChatMessage],
+    tools: &mut Vec<Value>,
+    shape: &ReplyShape,
+    events: &ReplyEvents,
+) -> Result<ChatMessage, AppError> {
+    let (trace, response) = send_chat_request(client, route, messages, tools, shape).await?;
+    let mut reply = ChatMessage::new("assistant", String::new());
+
+    let mut stream = response.bytes_stream();
+    let mut decoder = NdjsonDecoder::<OllamaChatChunk>::new(decoding);
+    let mut at_end = false;
+    while !at_end {
+        let decoded = match stream.next().await {
+            Some(chunk_bytes) => {
+                let chunk_bytes = chunk_bytes
+                    .map_err(|e| AppError::Interrupted(trace.tag_message(format!("Stream Read Error: {}", e))))?;
+                decoder.push(&chunk_bytes)
+            }
+            None => {
+                at_end = true;
+                decoder.finish()
+            }
+        };
+
+        for chunk in decoded {
+            match chunk {
+                Ok(chunk) => {
+                    if let Some(message) = chunk.message {
+                        if !message.content.is_empty() {
+                            reply.content.push_str(&message.content);
+                            events.sender.send(AppEvent::OllamaChunk { request: events.request, text: message.content }).await
+                                .map_err(|_| AppError::ChannelSend("Failed to send chunk".to_string()))?;
+                        }
+                        reply.tool_calls.extend(message.tool_calls);
+                    }
+                    if chunk.done {
+                        trace.record("done");
+                        if let Some(reason) = chunk.done_reason {
+                            let _ = events.sender.send(AppEvent::DoneReason { request: events.request, reason }).await;
+                        }
+                        let _ = events.sender.send(AppEvent::Generated { request: events.request, generation: chunk.generation }).await;
+                        return Ok(reply);
+                    }
+                }
+                Err(error_msg) => {
+                    let _ = events.sender.send(AppEvent::StreamDecodeError { request: events.request, report: ErrorReport::new(ErrorCategory::Parse, error_msg) }).await;
+                }
+            }
+        }
+    }
+    // The final chunk always has done set, so the server went away mid-response
+    Err(AppError::Interrupted(trace.tag_message("Connection closed before the response finished".to_string())))
+}
@@ -0,0 +1,68 @@
+use serde::Deserialize;
+
+use crate::{plain_text, AppError};
+
+// --- System Clipboard ---
+// The handle is created lazily and kept alive, since on X11 the copied text
+// is only served for as long as its owner exists.
+pub struct SystemClipboard {
+    inner: Option<arboard::Clipboard>,
+}
+
+impl SystemClipboard {
+    pub fn new() -> Self {
+        SystemClipboard { inner: None }
+    }
+
+    fn handle(&mut self) -> Result<&mut arboard::Clipboard, AppError> {
+        if self.inner.is_none() {
+            let clipboard = arboard::Clipboard::new().map_err(|e| AppError::Clipboard(e.to_string()))?;
+            self.inner = Some(clipboard);
+        }
+        Ok(self.inner.as_mut().expect("clipboard initialised above"))
+    }
+
+    pub fn copy(&mut self, text: &str) -> Result<(), AppError> {
+        self.handle()?
+            .set_text(text.to_string())
+            .map_err(|e| AppError::Clipboard(e.to_string()))
+    }
+
+    pub fn paste(&mut self) -> Result<String, AppError> {
+        self.handle()?.get_text().map_err(|e| AppError::Clipboard(e.to_string()))
+    }
+}
+
+// What copying a reply puts on the clipboard
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CopyFormat {
+    // The markdown source, as the model wrote it
+    #[default]
+    Markdown,
+    // The rendered text with markdown syntax stripped
+    Plain,
+}
+
+impl CopyFormat {
+    pub fn other(self) -> Self {
+        match self {
+            CopyFormat::Markdown => CopyFormat::Plain,
+            CopyFormat::Plain => CopyFormat::Markdown,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CopyFormat::Markdown => "markdown",
+            CopyFormat::Plain => "plain text",
+        }
+    }
+
+    pub fn apply(self, markdown: &str) -> String {
+        match self {
+            CopyFormat::Markdown => markdown.to_string(),
+            CopyFormat::Plain => plain_text::from_markdown(markdown),
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use ratatui::{prelude::*, text::{Line, Span}};
+
+// --- Folded Code Replies ---
+// A reply that is essentially one big code block is shown folded to a line
+// like "```python — 84 lines", so code-heavy conversations stay scannable.
+// On the selected turn Enter unfolds it, 'y' copies the code and 's' saves
+// it the way the code post-processing step does. `fold_code_lines` in the
+// config sets how long a block must be to fold; 0 never folds.
+
+// Prose around the block ("Here's the script:") that still counts as a
+// code-only reply
+const MAX_PROSE_LINES: usize = 3;
+const MAX_PROSE_CHARS: usize = 200;
+
+pub struct CodeOnly {
+    pub language: String,
+    pub code: String,
+    pub lines: usize,
+}
+
+// The reply's single fenced code block, if that is nearly all there is.
+// Scans lines rather than parsing, as it runs on every redraw
+pub fn code_only(text: &str) -> Option<CodeOnly> {
+    let mut block: Option<CodeOnly> = None;
+    // The opening fence while inside the block
+    let mut fence: Option<&str> = None;
+    let mut prose_lines = 0;
+    let mut prose_chars = 0;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        match fence {
+            // At least as long as the opening fence, of the same character
+            Some(open) if trimmed.len() >= open.len() && trimmed.chars().all(|c| open.starts_with(c)) => fence = None,
+            Some(_) => {
+                let block = block.as_mut()?;
+                block.code.push_str(line);
+                block.code.push('\n');
+                block.lines += 1;
+            }
+            None if trimmed.starts_with("```") || trimmed.starts_with("~~~") => {
+                // A second block makes it more than one big block
+                if block.is_some() {
+                    return None;
+                }
+                let marker = if trimmed.starts_with('`') { '`' } else { '~' };
+                let length = trimmed.chars().take_while(|c| *c == marker).count();
+                fence = Some(&trimmed[..length]);
+                let language = trimmed[length..].split_whitespace().next().unwrap_or("").to_string();
+                block = Some(CodeOnly { language, code: String::new(), lines: 0 });
+            }
+            None if trimmed.is_empty() => {}
+            None => {
+                prose_lines += 1;
+                prose_chars += trimmed.chars().count();
+                if prose_lines > MAX_PROSE_LINES || prose_chars > MAX_PROSE_CHARS {
+                    return None;
+                }
+            }
+        }
+    }
+    block
+}
+
+impl CodeOnly {
+    // "```python — 84 lines (Enter to expand, y to copy, s to save)"
+    pub fn folded_line(&self) -> Line<'static> {
+        let noun = if self.lines == 1 { "line" } else { "lines" };
+        Line::from(vec![
+            Span::styled(format!("```{} — {} {}", self.language, self.lines, noun), Style::default().fg(Color::Yellow)),
+            Span::styled(" (Enter to expand, y to copy, s to save)", Style::default().fg(Color::DarkGray)),
+        ])
+    }
+}
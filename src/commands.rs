@@ -0,0 +1,235 @@
+use crate::{bubbles::MessageLayout, formats::FormatPreset, theme::Theme};
+
+// --- Slash Commands ---
+// Input starting with '/' is treated as a command instead of a prompt
+pub enum Command {
+    File(String),
+    Diff { staged: bool },
+    CommitMsg,
+    Watch { path: String, template: String },
+    Unwatch,
+    Clear,
+    // Start over, blank or from a configured template: /new [template];
+    // opens the picker without an argument when there are templates
+    New(Option<String>),
+    // One prompt answered by another model, leaving the selection alone
+    Ask { model: String, prompt: String },
+    // One prompt whose reply must fit a preset: /format <preset> <prompt>
+    Format { preset: FormatPreset, prompt: String },
+    // `models` is a comma-separated list or "all"; `prompts` is text or @file
+    Bench { models: String, prompts: String },
+    BenchExport(String),
+    Arena(String),
+    // Queue models for download: /pull <model> [model...]
+    Pull(Vec<String>),
+    // Library browser, optionally pre-filtered: /browse [search]
+    Browse(String),
+    // One text, or two separated by "||" to compare: /embed <text> [|| <text>]
+    Embed(Vec<String>),
+    Search(String),
+    // Whole conversation, selected turn or a range; to the clipboard without a path
+    Export { turns: ExportTurns, path: Option<String> },
+    // Start logging to a file, or stop when no path is given
+    Tee(Option<String>),
+    // Show post-processing steps, or switch one: /post <step> [on|off]
+    Post { step: Option<String>, enabled: Option<bool> },
+    // Text put before/after every prompt of this conversation; empty removes it
+    Prefix(String),
+    Suffix(String),
+    // Translate the selected turn or the clipboard: /translate [from:]<to>;
+    // repeats the last languages without an argument
+    Translate(Option<Translation>),
+    // Add the selected exchange to the configured notes file
+    AppendToNote,
+    // Raw markdown source for every turn: /markdown [raw|rendered]; toggles without an argument
+    Markdown(Option<bool>),
+    // Prompts as bubbles or under labels: /layout [labels|bubbles]; toggles
+    // without an argument
+    Layout(Option<MessageLayout>),
+    // Cap reply length: /num_predict <tokens|off>
+    NumPredict(Option<i64>),
+    // Switch colour theme: /theme [name]; opens the picker without an argument
+    Theme(Option<Theme>),
+    // Open a second conversation beside this one, or close it again
+    Split,
+    // Read the config file again
+    Reload,
+    // Background jobs, with their progress
+    Jobs,
+    // Anything else may be registered by a Lua plugin
+    Plugin { name: String, args: String },
+}
+
+#[derive(Clone)]
+pub struct Translation {
+    pub from: Option<String>,
+    pub to: String,
+}
+
+// Which turns /export writes
+pub enum ExportTurns {
+    All,
+    Selected,
+    // 1-based, inclusive
+    Range(usize, usize),
+}
+
+// "3" or "3-5"
+fn parse_turn_range(text: &str) -> Option<(usize, usize)> {
+    let (first, last) = text.split_once('-').unwrap_or((text, text));
+    let (first, last) = (first.parse().ok()?, last.parse().ok()?);
+    (1 <= first && first <= last).then_some((first, last))
+}
+
+pub fn parse(input: &str) -> Option<Result<Command, String>> {
+    let input = input.trim();
+    let rest = input.strip_prefix('/')?;
+    let (name, args) = match rest.split_once(char::is_whitespace) {
+        Some((name, args)) => (name, args.trim()),
+        None => (rest, ""),
+    };
+
+    let command = match name {
+        "file" => {
+            if args.is_empty() {
+                Err("Usage: /file <path>".to_string())
+            } else {
+                Ok(Command::File(args.to_string()))
+            }
+        }
+        "diff" => match args {
+            "" => Ok(Command::Diff { staged: false }),
+            "--staged" | "--cached" => Ok(Command::Diff { staged: true }),
+            _ => Err("Usage: /diff [--staged]".to_string()),
+        },
+        "commit-msg" => Ok(Command::CommitMsg),
+        "watch" => match args.split_once(char::is_whitespace) {
+            Some((path, template)) if !template.trim().is_empty() => Ok(Command::Watch {
+                path: path.to_string(),
+                template: template.trim().to_string(),
+            }),
+            _ => Err("Usage: /watch <path> <prompt template, {{file}} = file contents>".to_string()),
+        },
+        "unwatch" => Ok(Command::Unwatch),
+        "clear" => Ok(Command::Clear),
+        "new" => Ok(Command::New(Some(args.to_string()).filter(|name| !name.is_empty()))),
+        "bench" => match args.split_once(char::is_whitespace) {
+            Some(("export", path)) => Ok(Command::BenchExport(path.trim().to_string())),
+            Some((models, prompts)) if !prompts.trim().is_empty() => Ok(Command::Bench {
+                models: models.to_string(),
+                prompts: prompts.trim().to_string(),
+            }),
+            _ => Err("Usage: /bench <model,model,...|all> <prompt | @file>, or /bench export <path.csv|.json>".to_string()),
+        },
+        "arena" => {
+            if args.is_empty() {
+                Err("Usage: /arena <prompt>".to_string())
+            } else {
+                Ok(Command::Arena(args.to_string()))
+            }
+        }
+        "pull" => {
+            if args.is_empty() {
+                Err("Usage: /pull <model> [model...]".to_string())
+            } else {
+                Ok(Command::Pull(args.split_whitespace().map(str::to_string).collect()))
+            }
+        }
+        "browse" => Ok(Command::Browse(args.to_string())),
+        "embed" => {
+            let texts: Vec<String> = args.split("||").map(|text| text.trim().to_string()).collect();
+            if texts.len() > 2 || texts.iter().any(String::is_empty) {
+                Err("Usage: /embed <text> [|| <text to compare>]".to_string())
+            } else {
+                Ok(Command::Embed(texts))
+            }
+        }
+        "post" => match args.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [] => Ok(Command::Post { step: None, enabled: None }),
+            [step] => Ok(Command::Post { step: Some(step.to_string()), enabled: None }),
+            [step, "on"] => Ok(Command::Post { step: Some(step.to_string()), enabled: Some(true) }),
+            [step, "off"] => Ok(Command::Post { step: Some(step.to_string()), enabled: Some(false) }),
+            _ => Err("Usage: /post [code|copy|journal|note|filter] [on|off]".to_string()),
+        },
+        "append-to-note" => Ok(Command::AppendToNote),
+        "translate" if args.is_empty() => Ok(Command::Translate(None)),
+        "translate" => match args.split_once(':') {
+            Some((from, to)) if !from.trim().is_empty() && !to.trim().is_empty() => Ok(Command::Translate(Some(Translation {
+                from: Some(from.trim().to_string()),
+                to: to.trim().to_string(),
+            }))),
+            Some(_) => Err("Usage: /translate [from:]<to>, e.g. /translate french or /translate de:en".to_string()),
+            None => Ok(Command::Translate(Some(Translation { from: None, to: args.to_string() }))),
+        },
+        "prefix" => Ok(Command::Prefix(args.to_string())),
+        "suffix" => Ok(Command::Suffix(args.to_string())),
+        "markdown" => match args {
+            "" => Ok(Command::Markdown(None)),
+            "raw" | "off" => Ok(Command::Markdown(Some(true))),
+            "rendered" | "on" => Ok(Command::Markdown(Some(false))),
+            _ => Err("Usage: /markdown [raw|rendered]".to_string()),
+        },
+        "layout" if args.is_empty() => Ok(Command::Layout(None)),
+        "layout" => MessageLayout::from_name(args)
+            .map(|layout| Command::Layout(Some(layout)))
+            .ok_or_else(|| "Usage: /layout [labels|bubbles]".to_string()),
+        "num_predict" => match args {
+            "off" => Ok(Command::NumPredict(None)),
+            args => args
+                .parse()
+                .ok()
+                .filter(|tokens| *tokens > 0)
+                .map(|tokens| Command::NumPredict(Some(tokens)))
+                .ok_or_else(|| "Usage: /num_predict <tokens|off>".to_string()),
+        },
+        "reload" => Ok(Command::Reload),
+        "jobs" => Ok(Command::Jobs),
+        "split" => Ok(Command::Split),
+        "theme" if args.is_empty() => Ok(Command::Theme(None)),
+        "theme" => Theme::from_name(args)
+            .map(|theme| Command::Theme(Some(theme)))
+            .ok_or_else(|| format!("Usage: /theme [{}]", Theme::names())),
+        "tee" => Ok(Command::Tee(Some(args.to_string()).filter(|path| !path.is_empty()))),
+        "search" => {
+            if args.is_empty() {
+                Err("Usage: /search <text>".to_string())
+            } else {
+                Ok(Command::Search(args.to_string()))
+            }
+        }
+        "export" => {
+            let usage = "Usage: /export [--selected | --turns N[-M]] [path.md|path.json], copies markdown without a path";
+            let (turns, path) = match args.split_once(char::is_whitespace).unwrap_or((args, "")) {
+                ("--selected", path) => (Some(ExportTurns::Selected), path),
+                ("--turns", rest) => {
+                    let (range, path) = rest.trim().split_once(char::is_whitespace).unwrap_or((rest.trim(), ""));
+                    (parse_turn_range(range).map(|(first, last)| ExportTurns::Range(first, last)), path)
+                }
+                (flag, _) if flag.starts_with("--") => (None, ""),
+                _ => (Some(ExportTurns::All), args),
+            };
+            match turns {
+                Some(turns) => Ok(Command::Export {
+                    turns,
+                    path: Some(path.trim().to_string()).filter(|path| !path.is_empty()),
+                }),
+                None => Err(usage.to_string()),
+            }
+        }
+        "ask" => match args.split_once(char::is_whitespace) {
+            Some((model, prompt)) if !prompt.trim().is_empty() => Ok(Command::Ask {
+                model: model.to_string(),
+                prompt: prompt.trim().to_string(),
+            }),
+            _ => Err("Usage: /ask <model> <prompt>".to_string()),
+        },
+        "format" => match args.split_once(char::is_whitespace) {
+            Some((preset, prompt)) if !prompt.trim().is_empty() => FormatPreset::from_name(preset)
+                .map(|preset| Command::Format { preset, prompt: prompt.trim().to_string() })
+                .ok_or_else(|| format!("Unknown format '{}'. Usage: /format <{}> <prompt>", preset, FormatPreset::names())),
+            _ => Err(format!("Usage: /format <{}> <prompt>", FormatPreset::names())),
+        },
+        _ => Ok(Command::Plugin { name: name.to_string(), args: args.to_string() }),
+    };
+    Some(command)
+}
@@ -0,0 +1,387 @@
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde::Deserialize;
+use std::{env, fs, io, path::PathBuf, time::Duration};
+use tokio::sync::mpsc;
+
+use crate::{
+    bubbles::MessageLayout,
+    clipboard::CopyFormat,
+    context_trim::TrimStrategy,
+    endpoint,
+    keys::KeyBinding,
+    mcp::McpServerConfig,
+    metrics::MetricsFormat,
+    ndjson::StreamDecoding,
+    redact::{self, RedactRule},
+    status_line,
+    templates::Template,
+    theme::Theme,
+    validation::Validator,
+    AppError, AppEvent,
+};
+
+// --- User Configuration ---
+// Read from <config dir>/ollama-tui/config.toml; every field is optional.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Config {
+    // Address of the Ollama server, in any form OLLAMA_HOST takes: "host",
+    // "host:port" or a URL. OLLAMA_HOST and --host win over it; read at
+    // startup only.
+    pub host: Option<String>,
+    // How long the server keeps a model loaded after each reply: seconds
+    // ("-1" keeps it, "0" unloads it) or a duration like "10m". The server's
+    // default without it; OLLAMA_KEEP_ALIVE and --keep-alive win over it.
+    pub keep_alive: Option<String>,
+    // Unload the models prompted from here after this many minutes without
+    // a key press, and load the selected one again on the next; 0 never does
+    pub unload_after_idle_mins: u64,
+    // Send the whole conversation through /api/chat instead of single prompts
+    // to /api/generate. Always on when MCP tools are available.
+    pub chat_mode: bool,
+    // Context size (in tokens) the input box warns about; Ollama's default
+    // num_ctx. OLLAMA_CONTEXT_LENGTH, the server's setting, wins over it.
+    pub context_window: usize,
+    // What to do when a prompt and its history are estimated to be over the
+    // context window: "drop-oldest", "summarize-oldest" or "error"; see
+    // context_trim.rs
+    pub context_trim: TrimStrategy,
+    // Most tokens a reply may run to (the num_predict option); the server's
+    // default when left out. /num_predict changes it at runtime
+    pub num_predict: Option<i64>,
+    // Sent as the think field: false stops thinking models from reasoning
+    // first, true has them reason apart from the reply (the reasoning isn't
+    // shown). Not sent when left out; needs Ollama >= 0.9.0
+    pub think: Option<bool>,
+    // "strict" reports every malformed stream line; "lossy" tolerates mangled
+    // chunk boundaries and only reports repeated failures
+    pub stream_decoding: StreamDecoding,
+    // Show the markdown source of every turn instead of rendering it; 'm'
+    // picks another renderer for single turns and /markdown switches it at
+    // runtime. Turns with ANSI codes or ASCII art get a fitting one anyway
+    pub raw_markdown: bool,
+    // Columns the conversation text is wrapped at, e.g. 100; on wider
+    // terminals it is centered in the pane. 0 uses the whole pane
+    pub max_content_width: u16,
+    // "labels" puts every turn on the left under its sender's name;
+    // "bubbles" right-aligns your prompts on a tinted background. /layout
+    // switches it at runtime
+    pub message_layout: MessageLayout,
+    // Replies that are one code block of at least this many lines are shown
+    // folded to a summary line until Enter unfolds them; 0 never folds
+    pub fold_code_lines: usize,
+    // Decode HTML entities (&amp;, &lt;, &#39;) in replies that arrive
+    // escaped, outside code; a reply with a literal "<" is left alone
+    pub decode_entities: bool,
+    // Turn :shortcode: emoji (":rocket:", ":+1:") in replies into the emoji
+    // itself, outside code
+    pub emoji_shortcodes: bool,
+    // What 'y' and the copy post-processing step put on the clipboard:
+    // "markdown" source or "plain" text; 'Y' copies the other one
+    pub copy_format: CopyFormat,
+    // Upper bound on redraws per second; idle screens aren't redrawn at all
+    pub max_fps: u32,
+    // Start with the FPS/frame-time readout shown (F12 toggles it)
+    pub show_fps: bool,
+    // Layout of the status bar, e.g. "{mode} | {model} | {host} |
+    // {ctx_used}/{ctx_max} | {tps} tok/s"; see status_line.rs for the
+    // variables. Sections whose variables have no value are left out
+    pub status_format: String,
+    // Use the kitty keyboard protocol where the terminal supports it, for
+    // Ctrl+Enter to send
+    pub keyboard_protocol: bool,
+    // Sends the draft, e.g. "ctrl+d" (the default), "alt+enter" or "f5"
+    pub send_key: KeyBinding,
+    // Whether the terminal shows RGB colours; detected when left out, and
+    // false on the legacy Windows console
+    pub true_color: Option<bool>,
+    // "default", "deuteranopia", "protanopia", "high-contrast" or
+    // "monochrome"; /theme switches it at runtime
+    pub theme: Theme,
+    // Show GPU and CPU use while a response is generating; F11 toggles it
+    pub resource_overlay: bool,
+    // Times a /format reply that doesn't fit its preset is sent back
+    pub format_retries: u32,
+    // Offer recent conversations to resume on launch
+    pub session_picker: bool,
+    // Conversations deleted from the session picker stay in the trash this
+    // many days; 0 keeps them until removed by hand
+    pub trash_days: u32,
+    // Models not prompted for this many days are flagged for deletion in
+    // the models list; 0 turns the flag off
+    pub cleanup_after_days: u32,
+    // Pulls that download at the same time; more wait in the queue
+    pub max_parallel_pulls: usize,
+    // JSON model index for /browse, replacing the bundled one
+    pub library_url: Option<String>,
+    pub hooks: HooksConfig,
+    pub mcp_servers: Vec<McpServerConfig>,
+    // Starter conversations for /new, see templates.rs
+    pub templates: Vec<Template>,
+    pub routing: RoutingConfig,
+    pub postprocess: PostprocessConfig,
+    pub limits: LimitsConfig,
+    pub proxy: ProxyConfig,
+    pub redact: RedactConfig,
+    pub validation: ValidationConfig,
+    pub metrics: MetricsConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            host: None,
+            keep_alive: None,
+            unload_after_idle_mins: 0,
+            chat_mode: false,
+            context_window: 4096,
+            context_trim: TrimStrategy::default(),
+            num_predict: None,
+            think: None,
+            stream_decoding: StreamDecoding::default(),
+            raw_markdown: false,
+            max_content_width: 0,
+            message_layout: MessageLayout::default(),
+            fold_code_lines: 20,
+            decode_entities: false,
+            emoji_shortcodes: false,
+            copy_format: CopyFormat::default(),
+            max_fps: 30,
+            show_fps: false,
+            status_format: status_line::DEFAULT_FORMAT.to_string(),
+            keyboard_protocol: true,
+            send_key: KeyBinding::ctrl('d'),
+            true_color: None,
+            theme: Theme::default(),
+            resource_overlay: false,
+            format_retries: 2,
+            session_picker: true,
+            trash_days: 30,
+            cleanup_after_days: 30,
+            max_parallel_pulls: 2,
+            library_url: None,
+            hooks: HooksConfig::default(),
+            mcp_servers: Vec::new(),
+            templates: Vec::new(),
+            routing: RoutingConfig::default(),
+            limits: LimitsConfig::default(),
+            proxy: ProxyConfig::default(),
+            postprocess: PostprocessConfig::default(),
+            redact: RedactConfig::default(),
+            validation: ValidationConfig::default(),
+            metrics: MetricsConfig::default(),
+        }
+    }
+}
+
+// External commands that receive the prompt/response on stdin.
+// Non-empty stdout replaces the text, a non-zero exit vetoes it.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct HooksConfig {
+    pub pre_send: Option<String>,
+    pub post_receive: Option<String>,
+    pub timeout_secs: u64,
+}
+
+impl Default for HooksConfig {
+    fn default() -> Self {
+        HooksConfig {
+            pre_send: None,
+            post_receive: None,
+            timeout_secs: 10,
+        }
+    }
+}
+
+impl HooksConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+}
+
+// What happens to each finished response by default; every step can be
+// switched per conversation with /post
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PostprocessConfig {
+    // Save fenced code blocks as files in `code_dir`
+    pub extract_code: bool,
+    pub copy_to_clipboard: bool,
+    // Append prompt and response to a daily markdown file in `journal_dir`
+    pub journal: bool,
+    // Pipe the response through hooks.post_receive
+    pub filter: bool,
+    // Append prompt and response to `notes_file`, like /append-to-note
+    pub note: bool,
+    pub code_dir: Option<PathBuf>,
+    pub journal_dir: Option<PathBuf>,
+    // A markdown file of your own, e.g. "~/Vault/Daily/%Y-%m-%d.md" for an
+    // Obsidian daily note; strftime patterns are filled in with today's date
+    pub notes_file: Option<String>,
+}
+
+impl Default for PostprocessConfig {
+    fn default() -> Self {
+        PostprocessConfig {
+            extract_code: false,
+            copy_to_clipboard: false,
+            journal: false,
+            filter: true,
+            note: false,
+            code_dir: None,
+            journal_dir: None,
+            notes_file: None,
+        }
+    }
+}
+
+// Limits for a server shared with others; 0 means no limit
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct LimitsConfig {
+    // Generations running at once; more wait their turn
+    pub max_concurrent: usize,
+    // Gap between the starts of two requests
+    pub min_interval_ms: u64,
+}
+
+// Masking of secrets, see redact.rs; `rules` replaces the built-in ones
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct RedactConfig {
+    // Redact prompts before they are sent (and shown in the conversation)
+    pub prompts: bool,
+    // Redact /export files and copies
+    pub exports: bool,
+    pub rules: Vec<RedactRule>,
+}
+
+impl Default for RedactConfig {
+    fn default() -> Self {
+        RedactConfig {
+            prompts: false,
+            exports: false,
+            rules: redact::default_rules(),
+        }
+    }
+}
+
+// Checks on every finished reply, see validation.rs; command validators
+// share hooks.timeout_secs
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ValidationConfig {
+    // Times a failed reply is asked for again before it is kept as it is
+    pub retries: u32,
+    pub validators: Vec<Validator>,
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        ValidationConfig { retries: 2, validators: Vec::new() }
+    }
+}
+
+// Usage counters for graphing, see metrics.rs; nothing is written without
+// `path`
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct MetricsConfig {
+    // e.g. "/var/lib/node_exporter/textfile/ollama_tui.prom"
+    pub path: Option<PathBuf>,
+    // "prometheus" or "json"
+    pub format: MetricsFormat,
+    // Also write every this many seconds while running; 0 writes on quit only
+    pub interval_secs: u64,
+}
+
+// Proxy for all requests; without `url`, HTTP(S)_PROXY and ALL_PROXY apply
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct ProxyConfig {
+    // e.g. "http://proxy.corp:3128" or "socks5h://127.0.0.1:1080"
+    pub url: Option<String>,
+    // Hosts to reach directly, on top of NO_PROXY and the local server
+    pub no_proxy: Vec<String>,
+}
+
+// Where a prompt is retried, in order, when the chosen model errors out or
+// its server is unreachable or overloaded
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct RoutingConfig {
+    pub fallbacks: Vec<Route>,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Route {
+    pub model: String,
+    // Base URL of another Ollama server; the default one if left out
+    #[serde(default)]
+    pub server: Option<String>,
+}
+
+impl Route {
+    pub fn base_url(&self) -> &str {
+        self.server.as_deref().unwrap_or(endpoint::base())
+    }
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("ollama-tui"))
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("config.toml"))
+}
+
+pub fn plugins_dir() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("plugins"))
+}
+
+// A missing config file is not an error; it just means defaults
+pub fn load() -> Result<Config, AppError> {
+    let Some(path) = config_path() else {
+        return Ok(with_env(Config::default()));
+    };
+    match fs::read_to_string(&path) {
+        Ok(contents) => toml::from_str(&contents)
+            .map(with_env)
+            .map_err(|e| AppError::Config(format!("{}: {}", path.display(), e))),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(with_env(Config::default())),
+        Err(e) => Err(AppError::Io(e)),
+    }
+}
+
+// The server's own settings that matter here too; the host and keep-alive
+// are resolved in endpoint.rs
+fn with_env(mut config: Config) -> Config {
+    if let Some(length) = env::var("OLLAMA_CONTEXT_LENGTH").ok().and_then(|value| value.trim().parse().ok()) {
+        config.context_window = length;
+    }
+    config
+}
+
+// Sends ConfigFileChanged whenever config.toml is written. The directory is
+// watched so editors that save by replacing the file are picked up too; it
+// has to exist already.
+pub fn watch(event_sender: mpsc::Sender<AppEvent>) -> Result<notify::RecommendedWatcher, AppError> {
+    let (Some(dir), Some(path)) = (config_dir(), config_path()) else {
+        return Err(AppError::Watch("No config directory available".to_string()));
+    };
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let Ok(event) = res else { return };
+        let relevant = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+            && event.paths.iter().any(|p| p == &path);
+        if relevant {
+            // Runs on notify's own thread, so block rather than await
+            let _ = event_sender.blocking_send(AppEvent::ConfigFileChanged);
+        }
+    })
+    .map_err(|e| AppError::Watch(e.to_string()))?;
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| AppError::Watch(e.to_string()))?;
+    Ok(watcher)
+}
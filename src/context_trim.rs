@@ -0,0 +1,126 @@
+use reqwest::Client;
+use serde::Deserialize;
+
+use crate::{chat::ChatMessage, summarize::{self, Summary}, AppError};
+
+// --- Context Trimming ---
+// Before a prompt is sent its size is estimated, history included; when it
+// is over `context_window` the server would quietly drop the start of it.
+// `context_trim` decides what happens instead:
+//   "drop-oldest"       exclude the oldest turns until it fits (the default)
+//   "summarize-oldest"  the same, with a summary of them sent in their place
+//   "error"             don't send; the draft stays for excluding turns by hand
+// Trimmed turns are excluded like with 'x', so they show struck through and
+// 'x' takes one back. The system prompt and template examples are kept.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TrimStrategy {
+    #[default]
+    DropOldest,
+    SummarizeOldest,
+    Error,
+}
+
+impl TrimStrategy {
+    pub fn name(self) -> &'static str {
+        match self {
+            TrimStrategy::DropOldest => "drop-oldest",
+            TrimStrategy::SummarizeOldest => "summarize-oldest",
+            TrimStrategy::Error => "error",
+        }
+    }
+}
+
+pub struct Plan {
+    // Positions of the messages to trim, oldest first
+    pub trimmed: Vec<usize>,
+    pub trimmed_tokens: usize,
+    // The estimate before and after trimming
+    pub total: usize,
+    pub remaining: usize,
+}
+
+// Given each message's estimated tokens and whether it may be trimmed, the
+// oldest ones to leave out for the rest to fit in `budget`; None when it
+// fits already. Trims everything it may when even that isn't enough.
+pub fn plan(sizes: &[(usize, bool)], budget: usize) -> Option<Plan> {
+    let total: usize = sizes.iter().map(|(tokens, _)| tokens).sum();
+    if total <= budget {
+        return None;
+    }
+    let mut plan = Plan { trimmed: Vec::new(), trimmed_tokens: 0, total, remaining: total };
+    for (position, (tokens, trimmable)) in sizes.iter().enumerate() {
+        if plan.remaining <= budget {
+            break;
+        }
+        if *trimmable {
+            plan.trimmed.push(position);
+            plan.trimmed_tokens += tokens;
+            plan.remaining -= tokens;
+        }
+    }
+    Some(plan)
+}
+
+// What was done to make a prompt fit, for the status bar
+pub struct Trimmed {
+    pub note: String,
+    pub earlier: Option<EarlierTurns>,
+}
+
+// The trimmed turns, to be summarized on the way out under
+// "summarize-oldest"; the summary is attached to the last of them
+pub struct EarlierTurns {
+    pub turn_index: usize,
+    pub turn_text: String,
+    // "You: ...\n\nllama3: ..." for the model to summarize
+    pub transcript: String,
+}
+
+// Summarizes `earlier` with `model` and puts the summary where the trimmed
+// turns were: after the system prompt, before everything kept
+pub async fn summarize_into(
+    client: Client,
+    model: &str,
+    earlier: &EarlierTurns,
+    messages: &mut Vec<ChatMessage>,
+) -> Result<Summary, AppError> {
+    let summary = summarize::summarize(client, model, &earlier.transcript).await?;
+    let position = messages.iter().take_while(|message| message.role == "system").count();
+    let content = format!("Summary of the earlier conversation, left out to fit the context:\n\n{}", summary.text);
+    messages.insert(position, ChatMessage::new("system", content));
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nothing_is_trimmed_when_it_fits() {
+        assert!(plan(&[(100, true), (50, false)], 150).is_none());
+    }
+
+    #[test]
+    fn the_oldest_trimmable_messages_go_first() {
+        let plan = plan(&[(100, false), (300, true), (200, true), (100, true), (50, false)], 400).unwrap();
+        assert_eq!(plan.trimmed, [1, 2]);
+        assert_eq!(plan.trimmed_tokens, 500);
+        assert_eq!((plan.total, plan.remaining), (750, 250));
+    }
+
+    #[test]
+    fn messages_that_may_not_be_trimmed_are_kept() {
+        let plan = plan(&[(400, false), (100, true), (400, false)], 700).unwrap();
+        assert_eq!(plan.trimmed, [1]);
+        assert_eq!(plan.remaining, 800);
+    }
+
+    #[test]
+    fn everything_trimmable_goes_when_it_still_does_not_fit() {
+        let plan = plan(&[(100, false), (200, true), (300, true), (900, false)], 500).unwrap();
+        assert_eq!(plan.trimmed, [1, 2]);
+        assert_eq!(plan.trimmed_tokens, 500);
+        assert!(plan.remaining > 500);
+    }
+}
@@ -0,0 +1,321 @@
+use ratatui::{prelude::*, widgets::*};
+use std::cell::Cell;
+
+use crate::layout;
+
+// Oldest undo steps are dropped beyond this
+const MAX_UNDO_STEPS: usize = 200;
+
+// Consecutive edits of the same kind are undone together, like most editors
+#[derive(PartialEq, Clone, Copy)]
+enum EditKind {
+    Typing,
+    Deleting,
+    Other,
+}
+
+// --- Multi-line Input Editor ---
+// Text plus a cursor (a byte offset that always sits on a char boundary).
+pub struct InputEditor {
+    text: String,
+    cursor: usize,
+    // First visible row; kept between frames so the view doesn't jump around
+    scroll: Cell<usize>,
+    undo_stack: Vec<(String, usize)>,
+    redo_stack: Vec<(String, usize)>,
+    last_edit: Option<EditKind>,
+}
+
+impl InputEditor {
+    pub fn new() -> Self {
+        InputEditor {
+            text: String::new(),
+            cursor: 0,
+            scroll: Cell::new(0),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            last_edit: None,
+        }
+    }
+
+    // Saves the current state before an edit, unless it continues the previous one
+    fn checkpoint(&mut self, kind: EditKind) {
+        if kind != EditKind::Other && self.last_edit == Some(kind) {
+            return;
+        }
+        self.undo_stack.push((self.text.clone(), self.cursor));
+        if self.undo_stack.len() > MAX_UNDO_STEPS {
+            self.undo_stack.remove(0);
+        }
+        self.redo_stack.clear();
+        self.last_edit = Some(kind);
+    }
+
+    pub fn undo(&mut self) -> bool {
+        let Some((text, cursor)) = self.undo_stack.pop() else { return false };
+        let current = (std::mem::replace(&mut self.text, text), self.cursor);
+        self.redo_stack.push(current);
+        self.cursor = cursor;
+        self.last_edit = None;
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        let Some((text, cursor)) = self.redo_stack.pop() else { return false };
+        let current = (std::mem::replace(&mut self.text, text), self.cursor);
+        self.undo_stack.push(current);
+        self.cursor = cursor;
+        self.last_edit = None;
+        true
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    // Replaces the whole draft, leaving the cursor at the end
+    pub fn set_text(&mut self, text: String) {
+        self.checkpoint(EditKind::Other);
+        self.cursor = text.len();
+        self.text = text;
+    }
+
+    pub fn clear(&mut self) {
+        if self.text.is_empty() {
+            return;
+        }
+        self.checkpoint(EditKind::Other);
+        self.text.clear();
+        self.cursor = 0;
+        self.scroll.set(0);
+    }
+
+    pub fn cursor_at_line_start(&self) -> bool {
+        self.cursor == 0 || self.text[..self.cursor].ends_with('\n')
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        // A newline ends the current typing run
+        self.checkpoint(if c == '\n' { EditKind::Other } else { EditKind::Typing });
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    pub fn insert_str(&mut self, s: &str) {
+        self.checkpoint(EditKind::Other);
+        self.text.insert_str(self.cursor, s);
+        self.cursor += s.len();
+    }
+
+    pub fn backspace(&mut self) {
+        if let Some(c) = self.text[..self.cursor].chars().next_back() {
+            self.checkpoint(EditKind::Deleting);
+            self.cursor -= c.len_utf8();
+            self.text.remove(self.cursor);
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if self.cursor < self.text.len() {
+            self.checkpoint(EditKind::Deleting);
+            self.text.remove(self.cursor);
+        }
+    }
+
+    // Deletes from the start of the line up to the cursor (Ctrl+U)
+    pub fn delete_to_line_start(&mut self) {
+        let start = self.line_start(self.cursor);
+        if start < self.cursor {
+            self.checkpoint(EditKind::Other);
+            self.text.replace_range(start..self.cursor, "");
+            self.cursor = start;
+        }
+    }
+
+    pub fn move_left(&mut self) {
+        self.last_edit = None;
+        if let Some(c) = self.text[..self.cursor].chars().next_back() {
+            self.cursor -= c.len_utf8();
+        }
+    }
+
+    pub fn move_right(&mut self) {
+        self.last_edit = None;
+        if let Some(c) = self.text[self.cursor..].chars().next() {
+            self.cursor += c.len_utf8();
+        }
+    }
+
+    fn line_start(&self, pos: usize) -> usize {
+        self.text[..pos].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    fn line_end(&self, pos: usize) -> usize {
+        self.text[pos..].find('\n').map_or(self.text.len(), |i| pos + i)
+    }
+
+    pub fn move_home(&mut self) {
+        self.last_edit = None;
+        self.cursor = self.line_start(self.cursor);
+    }
+
+    pub fn move_end(&mut self) {
+        self.last_edit = None;
+        self.cursor = self.line_end(self.cursor);
+    }
+
+    // Byte offset of the char at `column` on the line starting at `start`, clamped to its end
+    fn offset_in_line(&self, start: usize, column: usize) -> usize {
+        let end = self.line_end(start);
+        self.text[start..end]
+            .char_indices()
+            .nth(column)
+            .map_or(end, |(i, _)| start + i)
+    }
+
+    pub fn move_up(&mut self) {
+        self.last_edit = None;
+        let start = self.line_start(self.cursor);
+        if start == 0 {
+            return;
+        }
+        let column = self.text[start..self.cursor].chars().count();
+        let previous_start = self.line_start(start - 1);
+        self.cursor = self.offset_in_line(previous_start, column);
+    }
+
+    pub fn move_down(&mut self) {
+        self.last_edit = None;
+        let end = self.line_end(self.cursor);
+        if end == self.text.len() {
+            return;
+        }
+        let column = self.text[self.line_start(self.cursor)..self.cursor].chars().count();
+        self.cursor = self.offset_in_line(end + 1, column);
+    }
+
+    // Draws the draft with a line-number gutter and '↪' on soft-wrapped rows.
+    // Returns the screen position of the cursor.
+    pub fn render(&self, f: &mut Frame, area: Rect, block: Block) -> Option<(u16, u16)> {
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+        if inner.width == 0 || inner.height == 0 {
+            return None;
+        }
+
+        let line_count = self.text.split('\n').count();
+        let gutter_width = line_count.to_string().len() + 1;
+        let text_width = (inner.width as usize).saturating_sub(gutter_width).max(1);
+        let gutter_style = Style::default().fg(Color::DarkGray);
+
+        // Lay out every visual row, noting where the cursor lands
+        let mut rows: Vec<Line> = Vec::new();
+        let mut cursor_row_col = (0, 0);
+        let mut line_start = 0;
+        for (number, line) in self.text.split('\n').enumerate() {
+            let chars: Vec<char> = line.chars().collect();
+            // Char ranges of the rows, each at most text_width cells wide;
+            // a wide character that doesn't fit starts the next row
+            let mut line_rows = Vec::new();
+            let (mut start, mut used) = (0, 0);
+            for (index, c) in chars.iter().enumerate() {
+                let width = layout::char_width(*c);
+                if used + width > text_width && index > start {
+                    line_rows.push((start, index));
+                    (start, used) = (index, 0);
+                }
+                used += width;
+            }
+            line_rows.push((start, chars.len()));
+
+            let line_end = line_start + line.len();
+            if (line_start..=line_end).contains(&self.cursor) {
+                let column = self.text[line_start..self.cursor].chars().count();
+                let mut row_in_line = line_rows.iter().rposition(|(start, _)| *start <= column).unwrap_or(0);
+                let (row_start, _) = line_rows[row_in_line];
+                let mut x = chars[row_start..column].iter().copied().map(layout::char_width).sum::<usize>();
+                // A cursor just past a full row needs a row of its own
+                if x >= text_width {
+                    line_rows.push((chars.len(), chars.len()));
+                    row_in_line += 1;
+                    x = 0;
+                }
+                cursor_row_col = (rows.len() + row_in_line, x);
+            }
+
+            for (row, (start, end)) in line_rows.into_iter().enumerate() {
+                let gutter = if row == 0 {
+                    format!("{:>width$} ", number + 1, width = gutter_width - 1)
+                } else {
+                    format!("{:>width$} ", "↪", width = gutter_width - 1)
+                };
+                let content: String = chars[start..end].iter().collect();
+                rows.push(Line::from(vec![Span::styled(gutter, gutter_style), Span::raw(content)]));
+            }
+            line_start = line_end + 1;
+        }
+
+        // Scroll just enough to keep the cursor row visible
+        let height = inner.height as usize;
+        let mut scroll = self.scroll.get().min(rows.len().saturating_sub(1));
+        if cursor_row_col.0 < scroll {
+            scroll = cursor_row_col.0;
+        } else if cursor_row_col.0 >= scroll + height {
+            scroll = cursor_row_col.0 + 1 - height;
+        }
+        self.scroll.set(scroll);
+
+        let visible: Vec<Line> = rows.into_iter().skip(scroll).take(height).collect();
+        f.render_widget(Paragraph::new(visible), inner);
+
+        let x = inner.x + (gutter_width + cursor_row_col.1) as u16;
+        let y = inner.y + (cursor_row_col.0 - scroll) as u16;
+        Some((x.min(inner.right().saturating_sub(1)), y))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+
+    // The rows drawn for `text` in a box `width` wide (gutter included), and
+    // where the cursor went
+    fn draw(text: &str, width: u16) -> (Vec<String>, (u16, u16)) {
+        let mut editor = InputEditor::new();
+        editor.insert_str(text);
+        let mut terminal = Terminal::new(TestBackend::new(width, 4)).unwrap();
+        let mut cursor = None;
+        terminal.draw(|f| cursor = editor.render(f, f.size(), Block::default())).unwrap();
+        let buffer = terminal.backend().buffer();
+        let row = |y| {
+            let mut row = String::new();
+            let mut x = 0;
+            // A wide character covers the cell after it too
+            while x < width {
+                let symbol = buffer.get(x, y).symbol();
+                row.push_str(symbol);
+                x += symbol.chars().map(layout::char_width).sum::<usize>().max(1) as u16;
+            }
+            row.trim_end().to_string()
+        };
+        let rows = (0..4).map(row).collect();
+        (rows, cursor.unwrap())
+    }
+
+    #[test]
+    fn wide_characters_wrap_by_width() {
+        // Two columns of gutter leave four for text: two CJK characters a row
+        let (rows, cursor) = draw("日本語です", 6);
+        assert_eq!(rows[..3], ["1 日本", "↪ 語で", "↪ す"]);
+        assert_eq!(cursor, (4, 2));
+    }
+
+    #[test]
+    fn a_cursor_after_a_full_row_starts_the_next() {
+        let (rows, cursor) = draw("abcd", 6);
+        assert_eq!(rows[..2], ["1 abcd", "↪"]);
+        assert_eq!(cursor, (2, 1));
+    }
+}
@@ -0,0 +1,159 @@
+use ratatui::{prelude::*, widgets::*};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+use crate::{endpoint, trace, AppError};
+
+// --- Embedding Playground ('/embed') ---
+// Texts are embedded with the selected model; two at once are compared by
+// cosine similarity, and each is matched against the snippets embedded
+// earlier in the session with the same model.
+const NEIGHBORS: usize = 3;
+
+#[derive(Deserialize)]
+struct EmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+pub async fn embed(client: Client, model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>, AppError> {
+    let (trace, response) = trace::send(
+        client
+            .post(format!("{}/api/embed", endpoint::base()))
+            .json(&endpoint::with_keep_alive(json!({ "model": model, "input": texts })))
+            .timeout(Duration::from_secs(120)),
+    )
+    .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(trace.tag(AppError::ApiResponse(format!("Status {} - {}", status, body))));
+    }
+    let embedded: EmbedResponse = response.json().await.map_err(|e| trace.tag(e.into()))?;
+    if embedded.embeddings.len() != texts.len() {
+        return Err(trace.tag(AppError::ApiResponse(format!(
+            "Expected {} embeddings, got {}",
+            texts.len(),
+            embedded.embeddings.len()
+        ))));
+    }
+    Ok(embedded.embeddings)
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 { 0.0 } else { dot / norms }
+}
+
+struct Snippet {
+    model: String,
+    text: String,
+    vector: Vec<f32>,
+}
+
+// The latest /embed: its texts, their similarity when there are two, and
+// each text's closest earlier snippets
+struct Comparison {
+    model: String,
+    dimensions: usize,
+    texts: Vec<String>,
+    similarity: Option<f32>,
+    neighbors: Vec<Vec<(String, f32)>>,
+}
+
+#[derive(Default)]
+pub struct Playground {
+    snippets: Vec<Snippet>,
+    last: Option<Comparison>,
+}
+
+impl Playground {
+    pub fn add(&mut self, model: String, texts: Vec<String>, vectors: Vec<Vec<f32>>) {
+        let similarity = match vectors.as_slice() {
+            [a, b] => Some(cosine(a, b)),
+            _ => None,
+        };
+        let neighbors = vectors
+            .iter()
+            .map(|vector| {
+                let mut scored: Vec<(String, f32)> = self
+                    .snippets
+                    .iter()
+                    .filter(|snippet| snippet.model == model && snippet.vector.len() == vector.len())
+                    .map(|snippet| (snippet.text.clone(), cosine(&snippet.vector, vector)))
+                    .collect();
+                scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+                scored.truncate(NEIGHBORS);
+                scored
+            })
+            .collect();
+        let dimensions = vectors.first().map_or(0, Vec::len);
+        for (text, vector) in texts.iter().zip(vectors) {
+            self.snippets.push(Snippet { model: model.clone(), text: text.clone(), vector });
+        }
+        self.last = Some(Comparison { model, dimensions, texts, similarity, neighbors });
+    }
+}
+
+// One line of text, shortened for the playground's lists
+fn preview(text: &str) -> String {
+    let line = text.lines().next().unwrap_or("");
+    if line.chars().count() > 60 || text.lines().nth(1).is_some() {
+        format!("{}...", line.chars().take(60).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+fn score_style(score: f32) -> Style {
+    let color = if score >= 0.8 {
+        Color::Green
+    } else if score >= 0.5 {
+        Color::Yellow
+    } else {
+        Color::Red
+    };
+    Style::default().fg(color)
+}
+
+pub fn render(f: &mut Frame, area: Rect, playground: &Playground) {
+    let title = format!(" Embeddings, {} snippets embedded (Tab: Next view) ", playground.snippets.len());
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let Some(last) = &playground.last else {
+        let help = "Nothing embedded yet. Select an embedding model, then:\n\n\
+                    /embed <text>               embed a snippet and list its nearest earlier snippets\n\
+                    /embed <text A> || <text B>  also show the cosine similarity of A and B";
+        f.render_widget(Paragraph::new(help).block(block), area);
+        return;
+    };
+
+    let mut lines = vec![Line::styled(
+        format!("{} ({} dimensions)", last.model, last.dimensions),
+        Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+    )];
+    if let Some(similarity) = last.similarity {
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::raw("Cosine similarity of A and B: "),
+            Span::styled(format!("{:.4}", similarity), score_style(similarity).add_modifier(Modifier::BOLD)),
+        ]));
+    }
+    for (index, (text, neighbors)) in last.texts.iter().zip(&last.neighbors).enumerate() {
+        lines.push(Line::from(""));
+        let label = if last.texts.len() > 1 { ["A", "B"][index.min(1)] } else { "Text" };
+        lines.push(Line::styled(format!("{}: {}", label, preview(text)), Style::default().add_modifier(Modifier::BOLD)));
+        if neighbors.is_empty() {
+            lines.push(Line::styled("  No earlier snippets from this model.", Style::default().fg(Color::DarkGray)));
+        }
+        for (neighbor, score) in neighbors {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {:.4}  ", score), score_style(*score)),
+                Span::raw(preview(neighbor)),
+            ]));
+        }
+    }
+    f.render_widget(Paragraph::new(lines).block(block).wrap(Wrap { trim: false }), area);
+}
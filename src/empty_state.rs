@@ -0,0 +1,49 @@
+use ratatui::{prelude::*, widgets::*};
+
+use crate::endpoint;
+
+// --- No Models Installed ---
+// Shown in place of the conversation when the server answered but has no
+// models, with a few small starters that 1-3 pull straight away.
+pub const STARTERS: [(&str, &str); 3] = [
+    ("llama3.2:3b", "about 2 GB, general chat with tool calling"),
+    ("qwen2.5-coder:1.5b", "about 1 GB, code questions"),
+    ("gemma3:1b", "under 1 GB, the quickest to try"),
+];
+
+// The starter for digit key `c`, if any
+pub fn starter_for_key(c: char) -> Option<&'static str> {
+    let index = c.to_digit(10)?.checked_sub(1)? as usize;
+    STARTERS.get(index).map(|(model, _)| *model)
+}
+
+pub fn render(f: &mut Frame, area: Rect) {
+    let key_style = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+    let mut lines = vec![
+        Line::styled("No models installed", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+        Line::from(""),
+        Line::from(format!("The Ollama server at {} is running but has no models yet.", endpoint::base())),
+        Line::from("Pull one to start chatting:"),
+        Line::from(""),
+    ];
+    for (index, (model, blurb)) in STARTERS.iter().enumerate() {
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {}  ", index + 1), key_style),
+            Span::styled(format!("{:<20}", model), Style::default().add_modifier(Modifier::BOLD)),
+            Span::styled(*blurb, Style::default().fg(Color::DarkGray)),
+        ]));
+    }
+    lines.extend([
+        Line::from(""),
+        Line::from(vec![Span::styled("  b  ", key_style), Span::raw("browse the model library")]),
+        Line::from(vec![Span::styled("  R  ", key_style), Span::raw("check the server again")]),
+        Line::from(""),
+        Line::styled(
+            "Expected models here? Only the server above is checked. If they live on another machine, forward \
+             it to this address (e.g. `ssh -L 11434:localhost:11434 <host>`) and press R.",
+            Style::default().fg(Color::DarkGray),
+        ),
+    ]);
+    let block = Block::default().borders(Borders::ALL).title(" Getting Started ");
+    f.render_widget(Paragraph::new(lines).block(block).wrap(Wrap { trim: false }), area);
+}
@@ -0,0 +1,172 @@
+use serde_json::Value;
+use std::{env, sync::OnceLock};
+
+use crate::config::Config;
+
+// --- Server Address ---
+// Where the Ollama server is and how long it keeps models loaded, resolved
+// once at startup the way the official ollama CLI does, so both tools talk to
+// the same server without extra setup. The first of these wins:
+//   --host / --keep-alive on the command line
+//   OLLAMA_HOST / OLLAMA_KEEP_ALIVE
+//   `host` / `keep_alive` in the config file
+//   http://127.0.0.1:11434 and the server's own keep-alive
+pub const DEFAULT_BASE_URL: &str = "http://127.0.0.1:11434";
+const DEFAULT_PORT: u16 = 11434;
+
+struct Endpoint {
+    base_url: String,
+    // Where `base_url` came from, for the Health tab
+    source: &'static str,
+    keep_alive: Option<Value>,
+}
+
+static ENDPOINT: OnceLock<Endpoint> = OnceLock::new();
+
+// The --host and --keep-alive values, taken out of `args` so the rest of
+// the command line reads as before
+#[derive(Default)]
+pub struct Flags {
+    host: Option<String>,
+    keep_alive: Option<String>,
+}
+
+pub fn take_flags(args: &mut Vec<String>) -> Result<Flags, String> {
+    let mut flags = Flags::default();
+    let mut index = 1;
+    while index < args.len() {
+        let slot = match args[index].as_str() {
+            "--host" => &mut flags.host,
+            "--keep-alive" => &mut flags.keep_alive,
+            _ => {
+                index += 1;
+                continue;
+            }
+        };
+        let flag = args.remove(index);
+        if index >= args.len() {
+            return Err(format!("Usage: ollama-tui {} <value>", flag));
+        }
+        *slot = Some(args.remove(index));
+    }
+    Ok(flags)
+}
+
+// The value and the variable's name as its source
+fn env_var(name: &'static str) -> Option<(String, &'static str)> {
+    env::var(name).ok().filter(|value| !value.trim().is_empty()).map(|value| (value, name))
+}
+
+pub fn init(flags: &Flags, config: &Config) -> Result<(), String> {
+    let host = flags
+        .host
+        .clone()
+        .map(|host| (host, "--host"))
+        .or_else(|| env_var("OLLAMA_HOST"))
+        .or_else(|| config.host.clone().map(|host| (host, "config")));
+    let (base_url, source) = match host {
+        Some((host, source)) => (base_url(&host).map_err(|e| format!("{} {:?}: {}", source, host, e))?, source),
+        None => (DEFAULT_BASE_URL.to_string(), "default"),
+    };
+    let keep_alive = flags
+        .keep_alive
+        .clone()
+        .map(|value| (value, "--keep-alive"))
+        .or_else(|| env_var("OLLAMA_KEEP_ALIVE"))
+        .or_else(|| config.keep_alive.clone().map(|value| (value, "config")));
+    let keep_alive = match keep_alive {
+        Some((value, source)) => Some(parse_keep_alive(&value).map_err(|e| format!("{} {:?}: {}", source, value, e))?),
+        None => None,
+    };
+    let _ = ENDPOINT.set(Endpoint { base_url, source, keep_alive });
+    Ok(())
+}
+
+pub fn base() -> &'static str {
+    ENDPOINT.get().map_or(DEFAULT_BASE_URL, |endpoint| endpoint.base_url.as_str())
+}
+
+pub fn source() -> &'static str {
+    ENDPOINT.get().map_or("default", |endpoint| endpoint.source)
+}
+
+// The host name alone, for keeping the server out of the proxy
+pub fn host_name() -> &'static str {
+    let rest = base().split_once("://").map_or(base(), |(_, rest)| rest);
+    let host_port = rest.split('/').next().unwrap_or(rest);
+    match host_port.strip_prefix('[') {
+        Some(bracketed) => bracketed.split(']').next().unwrap_or(bracketed),
+        None => host_port.rsplit_once(':').map_or(host_port, |(host, _)| host),
+    }
+}
+
+// Sent with every generation; None leaves it to the server
+pub fn keep_alive() -> Option<Value> {
+    ENDPOINT.get().and_then(|endpoint| endpoint.keep_alive.clone())
+}
+
+// `body` of a generation request, with the keep-alive when one is set
+pub fn with_keep_alive(mut body: Value) -> Value {
+    if let (Some(keep_alive), Some(fields)) = (keep_alive(), body.as_object_mut()) {
+        fields.insert("keep_alive".to_string(), keep_alive);
+    }
+    body
+}
+
+// OLLAMA_HOST's forms: "host", "host:port", ":port", "[::1]:port" or a
+// URL, optionally with a path for a reverse proxy. Plain http and port 11434
+// are assumed, or 443 for https.
+fn base_url(host: &str) -> Result<String, String> {
+    let host = host.trim();
+    let (scheme, rest) = match host.split_once("://") {
+        Some((scheme @ ("http" | "https"), rest)) => (scheme, rest),
+        Some((scheme, _)) => return Err(format!("unsupported scheme {}", scheme)),
+        None => ("http", host),
+    };
+    let (host_port, path) = match rest.find('/') {
+        Some(slash) => (&rest[..slash], rest[slash..].trim_end_matches('/')),
+        None => (rest, ""),
+    };
+    let (name, port) = if let Some(bracketed) = host_port.strip_prefix('[') {
+        let (name, after) = bracketed.split_once(']').ok_or("unclosed [ in the address")?;
+        (format!("[{}]", name), after.strip_prefix(':'))
+    } else if host_port.matches(':').count() > 1 {
+        // A bare IPv6 address
+        (format!("[{}]", host_port), None)
+    } else {
+        match host_port.split_once(':') {
+            Some((name, port)) => (name.to_string(), Some(port)),
+            None => (host_port.to_string(), None),
+        }
+    };
+    let name = if name.is_empty() { "127.0.0.1".to_string() } else { name };
+    let port = match port {
+        Some(port) => port.parse::<u16>().map_err(|_| format!("invalid port {:?}", port))?,
+        None if scheme == "https" => 443,
+        None => DEFAULT_PORT,
+    };
+    Ok(format!("{}://{}:{}{}", scheme, name, port, path))
+}
+
+// Seconds as a number ("300", "-1" for forever, "0" to unload right away)
+// or a duration like "10m" or "1h30m", as the API takes them
+fn parse_keep_alive(value: &str) -> Result<Value, String> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<i64>() {
+        return Ok(Value::from(seconds));
+    }
+    let mut rest = value.strip_prefix('-').unwrap_or(value);
+    if rest.is_empty() {
+        return Err("expected seconds or a duration like 10m".to_string());
+    }
+    while !rest.is_empty() {
+        let number = rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rest.len());
+        let unit_end = rest[number..].find(|c: char| c.is_ascii_digit() || c == '.').map_or(rest.len(), |end| number + end);
+        let unit = &rest[number..unit_end];
+        if number == 0 || !["ns", "us", "µs", "ms", "s", "m", "h"].contains(&unit) {
+            return Err("expected seconds or a duration like 10m".to_string());
+        }
+        rest = &rest[unit_end..];
+    }
+    Ok(Value::from(value))
+}
@@ -0,0 +1,233 @@
+// --- Escaped Replies ---
+// Some models answer with HTML-escaped text ("&lt;div&gt;", "Tom &amp;
+// Jerry"), which shows literally in code blocks and the plain renderer, and
+// some write GitHub-style ":rocket:" shortcodes instead of emoji. Finished
+// replies are decoded before they are shown and sent back as history; code
+// spans and fenced blocks are left as written, since "&lt;" or ":x:" there is
+// usually meant literally.
+
+const NAMED: [(&str, &str); 24] = [
+    ("amp", "&"),
+    ("lt", "<"),
+    ("gt", ">"),
+    ("quot", "\""),
+    ("apos", "'"),
+    ("nbsp", "\u{a0}"),
+    ("hellip", "…"),
+    ("mdash", "—"),
+    ("ndash", "–"),
+    ("lsquo", "‘"),
+    ("rsquo", "’"),
+    ("ldquo", "“"),
+    ("rdquo", "”"),
+    ("laquo", "«"),
+    ("raquo", "»"),
+    ("bull", "•"),
+    ("middot", "·"),
+    ("times", "×"),
+    ("divide", "÷"),
+    ("deg", "°"),
+    ("copy", "©"),
+    ("reg", "®"),
+    ("trade", "™"),
+    ("euro", "€"),
+];
+
+const SHORTCODES: [(&str, &str); 40] = [
+    ("smile", "😄"),
+    ("grin", "😁"),
+    ("joy", "😂"),
+    ("wink", "😉"),
+    ("blush", "😊"),
+    ("thinking", "🤔"),
+    ("sweat_smile", "😅"),
+    ("cry", "😢"),
+    ("heart", "❤️"),
+    ("thumbsup", "👍"),
+    ("+1", "👍"),
+    ("thumbsdown", "👎"),
+    ("-1", "👎"),
+    ("clap", "👏"),
+    ("wave", "👋"),
+    ("pray", "🙏"),
+    ("muscle", "💪"),
+    ("eyes", "👀"),
+    ("point_right", "👉"),
+    ("rocket", "🚀"),
+    ("fire", "🔥"),
+    ("sparkles", "✨"),
+    ("star", "⭐"),
+    ("tada", "🎉"),
+    ("bulb", "💡"),
+    ("memo", "📝"),
+    ("books", "📚"),
+    ("wrench", "🔧"),
+    ("hammer", "🔨"),
+    ("gear", "⚙️"),
+    ("lock", "🔒"),
+    ("key", "🔑"),
+    ("bug", "🐛"),
+    ("warning", "⚠️"),
+    ("x", "❌"),
+    ("white_check_mark", "✅"),
+    ("heavy_check_mark", "✔️"),
+    ("question", "❓"),
+    ("exclamation", "❗"),
+    ("zap", "⚡"),
+];
+
+// The decoded reply, or None when there was nothing to decode
+pub fn decode(text: &str, entities: bool, shortcodes: bool) -> Option<String> {
+    let mut decoded = None;
+    // A reply with a literal "<" was not escaped, and its entities are
+    // probably meant, e.g. an explanation of "&amp;"
+    if entities && looks_escaped(text) {
+        decoded = Some(map_prose(text, decode_entities));
+    }
+    if shortcodes {
+        let current = decoded.as_deref().unwrap_or(text);
+        if current.contains(':') {
+            let replaced = map_prose(current, replace_in_prose);
+            if replaced != current {
+                decoded = Some(replaced);
+            }
+        }
+    }
+    decoded.filter(|decoded| decoded != text)
+}
+
+fn looks_escaped(text: &str) -> bool {
+    !text.contains('<') && text.contains('&') && text.contains(';')
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        // Entities are short; anything longer isn't one
+        let entity = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| entity_value(&rest[1..end + 1]).map(|value| (end, value)));
+        match entity {
+            Some((end, value)) => {
+                out.push_str(&value);
+                rest = &rest[end + 2..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn entity_value(name: &str) -> Option<String> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code).filter(|c| *c != '\0').map(String::from);
+    }
+    NAMED.iter().find(|(entity, _)| *entity == name).map(|(_, value)| value.to_string())
+}
+
+// `replace` applied to prose only; code keeps its text (e.g. "std::io",
+// "&amp;")
+fn map_prose(text: &str, replace: impl Fn(&str) -> String) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_fence = false;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            out.push_str(line);
+            continue;
+        }
+        if in_fence {
+            out.push_str(line);
+            continue;
+        }
+        // Odd pieces between backticks are inline code
+        for (index, piece) in line.split('`').enumerate() {
+            if index > 0 {
+                out.push('`');
+            }
+            if index % 2 == 1 {
+                out.push_str(piece);
+            } else {
+                out.push_str(&replace(piece));
+            }
+        }
+    }
+    out
+}
+
+// A shortcode stands apart from the words around it, so "1:x:2" stays text
+fn replace_in_prose(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(':') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let apart = |c: Option<char>| !c.is_some_and(char::is_alphanumeric);
+        let code = after
+            .find(':')
+            .filter(|_| apart(out.chars().next_back()))
+            .filter(|&end| apart(after[end + 1..].chars().next()))
+            .map(|end| &after[..end])
+            .and_then(|name| SHORTCODES.iter().find(|(code, _)| *code == name));
+        match code {
+            Some((name, emoji)) => {
+                out.push_str(emoji);
+                rest = &after[name.len() + 1..];
+            }
+            None => {
+                out.push(':');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escaped_replies_are_decoded() {
+        assert_eq!(decode("Tom &amp; Jerry &quot;hi&quot;", true, false).as_deref(), Some("Tom & Jerry \"hi\""));
+        assert_eq!(decode("&lt;div&gt; &#169; &#x1F600;", true, false).as_deref(), Some("<div> © 😀"));
+        // Unknown or overlong names are not entities
+        assert_eq!(decode("a &bogus; b &notreallyanentity; c", true, false), None);
+        assert_eq!(decode("Tom &amp; Jerry", false, false), None);
+    }
+
+    #[test]
+    fn a_literal_angle_bracket_means_the_reply_was_not_escaped() {
+        assert_eq!(decode("Write &amp; as <code>&amp;amp;</code>", true, false), None);
+    }
+
+    #[test]
+    fn code_keeps_its_text() {
+        assert_eq!(decode("Use `&amp;` for &amp;", true, false).as_deref(), Some("Use `&amp;` for &"));
+        let fenced = "```html\n&lt;br&gt;\n```\nDone &amp; dusted :tada:";
+        assert_eq!(decode(fenced, true, true).as_deref(), Some("```html\n&lt;br&gt;\n```\nDone & dusted 🎉"));
+        assert_eq!(decode("Call `std::io::stdin` :x:", false, true).as_deref(), Some("Call `std::io::stdin` ❌"));
+    }
+
+    #[test]
+    fn shortcodes_must_stand_apart() {
+        assert_eq!(decode("Ship it :rocket:!", false, true).as_deref(), Some("Ship it 🚀!"));
+        assert_eq!(decode("(:x:) done", false, true).as_deref(), Some("(❌) done"));
+        assert_eq!(decode("ratio 1:x:2", false, true), None);
+        assert_eq!(decode("the a:x: axis", false, true), None);
+    }
+}
@@ -0,0 +1,143 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+// --- File Picker Popup (Ctrl+O while editing) ---
+pub struct FilePicker {
+    dir: PathBuf,
+    entries: Vec<PickerEntry>,
+    selected: usize,
+    error: Option<String>,
+}
+
+struct PickerEntry {
+    name: String,
+    path: PathBuf,
+    is_dir: bool,
+}
+
+pub enum PickerAction {
+    None,
+    Close,
+    Picked(PathBuf),
+}
+
+impl FilePicker {
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        let mut picker = FilePicker {
+            dir: dir.canonicalize()?,
+            entries: Vec::new(),
+            selected: 0,
+            error: None,
+        };
+        picker.load()?;
+        Ok(picker)
+    }
+
+    // Reads the current directory: parent link first, then folders, then files
+    fn load(&mut self) -> io::Result<()> {
+        let mut entries: Vec<PickerEntry> = fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| {
+                let path = entry.path();
+                PickerEntry {
+                    name: entry.file_name().to_string_lossy().into_owned(),
+                    is_dir: path.is_dir(),
+                    path,
+                }
+            })
+            .collect();
+        entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+
+        if let Some(parent) = self.dir.parent() {
+            entries.insert(0, PickerEntry {
+                name: "..".to_string(),
+                path: parent.to_path_buf(),
+                is_dir: true,
+            });
+        }
+
+        self.entries = entries;
+        self.selected = 0;
+        Ok(())
+    }
+
+    fn change_dir(&mut self, dir: PathBuf) {
+        let previous = std::mem::replace(&mut self.dir, dir);
+        match self.load() {
+            Ok(()) => self.error = None,
+            Err(e) => {
+                // Stay where we were if the directory can't be read
+                self.error = Some(format!("Cannot open {}: {}", self.dir.display(), e));
+                self.dir = previous;
+            }
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> PickerAction {
+        match key.code {
+            KeyCode::Esc => return PickerAction::Close,
+            KeyCode::Char('j') | KeyCode::Down if !self.entries.is_empty() => {
+                self.selected = (self.selected + 1) % self.entries.len();
+            }
+            KeyCode::Char('k') | KeyCode::Up if !self.entries.is_empty() => {
+                self.selected = self.selected.checked_sub(1).unwrap_or(self.entries.len() - 1);
+            }
+            KeyCode::Backspace | KeyCode::Left | KeyCode::Char('h') => {
+                if let Some(parent) = self.dir.parent() {
+                    self.change_dir(parent.to_path_buf());
+                }
+            }
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => {
+                if let Some(entry) = self.entries.get(self.selected) {
+                    if entry.is_dir {
+                        let dir = entry.path.clone();
+                        self.change_dir(dir);
+                    } else {
+                        return PickerAction::Picked(entry.path.clone());
+                    }
+                }
+            }
+            _ => {}
+        }
+        PickerAction::None
+    }
+
+    pub fn render(&self, f: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                if entry.is_dir {
+                    ListItem::new(format!("{}/", entry.name)).style(Style::default().fg(Color::Blue))
+                } else {
+                    ListItem::new(entry.name.as_str())
+                }
+            })
+            .collect();
+
+        let title = format!(" Attach file: {} ", self.dir.display());
+        let footer = match &self.error {
+            Some(err) => Line::styled(format!(" {} ", err), Style::default().fg(Color::Red)),
+            None => Line::from(" Enter: open/attach, Backspace: up, Esc: cancel "),
+        };
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Yellow))
+                    .title(title)
+                    .title_bottom(footer),
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD).bg(Color::Blue))
+            .highlight_symbol("> ");
+
+        let mut state = ListState::default();
+        state.select(Some(self.selected));
+
+        f.render_stateful_widget(list, area, &mut state);
+    }
+}
@@ -0,0 +1,55 @@
+use ratatui::{prelude::*, text::Span};
+use serde::{Deserialize, Serialize};
+
+// --- How Replies End ---
+// Taken from the final chunk's done_reason, or from the error that stopped
+// the stream part way, and kept on the turn so a reply that ran out of
+// tokens or never got going doesn't look like one that was finished.
+// Interrupted connections keep their own marker.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Finish {
+    // The model finished on its own
+    Stop,
+    // num_predict or the context ran out; 'C' continues it
+    Length,
+    // The server only loaded the model and generated nothing
+    Load,
+    // The server reported an error after part of the reply arrived
+    Error(String),
+    // A done_reason this version doesn't know
+    Other(String),
+}
+
+impl Finish {
+    pub fn from_reason(reason: &str) -> Finish {
+        match reason {
+            "stop" => Finish::Stop,
+            "length" => Finish::Length,
+            "load" => Finish::Load,
+            reason => Finish::Other(reason.to_string()),
+        }
+    }
+
+    // Shown after the turn's header; `tokens` is the reply's eval count
+    pub fn marker(&self, tokens: Option<u64>) -> Span<'static> {
+        let dim = Style::default().fg(Color::DarkGray);
+        let failed = Style::default().fg(Color::Red);
+        match self {
+            Finish::Stop => match tokens {
+                Some(tokens) => Span::styled(format!("✓ {} tokens", tokens), dim),
+                None => Span::styled("✓", dim),
+            },
+            Finish::Length => {
+                Span::styled("(response truncated — press C to continue)", Style::default().fg(Color::Yellow))
+            }
+            Finish::Load => Span::styled("(the model loaded but generated nothing)", failed),
+            Finish::Error(message) => Span::styled(format!("(failed: {})", first_line(message)), failed),
+            Finish::Other(reason) => Span::styled(format!("(ended: {})", reason), dim),
+        }
+    }
+}
+
+fn first_line(message: &str) -> &str {
+    message.lines().next().unwrap_or_default()
+}
@@ -0,0 +1,70 @@
+// --- Focus ---
+// Which part of the screen keys go to. Tab and Shift+Tab move between the
+// panes of the chat screen (the models list, the conversation, the input);
+// an open popup takes every key until it closes, and focus returns to the
+// pane it came from.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Focus {
+    // j/k pick a model, s sorts, D deletes
+    Models,
+    // j/k scroll, [ and ] select turns; on other screens, the screen itself
+    Conversation,
+    // Typing the draft
+    Input,
+    // Editing the scratchpad beside the conversation
+    Scratchpad,
+    Popup(Popup),
+}
+
+// What has keys while a popup is open: the error modal above everything,
+// then the top of the modal stack, then visual selection
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Popup {
+    Error,
+    SessionPicker,
+    FilePicker,
+    Outline,
+    Library,
+    ThemePicker,
+    TemplatePicker,
+    Jobs,
+    // Keyboard text selection over the conversation
+    Visual,
+    // A y/n question
+    Confirmation,
+    // The key reference (F1)
+    Help,
+}
+
+impl Focus {
+    // The pane after this one; `sidebar` says whether the models list is shown
+    pub fn next(self, sidebar: bool) -> Focus {
+        match self {
+            Focus::Models => Focus::Conversation,
+            Focus::Conversation => Focus::Input,
+            Focus::Input if sidebar => Focus::Models,
+            Focus::Input => Focus::Conversation,
+            other => other,
+        }
+    }
+
+    pub fn previous(self, sidebar: bool) -> Focus {
+        match self {
+            Focus::Models => Focus::Input,
+            Focus::Conversation if sidebar => Focus::Models,
+            Focus::Conversation => Focus::Input,
+            Focus::Input => Focus::Conversation,
+            other => other,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Focus::Models => "models",
+            Focus::Conversation => "conversation",
+            Focus::Input => "input",
+            Focus::Scratchpad => "scratchpad",
+            Focus::Popup(_) => "popup",
+        }
+    }
+}
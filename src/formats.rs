@@ -0,0 +1,122 @@
+use serde_json::{json, Value};
+
+// --- Output Format Presets (/format) ---
+// A prompt sent with a preset asks for a fixed shape of reply. JSON goes
+// through the server's `format` field, the others through an instruction
+// added to the prompt; either way the reply is checked, and one that doesn't
+// fit is sent back with the problem up to `format_retries` times.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FormatPreset {
+    Json,
+    YesNo,
+    Bullets,
+    Csv,
+}
+
+const PRESETS: [FormatPreset; 4] = [FormatPreset::Json, FormatPreset::YesNo, FormatPreset::Bullets, FormatPreset::Csv];
+
+impl FormatPreset {
+    pub fn name(self) -> &'static str {
+        match self {
+            FormatPreset::Json => "json",
+            FormatPreset::YesNo => "yes-no",
+            FormatPreset::Bullets => "bullets",
+            FormatPreset::Csv => "csv",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        PRESETS.into_iter().find(|preset| preset.name() == name)
+    }
+
+    pub fn names() -> String {
+        PRESETS.map(FormatPreset::name).join("|")
+    }
+
+    // Added after the prompt, as a suffix
+    pub fn instruction(self) -> &'static str {
+        match self {
+            FormatPreset::Json => "Reply with a single valid JSON value only, without code fences or comments.",
+            FormatPreset::YesNo => "Reply with exactly one word: yes or no.",
+            FormatPreset::Bullets => "Reply with a bulleted list only: every line starts with \"- \", with no other text.",
+            FormatPreset::Csv => {
+                "Reply with CSV only: a header row, then one row per record, every row with the same number of \
+                 comma-separated fields, without code fences."
+            }
+        }
+    }
+
+    // The request's `format` field
+    pub fn server_format(self) -> Option<Value> {
+        match self {
+            FormatPreset::Json => Some(json!("json")),
+            _ => None,
+        }
+    }
+
+    // Why `reply` doesn't fit, if it doesn't
+    pub fn validate(self, reply: &str) -> Result<(), String> {
+        let reply = strip_fence(reply.trim());
+        match self {
+            FormatPreset::Json => serde_json::from_str::<Value>(reply).map(|_| ()).map_err(|e| format!("invalid JSON ({})", e)),
+            FormatPreset::YesNo => {
+                let word = reply.trim_end_matches(['.', '!']).to_lowercase();
+                match word.as_str() {
+                    "yes" | "no" => Ok(()),
+                    _ => Err("the reply is not just \"yes\" or \"no\"".to_string()),
+                }
+            }
+            FormatPreset::Bullets => {
+                let lines: Vec<&str> = reply.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+                match lines.iter().find(|line| !["- ", "* ", "• "].iter().any(|bullet| line.starts_with(bullet))) {
+                    _ if lines.is_empty() => Err("the reply is empty".to_string()),
+                    Some(line) => Err(format!("\"{}\" is not a bullet point", line)),
+                    None => Ok(()),
+                }
+            }
+            FormatPreset::Csv => {
+                let mut widths = reply.lines().filter(|line| !line.trim().is_empty()).map(csv_fields);
+                let Some(header) = widths.next() else {
+                    return Err("the reply is empty".to_string());
+                };
+                let header = header?;
+                for (row, width) in widths.enumerate() {
+                    let width = width?;
+                    if width != header {
+                        return Err(format!("row {} has {} fields, the header has {}", row + 2, width, header));
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// Models often fence a reply even when told not to
+pub fn strip_fence(reply: &str) -> &str {
+    let Some(body) = reply.strip_prefix("```") else { return reply };
+    let body = body.split_once('\n').map_or("", |(_, body)| body);
+    body.trim_end().strip_suffix("```").unwrap_or(body).trim()
+}
+
+// Number of fields in one CSV line, with double-quoted fields
+fn csv_fields(line: &str) -> Result<usize, String> {
+    let mut fields = 1;
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields += 1,
+            _ => {}
+        }
+    }
+    if quoted {
+        return Err(format!("unbalanced quotes in \"{}\"", line.trim()));
+    }
+    Ok(fields)
+}
+
+// The prompt that asks again after a reply failed the check
+pub fn reask_prompt(prompt: &str, error: &str) -> String {
+    format!("Your previous reply didn't follow the required format: {}. Answer again:\n\n{}", error, prompt)
+}
@@ -0,0 +1,71 @@
+use ratatui::{prelude::*, widgets::*};
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+// --- Frame Pacing ---
+// The UI is only redrawn when something changed (App::needs_redraw), and
+// never faster than `max_fps`. Draw timings feed the optional FPS readout
+// toggled with F12.
+pub struct FrameClock {
+    interval: Duration,
+    last_frame: Option<Instant>,
+    // Start times of the frames drawn in the last second
+    recent: VecDeque<Instant>,
+    last_draw_time: Duration,
+    pub show_stats: bool,
+}
+
+impl FrameClock {
+    pub fn new(max_fps: u32, show_stats: bool) -> Self {
+        FrameClock {
+            interval: Duration::from_secs(1) / max_fps.max(1),
+            last_frame: None,
+            recent: VecDeque::new(),
+            last_draw_time: Duration::ZERO,
+            show_stats,
+        }
+    }
+
+    // For a config reload; the readout stays as it is
+    pub fn set_max_fps(&mut self, max_fps: u32) {
+        self.interval = Duration::from_secs(1) / max_fps.max(1);
+    }
+
+    pub fn since_last_frame(&self) -> Duration {
+        self.last_frame.map_or(Duration::MAX, |at| at.elapsed())
+    }
+
+    // How long until the FPS cap allows the next frame
+    pub fn until_ready(&self) -> Duration {
+        self.interval.saturating_sub(self.since_last_frame())
+    }
+
+    pub fn record(&mut self, started: Instant) {
+        self.last_draw_time = started.elapsed();
+        self.last_frame = Some(started);
+        self.recent.push_back(started);
+        while self.recent.front().is_some_and(|at| at.elapsed() > Duration::from_secs(1)) {
+            self.recent.pop_front();
+        }
+    }
+
+    // Small readout in the top-right corner of `area`, followed by `detail`
+    pub fn render(&self, f: &mut Frame, area: Rect, detail: &str) {
+        if !self.show_stats {
+            return;
+        }
+        let text = format!(
+            " {} fps | {:.1} ms | {} ",
+            self.recent.len(),
+            self.last_draw_time.as_secs_f64() * 1000.0,
+            detail
+        );
+        let width = (text.len() as u16).min(area.width);
+        let rect = Rect::new(area.right() - width, area.y, width, 1.min(area.height));
+        let readout = Paragraph::new(text).style(Style::default().fg(Color::Black).bg(Color::DarkGray));
+        f.render_widget(Clear, rect);
+        f.render_widget(readout, rect);
+    }
+}
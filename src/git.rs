@@ -0,0 +1,46 @@
+use std::process::Command;
+
+use crate::AppError;
+
+// --- Git Helpers (/diff, /commit-msg) ---
+fn run_git(args: &[&str]) -> Result<String, AppError> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|e| AppError::Git(format!("Failed to run git: {}", e)))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(AppError::Git(stderr.trim().to_string()))
+    }
+}
+
+pub fn ensure_repo() -> Result<(), AppError> {
+    match run_git(&["rev-parse", "--is-inside-work-tree"]) {
+        Ok(out) if out.trim() == "true" => Ok(()),
+        _ => Err(AppError::Git("Not inside a git repository".to_string())),
+    }
+}
+
+// Working tree changes, or only the staged ones when `staged` is set
+pub fn diff(staged: bool) -> Result<String, AppError> {
+    ensure_repo()?;
+    if staged {
+        run_git(&["diff", "--cached", "--no-color"])
+    } else {
+        run_git(&["diff", "--no-color"])
+    }
+}
+
+// Models often wrap the message in a code fence; strip it before copying
+pub fn clean_commit_message(reply: &str) -> String {
+    let trimmed = reply.trim();
+    let Some(rest) = trimmed.strip_prefix("```") else {
+        return trimmed.to_string();
+    };
+    // Drop the language tag on the opening fence and the closing fence
+    let body = rest.split_once('\n').map_or("", |(_, body)| body);
+    body.trim_end().trim_end_matches('`').trim().to_string()
+}
@@ -0,0 +1,220 @@
+use ratatui::{prelude::*, widgets::*};
+use reqwest::Client;
+use serde::Deserialize;
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::{endpoint, trace, version::OllamaVersion, AppError};
+
+// --- Server Health Dashboard ---
+// Refreshed this often while the Health tab is open
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+// Errors older than this are forgotten
+const ERROR_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Deserialize, Debug)]
+struct ModelSizes {
+    models: Vec<ModelSize>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ModelSize {
+    name: String,
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    size_vram: u64,
+}
+
+#[derive(Debug)]
+pub struct HealthReport {
+    pub version: Option<OllamaVersion>,
+    // Round trip of the /api/version request; None if the server didn't answer
+    pub latency: Option<Duration>,
+    // Models currently loaded: (name, total size, size in VRAM)
+    pub running: Vec<(String, u64, u64)>,
+    // Installed models and their size on disk, largest first
+    pub installed: Vec<(String, u64)>,
+    pub errors: Vec<String>,
+    pub checked_at: Instant,
+}
+
+async fn get_json<T: for<'de> Deserialize<'de>>(client: &Client, path: &str) -> Result<T, AppError> {
+    let (trace, response) = trace::send(client.get(format!("{}{}", endpoint::base(), path)).timeout(CHECK_TIMEOUT)).await?;
+    if !response.status().is_success() {
+        return Err(trace.tag(AppError::ApiResponse(format!("Status {} from {}", response.status(), path))));
+    }
+    response.json().await.map_err(|e| trace.tag(e.into()))
+}
+
+// Whether the model is already in memory; if not, the next request waits for
+// it to load before the first token
+pub async fn is_loaded(client: &Client, model: &str) -> Result<bool, AppError> {
+    let ps: ModelSizes = get_json(client, "/api/ps").await?;
+    let tagged = |name: &str| if name.contains(':') { name.to_string() } else { format!("{}:latest", name) };
+    Ok(ps.models.iter().any(|running| tagged(&running.name) == tagged(model)))
+}
+
+// Queries the server's version, loaded models and installed models
+pub async fn check(client: Client) -> HealthReport {
+    let mut report = HealthReport {
+        version: None,
+        latency: None,
+        running: Vec::new(),
+        installed: Vec::new(),
+        errors: Vec::new(),
+        checked_at: Instant::now(),
+    };
+
+    let started = Instant::now();
+    match crate::version::fetch_version(client.clone()).await {
+        Ok(version) => {
+            report.latency = Some(started.elapsed());
+            report.version = Some(version);
+        }
+        Err(e) => {
+            report.errors.push(format!("/api/version: {}", e));
+            // Nothing else will answer either
+            if matches!(e.untraced(), AppError::ApiRequest(_)) {
+                return report;
+            }
+            report.latency = Some(started.elapsed());
+        }
+    }
+
+    match get_json::<ModelSizes>(&client, "/api/ps").await {
+        Ok(ps) => {
+            report.running = ps.models.into_iter().map(|m| (m.name, m.size, m.size_vram)).collect();
+        }
+        Err(e) => report.errors.push(format!("/api/ps: {}", e)),
+    }
+    match get_json::<ModelSizes>(&client, "/api/tags").await {
+        Ok(tags) => {
+            report.installed = tags.models.into_iter().map(|m| (m.name, m.size)).collect();
+            report.installed.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+        }
+        Err(e) => report.errors.push(format!("/api/tags: {}", e)),
+    }
+    report
+}
+
+// Timestamps of failed requests during the last hour
+pub struct ErrorLog {
+    times: VecDeque<Instant>,
+    total: usize,
+}
+
+impl ErrorLog {
+    pub fn new() -> Self {
+        ErrorLog { times: VecDeque::new(), total: 0 }
+    }
+
+    pub fn record(&mut self) {
+        let now = Instant::now();
+        while self.times.front().is_some_and(|t| now.duration_since(*t) > ERROR_WINDOW) {
+            self.times.pop_front();
+        }
+        self.times.push_back(now);
+        self.total += 1;
+    }
+
+    fn count_within(&self, window: Duration) -> usize {
+        self.times.iter().filter(|t| t.elapsed() <= window).count()
+    }
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+fn heading(text: &str) -> Line<'static> {
+    Line::styled(text.to_string(), Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
+}
+
+pub fn render(f: &mut Frame, area: Rect, report: Option<&HealthReport>, errors: &ErrorLog) {
+    let block = Block::default().borders(Borders::ALL).title(" Server Health (Tab: Next view, r: Refresh) ");
+    let Some(report) = report else {
+        f.render_widget(Paragraph::new("Checking server...").block(block), area);
+        return;
+    };
+
+    let mut lines = vec![heading("Server")];
+    lines.push(Line::from(vec![
+        Span::raw(format!("  Address:   {}", endpoint::base())),
+        Span::styled(format!(" (from {})", endpoint::source()), Style::default().fg(Color::DarkGray)),
+    ]));
+    lines.push(match report.latency {
+        Some(latency) => Line::from(vec![
+            Span::raw("  Status:    "),
+            Span::styled("reachable", Style::default().fg(Color::Green)),
+            Span::raw(format!(" ({} ms)", latency.as_millis())),
+        ]),
+        None => Line::from(vec![
+            Span::raw("  Status:    "),
+            Span::styled("unreachable", Style::default().fg(Color::Red)),
+        ]),
+    });
+    let version = report.version.map_or("unknown".to_string(), |v| v.to_string());
+    lines.push(Line::from(format!("  Version:   {}", version)));
+    lines.push(Line::from(format!("  Checked:   {}s ago", report.checked_at.elapsed().as_secs())));
+
+    lines.push(Line::raw(""));
+    lines.push(heading("Loaded models"));
+    if report.running.is_empty() {
+        lines.push(Line::styled("  none", Style::default().fg(Color::DarkGray)));
+    }
+    for (name, size, size_vram) in &report.running {
+        let percent = if *size > 0 { size_vram * 100 / size } else { 0 };
+        lines.push(Line::from(format!(
+            "  {:<32} {:>10} VRAM ({}% of {})",
+            name,
+            format_bytes(*size_vram),
+            percent,
+            format_bytes(*size)
+        )));
+    }
+
+    lines.push(Line::raw(""));
+    let disk_total: u64 = report.installed.iter().map(|(_, size)| size).sum();
+    lines.push(heading(&format!(
+        "Installed models ({}, {} on disk)",
+        report.installed.len(),
+        format_bytes(disk_total)
+    )));
+    for (name, size) in &report.installed {
+        lines.push(Line::from(format!("  {:<32} {:>10}", name, format_bytes(*size))));
+    }
+
+    lines.push(Line::raw(""));
+    lines.push(heading("Request errors"));
+    let recent = errors.count_within(Duration::from_secs(5 * 60));
+    let recent_style = if recent > 0 { Style::default().fg(Color::Red) } else { Style::default() };
+    lines.push(Line::from(vec![
+        Span::raw("  Last 5 min: "),
+        Span::styled(recent.to_string(), recent_style),
+        Span::raw(format!(
+            "   Last hour: {}   This session: {}",
+            errors.count_within(ERROR_WINDOW),
+            errors.total
+        )),
+    ]));
+    for error in &report.errors {
+        lines.push(Line::styled(format!("  {}", error), Style::default().fg(Color::Red)));
+    }
+
+    f.render_widget(Paragraph::new(lines).block(block).wrap(Wrap { trim: false }), area);
+}
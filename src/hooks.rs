@@ -0,0 +1,61 @@
+use std::{process::Stdio, time::Duration};
+use tokio::{io::AsyncWriteExt, process::Command};
+
+use crate::AppError;
+
+// --- Pre-send / Post-receive Hooks ---
+#[derive(Debug)]
+pub enum HookOutcome {
+    Unchanged,
+    Replaced(String),
+}
+
+pub fn shell_command(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    }
+}
+
+// Pipes `input` through the hook command. Empty output leaves the text as is;
+// a non-zero exit status or a timeout vetoes it.
+pub async fn run_hook(command: &str, input: &str, timeout: Duration) -> Result<HookOutcome, AppError> {
+    let mut child = shell_command(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true) // Timed-out hooks are killed when the future is dropped
+        .spawn()
+        .map_err(|e| AppError::Hook(format!("Failed to start `{}`: {}", command, e)))?;
+
+    let input = input.to_string();
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let output = async move {
+        // A hook may exit without reading its input; that's not an error
+        let _ = stdin.write_all(input.as_bytes()).await;
+        drop(stdin);
+        child.wait_with_output().await
+    };
+
+    let output = tokio::time::timeout(timeout, output)
+        .await
+        .map_err(|_| AppError::Hook(format!("`{}` timed out after {}s", command, timeout.as_secs())))??;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = if stderr.trim().is_empty() { output.status.to_string() } else { stderr.trim().to_string() };
+        return Err(AppError::Hook(format!("`{}` rejected it: {}", command, reason)));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if stdout.trim().is_empty() {
+        Ok(HookOutcome::Unchanged)
+    } else {
+        Ok(HookOutcome::Replaced(stdout.trim_end().to_string()))
+    }
+}
@@ -0,0 +1,83 @@
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeSet,
+    time::{Duration, Instant},
+};
+
+use crate::{endpoint, trace, AppError};
+
+// --- Idle Unloading ---
+// With `unload_after_idle_mins` set, the models prompted from here are
+// unloaded (keep_alive 0) once no key has been pressed for that long, freeing
+// VRAM while the app sits open all day. The first key afterwards loads the
+// selected model again, so it is usually warm by the time the prompt is sent.
+pub struct IdleTracker {
+    last_activity: Instant,
+    // Prompted since the last unload, so possibly still loaded
+    used: BTreeSet<String>,
+    // Unloaded for being idle, and not loaded again since
+    unloaded: BTreeSet<String>,
+}
+
+impl Default for IdleTracker {
+    fn default() -> Self {
+        IdleTracker { last_activity: Instant::now(), used: BTreeSet::new(), unloaded: BTreeSet::new() }
+    }
+}
+
+impl IdleTracker {
+    // A key, click or paste, or a reply still generating
+    pub fn touch(&mut self) {
+        self.last_activity = Instant::now();
+    }
+
+    pub fn used(&mut self, model: &str) {
+        self.unloaded.remove(model);
+        self.used.insert(model.to_string());
+    }
+
+    // The models to unload now, if the user has been away for `after`
+    pub fn due(&mut self, after: Duration) -> Vec<String> {
+        if after.is_zero() || self.last_activity.elapsed() < after || self.used.is_empty() {
+            return Vec::new();
+        }
+        let models = std::mem::take(&mut self.used);
+        self.unloaded.extend(models.iter().cloned());
+        models.into_iter().collect()
+    }
+
+    // Whether `model` was unloaded for being idle; forgets it either way,
+    // as it is about to be loaded again
+    pub fn rewarm(&mut self, model: &str) -> bool {
+        let unloaded = self.unloaded.remove(model);
+        self.unloaded.clear();
+        unloaded
+    }
+}
+
+// Loads `model` with the configured keep-alive, or unloads it with
+// `Some(0)`; an empty generate request does either without a prompt
+pub async fn set_loaded(client: &Client, model: &str, keep_alive: Option<i64>) -> Result<(), AppError> {
+    let mut body = json!({ "model": model, "stream": false });
+    body = match keep_alive {
+        Some(seconds) => {
+            body["keep_alive"] = Value::from(seconds);
+            body
+        }
+        None => endpoint::with_keep_alive(body),
+    };
+    let (trace, response) = trace::send(
+        client
+            .post(format!("{}/api/generate", endpoint::base()))
+            .json(&body)
+            .timeout(Duration::from_secs(300)),
+    )
+    .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(trace.tag(AppError::ApiResponse(format!("Status {} - {}", status, body))));
+    }
+    Ok(())
+}
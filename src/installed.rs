@@ -0,0 +1,110 @@
+use ratatui::{prelude::*, widgets::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    capabilities::{self, Capability},
+    health::format_bytes,
+};
+
+// --- Models Tab ---
+// The installed models with room for details the sidebar has to squeeze:
+// size, capabilities and when each was last used. The sidebar's keys work
+// here too (j/k, s: Sort, D: Delete); Enter chats with the selected model.
+pub struct ModelRow<'a> {
+    pub name: &'a str,
+    pub size: Option<u64>,
+    pub capabilities: &'a [Capability],
+    pub idle_days: Option<i64>,
+    // Unused for longer than `cleanup_after_days`
+    pub idle: bool,
+}
+
+// From the `details` of /api/tags, e.g. "8B" and "Q4_K_M"
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct ModelDetails {
+    #[serde(default)]
+    pub parameter_size: String,
+    #[serde(default)]
+    pub quantization_level: String,
+}
+
+impl ModelDetails {
+    // "(4.7 GB, Q4_K_M, 8B)", leaving out what the server didn't say
+    pub fn summary(&self, size: Option<u64>) -> String {
+        let parts: Vec<String> = [size.map(format_bytes), Some(self.quantization_level.clone()), Some(self.parameter_size.clone())]
+            .into_iter()
+            .flatten()
+            .filter(|part| !part.is_empty())
+            .collect();
+        if parts.is_empty() {
+            String::new()
+        } else {
+            format!("({})", parts.join(", "))
+        }
+    }
+}
+
+// Fits a model name into `width` columns by cutting its middle, since tags
+// like "llama3:8b-instruct-q4_K_M" differ at both ends
+pub fn fit_name(name: &str, width: usize) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    if chars.len() <= width {
+        return name.to_string();
+    }
+    let kept = width.saturating_sub(1);
+    let tail = kept / 2;
+    let head = kept - tail;
+    let mut fitted: String = chars[..head].iter().collect();
+    fitted.push('…');
+    fitted.extend(&chars[chars.len() - tail..]);
+    fitted
+}
+
+// `cached` is the age of the list when the server is unreachable
+pub fn render(f: &mut Frame, area: Rect, models: &[ModelRow], selected: Option<usize>, cached: Option<&str>) {
+    let total: u64 = models.iter().filter_map(|model| model.size).sum();
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" Installed models, {} on disk (Tab: Next view) ", format_bytes(total)))
+        .title_bottom(" j/k: Select, Enter: Chat, s: Sort, D: Delete, b: Browse the library, R: Refresh ");
+    if let Some(age) = cached {
+        block = block.title(
+            Line::styled(format!(" Server unreachable, cached {} ", age), Style::default().fg(Color::Yellow)).right_aligned(),
+        );
+    }
+    if models.is_empty() {
+        let help = "No models installed.\n\nb               browse the library\n/pull <model>   download one from the registry";
+        f.render_widget(Paragraph::new(help).block(block), area);
+        return;
+    }
+
+    let rows: Vec<Row> = models
+        .iter()
+        .map(|model| {
+            let last_used = match model.idle_days {
+                Some(0) => "today".to_string(),
+                Some(1) => "yesterday".to_string(),
+                Some(days) => format!("{} days ago", days),
+                None => "never".to_string(),
+            };
+            let last_used_style = if model.idle { Style::default().fg(Color::Red) } else { Style::default() };
+            Row::new(vec![
+                Cell::from(model.name),
+                Cell::from(model.size.map(format_bytes).unwrap_or_default()),
+                Cell::from(Line::from(capabilities::badges(model.capabilities))),
+                Cell::from(last_used).style(last_used_style),
+            ])
+        })
+        .collect();
+    let header = Row::new(vec!["Model", "Size", "Capabilities", "Last used"])
+        .style(Style::default().fg(Color::DarkGray).add_modifier(Modifier::BOLD));
+    let widths = [Constraint::Percentage(40), Constraint::Length(10), Constraint::Min(20), Constraint::Length(14)];
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD).bg(Color::Blue))
+        .highlight_symbol("> ");
+
+    let mut state = TableState::default().with_selected(selected);
+    f.render_stateful_widget(table, area, &mut state);
+}
@@ -0,0 +1,285 @@
+use std::{
+    fmt::Display,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use futures::FutureExt;
+use ratatui::{prelude::*, widgets::*};
+use tokio::task::JoinHandle;
+
+// --- Background Jobs ---
+// Every request the app starts runs as a job registered here, with an ID, a
+// label for the jobs popup ('J' or /jobs) and whatever progress the app
+// reports for it. A job can be cancelled from the popup, and quitting
+// cancels them all: aborting a task drops its HTTP response, which closes the
+// connection and makes the server stop generating. Child processes (hooks,
+// validators, MCP servers) are spawned with kill_on_drop and go with it.
+//
+// The UI polls for ended jobs every tick, so an error a job returns or a
+// panic is recorded instead of vanishing with the task.
+pub const SHUTDOWN_GRACE: Duration = Duration::from_millis(500);
+
+// Finished jobs the popup keeps showing
+const HISTORY: usize = 20;
+
+pub type JobId = u64;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum JobState {
+    Running,
+    Done,
+    Failed(String),
+    Cancelled,
+    Panicked,
+}
+
+// What a job's future returns: nothing, or a Result whose error is kept
+pub trait JobOutput {
+    fn failure(self) -> Option<String>;
+}
+
+impl JobOutput for () {
+    fn failure(self) -> Option<String> {
+        None
+    }
+}
+
+impl<T, E: Display> JobOutput for Result<T, E> {
+    fn failure(self) -> Option<String> {
+        self.err().map(|e| e.to_string())
+    }
+}
+
+struct Job {
+    id: JobId,
+    label: String,
+    started: Instant,
+    ended: Option<Instant>,
+    progress: Option<String>,
+    state: JobState,
+    handle: Option<JoinHandle<Option<String>>>,
+}
+
+// A job as the popup shows it
+#[derive(Clone)]
+pub struct JobRow {
+    pub id: JobId,
+    pub label: String,
+    pub state: JobState,
+    pub elapsed: Duration,
+    pub progress: Option<String>,
+}
+
+// A job that ended since the last poll
+pub struct Ended {
+    pub id: JobId,
+    pub label: String,
+    pub state: JobState,
+}
+
+#[derive(Default)]
+pub struct Jobs {
+    jobs: Mutex<Vec<Job>>,
+    next_id: AtomicU64,
+}
+
+impl Jobs {
+    pub fn spawn<F>(&self, label: impl Into<String>, future: F) -> JobId
+    where
+        F: Future + Send + 'static,
+        F::Output: JobOutput,
+    {
+        let handle = tokio::spawn(async move { future.await.failure() });
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        self.lock().push(Job {
+            id,
+            label: label.into(),
+            started: Instant::now(),
+            ended: None,
+            progress: None,
+            state: JobState::Running,
+            handle: Some(handle),
+        });
+        id
+    }
+
+    pub fn set_progress(&self, id: JobId, progress: impl Into<String>) {
+        if let Some(job) = self.lock().iter_mut().find(|job| job.id == id && job.state == JobState::Running) {
+            job.progress = Some(progress.into());
+        }
+    }
+
+    // Aborts a running job; the next poll reports it as cancelled
+    pub fn cancel(&self, id: JobId) -> bool {
+        let jobs = self.lock();
+        let Some(handle) = jobs.iter().find(|job| job.id == id).and_then(|job| job.handle.as_ref()) else { return false };
+        handle.abort();
+        true
+    }
+
+    // Records the jobs that have ended and returns them; finished history
+    // beyond HISTORY is dropped
+    pub fn poll(&self) -> Vec<Ended> {
+        let mut jobs = self.lock();
+        let mut ended = Vec::new();
+        for job in jobs.iter_mut() {
+            if !job.handle.as_ref().is_some_and(JoinHandle::is_finished) {
+                continue;
+            }
+            let Some(handle) = job.handle.take() else { continue };
+            job.state = match handle.now_or_never() {
+                Some(Ok(None)) => JobState::Done,
+                Some(Ok(Some(failure))) => JobState::Failed(failure),
+                Some(Err(e)) if e.is_panic() => JobState::Panicked,
+                Some(Err(_)) | None => JobState::Cancelled,
+            };
+            job.ended = Some(Instant::now());
+            ended.push(Ended { id: job.id, label: job.label.clone(), state: job.state.clone() });
+        }
+        let finished = jobs.iter().filter(|job| job.handle.is_none()).count();
+        let mut excess = finished.saturating_sub(HISTORY);
+        jobs.retain(|job| {
+            let drop = excess > 0 && job.handle.is_none();
+            if drop {
+                excess -= 1;
+            }
+            !drop
+        });
+        ended
+    }
+
+    pub fn running(&self) -> usize {
+        self.lock().iter().filter(|job| job.handle.as_ref().is_some_and(|handle| !handle.is_finished())).count()
+    }
+
+    // Running jobs first, then the most recently started
+    pub fn rows(&self) -> Vec<JobRow> {
+        let jobs = self.lock();
+        let mut rows: Vec<JobRow> = jobs
+            .iter()
+            .map(|job| JobRow {
+                id: job.id,
+                label: job.label.clone(),
+                state: job.state.clone(),
+                elapsed: job.ended.unwrap_or_else(Instant::now).duration_since(job.started),
+                progress: job.progress.clone(),
+            })
+            .collect();
+        rows.sort_by_key(|row| (row.state != JobState::Running, std::cmp::Reverse(row.id)));
+        rows
+    }
+
+    // Aborts everything still running and waits up to `grace` for the tasks
+    // to wind down; a blocking call that can't be interrupted is left behind
+    pub async fn shutdown(&self, grace: Duration) {
+        let handles: Vec<JoinHandle<Option<String>>> = self.lock().iter_mut().filter_map(|job| job.handle.take()).collect();
+        for handle in &handles {
+            handle.abort();
+        }
+        let _ = tokio::time::timeout(grace, async {
+            for handle in handles {
+                let _ = handle.await;
+            }
+        })
+        .await;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Job>> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+// --- Jobs Popup ---
+// A snapshot of the jobs, refreshed every tick while the popup is open
+pub struct JobList {
+    rows: Vec<JobRow>,
+    selected: usize,
+}
+
+pub enum JobAction {
+    None,
+    Close,
+    Cancel(JobId),
+}
+
+impl JobList {
+    pub fn new(rows: Vec<JobRow>) -> Self {
+        JobList { rows, selected: 0 }
+    }
+
+    // Keeps the same job selected when the order changes
+    pub fn refresh(&mut self, rows: Vec<JobRow>) {
+        let id = self.rows.get(self.selected).map(|row| row.id);
+        self.selected = id.and_then(|id| rows.iter().position(|row| row.id == id)).unwrap_or(0);
+        self.rows = rows;
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> JobAction {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('J') => return JobAction::Close,
+            KeyCode::Char('j') | KeyCode::Down if !self.rows.is_empty() => {
+                self.selected = (self.selected + 1) % self.rows.len();
+            }
+            KeyCode::Char('k') | KeyCode::Up if !self.rows.is_empty() => {
+                self.selected = self.selected.checked_sub(1).unwrap_or(self.rows.len() - 1);
+            }
+            KeyCode::Char('c') | KeyCode::Char('x') => {
+                if let Some(row) = self.rows.get(self.selected).filter(|row| row.state == JobState::Running) {
+                    return JobAction::Cancel(row.id);
+                }
+            }
+            _ => {}
+        }
+        JobAction::None
+    }
+
+    pub fn render(&self, f: &mut Frame, area: Rect) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow))
+            .title(" Jobs ")
+            .title_bottom(" j/k: Select, c: Cancel, Esc: Close ");
+        if self.rows.is_empty() {
+            f.render_widget(Paragraph::new("Nothing running.").block(block), area);
+            return;
+        }
+
+        let rows: Vec<Row> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let (state, style) = match &row.state {
+                    JobState::Running => ("running".to_string(), Style::default().fg(Color::Cyan)),
+                    JobState::Done => ("done".to_string(), Style::default().fg(Color::DarkGray)),
+                    JobState::Failed(e) => (format!("failed: {}", e), Style::default().fg(Color::Red)),
+                    JobState::Cancelled => ("cancelled".to_string(), Style::default().fg(Color::Yellow)),
+                    JobState::Panicked => ("crashed".to_string(), Style::default().fg(Color::Red)),
+                };
+                let detail = match (&row.state, &row.progress) {
+                    (JobState::Running, Some(progress)) => progress.clone(),
+                    _ => state,
+                };
+                Row::new(vec![
+                    Cell::from(format!("#{}", row.id)).style(Style::default().fg(Color::DarkGray)),
+                    Cell::from(row.label.as_str()),
+                    Cell::from(format!("{:.1}s", row.elapsed.as_secs_f64())),
+                    Cell::from(detail).style(style),
+                ])
+            })
+            .collect();
+        let widths = [Constraint::Length(5), Constraint::Percentage(45), Constraint::Length(8), Constraint::Min(10)];
+        let table = Table::new(rows, widths)
+            .block(block)
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD).bg(Color::Blue))
+            .highlight_symbol("> ");
+
+        let mut state = TableState::default().with_selected(Some(self.selected));
+        f.render_stateful_widget(table, area, &mut state);
+    }
+}
@@ -0,0 +1,139 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+
+// --- Configurable Key Bindings ---
+// Written like "ctrl+d", "alt+enter" or "f5" in the config. Raw mode already
+// turns off XON/XOFF flow control, but tmux, screen and some terminal
+// emulators still swallow Ctrl+S before it gets here, so sending defaults to
+// Ctrl+D instead.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    pub const fn ctrl(c: char) -> Self {
+        KeyBinding { code: KeyCode::Char(c), modifiers: KeyModifiers::CONTROL }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut parts: Vec<String> = text.split('+').map(|part| part.trim().to_lowercase()).collect();
+        let key = parts.pop()?;
+        for part in parts {
+            modifiers |= match part.as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return None,
+            };
+        }
+        let code = match key.as_str() {
+            "enter" | "return" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "space" => KeyCode::Char(' '),
+            _ if key.chars().count() == 1 => KeyCode::Char(key.chars().next()?),
+            _ => KeyCode::F(key.strip_prefix('f')?.parse().ok().filter(|n| (1..=12).contains(n))?),
+        };
+        Some(KeyBinding { code, modifiers })
+    }
+
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        let code = match key.code {
+            KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+            code => code,
+        };
+        code == self.code && key.modifiers == self.modifiers
+    }
+}
+
+// As shown in hints, e.g. "Ctrl+D"
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (modifier, name) in [(KeyModifiers::CONTROL, "Ctrl+"), (KeyModifiers::ALT, "Alt+"), (KeyModifiers::SHIFT, "Shift+")] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            KeyCode::Enter => f.write_str("Enter"),
+            KeyCode::Tab => f.write_str("Tab"),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            _ => f.write_str("?"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyBinding {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        KeyBinding::parse(&text).ok_or_else(|| serde::de::Error::custom(format!("unknown key `{}`", text)))
+    }
+}
+
+// --- Key Repeat ---
+// Holding a key repeats it only where that is safe: moving around, deleting
+// and typing. Everything else (sending, quitting, deleting a model...) acts
+// once per press, however long it is held. Terminals speaking the kitty
+// protocol mark repeats themselves; Windows reports every release, so a
+// second press without one in between is a repeat. Elsewhere repeats look
+// like presses and all keys repeat, as they always did.
+#[derive(Default)]
+pub struct RepeatDetector {
+    // The key down since its last press, on Windows
+    held: Option<KeyCode>,
+}
+
+impl RepeatDetector {
+    pub fn kind(&mut self, key: &KeyEvent) -> KeyEventKind {
+        if !cfg!(windows) {
+            return key.kind;
+        }
+        match key.kind {
+            KeyEventKind::Press if self.held == Some(key.code) => KeyEventKind::Repeat,
+            KeyEventKind::Press => {
+                self.held = Some(key.code);
+                KeyEventKind::Press
+            }
+            KeyEventKind::Release => {
+                if self.held == Some(key.code) {
+                    self.held = None;
+                }
+                KeyEventKind::Release
+            }
+            KeyEventKind::Repeat => KeyEventKind::Repeat,
+        }
+    }
+}
+
+// Whether holding `key` should repeat it; `typing` is true in the draft and
+// the scratchpad, where letters and Enter repeat too, though `send` doesn't
+pub fn repeats(key: &KeyEvent, typing: bool, send: &KeyBinding) -> bool {
+    let navigation = matches!(
+        key.code,
+        KeyCode::Up
+            | KeyCode::Down
+            | KeyCode::Left
+            | KeyCode::Right
+            | KeyCode::PageUp
+            | KeyCode::PageDown
+            | KeyCode::Home
+            | KeyCode::End
+            | KeyCode::Backspace
+            | KeyCode::Delete
+    );
+    if navigation {
+        return true;
+    }
+    if typing {
+        let plain = key.modifiers.difference(KeyModifiers::SHIFT).is_empty();
+        let undo = key.modifiers == KeyModifiers::CONTROL && matches!(key.code, KeyCode::Char('z' | 'y'));
+        return !send.matches(key) && (undo || plain && matches!(key.code, KeyCode::Char(_) | KeyCode::Enter));
+    }
+    // Scrolling, turn selection and moving through lists
+    key.modifiers.is_empty() && matches!(key.code, KeyCode::Char('j' | 'k' | 'h' | 'l' | '[' | ']'))
+}
@@ -0,0 +1,261 @@
+use ratatui::{prelude::*, text::{Line, Span}};
+use unicode_width::UnicodeWidthChar;
+
+// --- Screen Layout ---
+// Pane geometry lives here so the scroll math (App::turn_line_offsets) and
+// ui() agree on how wide the conversation is.
+pub struct Panes {
+    // One row across the top naming the screens
+    pub tabs: Rect,
+    // Zero-width on screens other than the chat
+    pub models: Rect,
+    // Split off the top of the conversation area while a turn is pinned
+    pub pinned: Option<Rect>,
+    pub conversation: Rect,
+    // The unfocused half of a split conversation area
+    pub other: Option<Rect>,
+    // Beside the conversation while the scratchpad is shown
+    pub scratchpad: Option<Rect>,
+    pub input: Rect,
+    pub status: Rect,
+}
+
+// The pinned split takes at most this share of the conversation area
+const MAX_PINNED_PERCENT: u16 = 40;
+const SCRATCHPAD_PERCENT: u16 = 35;
+
+// Which half of a split conversation area is live
+#[derive(Clone, Copy, PartialEq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+// `pinned_rows` is the wrapped height of the pinned turn, if there is one;
+// the pinned split goes above the focused conversation. Without `sidebar`
+// the screen gets the full width
+pub fn panes(area: Rect, pinned_rows: Option<u16>, scratchpad: bool, split: Option<Side>, sidebar: bool) -> Panes {
+    // Tab Bar / Screen
+    let screen = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(1)])
+        .split(area);
+    // Models List | Right Pane
+    let sidebar_percent = if sidebar { 25 } else { 0 };
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(sidebar_percent), Constraint::Percentage(100 - sidebar_percent)])
+        .split(screen[1]);
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(1),    // Conversation area
+            Constraint::Length(7), // Input area height
+            Constraint::Length(1), // Status bar height
+        ])
+        .split(columns[1]);
+    let (main, scratchpad) = if scratchpad {
+        let split = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(100 - SCRATCHPAD_PERCENT), Constraint::Percentage(SCRATCHPAD_PERCENT)])
+            .split(rows[0]);
+        (split[0], Some(split[1]))
+    } else {
+        (rows[0], None)
+    };
+    let (main, other) = match split {
+        Some(focus) => {
+            let halves = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(main);
+            match focus {
+                Side::Left => (halves[0], Some(halves[1])),
+                Side::Right => (halves[1], Some(halves[0])),
+            }
+        }
+        None => (main, None),
+    };
+    let (pinned, conversation) = match pinned_rows {
+        Some(pinned_rows) => {
+            let max_height = main.height * MAX_PINNED_PERCENT / 100;
+            let split = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length((pinned_rows + 2).min(max_height)), Constraint::Min(1)])
+                .split(main);
+            (Some(split[0]), split[1])
+        }
+        None => (None, main),
+    };
+    Panes { tabs: screen[0], models: columns[0], pinned, conversation, other, scratchpad, input: rows[1], status: rows[2] }
+}
+
+// Columns available for text inside the bordered conversation pane
+// (the narrower half when split, so neither side's lines overflow); only
+// the chat screen shows a conversation, and it has the sidebar
+pub fn conversation_text_width(area: Rect, scratchpad: bool, split: bool) -> u16 {
+    let width = |split| panes(area, None, scratchpad, split, true).conversation.width.saturating_sub(2);
+    if split { width(Some(Side::Left)).min(width(Some(Side::Right))) } else { width(None) }
+}
+
+// Where `width` columns of text go in a bordered pane: centered, when the
+// pane is wider than the max_content_width setting
+pub fn text_area(pane: Rect, width: u16) -> Rect {
+    let inner = pane.inner(Margin::new(1, 1));
+    let width = width.min(inner.width);
+    Rect { x: inner.x + (inner.width - width) / 2, width, ..inner }
+}
+
+// Characters of blockquote gutter at the start of a (wrapped) line
+pub fn gutter_chars(line: &Line) -> usize {
+    line.spans
+        .iter()
+        .take_while(|span| span.content == QUOTE_GUTTER)
+        .map(|span| span.content.chars().count())
+        .sum()
+}
+
+// --- Width-Aware Wrapping ---
+// Lines are wrapped here, by display width, before they reach ratatui, so a
+// CJK character or emoji counts as the two columns it takes on screen and the
+// line count used for scrolling is exactly what gets drawn.
+pub fn char_width(c: char) -> usize {
+    c.width().unwrap_or(0)
+}
+
+// Leading spans with exactly this content are blockquote gutter bars, which
+// are repeated on every row the line wraps onto
+pub const QUOTE_GUTTER: &str = "│ ";
+
+pub fn wrap_line(line: Line<'_>, width: u16) -> Vec<Line<'_>> {
+    let width = width as usize;
+    if width == 0 || line.width() <= width {
+        return vec![line];
+    }
+
+    let gutter_len = line.spans.iter().take_while(|span| span.content == QUOTE_GUTTER).count();
+    let gutter = &line.spans[..gutter_len];
+    let gutter_width: usize = gutter.iter().map(|span| span.width()).sum();
+    // Keep at least a few columns for text under deeply nested quotes
+    let (gutter, width) = if gutter_width + 4 <= width { (gutter, width - gutter_width) } else { (&[][..], width) };
+
+    // Words and the whitespace between them, as styled pieces; a word can
+    // cross spans, e.g. "**bold**text"
+    let mut tokens: Vec<(bool, Vec<(String, Style)>)> = Vec::new();
+    for span in &line.spans[gutter.len()..] {
+        let mut rest = span.content.as_ref();
+        while let Some(c) = rest.chars().next() {
+            let is_space = c.is_whitespace();
+            let end = rest.find(|ch: char| ch.is_whitespace() != is_space).unwrap_or(rest.len());
+            let piece = (rest[..end].to_string(), span.style);
+            match tokens.last_mut() {
+                Some((last_space, pieces)) if *last_space == is_space => pieces.push(piece),
+                _ => tokens.push((is_space, vec![piece])),
+            }
+            rest = &rest[end..];
+        }
+    }
+
+    let mut rows = RowBuilder { rows: Vec::new(), current: Vec::new(), current_width: 0 };
+    for (is_space, pieces) in tokens {
+        let token_width: usize = pieces.iter().flat_map(|(text, _)| text.chars()).map(char_width).sum();
+        if rows.current_width + token_width <= width {
+            for (text, style) in pieces {
+                rows.push(text, style);
+            }
+        } else if is_space {
+            // Whitespace the break falls on stays at the end of the row, past
+            // the edge, so the rows join back into the original line
+            for (text, style) in pieces {
+                rows.push(text, style);
+            }
+            rows.break_row();
+        } else {
+            if token_width <= width && rows.current_width > 0 {
+                rows.break_row();
+            }
+            // Words wider than the pane are split wherever the edge falls
+            for (text, style) in pieces {
+                for c in text.chars() {
+                    let w = char_width(c);
+                    if rows.current_width + w > width && rows.current_width > 0 {
+                        rows.break_row();
+                    }
+                    rows.push(c.to_string(), style);
+                }
+            }
+        }
+    }
+    if !rows.current.is_empty() {
+        rows.break_row();
+    }
+
+    rows.rows
+        .into_iter()
+        .map(|spans| {
+            let mut wrapped = Line::from([gutter, &spans[..]].concat());
+            wrapped.style = line.style;
+            wrapped.alignment = line.alignment;
+            wrapped
+        })
+        .collect()
+}
+
+struct RowBuilder<'a> {
+    rows: Vec<Vec<Span<'a>>>,
+    current: Vec<Span<'a>>,
+    current_width: usize,
+}
+
+impl<'a> RowBuilder<'a> {
+    fn push(&mut self, text: String, style: Style) {
+        self.current_width += text.chars().map(char_width).sum::<usize>();
+        match self.current.last_mut() {
+            Some(last) if last.style == style => last.content.to_mut().push_str(&text),
+            _ => self.current.push(Span::styled(text, style)),
+        }
+    }
+
+    fn break_row(&mut self) {
+        self.rows.push(std::mem::take(&mut self.current));
+        self.current_width = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(line: Line<'_>, width: u16) -> Vec<String> {
+        wrap_line(line, width).iter().map(|row| row.spans.iter().map(|span| span.content.as_ref()).collect()).collect()
+    }
+
+    #[test]
+    fn words_wrap_at_spaces() {
+        assert_eq!(rows(Line::from("the quick brown fox"), 10), ["the quick ", "brown fox"]);
+        assert_eq!(rows(Line::from("fits"), 10), ["fits"]);
+    }
+
+    #[test]
+    fn wide_characters_count_double() {
+        // Each of these takes two columns
+        assert_eq!(rows(Line::from("日本語のテキスト"), 6), ["日本語", "のテキ", "スト"]);
+        assert_eq!(rows(Line::from("🦙🦙🦙 ok"), 5), ["🦙🦙", "🦙 ok"]);
+        // A wide character never straddles the edge
+        assert_eq!(rows(Line::from("ab日本"), 3), ["ab", "日", "本"]);
+    }
+
+    #[test]
+    fn words_wider_than_the_pane_are_split() {
+        assert_eq!(rows(Line::from("see abcdefghij"), 4), ["see ", "abcd", "efgh", "ij"]);
+    }
+
+    #[test]
+    fn quote_gutters_repeat_on_every_row() {
+        let line = Line::from(vec![Span::raw(QUOTE_GUTTER), Span::raw(QUOTE_GUTTER), Span::raw("one two three")]);
+        assert_eq!(rows(line, 10), ["│ │ one ", "│ │ two ", "│ │ three"]);
+        // Too deep to leave room for text, the gutter isn't repeated
+        let deep = Line::from(vec![Span::raw(QUOTE_GUTTER), Span::raw(QUOTE_GUTTER), Span::raw("one two")]);
+        assert_eq!(rows(deep, 6), ["│ │ ", "one ", "two"]);
+    }
+}
@@ -0,0 +1,207 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+use reqwest::Client;
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::{capabilities::{self, Capability}, health::format_bytes, trace, AppError};
+
+// --- Model Library Browser ('/browse') ---
+// ollama.com has no listing API, so a small index of popular models ships
+// with the app; `library_url` in the config can point at a newer index in
+// the same JSON format. Sizes are approximate download sizes.
+const BUNDLED_INDEX: &str = include_str!("library.json");
+
+// Size filter steps, cycled with Shift+Tab; None shows everything
+const GIB: u64 = 1024 * 1024 * 1024;
+const SIZE_LIMITS: [Option<u64>; 5] = [None, Some(2 * GIB), Some(5 * GIB), Some(10 * GIB), Some(25 * GIB)];
+// Capability filter steps, cycled with Tab
+const CAPABILITY_FILTERS: [Option<Capability>; 5] =
+    [None, Some(Capability::Tools), Some(Capability::Vision), Some(Capability::Code), Some(Capability::Embed)];
+
+#[derive(Deserialize, Debug)]
+pub struct LibraryModel {
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    capabilities: Vec<Capability>,
+    tags: Vec<LibraryTag>,
+}
+
+#[derive(Deserialize, Debug)]
+struct LibraryTag {
+    tag: String,
+    size: u64,
+}
+
+pub fn bundled_index() -> Vec<LibraryModel> {
+    serde_json::from_str(BUNDLED_INDEX).unwrap_or_default()
+}
+
+pub async fn fetch_index(client: Client, url: &str) -> Result<Vec<LibraryModel>, AppError> {
+    let (trace, response) = trace::send(client.get(url).timeout(Duration::from_secs(15))).await?;
+    if !response.status().is_success() {
+        return Err(trace.tag(AppError::ApiResponse(format!("Status {} from {}", response.status(), url))));
+    }
+    response.json().await.map_err(|e| trace.tag(e.into()))
+}
+
+pub struct LibraryBrowser {
+    models: Vec<LibraryModel>,
+    query: String,
+    capability: usize,
+    size_limit: usize,
+    // Index into the filtered list, and the chosen tag of that model
+    selected: usize,
+    tag: usize,
+    // Where the index came from, for the title
+    source: String,
+}
+
+pub enum BrowserAction {
+    None,
+    Close,
+    Pull(String),
+}
+
+impl LibraryBrowser {
+    pub fn new(models: Vec<LibraryModel>, source: String, query: String) -> Self {
+        let mut browser = LibraryBrowser { models, query, capability: 0, size_limit: 0, selected: 0, tag: 0, source };
+        browser.reset_selection();
+        browser
+    }
+
+    // Replaces the index, e.g. once a newer one has been downloaded
+    pub fn set_index(&mut self, models: Vec<LibraryModel>, source: String) {
+        self.models = models;
+        self.source = source;
+        self.reset_selection();
+    }
+
+    fn fits(&self, tag: &LibraryTag) -> bool {
+        SIZE_LIMITS[self.size_limit].is_none_or(|limit| tag.size <= limit)
+    }
+
+    // Models matching the search text and filters
+    fn visible(&self) -> Vec<&LibraryModel> {
+        let query = self.query.to_lowercase();
+        self.models
+            .iter()
+            .filter(|model| {
+                model.name.to_lowercase().contains(&query) || model.description.to_lowercase().contains(&query)
+            })
+            .filter(|model| CAPABILITY_FILTERS[self.capability].is_none_or(|wanted| model.capabilities.contains(&wanted)))
+            .filter(|model| model.tags.iter().any(|tag| self.fits(tag)))
+            .collect()
+    }
+
+    // Back to the first model, on its first tag within the size limit
+    fn reset_selection(&mut self) {
+        self.selected = 0;
+        self.tag = self.visible().first().and_then(|model| model.tags.iter().position(|tag| self.fits(tag))).unwrap_or(0);
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let count = self.visible().len();
+        if count == 0 {
+            return;
+        }
+        self.selected = self.selected.saturating_add_signed(delta).min(count - 1);
+        let model = self.visible()[self.selected];
+        self.tag = model.tags.iter().position(|tag| self.fits(tag)).unwrap_or(0);
+    }
+
+    // Next or previous tag of the selected model that fits the size limit
+    fn move_tag(&mut self, forward: bool) {
+        let visible = self.visible();
+        let Some(model) = visible.get(self.selected) else { return };
+        let fitting = (0..model.tags.len()).filter(|&index| self.fits(&model.tags[index]));
+        let next = if forward {
+            fitting.filter(|&index| index > self.tag).min()
+        } else {
+            fitting.filter(|&index| index < self.tag).max()
+        };
+        if let Some(next) = next {
+            self.tag = next;
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> BrowserAction {
+        match key.code {
+            KeyCode::Esc => return BrowserAction::Close,
+            KeyCode::Down => self.move_selection(1),
+            KeyCode::Up => self.move_selection(-1),
+            KeyCode::Right => self.move_tag(true),
+            KeyCode::Left => self.move_tag(false),
+            KeyCode::Tab => {
+                self.capability = (self.capability + 1) % CAPABILITY_FILTERS.len();
+                self.reset_selection();
+            }
+            KeyCode::BackTab => {
+                self.size_limit = (self.size_limit + 1) % SIZE_LIMITS.len();
+                self.reset_selection();
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.reset_selection();
+            }
+            KeyCode::Char(c) => {
+                self.query.push(c);
+                self.reset_selection();
+            }
+            KeyCode::Enter => {
+                let visible = self.visible();
+                if let Some(tag) = visible.get(self.selected).and_then(|model| Some((model, model.tags.get(self.tag)?))) {
+                    return BrowserAction::Pull(format!("{}:{}", tag.0.name, tag.1.tag));
+                }
+            }
+            _ => {}
+        }
+        BrowserAction::None
+    }
+
+    pub fn render(&self, f: &mut Frame, area: Rect) {
+        let visible = self.visible();
+        let items: Vec<ListItem> = visible
+            .iter()
+            .enumerate()
+            .map(|(index, model)| {
+                let mut name = vec![Span::styled(model.name.as_str(), Style::default().add_modifier(Modifier::BOLD))];
+                name.extend(capabilities::badges(&model.capabilities));
+                name.push(Span::styled(format!("  {}", model.description), Style::default().fg(Color::DarkGray)));
+
+                // Tags with their sizes; the chosen one is marked on the selected model
+                let mut tags = vec![Span::raw("    ")];
+                for (tag_index, tag) in model.tags.iter().enumerate() {
+                    let mut style = if self.fits(tag) { Style::default().fg(Color::Cyan) } else { Style::default().fg(Color::DarkGray) };
+                    if index == self.selected && tag_index == self.tag {
+                        style = style.add_modifier(Modifier::REVERSED);
+                    }
+                    tags.push(Span::styled(format!("{} ({})", tag.tag, format_bytes(tag.size)), style));
+                    tags.push(Span::raw("  "));
+                }
+                ListItem::new(vec![Line::from(name), Line::from(tags)])
+            })
+            .collect();
+
+        let capability = CAPABILITY_FILTERS[self.capability].map_or("any", |capability| capability.badge());
+        let size = SIZE_LIMITS[self.size_limit].map_or("any size".to_string(), |limit| format!("<= {}", format_bytes(limit)));
+        let title = format!(" Library ({}): search \"{}\", {}, {} ", self.source, self.query, capability, size);
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Yellow))
+                    .title(title)
+                    .title_bottom(" Type: search, Up/Down: model, Left/Right: tag, Tab: capability, Shift+Tab: size, Enter: pull, Esc: close "),
+            )
+            .highlight_style(Style::default().bg(Color::Blue))
+            .highlight_symbol("> ");
+
+        let mut state = ListState::default();
+        state.select((!visible.is_empty()).then_some(self.selected));
+
+        f.render_stateful_widget(list, area, &mut state);
+    }
+}
@@ -0,0 +1,112 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::{
+    sync::{Mutex, OwnedSemaphorePermit, Semaphore},
+    time::Instant,
+};
+
+use crate::config::LimitsConfig;
+
+// --- Request Limits for Shared Servers ---
+// Every generation (prompts, arena sides, benchmark runs) takes a slot before
+// it is sent, so a busy GPU box isn't flooded from one TUI. Requests that
+// can't start yet wait here and are counted for the status bar. A config
+// reload resizes it in place, so requests already running keep their slots.
+pub struct Limiter {
+    // None when concurrency is unlimited, with how many slots there are
+    slots: std::sync::Mutex<Option<(Arc<Semaphore>, usize)>>,
+    min_interval_ms: AtomicU64,
+    last_start: Mutex<Option<Instant>>,
+    waiting: AtomicUsize,
+}
+
+// Held for as long as the request runs
+pub struct Slot {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+// Counts a request as waiting until it gets its slot or is dropped
+struct Waiting<'a>(&'a AtomicUsize);
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Limiter {
+    pub fn new(config: &LimitsConfig) -> Self {
+        Limiter {
+            slots: std::sync::Mutex::new(new_slots(config.max_concurrent)),
+            min_interval_ms: AtomicU64::new(config.min_interval_ms),
+            last_start: Mutex::new(None),
+            waiting: AtomicUsize::new(0),
+        }
+    }
+
+    // Applies reloaded limits. Lowering max_concurrent takes the spare slots
+    // away as the requests holding them finish; going unlimited lets
+    // everything waiting through.
+    pub fn reconfigure(&self, config: &LimitsConfig) {
+        self.min_interval_ms.store(config.min_interval_ms, Ordering::Relaxed);
+        let mut slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+        match (slots.as_mut(), config.max_concurrent) {
+            (Some((semaphore, count)), max) if max > 0 => {
+                if max > *count {
+                    semaphore.add_permits(max - *count);
+                } else if max < *count {
+                    let excess = (*count - max) as u32;
+                    let forgotten = semaphore.forget_permits(excess as usize) as u32;
+                    if forgotten < excess {
+                        let semaphore = semaphore.clone();
+                        tokio::spawn(async move {
+                            if let Ok(permits) = semaphore.acquire_many_owned(excess - forgotten).await {
+                                permits.forget();
+                            }
+                        });
+                    }
+                }
+                *count = max;
+            }
+            (Some((semaphore, _)), _) => {
+                semaphore.close();
+                *slots = None;
+            }
+            (None, max) => *slots = new_slots(max),
+        }
+    }
+
+    // Waits for a free slot, then until `min_interval` has passed since the
+    // previous request started
+    pub async fn acquire(&self) -> Slot {
+        self.waiting.fetch_add(1, Ordering::Relaxed);
+        let _waiting = Waiting(&self.waiting);
+        let semaphore = self.slots.lock().unwrap_or_else(|e| e.into_inner()).as_ref().map(|(semaphore, _)| semaphore.clone());
+        // A closed semaphore means the limit was lifted while waiting
+        let permit = match semaphore {
+            Some(semaphore) => semaphore.acquire_owned().await.ok(),
+            None => None,
+        };
+        let mut last_start = self.last_start.lock().await;
+        if let Some(previous) = *last_start {
+            let min_interval = Duration::from_millis(self.min_interval_ms.load(Ordering::Relaxed));
+            tokio::time::sleep_until(previous + min_interval).await;
+        }
+        *last_start = Some(Instant::now());
+        Slot { _permit: permit }
+    }
+
+    // Requests currently waiting for a slot
+    pub fn waiting(&self) -> usize {
+        self.waiting.load(Ordering::Relaxed)
+    }
+}
+
+fn new_slots(max_concurrent: usize) -> Option<(Arc<Semaphore>, usize)> {
+    (max_concurrent > 0).then(|| (Arc::new(Semaphore::new(max_concurrent)), max_concurrent))
+}
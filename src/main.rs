@@ -1,29 +1,178 @@
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event, KeyCode,
+        KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags, MouseButton, MouseEvent, MouseEventKind,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{
+        disable_raw_mode, enable_raw_mode, supports_keyboard_enhancement, EnterAlternateScreen, LeaveAlternateScreen,
+    },
 };
 use ratatui::{prelude::*, widgets::*, text::{Line, Span}};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     error::Error,
     io::{self},
-    time::{Duration},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
 };
 use thiserror::Error;
 use tokio::sync::mpsc;
 use futures::StreamExt;
-use pulldown_cmark::{Event as MDEvent, Options, Parser, Tag as MDTag};
+use pulldown_cmark::{CodeBlockKind, Event as MDEvent, LinkType, Options, Parser, Tag as MDTag};
 
-const OLLAMA_BASE_URL: &str = "http://127.0.0.1:11434";
+mod arena;
+mod attachment;
+mod batch;
+mod bubbles;
+mod capabilities;
+mod catalog;
+mod bench;
+mod chat;
+mod clipboard;
+mod code_fold;
+mod commands;
+mod config;
+mod context_trim;
+mod editor;
+mod embeddings;
+mod endpoint;
+mod empty_state;
+mod entities;
+mod file_picker;
+mod finish;
+mod focus;
+mod formats;
+mod frames;
+mod git;
+mod health;
+mod hooks;
+mod idle;
+mod installed;
+mod jobs;
+mod keys;
+mod layout;
+mod library;
+mod limiter;
+mod math;
+mod mcp;
+mod metrics;
+mod modal;
+mod ndjson;
+mod notifications;
+mod onboarding;
+mod outline;
+mod plain_text;
+mod plugins;
+mod postprocess;
+mod proxy;
+mod queue;
+mod redact;
+mod renderer;
+mod resources;
+mod scratchpad;
+mod session;
+mod session_picker;
+mod split;
+mod stats;
+mod status_line;
+mod summarize;
+mod tee;
+mod templates;
+mod theme;
+mod tokens;
+mod trace;
+mod transfers;
+mod turn_text;
+mod usage;
+mod validation;
+mod variables;
+mod version;
+mod visual;
+mod watch;
+
+use arena::{ArenaMatch, Scoreboard, Vote};
+use bench::{BenchResult, BenchRun};
+use bubbles::MessageLayout;
+use capabilities::Capability;
+use catalog::{CachedModel, Catalog};
+use chat::{ChatMessage, ToolCall, ToolCallFunction};
+use clipboard::{CopyFormat, SystemClipboard};
+use code_fold::CodeOnly;
+
+use commands::{Command, ExportTurns, Translation};
+use config::{Config, HooksConfig, Route};
+use context_trim::{EarlierTurns, TrimStrategy, Trimmed};
+use editor::InputEditor;
+use embeddings::Playground;
+use file_picker::{FilePicker, PickerAction};
+use finish::Finish;
+use focus::{Focus, Popup};
+use formats::FormatPreset;
+use frames::FrameClock;
+use health::{ErrorLog, HealthReport};
+use hooks::HookOutcome;
+use idle::IdleTracker;
+use installed::ModelDetails;
+use jobs::{JobAction, JobList, JobState, Jobs};
+use keys::RepeatDetector;
+use layout::Side;
+use library::{BrowserAction, LibraryBrowser, LibraryModel};
+use limiter::Limiter;
+use mcp::McpManager;
+use metrics::Metrics;
+use modal::{Modal, Modals};
+use ndjson::{NdjsonDecoder, StreamDecoding};
+use notifications::{ErrorReport, Notifications};
+use onboarding::{Hint, Onboarding};
+use outline::{Outline, OutlineAction, OutlineEntry};
+use plugins::PluginHost;
+use postprocess::{Step, Steps};
+use queue::QueueStats;
+use redact::Redactor;
+use renderer::Renderer;
+use resources::{Resources, Snapshot};
+use scratchpad::{Scratchpad, ScratchpadAction};
+use session::{Folder, Session};
+use session_picker::{SessionAction, SessionPicker};
+use split::{ParkedConversation, SplitView};
+use stats::{Generation, RecentGenerations, UsageStats};
+use summarize::Summary;
+use tee::Tee;
+use templates::{TemplateAction, TemplatePicker, TemplateRole};
+use theme::{Theme, ThemeAction, ThemePicker};
+use transfers::{PullProgress, Transfers};
+use turn_text::TurnText;
+use usage::ModelUsage;
+use variables::Variable;
+use version::{Feature, OllamaVersion, Preflight};
+use visual::{Position, Row, Selection, VisualAction};
+use watch::FileWatch;
+
+// Editors often emit several events per save; wait for them to settle
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+// Frames of the streaming spinner, advanced every SPINNER_INTERVAL
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const SPINNER_INTERVAL: Duration = Duration::from_millis(100);
+// Model loads shorter than this go unmentioned
+const LOAD_NOTE_THRESHOLD: Duration = Duration::from_millis(500);
+// How often the server is asked again while cached models are shown
+const OFFLINE_RETRY: Duration = Duration::from_secs(30);
+// Model names in a narrow sidebar are shortened no further than this
+const MIN_MODEL_NAME_WIDTH: usize = 12;
+// Input poll timeout while nothing is waiting to be drawn
+const IDLE_POLL: Duration = Duration::from_millis(50);
 
 // --- Error Handling ---
 #[derive(Error, Debug)]
 enum AppError {
     #[error("IO Error: {0}")]
     Io(#[from] io::Error),
-    #[error("API Request Error: {0}")]
+    #[error("{}", describe_request_error(.0))]
     ApiRequest(#[from] reqwest::Error),
     #[error("API Response Error: {0}")]
     ApiResponse(String),
@@ -33,6 +182,130 @@ enum AppError {
     ChannelSend(String),
     #[error("Channel Receive Error")]
     ChannelReceive,
+    #[error("Attachment Error: {0}")]
+    Attachment(String),
+    #[error("Git Error: {0}")]
+    Git(String),
+    #[error("Clipboard Error: {0}")]
+    Clipboard(String),
+    #[error("Watch Error: {0}")]
+    Watch(String),
+    #[error("Config Error: {0}")]
+    Config(String),
+    #[error("Hook Error: {0}")]
+    Hook(String),
+    #[error("Plugin Error: {0}")]
+    Plugin(String),
+    #[error("MCP Error: {0}")]
+    Mcp(String),
+    #[error("Session Error: {0}")]
+    Session(String),
+    #[error("Batch Error: {0}")]
+    Batch(String),
+    #[error("Stream Interrupted: {0}")]
+    Interrupted(String),
+    // The server couldn't take the request at all, so another route may still take it
+    #[error("{0}")]
+    Unavailable(String, ErrorCategory),
+    // Any of the above from an API request, ending with the request's ID
+    #[error("{source} [request {id}]")]
+    Traced { id: String, source: Box<AppError> },
+}
+
+impl AppError {
+    // The error underneath any request ID
+    fn untraced(&self) -> &AppError {
+        match self {
+            AppError::Traced { source, .. } => source.untraced(),
+            error => error,
+        }
+    }
+
+    fn category(&self) -> ErrorCategory {
+        match self {
+            AppError::ApiRequest(e) if e.is_decode() || e.is_body() => ErrorCategory::Parse,
+            AppError::ApiRequest(e) if e.is_status() => ErrorCategory::Server,
+            AppError::ApiRequest(e) if e.is_builder() => ErrorCategory::Config,
+            AppError::ApiRequest(_) | AppError::Interrupted(_) => ErrorCategory::Network,
+            AppError::ApiResponse(_) => ErrorCategory::Server,
+            AppError::Json(_) => ErrorCategory::Parse,
+            AppError::Config(_) | AppError::Hook(_) | AppError::Plugin(_) | AppError::Mcp(_) => ErrorCategory::Config,
+            AppError::Unavailable(_, category) => *category,
+            AppError::Traced { source, .. } => source.category(),
+            _ => ErrorCategory::Local,
+        }
+    }
+
+    // Keeps the category of an error that another route may get around
+    fn unavailable(error: AppError) -> AppError {
+        AppError::Unavailable(error.to_string(), error.category())
+    }
+}
+
+// What kind of failure an error is, for the heading and suggestion in the
+// error modal
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ErrorCategory {
+    Network,
+    Server,
+    Parse,
+    Config,
+    Local,
+}
+
+impl ErrorCategory {
+    fn label(self) -> &'static str {
+        match self {
+            ErrorCategory::Network => "Network error",
+            ErrorCategory::Server => "Server error",
+            ErrorCategory::Parse => "Unexpected reply",
+            ErrorCategory::Config => "Configuration error",
+            ErrorCategory::Local => "Error",
+        }
+    }
+
+    // A next step for the user, given the error's message
+    fn suggestion(self, message: &str) -> Option<String> {
+        let suggestion = match self {
+            ErrorCategory::Network if message.contains("timed out") => {
+                "The server may be busy or still loading a large model; try again, or pick a smaller model.".to_string()
+            }
+            ErrorCategory::Network if message.contains("closed") || message.contains("Stream Read Error") => {
+                "The connection dropped mid-reply; press 'r' to resume it.".to_string()
+            }
+            ErrorCategory::Network => format!(
+                "Is `ollama serve` running? The app expects it at {} (OLLAMA_HOST or --host change that), unless a route in the config names another server.",
+                endpoint::base()
+            ),
+            ErrorCategory::Server if message.contains("404") || message.contains("not found") => {
+                "The model may not be installed; add it with /pull <model>.".to_string()
+            }
+            ErrorCategory::Server => "Check the server's log (the terminal running `ollama serve`, or `journalctl -u ollama`).".to_string(),
+            ErrorCategory::Parse => "The server sent something this app doesn't understand; the Health tab shows whether its version is supported.".to_string(),
+            ErrorCategory::Config => match config::config_path() {
+                Some(path) => format!("Fix {} and run /reload.", path.display()),
+                None => "Fix the config file and run /reload.".to_string(),
+            },
+            ErrorCategory::Local => return None,
+        };
+        Some(suggestion)
+    }
+}
+
+// reqwest's own messages ("error sending request for url …") say little
+fn describe_request_error(e: &reqwest::Error) -> String {
+    let url = e.url().map(|url| url.as_str()).unwrap_or("the server");
+    if e.is_connect() {
+        format!("Couldn't connect to {}", url)
+    } else if e.is_timeout() {
+        format!("The request to {} timed out", url)
+    } else if e.is_decode() || e.is_body() {
+        format!("Couldn't read the reply from {}: {}", url, e)
+    } else if let Some(status) = e.status() {
+        format!("{} answered with status {}", url, status)
+    } else {
+        format!("Request to {} failed: {}", url, e)
+    }
 }
 
 // --- Ollama API Structures ---
@@ -40,22 +313,32 @@ enum AppError {
 struct OllamaGenerateRequest {
     model: String,
     prompt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    think: Option<bool>,
     stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<serde_json::Value>,
 }
 
 #[derive(Deserialize, Debug)]
 struct OllamaGenerateChunk {
-    model: String,
-    created_at: String,
+    // model: String,
+    // created_at: String,
     response: String,
     done: bool,
+    // Why it stopped, on the final chunk: "stop", "length" or "load"
+    #[serde(default)]
+    done_reason: Option<String>,
     // context: Option<Vec<i64>>,
-    // total_duration: Option<u64>,
-    // load_duration: Option<u64>,
     // prompt_eval_count: Option<usize>,
     // prompt_eval_duration: Option<u64>,
-    // eval_count: Option<usize>,
-    // eval_duration: Option<u64>,
+    // Token count and timings, only on the final chunk
+    #[serde(flatten)]
+    generation: Generation,
 }
 
 
@@ -68,58 +351,674 @@ struct OllamaTagsResponse {
 struct ModelInfo {
     name: String,
     // modified_at: String,
-    // size: u64,
+    #[serde(default)]
+    size: u64,
     // digest: String,
-    // details: ModelDetails,
+    #[serde(default)]
+    details: ModelDetails,
 }
 
 // --- Application State ---
-enum InputMode {
-    Normal,
-    Editing,
+// The screens of the tab bar; Tab and Shift+Tab step through them, Alt+1..9
+// jumps straight to one
+#[derive(Clone, Copy, PartialEq)]
+enum ActiveTab {
+    Chat,
+    Models,
+    Sessions,
+    Stats,
+    Health,
+    Bench,
+    Arena,
+    Transfers,
+    Embeddings,
+}
+
+impl ActiveTab {
+    const ALL: [ActiveTab; 9] = [
+        ActiveTab::Chat,
+        ActiveTab::Models,
+        ActiveTab::Sessions,
+        ActiveTab::Stats,
+        ActiveTab::Health,
+        ActiveTab::Bench,
+        ActiveTab::Arena,
+        ActiveTab::Transfers,
+        ActiveTab::Embeddings,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            ActiveTab::Chat => "Chat",
+            ActiveTab::Models => "Models",
+            ActiveTab::Sessions => "Sessions",
+            ActiveTab::Stats => "Stats",
+            ActiveTab::Health => "Health",
+            ActiveTab::Bench => "Bench",
+            ActiveTab::Arena => "Arena",
+            ActiveTab::Transfers => "Transfers",
+            ActiveTab::Embeddings => "Embeddings",
+        }
+    }
+
+    fn index(self) -> usize {
+        ActiveTab::ALL.iter().position(|tab| *tab == self).unwrap_or(0)
+    }
+}
+
+// Who a turn is from. The renderer, exports and the chat history go by this,
+// never by the name shown with the turn
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+enum Role {
+    User,
+    Assistant,
+    // Notices from the app itself, like the tool calls made for the model.
+    // Never sent.
+    System,
+    // Errors in sessions saved before errors moved to the modal. Never sent.
+    Error,
+    Tool,
+    // The instructions a template starts the conversation with, sent as the
+    // "system" message
+    #[serde(rename = "system_prompt")]
+    SystemPrompt,
+}
+
+impl Role {
+    // Sessions saved before turns had a role only have the sender's name
+    fn from_sender(name: &str) -> Role {
+        match name {
+            "You" => Role::User,
+            "Tool Result" => Role::Tool,
+            "Tool Call" => Role::System,
+            "Error" | "System Error" => Role::Error,
+            _ => Role::Assistant,
+        }
+    }
+
+    // The role in /api/chat messages
+    fn chat_role(self) -> Option<&'static str> {
+        match self {
+            Role::User => Some("user"),
+            Role::Assistant => Some("assistant"),
+            Role::Tool => Some("tool"),
+            Role::SystemPrompt => Some("system"),
+            Role::System | Role::Error => None,
+        }
+    }
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(from = "SavedTurn")]
 struct ConversationTurn {
+    role: Role,
+    // Shown before the text: "You", the model that answered, "Tool Call"...
+    #[serde(rename = "sender")]
+    name: String,
+    text: TurnText,
+    // Still shown (struck through) but left out of the chat history sent to the model
+    #[serde(default)]
+    excluded: bool,
+    // The connection dropped before the model finished this reply
+    #[serde(default)]
+    interrupted: bool,
+    // The request a "Tool Call" turn shows, for the chat history
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tool_call: Option<ToolCall>,
+    // How the reply ended, see finish.rs; None while streaming and for
+    // older sessions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    finish: Option<Finish>,
+    // Tokens the reply took to generate, from its final chunk
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tokens: Option<u64>,
+    // Set when a fallback model answered instead of the one asked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    routed: Option<String>,
+    // Chosen with 'm'; otherwise picked from the text, falling back to the
+    // global markdown setting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    renderer: Option<Renderer>,
+    // The prefix and suffix sent around this prompt; only `text` is shown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    wrap: Option<PromptWrap>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    summary: Option<Summary>,
+    // One of a template's exemplar exchanges rather than something typed
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    example: bool,
+    // A code-only reply unfolded with Enter
+    #[serde(skip)]
+    code_expanded: bool,
+    // The request whose reply streams into this turn; chunks are routed by
+    // it rather than by the sender's name, which two adjacent replies share
+    #[serde(skip)]
+    request: Option<u64>,
+}
+
+// A turn as saved, with the role optional for older sessions
+#[derive(Deserialize)]
+struct SavedTurn {
+    role: Option<Role>,
     sender: String,
-    text: String,
+    text: TurnText,
+    #[serde(default)]
+    excluded: bool,
+    #[serde(default)]
+    interrupted: bool,
+    #[serde(default)]
+    tool_call: Option<ToolCall>,
+    #[serde(default)]
+    finish: Option<Finish>,
+    #[serde(default)]
+    tokens: Option<u64>,
+    #[serde(default)]
+    routed: Option<String>,
+    #[serde(default)]
+    renderer: Option<Renderer>,
+    // Before there were renderers: true for the markdown source
+    #[serde(default)]
+    raw: Option<bool>,
+    #[serde(default)]
+    wrap: Option<PromptWrap>,
+    #[serde(default)]
+    summary: Option<Summary>,
+    #[serde(default)]
+    example: bool,
+}
+
+impl From<SavedTurn> for ConversationTurn {
+    fn from(saved: SavedTurn) -> Self {
+        ConversationTurn {
+            role: saved.role.unwrap_or_else(|| Role::from_sender(&saved.sender)),
+            name: saved.sender,
+            text: saved.text,
+            excluded: saved.excluded,
+            interrupted: saved.interrupted,
+            tool_call: saved.tool_call,
+            finish: saved.finish,
+            tokens: saved.tokens,
+            routed: saved.routed,
+            renderer: saved.renderer.or(saved.raw.map(|raw| if raw { Renderer::Plain } else { Renderer::Markdown })),
+            wrap: saved.wrap,
+            summary: saved.summary,
+            example: saved.example,
+            code_expanded: false,
+            request: None,
+        }
+    }
+}
+
+impl ConversationTurn {
+    fn new(role: Role, name: impl Into<String>, text: impl Into<String>) -> Self {
+        ConversationTurn {
+            role,
+            name: name.into(),
+            text: TurnText::from(text.into()),
+            excluded: false,
+            interrupted: false,
+            tool_call: None,
+            finish: None,
+            tokens: None,
+            routed: None,
+            renderer: None,
+            wrap: None,
+            summary: None,
+            example: false,
+            code_expanded: false,
+            request: None,
+        }
+    }
+
+    // The text as the model got it
+    fn sent_text(&self) -> String {
+        match &self.wrap {
+            Some(wrap) => wrap.apply(&self.text),
+            None => self.text.to_string(),
+        }
+    }
+
+    // The message content sent for this turn in chat mode
+    fn chat_content(&self) -> String {
+        if self.role == Role::User { self.sent_text() } else { self.text.to_string() }
+    }
+
+    fn user(text: impl Into<String>) -> Self {
+        ConversationTurn::new(Role::User, "You", text)
+    }
+
+    // Text streamed back from a model, as opposed to prompts and tool notices
+    fn is_model_reply(&self) -> bool {
+        self.role == Role::Assistant
+    }
+}
+
+// Text put before and after every prompt of a conversation (/prefix, /suffix)
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct PromptWrap {
+    #[serde(default)]
+    prefix: String,
+    #[serde(default)]
+    suffix: String,
+}
+
+impl PromptWrap {
+    fn is_empty(&self) -> bool {
+        self.prefix.is_empty() && self.suffix.is_empty()
+    }
+
+    fn apply(&self, prompt: &str) -> String {
+        [self.prefix.as_str(), prompt, self.suffix.as_str()]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    // For the input box, e.g. "prefix: Answer in French"
+    fn summary(&self) -> String {
+        let short = |text: &str| match text.char_indices().nth(30) {
+            Some((end, _)) => format!("{}…", &text[..end]),
+            None => text.to_string(),
+        };
+        [("prefix", &self.prefix), ("suffix", &self.suffix)]
+            .into_iter()
+            .filter(|(_, text)| !text.is_empty())
+            .map(|(name, text)| format!("{}: {}", name, short(text)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+// A cleared conversation kept around for a single level of undo
+struct ClearedConversation {
+    turns: Vec<ConversationTurn>,
+    draft: String,
+    archive_path: Option<PathBuf>,
+    postprocess: Steps,
+    prompt_wrap: PromptWrap,
+    scratchpad: String,
+}
+
+// Actions waiting for a y/n answer in a dialog
+enum Confirmation {
+    ClearConversation,
+    DeleteModel(String),
+    // Pulls left unfinished when the app last exited
+    ResumePulls(Vec<String>),
+}
+
+// Follow-up work to do once the current response has finished streaming
+enum ResponseAction {
+    CopyCommitMessage,
+}
+
+// A /format reply still to be checked, with the prompt to ask again
+struct FormatCheck {
+    preset: FormatPreset,
+    prompt: String,
+    retries_left: u32,
+}
+
+// A prompt asked again after its reply failed a validator
+struct ValidationRetry {
+    prompt: String,
+    retries_left: u32,
 }
 
 struct App {
-    input_mode: InputMode,
-    input_buffer: String,
+    config: Config,
+    plugins: PluginHost,
+    // The pane keys go to when no popup is open
+    focus: Focus,
+    input_buffer: InputEditor,
+    // The draft with its variables filled in, shown instead of it (Ctrl+P)
+    input_preview: Option<String>,
     conversation: Vec<ConversationTurn>,
     models: Vec<String>,
+    // From /api/show, filled in as each model's details arrive
+    capabilities: HashMap<String, Vec<Capability>>,
+    // Disk size of each installed model, from /api/tags
+    model_sizes: HashMap<String, u64>,
+    // Parameter count and quantization of each, also from /api/tags
+    model_details: HashMap<String, ModelDetails>,
+    // The last model list and capabilities, for when the server is down
+    catalog: Catalog,
+    // When the shown models were cached, while the server can't be reached
+    models_cached: Option<chrono::DateTime<chrono::Local>>,
+    models_retried: Option<Instant>,
+    // Largest models first instead of the server's order ('s')
+    sort_models_by_size: bool,
+    usage: ModelUsage,
+    stats: UsageStats,
+    // Speed and latency of the last few responses, for the status bar
+    recent_generations: RecentGenerations,
+    resources: Resources,
+    transfers: Transfers,
+    playground: Playground,
+    limiter: Arc<Limiter>,
+    redactor: Redactor,
     selected_model_index: Option<usize>,
     is_loading: bool,
+    stream_started: Option<Instant>,
+    // Model answering the current request; not always the selected one (/ask)
+    streaming_model: Option<String>,
+    // ID of the request being streamed, and the last one handed out
+    active_request: Option<u64>,
+    request_count: u64,
+    // Job streaming the current request, to finish it if the job is cancelled
+    request_job: Option<jobs::JobId>,
+    // Shown on the next model turn when a fallback route answers it
+    pending_route_note: Option<String>,
+    // The server is loading the model; cleared by the first token
+    model_loading: bool,
+    // How long the last reply's model took to load, for the status line
+    load_note: Option<Duration>,
     status_message: String,
     scroll_offset: u16,
+    // Text columns in the conversation pane as of the last loop iteration
+    conversation_width: u16,
+    // The conversation pane as last drawn, for paging and mouse hits;
+    // laying it out means rendering the whole conversation
+    conversation_pane: Rect,
+    conversation_height: u16,
+    // Keyboard text selection over the conversation ('v')
+    visual: Option<Selection>,
+    // The selection follows the mouse until the button is released
+    mouse_selecting: bool,
+    selected_turn: Option<usize>,
+    // Turn kept in view in a split above the conversation ('p')
+    pinned_turn: Option<usize>,
+    cleared_conversation: Option<ClearedConversation>,
+    // Popups, the top one taking keys
+    modals: Modals,
+    // The Sessions tab, listed when it is first shown
+    session_browser: Option<SessionPicker>,
+    // Saved copy of a resumed conversation, replaced when it is archived again
+    resumed_session: Option<PathBuf>,
+    scratchpad: Scratchpad,
+    split: Option<SplitView>,
+    // Selected once the models arrive, e.g. the model of a resumed session
+    preferred_model: Option<String>,
+    response_action: Option<ResponseAction>,
+    format_check: Option<FormatCheck>,
+    validation_retry: Option<ValidationRetry>,
+    clipboard: SystemClipboard,
+    file_watch: Option<FileWatch>,
+    watch_pending_since: Option<Instant>,
+    // Reloads the config when the file changes; dropping it stops that
+    config_watch: Option<notify::RecommendedWatcher>,
+    config_pending_since: Option<Instant>,
+    mcp: Option<Arc<McpManager>>,
+    // None until /api/version answers (or if it never does)
+    server_version: Option<OllamaVersion>,
+    // The server answered /api/tags with no models at all
+    no_models: bool,
+    onboarding: Onboarding,
+    // Server of the last pre-flight and its round trip (None: unreachable)
+    server_latency: Option<(String, Option<Duration>)>,
+    active_tab: ActiveTab,
+    health: Option<HealthReport>,
+    // When the last health check was started
+    health_requested: Option<Instant>,
+    request_errors: ErrorLog,
+    // Counters for the metrics file, see metrics.rs
+    metrics: Metrics,
+    bench: Option<BenchRun>,
+    // Post-processing steps for this conversation
+    postprocess: Steps,
+    // Wrapped around every prompt of this conversation
+    prompt_wrap: PromptWrap,
+    // Languages of the last /translate, for repeating it
+    last_translation: Option<Translation>,
+    // Show markdown source instead of rendering it, unless a turn says otherwise
+    raw_markdown: bool,
+    // Labels or bubbles, see bubbles.rs
+    message_layout: MessageLayout,
+    // Token limit sent with each prompt; replies that hit it can be continued
+    num_predict: Option<i64>,
+    theme: Theme,
+    // RGB colours are drawn as they are; otherwise they become the nearest
+    // of the 16 basic colours
+    true_color: bool,
+    // The terminal speaks the kitty keyboard protocol, so Ctrl+Enter is
+    // distinguishable from Enter
+    enhanced_keys: bool,
+    key_repeat: RepeatDetector,
+    // When the user was last around, for unloading models left idle
+    idle: IdleTracker,
+    tee: Option<Tee>,
+    // Errors waiting to be shown in the error modal
+    notifications: Notifications,
+    // Viewing a saved session: nothing is sent and the server is never contacted
+    read_only: bool,
+    // Turns containing the last /search query, and which one is selected
+    search_matches: Vec<usize>,
+    arena: Option<ArenaMatch>,
+    // Set by anything that changes what's on screen; cleared after each draw
+    needs_redraw: bool,
+    frames: FrameClock,
+    scoreboard: Scoreboard,
     http_client: Client,
     event_receiver: mpsc::Receiver<AppEvent>,
+    // Requests and other background work, listed in the jobs popup and
+    // cancelled on quit
+    jobs: Jobs,
+    queue: QueueStats,
     event_sender: mpsc::Sender<AppEvent>,
 }
 
 // --- Events for Async Communication ---
 #[derive(Debug)]
 enum AppEvent {
-    ModelsFetched(Result<Vec<String>, AppError>),
-    OllamaChunk(String),
-    OllamaDone,
-    OllamaError(String),
+    ModelsFetched(Result<Vec<ModelInfo>, AppError>),
+    ModelDeleted { model: String, result: Result<(), AppError> },
+    PullProgress { model: String, progress: PullProgress },
+    PullFinished { model: String, result: Result<(), String> },
+    LibraryFetched(Result<Vec<LibraryModel>, AppError>),
+    Embedded { model: String, texts: Vec<String>, result: Result<Vec<Vec<f32>>, AppError> },
+    CapabilitiesFetched { model: String, capabilities: Vec<Capability> },
+    PreflightDone(Preflight),
+    HealthChecked(HealthReport),
+    // `text` is the turn's text when it was sent off, to catch turns that changed since
+    Summarized { turn_index: usize, text: String, result: Result<Summary, AppError> },
+    // None when the reply passed every validator, else why it failed
+    ResponseValidated { turn_index: usize, result: Result<Option<String>, AppError> },
+    ResourcesProbed(Snapshot),
+    BenchResult(BenchResult),
+    BenchFinished,
+    ArenaChunk { side: usize, text: String },
+    ArenaDone { side: usize, error: Option<String> },
+    // /api/ps didn't list the model, so the server is loading it first
+    ModelLoading(String),
+    // The rest up to WatchedFileChanged belong to one request's reply, by
+    // its id (see ReplyEvents)
+    OllamaChunk { request: u64, text: String },
+    // Token count and timings of a finished generation
+    Generated { request: u64, generation: Generation },
+    OllamaDone { request: u64 },
+    // The request failed; no more of the reply is coming
+    OllamaError { request: u64, report: ErrorReport },
+    // A line of the stream couldn't be decoded; the rest still streams
+    StreamDecodeError { request: u64, report: ErrorReport },
+    // The stream broke off mid-response; the partial text is kept
+    OllamaInterrupted { request: u64, message: String },
+    // The final chunk's done_reason: "stop", "length", "load"...
+    DoneReason { request: u64, reason: String },
+    // A fallback route is being tried because the previous one failed
+    Rerouted { request: u64, route: Route, reason: String },
+    PromptRewritten { request: u64, prompt: String },
+    ToolCall { request: u64, name: String, arguments: serde_json::Value },
+    ToolResult { request: u64, name: String, output: String, is_error: bool },
+    WatchedFileChanged,
+    ConfigFileChanged,
+    // `text` is the reply the hook was given
+    ResponseHookDone { turn_index: usize, text: String, result: Result<HookOutcome, AppError> },
+    McpConnected { manager: Arc<McpManager>, errors: Vec<String> },
+}
+
+impl AppEvent {
+    // The request a reply event belongs to
+    fn request(&self) -> Option<u64> {
+        match self {
+            AppEvent::OllamaChunk { request, .. }
+            | AppEvent::Generated { request, .. }
+            | AppEvent::OllamaDone { request }
+            | AppEvent::OllamaError { request, .. }
+            | AppEvent::StreamDecodeError { request, .. }
+            | AppEvent::OllamaInterrupted { request, .. }
+            | AppEvent::DoneReason { request, .. }
+            | AppEvent::Rerouted { request, .. }
+            | AppEvent::PromptRewritten { request, .. }
+            | AppEvent::ToolCall { request, .. }
+            | AppEvent::ToolResult { request, .. } => Some(*request),
+            _ => None,
+        }
+    }
+}
+
+// Where a reply's events go, stamped with its request so that any still
+// queued after the request was interrupted or replaced can be dropped
+#[derive(Clone)]
+struct ReplyEvents {
+    request: u64,
+    sender: mpsc::Sender<AppEvent>,
+}
+
+// How a prompt is sent to the server
+// `earlier` is history trimmed to fit the context, summarized before the
+// request is sent
+enum PromptRequest {
+    Generate { shape: ReplyShape },
+    Chat {
+        messages: Vec<ChatMessage>,
+        mcp: Option<Arc<McpManager>>,
+        shape: ReplyShape,
+        earlier: Option<EarlierTurns>,
+    },
+}
+
+// What the reply should look like: the request's format field, e.g. "json",
+// the most tokens to generate (num_predict) and the think field
+#[derive(Clone, Default)]
+struct ReplyShape {
+    format: Option<serde_json::Value>,
+    num_predict: Option<i64>,
+    think: Option<bool>,
+}
+
+impl ReplyShape {
+    // The request's options object, when there is anything to set
+    fn options(&self) -> Option<serde_json::Value> {
+        self.num_predict.map(|limit| serde_json::json!({ "num_predict": limit }))
+    }
 }
 
 impl App {
-    fn new(rx: mpsc::Receiver<AppEvent>, tx: mpsc::Sender<AppEvent>) -> Self {
+    fn new(rx: mpsc::Receiver<AppEvent>, tx: mpsc::Sender<AppEvent>, config: Config, plugins: PluginHost) -> Self {
+        let postprocess = Steps::from_config(&config.postprocess);
+        let frames = FrameClock::new(config.max_fps, config.show_fps);
+        let raw_markdown = config.raw_markdown;
+        let message_layout = config.message_layout;
+        let num_predict = config.num_predict;
+        let theme = config.theme;
+        let true_color = config.true_color.unwrap_or_else(theme::true_color_supported);
+        let transfers = Transfers::new(config.max_parallel_pulls);
+        let limiter = Arc::new(Limiter::new(&config.limits));
+        let redactor = Redactor::new(&config.redact);
+        let resource_overlay = config.resource_overlay;
         App {
-            input_mode: InputMode::Normal,
-            input_buffer: String::new(),
+            config,
+            plugins,
+            focus: Focus::Models,
+            input_buffer: InputEditor::new(),
+            input_preview: None,
             conversation: Vec::new(),
             models: Vec::new(),
+            capabilities: HashMap::new(),
+            model_sizes: HashMap::new(),
+            model_details: HashMap::new(),
+            catalog: Catalog::default(),
+            models_cached: None,
+            models_retried: None,
+            sort_models_by_size: false,
+            usage: ModelUsage::default(),
+            stats: UsageStats::default(),
+            recent_generations: RecentGenerations::default(),
+            resources: Resources { enabled: resource_overlay, ..Resources::default() },
+            transfers,
+            playground: Playground::default(),
+            limiter,
+            redactor,
             selected_model_index: None,
             is_loading: false,
+            stream_started: None,
+            streaming_model: None,
+            pending_route_note: None,
+            active_request: None,
+            request_job: None,
+            request_count: 0,
+            model_loading: false,
+            load_note: None,
             status_message: "Fetching models...".to_string(),
             scroll_offset: 0,
+            conversation_width: 0,
+            conversation_pane: Rect::default(),
+            conversation_height: 0,
+            visual: None,
+            pinned_turn: None,
+            mouse_selecting: false,
+            selected_turn: None,
+            cleared_conversation: None,
+            modals: Modals::default(),
+            session_browser: None,
+            resumed_session: None,
+            scratchpad: Scratchpad::new(),
+            split: None,
+            preferred_model: None,
+            response_action: None,
+            format_check: None,
+            validation_retry: None,
+            clipboard: SystemClipboard::new(),
+            file_watch: None,
+            watch_pending_since: None,
+            config_watch: None,
+            config_pending_since: None,
+            mcp: None,
+            server_version: None,
+            no_models: false,
+            onboarding: Onboarding::default(),
+            server_latency: None,
+            active_tab: ActiveTab::Chat,
+            health: None,
+            health_requested: None,
+            request_errors: ErrorLog::new(),
+            metrics: Metrics::default(),
+            bench: None,
+            postprocess,
+            prompt_wrap: PromptWrap::default(),
+            last_translation: None,
+            raw_markdown,
+            message_layout,
+            num_predict,
+            theme,
+            enhanced_keys: false,
+            key_repeat: RepeatDetector::default(),
+            idle: IdleTracker::default(),
+            true_color,
+            tee: None,
+            notifications: Notifications::new(),
+            read_only: false,
+            search_matches: Vec::new(),
+            arena: None,
+            needs_redraw: true,
+            frames,
+            scoreboard: Scoreboard::default(),
             http_client: Client::new(),
             event_receiver: rx,
+            jobs: Jobs::default(),
+            queue: QueueStats::default(),
             event_sender: tx,
         }
     }
@@ -129,283 +1028,3647 @@ impl App {
             .and_then(|index| self.models.get(index).cloned())
     }
 
-    fn submit_prompt(&mut self) {
-        if let Some(model_name) = self.get_selected_model_name() {
-            if !self.input_buffer.is_empty() {
-                let prompt = self.input_buffer.trim().to_string();
-                if prompt.is_empty() {
-                     self.status_message = "Cannot send an empty prompt.".to_string();
-                     self.input_mode = InputMode::Normal;
-                     return;
-                }
-
-                self.conversation.push(ConversationTurn {
-                    sender: "You".to_string(),
-                    text: prompt.clone(),
-                });
-                self.input_buffer.clear();
-                self.is_loading = true;
-                self.status_message = format!("Asking {}...", model_name);
-                self.scroll_offset = 0;
-
-                let client = self.http_client.clone();
-                let event_sender = self.event_sender.clone();
-                tokio::spawn(async move {
-                    // We handle errors inside stream_ollama_response by sending AppEvents
-                    // So we don't necessarily need to handle the task result here unless it panics
-                    let _ = stream_ollama_response(client, model_name, prompt, event_sender.clone()).await;
-                });
-            } else {
-                // Buffer is empty or only whitespace
-                 self.status_message = "Cannot send an empty prompt.".to_string();
+    // The turn the active request's reply streams into, once it has started
+    fn streaming_turn_mut(&mut self) -> Option<&mut ConversationTurn> {
+        let request = self.active_request?;
+        self.conversation.last_mut().filter(|turn| turn.request == Some(request))
+    }
+
+    // What gets the next key: the error modal, the top popup, else the focused pane
+    fn focus(&self) -> Focus {
+        let popup = if !self.notifications.is_empty() {
+            Popup::Error
+        } else if let Some(modal) = self.modals.top() {
+            modal.kind()
+        } else if self.visual.is_some() {
+            Popup::Visual
+        } else if self.scratchpad.focused {
+            return Focus::Scratchpad;
+        } else {
+            return self.focus;
+        };
+        Focus::Popup(popup)
+    }
+
+    // Tab / Shift+Tab on the chat screen. Each side of a split is a stop of
+    // its own, left before right
+    fn cycle_focus(&mut self, forward: bool) {
+        let sidebar = self.active_tab == ActiveTab::Chat;
+        let (far_side, near_side) = if forward { (Side::Right, Side::Left) } else { (Side::Left, Side::Right) };
+        let split_side = self.split.as_ref().map(|split| split.side);
+        if self.focus == Focus::Conversation && split_side.is_some_and(|side| side != far_side) && !self.is_loading {
+            self.switch_split_side();
+            return;
+        }
+        let next = if forward { self.focus.next(sidebar) } else { self.focus.previous(sidebar) };
+        self.focus = next;
+        match next {
+            Focus::Conversation if split_side.is_some_and(|side| side != near_side) && !self.is_loading => {
+                self.switch_split_side()
+            }
+            // Without a model there is nothing to type to; skip the input
+            Focus::Input => {
+                self.start_editing();
+                if self.focus != Focus::Input {
+                    self.focus = if forward { next.next(sidebar) } else { next.previous(sidebar) };
+                }
             }
+            _ => {}
+        }
+    }
+
+    fn start_editing(&mut self) {
+        if self.selected_model_index.is_some() || self.read_only {
+            self.focus = Focus::Input;
+            self.status_message = format!("Editing prompt... Enter: Newline, {}: Send, Ctrl+O: Attach file, Ctrl+Z/Y: Undo/Redo, Ctrl+K: Clear, Esc: Leave (keeps draft).", self.send_key());
+        } else if self.no_models {
+            self.status_message = "No models installed yet. Press 1-3 to pull a starter, b to browse.".to_string();
         } else {
-            self.status_message = "Error: No model selected.".to_string();
+            self.status_message = "Select a model first (Up/Down keys).".to_string();
         }
-        // Always return to Normal mode after trying to submit
-        self.input_mode = InputMode::Normal;
     }
 
+    // Asks in a dialog before running `action`
+    fn confirm(&mut self, question: String, action: Confirmation) {
+        self.modals.push(Modal::Confirm { question, action });
+    }
 
-    fn scroll_down(&mut self, amount: u16) {
-        self.scroll_offset = self.scroll_offset.saturating_add(amount);
-        // TODO: Clamp scroll_offset based on actual content height for more robust scrolling
+    // Ctrl+L and /clear both ask first
+    fn confirm_clear(&mut self) {
+        self.confirm("Clear the conversation? It will be archived.".to_string(), Confirmation::ClearConversation);
     }
 
-    fn scroll_up(&mut self, amount: u16) {
-        self.scroll_offset = self.scroll_offset.saturating_sub(amount);
+    // The models list has focus, or the Models screen is shown
+    fn model_keys(&self) -> bool {
+        self.active_tab == ActiveTab::Models || (self.active_tab == ActiveTab::Chat && self.focus == Focus::Models)
     }
-}
 
-// --- Main Application Logic ---
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    // Setup terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    // Current spinner frame while a response is streaming
+    fn spinner_frame(&self) -> Option<&'static str> {
+        let started = self.stream_started?;
+        let frame = started.elapsed().as_millis() / SPINNER_INTERVAL.as_millis();
+        Some(SPINNER_FRAMES[frame as usize % SPINNER_FRAMES.len()])
+    }
 
-    // Create communication channel and App instance
-    let (tx, rx) = mpsc::channel(100); // Channel buffer size 100
-    let mut app = App::new(rx, tx.clone());
+    // Something on screen moves with time alone: the spinner and streaming
+    // cursor, the health tab's "checked Ns ago", or the FPS readout itself
+    fn is_animating(&self) -> bool {
+        self.is_loading || self.active_tab == ActiveTab::Health || self.frames.show_stats || self.limiter.waiting() > 0
+    }
 
-    // --- Initial Async Tasks ---
-    // Fetch models immediately
-    let client = app.http_client.clone();
-    let initial_event_sender = app.event_sender.clone();
-    tokio::spawn(async move {
-        let models_result = fetch_models(client).await;
-        // Send result back, handling potential channel send error
-        if initial_event_sender.send(AppEvent::ModelsFetched(models_result)).await.is_err() {
-            // Error sending back to main loop, maybe log to stderr
-            eprintln!("Error: Failed to send fetched models back to main loop.");
+    fn chat_mode(&self) -> bool {
+        self.config.chat_mode
+            || self.available_tools().is_some()
+            // Only /api/chat takes a system prompt and earlier turns
+            || self.conversation.iter().any(|turn| turn.role == Role::SystemPrompt || turn.example)
+    }
+
+    // MCP tools to offer the model, if there are any and the server can call them
+    fn available_tools(&self) -> Option<Arc<McpManager>> {
+        self.mcp
+            .clone()
+            .filter(|mcp| mcp.tool_count() > 0)
+            .filter(|_| Feature::Tools.check(self.server_version).is_ok())
+    }
+
+    // The tools, unless `model` is known not to call them
+    fn tools(&self, model: &str) -> Option<Arc<McpManager>> {
+        self.available_tools().filter(|_| self.may_use_tools(model))
+    }
+
+    // False only once /api/show has said the model can't call tools
+    fn may_use_tools(&self, model: &str) -> bool {
+        self.capabilities.get(model).is_none_or(|capabilities| capabilities.contains(&Capability::Tools))
+    }
+
+    // Orders the models list by size or name, keeping the same model selected
+    fn sort_models(&mut self) {
+        let selected = self.get_selected_model_name();
+        if self.sort_models_by_size {
+            let sizes = &self.model_sizes;
+            self.models.sort_by_key(|model| std::cmp::Reverse(sizes.get(model).copied().unwrap_or(0)));
+        } else {
+            self.models.sort();
         }
-    });
+        self.select_model(selected);
+    }
 
-    // Run the main TUI loop
-    let res = run_app(&mut terminal, &mut app).await;
+    fn toggle_model_sort(&mut self) {
+        self.sort_models_by_size = !self.sort_models_by_size;
+        self.sort_models();
+        self.status_message = if self.sort_models_by_size {
+            "Models sorted by size, largest first. 's' to sort by name.".to_string()
+        } else {
+            "Models sorted by name. 's' to sort by size.".to_string()
+        };
+    }
 
-    // Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    // Selects `model` if it's still listed, otherwise the first model
+    fn select_model(&mut self, model: Option<String>) {
+        self.selected_model_index = match model.and_then(|model| self.models.iter().position(|name| *name == model)) {
+            Some(index) => Some(index),
+            None if self.models.is_empty() => None,
+            None => Some(0),
+        };
+    }
 
-    // Print errors if the app loop returned an error
-    if let Err(err) = res {
-        eprintln!("TUI Error: {}", err);
+    // Idle long enough to suggest deleting; 0 days in the config turns this off
+    fn is_cleanup_candidate(&self, model: &str) -> bool {
+        let days = self.config.cleanup_after_days;
+        days > 0 && self.usage.idle_days(model).is_some_and(|idle| idle >= days as i64)
     }
 
-    Ok(())
-}
+    fn confirm_delete_model(&mut self) {
+        if self.refuse_if_read_only() {
+            return;
+        }
+        let Some(model) = self.get_selected_model_name() else {
+            self.status_message = "Select a model first (Up/Down keys).".to_string();
+            return;
+        };
+        let size = self.model_sizes.get(&model).map_or(String::new(), |&size| format!(" ({})", health::format_bytes(size)));
+        self.confirm(format!("Delete {}{} from the server?", model, size), Confirmation::DeleteModel(model));
+    }
 
+    fn delete_model(&mut self, model: String) {
+        let client = self.http_client.clone();
+        let sender = self.event_sender.clone();
+        self.status_message = format!("Deleting {}...", model);
+        self.jobs.spawn(format!("Delete {}", model), async move {
+            let result = usage::delete_model(client, &model).await;
+            let _ = sender.send(AppEvent::ModelDeleted { model, result }).await;
+        });
+    }
 
-// --- Main Event Loop ---
-async fn run_app<B: Backend>(
-    terminal: &mut Terminal<B>,
-    app: &mut App,
-) -> Result<(), AppError> {
+    // Times a round trip to `server` for the status bar; the default server
+    // also reports its version
+    fn preflight(&self, server: String) {
+        let client = self.http_client.clone();
+        let sender = self.event_sender.clone();
+        self.jobs.spawn(format!("Ping {}", server), async move {
+            let preflight = version::preflight(client, server).await;
+            let _ = sender.send(AppEvent::PreflightDone(preflight)).await;
+        });
+    }
 
-    loop {
-        // Draw UI on each iteration
-        terminal.draw(|f| ui(f, app))?;
+    fn refresh_models(&self) {
+        let client = self.http_client.clone();
+        let event_sender = self.event_sender.clone();
+        self.jobs.spawn("Refresh the model list", async move {
+            let models_result = fetch_models(client).await;
+            // Fails only once the main loop has gone; printing then would
+            // draw over the screen (or the console on Windows)
+            let _ = event_sender.send(AppEvent::ModelsFetched(models_result)).await;
+        });
+    }
+
+    // Opens the library browser on the bundled index, swapping in the
+    // configured one when it arrives
+    fn open_library(&mut self, query: String) {
+        if self.refuse_if_read_only() {
+            return;
+        }
+        self.input_buffer.clear();
+        self.focus = Focus::Conversation;
+        self.modals.push(Modal::Library(LibraryBrowser::new(library::bundled_index(), "bundled index".to_string(), query)));
+        if let Some(url) = self.config.library_url.clone() {
+            let client = self.http_client.clone();
+            let sender = self.event_sender.clone();
+            self.jobs.spawn("Fetch the library index", async move {
+                let result = library::fetch_index(client, &url).await;
+                let _ = sender.send(AppEvent::LibraryFetched(result)).await;
+            });
+        }
+    }
+
+    fn queue_pulls(&mut self, models: Vec<String>) {
+        if self.refuse_if_read_only() {
+            return;
+        }
+        let queued = models.iter().filter(|model| self.transfers.queue(model)).count();
+        self.save_pending_pulls();
+        self.input_buffer.clear();
+        self.focus = Focus::Conversation;
+        self.active_tab = ActiveTab::Transfers;
+        self.status_message = match queued {
+            0 => "Already pulling those models.".to_string(),
+            n => format!("Queued {} pull(s). j/k: Select, c: Cancel.", n),
+        };
+        self.start_pulls();
+    }
+
+    // Starts queued pulls while there are free slots
+    fn start_pulls(&mut self) {
+        for model in self.transfers.start_ready() {
+            let job = self.jobs.spawn(
+                format!("Pull {}", model),
+                transfers::pull(self.http_client.clone(), self.config.stream_decoding, model.clone(), self.event_sender.clone()),
+            );
+            self.transfers.set_job(&model, job);
+        }
+    }
+
+    // Remembers unfinished pulls so the next launch can resume them
+    fn save_pending_pulls(&mut self) {
+        if let Err(e) = self.transfers.save_pending() {
+            self.notifications.push(ErrorReport::from(&e).context("Failed to save the pull queue"));
+        }
+    }
+
+    fn cancel_pull(&mut self) {
+        match self.transfers.cancel_selected() {
+            Some((model, job)) => {
+                if let Some(job) = job {
+                    self.jobs.cancel(job);
+                }
+                self.status_message = format!("Cancelled the pull of {}.", model);
+                self.save_pending_pulls();
+                self.start_pulls();
+            }
+            None => self.status_message = "Select a queued or running pull to cancel.".to_string(),
+        }
+    }
+
+    // Looks up each model's capabilities in the background; failures just
+    // leave the model without badges
+    fn fetch_capabilities(&self) {
+        for model in &self.models {
+            let client = self.http_client.clone();
+            let sender = self.event_sender.clone();
+            let model = model.clone();
+            self.jobs.spawn(format!("Capabilities of {}", model), async move {
+                if let Ok(capabilities) = capabilities::fetch(client, &model).await {
+                    let _ = sender.send(AppEvent::CapabilitiesFetched { model, capabilities }).await;
+                }
+            });
+        }
+    }
+
+    // Estimated tokens the draft would send, including history in chat mode
+    fn draft_token_estimate(&self) -> usize {
+        let history = if self.chat_mode() {
+            self.chat_history().iter().map(|message| tokens::estimate_tokens(&message.content)).sum()
+        } else {
+            0
+        };
+        history + tokens::estimate_tokens(self.input_buffer.text())
+    }
+
+    // The conversation as /api/chat messages, leaving out errors and notices.
+    // Tool calls go back as the assistant's request, each followed by its
+    // result; a result whose call was excluded is left out with it
+    fn chat_history(&self) -> Vec<ChatMessage> {
+        let mut messages = Vec::new();
+        let mut call_sent = false;
+        for turn in &self.conversation {
+            if let Some(call) = &turn.tool_call {
+                call_sent = !turn.excluded;
+                if call_sent {
+                    let mut message = ChatMessage::new("assistant", String::new());
+                    message.tool_calls.push(call.clone());
+                    messages.push(message);
+                }
+                continue;
+            }
+            if turn.excluded || (turn.role == Role::Tool && !call_sent) {
+                continue;
+            }
+            if let Some(role) = turn.role.chat_role() {
+                messages.push(ChatMessage::new(role, turn.chat_content()));
+            }
+        }
+        messages
+    }
+
+    fn submit_prompt(&mut self) {
+        // Slash commands are handled locally and never sent to the model
+        if let Some(command) = commands::parse(self.input_buffer.text()) {
+            self.run_command(command);
+            return;
+        }
+
+        let prompt = self.input_buffer.text().trim().to_string();
+        if prompt.is_empty() {
+            // Buffer is empty or only whitespace
+            self.status_message = "Cannot send an empty prompt.".to_string();
+        } else {
+            match self.expand_variables(&prompt) {
+                Ok(prompt) => {
+                    if self.send_prompt(prompt) {
+                        self.input_buffer.clear();
+                    }
+                }
+                // Keep the draft so the variable can be fixed
+                Err(e) => self.status_message = format!("Prompt not sent: {}", e),
+            }
+        }
+        // Always return to Normal mode after trying to submit
+        self.focus = Focus::Conversation;
+    }
+
+    // Adds the prompt to the conversation and starts streaming the reply.
+    // Returns false if nothing was sent.
+    fn send_prompt(&mut self, prompt: String) -> bool {
+        let Some(model_name) = self.get_selected_model_name() else {
+            self.status_message = "Error: No model selected.".to_string();
+            return false;
+        };
+        self.send_prompt_to(model_name, prompt)
+    }
+
+    fn send_prompt_to(&mut self, model_name: String, prompt: String) -> bool {
+        self.send_formatted_prompt(model_name, prompt, None)
+    }
+
+    // With a preset, its instruction goes after the prompt like a suffix
+    fn send_formatted_prompt(&mut self, model_name: String, prompt: String, preset: Option<FormatPreset>) -> bool {
+        if self.refuse_if_read_only() {
+            return false;
+        }
+        let prompt = match self.plugins.transform_outgoing(prompt) {
+            Ok(prompt) => prompt,
+            Err(e) => {
+                self.status_message = format!("Error in plugin on_send: {}", e);
+                return false;
+            }
+        };
+        // Redacted before anything else sees it, the conversation included
+        let (prompt, redacted) = match self.redactor.redact_prompt(prompt) {
+            Ok(redacted) => redacted,
+            Err(e) => {
+                self.status_message = format!("Prompt not sent: {}", e);
+                return false;
+            }
+        };
+
+        if self.chat_mode() {
+            if let Err(msg) = Feature::ChatEndpoint.check(self.server_version) {
+                self.status_message = format!("Error: {}. Turn off chat_mode in the config.", msg);
+                return false;
+            }
+        }
+        let shape = match self.reply_shape(preset.and_then(FormatPreset::server_format)) {
+            Ok(shape) => shape,
+            Err(msg) => {
+                self.status_message = format!("Error: {}.", msg);
+                return false;
+            }
+        };
+
+        let mut turn = ConversationTurn::user(prompt);
+        let mut wrap = self.prompt_wrap.clone();
+        if let Some(preset) = preset {
+            wrap.suffix = [wrap.suffix.as_str(), preset.instruction()]
+                .into_iter()
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n\n");
+        }
+        turn.wrap = Some(wrap).filter(|wrap| !wrap.is_empty());
+        let prompt = turn.sent_text();
+        let trimmed = match self.fit_context(&prompt) {
+            Ok(trimmed) => trimmed,
+            Err(e) => {
+                self.status_message = format!("Prompt not sent: {}", e);
+                return false;
+            }
+        };
+        let (note, earlier) = match trimmed {
+            Some(Trimmed { note, earlier }) => (Some(note), earlier),
+            None => (None, None),
+        };
+        self.conversation.push(turn);
+        self.write_tee(|tee| tee.prompt(&prompt));
+        let request = if self.chat_mode() {
+            PromptRequest::Chat { messages: self.chat_history(), mcp: self.tools(&model_name), shape, earlier }
+        } else {
+            PromptRequest::Generate { shape }
+        };
+        self.start_request(model_name, prompt, request);
+        if redacted > 0 {
+            self.status_message = format!("{} ({} redacted)", self.status_message, redacted);
+        }
+        if let Some(note) = note {
+            self.status_message = format!("{} ({})", self.status_message, note);
+        }
+        true
+    }
+
+    // Makes room for `prompt` when it and the history are estimated to be
+    // over the context window, as `context_trim` says: the oldest turns are
+    // excluded until it fits. Err when the prompt shouldn't be sent
+    fn fit_context(&mut self, prompt: &str) -> Result<Option<Trimmed>, String> {
+        let budget = self.config.context_window;
+        // (turn index, tokens, trimmable) of the history that would be sent
+        let history: Vec<(usize, usize, bool)> = if self.chat_mode() {
+            self.conversation
+                .iter()
+                .enumerate()
+                .filter(|(_, turn)| !turn.excluded && turn.role.chat_role().is_some())
+                .map(|(index, turn)| {
+                    let trimmable = turn.role != Role::SystemPrompt && !turn.example;
+                    (index, tokens::estimate_tokens(&turn.chat_content()), trimmable)
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let sizes: Vec<(usize, bool)> = history
+            .iter()
+            .map(|&(_, tokens, trimmable)| (tokens, trimmable))
+            .chain([(tokens::estimate_tokens(prompt), false)])
+            .collect();
+        let Some(plan) = context_trim::plan(&sizes, budget) else { return Ok(None) };
+
+        let strategy = self.config.context_trim;
+        if strategy == TrimStrategy::Error {
+            return Err(format!(
+                "~{} tokens is over the {}-token context; exclude turns with 'x' or set context_trim",
+                plan.total, budget
+            ));
+        }
+        let over = if plan.remaining > budget {
+            format!("~{} tokens is over the {}-token context, so the server may cut the start", plan.remaining, budget)
+        } else {
+            String::new()
+        };
+        if plan.trimmed.is_empty() {
+            return Ok(Some(Trimmed { note: over, earlier: None }));
+        }
+
+        let indices: Vec<usize> = plan.trimmed.iter().map(|&position| history[position].0).collect();
+        let earlier = match strategy {
+            TrimStrategy::SummarizeOldest => {
+                let last = indices[indices.len() - 1];
+                let transcript = indices
+                    .iter()
+                    .map(|&index| format!("{}: {}", self.conversation[index].name, self.conversation[index].chat_content()))
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+                // The summary model sees these turns too
+                let (transcript, _) = self.redactor.redact_prompt(transcript).map_err(|e| e.to_string())?;
+                Some(EarlierTurns { turn_index: last, turn_text: self.conversation[last].text.to_string(), transcript })
+            }
+            _ => None,
+        };
+        for &index in &indices {
+            self.conversation[index].excluded = true;
+        }
+        let noun = if indices.len() == 1 { "turn" } else { "turns" };
+        let mut note = format!(
+            "left out the {} oldest {}, ~{} tokens, to fit the {}-token context",
+            indices.len(),
+            noun,
+            plan.trimmed_tokens,
+            budget
+        );
+        if earlier.is_some() {
+            note.push_str(", sending a summary instead");
+        }
+        if !over.is_empty() {
+            note = format!("{}; {}", note, over);
+        }
+        Ok(Some(Trimmed { note, earlier }))
+    }
+
+    // The getting-started view replaces an empty conversation while the
+    // server has no models
+    fn showing_empty_state(&self) -> bool {
+        self.no_models && self.active_tab == ActiveTab::Chat && self.conversation.is_empty() && !self.read_only
+    }
+
+    // The key named in hints for sending the draft
+    fn send_key(&self) -> String {
+        if self.enhanced_keys { "Ctrl+Enter".to_string() } else { self.config.send_key.to_string() }
+    }
+
+    fn refuse_if_read_only(&mut self) -> bool {
+        if self.read_only {
+            self.status_message = "Read-only transcript: only /search and /export are available.".to_string();
+        }
+        self.read_only
+    }
+
+    // Selects every turn containing `query` (case-insensitive), starting with the first
+    fn search(&mut self, query: &str) {
+        let query = query.to_lowercase();
+        self.search_matches = self
+            .conversation
+            .iter()
+            .enumerate()
+            .filter(|(_, turn)| turn.text.to_lowercase().contains(&query))
+            .map(|(index, _)| index)
+            .collect();
+        if self.search_matches.is_empty() {
+            self.status_message = format!("No turns contain '{}'.", query);
+        } else {
+            self.select_search_match(0);
+        }
+    }
+
+    // Moves to the next (delta 1) or previous (-1) search match, wrapping around
+    fn next_search_match(&mut self, delta: isize) {
+        if self.search_matches.is_empty() {
+            self.status_message = "No search results. Press '/' to search.".to_string();
+            return;
+        }
+        let count = self.search_matches.len() as isize;
+        let current = self
+            .selected_turn
+            .and_then(|turn| self.search_matches.iter().position(|&index| index == turn))
+            .map_or(-1, |position| position as isize);
+        self.select_search_match((current + delta).rem_euclid(count) as usize);
+    }
+
+    fn select_search_match(&mut self, position: usize) {
+        let index = self.search_matches[position];
+        self.selected_turn = Some(index);
+        self.scroll_offset = self.turn_line_offsets()[index];
+        self.status_message = format!(
+            "Match {}/{} (turn {}). n/N: Next/previous match.",
+            position + 1,
+            self.search_matches.len(),
+            index + 1
+        );
+    }
+
+    // Writes to the tee log, turning tee mode off if the file can't be written
+    fn write_tee(&mut self, write: impl FnOnce(&mut Tee) -> Result<(), AppError>) {
+        let Some(tee) = self.tee.as_mut() else { return };
+        if let Err(e) = write(tee) {
+            self.status_message = format!("Error writing to {}, tee stopped: {}", tee.path.display(), e);
+            self.tee = None;
+        }
+    }
+
+    // Finds an installed model by name, accepting "name" for "name:latest"
+    fn resolve_model(&self, name: &str) -> Option<String> {
+        self.models
+            .iter()
+            .find(|model| *model == name || model.strip_suffix(":latest") == Some(name))
+            .cloned()
+    }
+
+    fn start_arena(&mut self, prompt: String) {
+        if self.refuse_if_read_only() {
+            return;
+        }
+        if self.arena.as_ref().is_some_and(|arena| !arena.is_finished()) {
+            self.status_message = "The current arena match is still running.".to_string();
+            return;
+        }
+        let Some(arena) = ArenaMatch::new(prompt, &self.models) else {
+            self.status_message = "Error: The arena needs at least two installed models.".to_string();
+            return;
+        };
+        for (side, model) in arena.models.iter().enumerate() {
+            self.jobs.spawn(format!("Arena: {}", model), arena::stream_side(
+                self.http_client.clone(),
+                self.config.stream_decoding,
+                self.limiter.clone(),
+                model.clone(),
+                arena.prompt.clone(),
+                side,
+                self.event_sender.clone(),
+            ));
+        }
+        self.arena = Some(arena);
+        self.input_buffer.clear();
+        self.focus = Focus::Conversation;
+        self.active_tab = ActiveTab::Arena;
+        self.status_message = "Two hidden models are answering...".to_string();
+    }
+
+    // Embeds one or two texts with the selected model for the playground
+    fn embed_texts(&mut self, texts: Vec<String>) {
+        if self.refuse_if_read_only() {
+            return;
+        }
+        let Some(model) = self.get_selected_model_name() else {
+            self.status_message = "Error: No model selected.".to_string();
+            return;
+        };
+        let redacted = texts.into_iter().map(|text| self.redactor.redact_prompt(text).map(|(text, _)| text));
+        let texts: Vec<String> = match redacted.collect() {
+            Ok(texts) => texts,
+            Err(e) => {
+                self.status_message = format!("Not embedded: {}", e);
+                return;
+            }
+        };
+        let note = match self.capabilities.get(&model) {
+            Some(capabilities) if !capabilities.contains(&Capability::Embed) => " (it has no [embed] badge, so this may fail)",
+            _ => "",
+        };
+        self.input_buffer.clear();
+        self.focus = Focus::Conversation;
+        self.active_tab = ActiveTab::Embeddings;
+        self.status_message = format!("Embedding with {}{}...", model, note);
+        let client = self.http_client.clone();
+        let sender = self.event_sender.clone();
+        self.jobs.spawn(format!("Embed with {}", model), async move {
+            let result = embeddings::embed(client, &model, &texts).await;
+            let _ = sender.send(AppEvent::Embedded { model, texts, result }).await;
+        });
+    }
+
+    fn vote_arena(&mut self, vote: Vote) {
+        let Some(arena) = self.arena.as_mut().filter(|arena| arena.vote.is_none()) else {
+            self.status_message = "Nothing to vote on. Start a match with /arena <prompt>.".to_string();
+            return;
+        };
+        if !arena.is_finished() {
+            self.status_message = "Wait for both answers before voting.".to_string();
+            return;
+        }
+        arena.vote = Some(vote);
+        let [model_a, model_b] = &arena.models;
+        self.status_message = match self.scoreboard.record(model_a, model_b, vote) {
+            Ok(()) => format!("A was {}, B was {}.", model_a, model_b),
+            Err(e) => format!("A was {}, B was {}. Error saving the scoreboard: {}", model_a, model_b, e),
+        };
+    }
+
+    fn start_bench(&mut self, models: &str, prompts: &str) {
+        if self.refuse_if_read_only() {
+            return;
+        }
+        if self.bench.as_ref().is_some_and(|run| !run.finished) {
+            self.status_message = "A benchmark is already running.".to_string();
+            return;
+        }
+        let models = if models == "all" {
+            self.models.clone()
+        } else {
+            let mut resolved = Vec::new();
+            for name in models.split(',').filter(|name| !name.is_empty()) {
+                match self.resolve_model(name) {
+                    Some(model) => resolved.push(model),
+                    None => {
+                        self.status_message = format!("Error: No installed model named '{}'.", name);
+                        return;
+                    }
+                }
+            }
+            resolved
+        };
+        if models.is_empty() {
+            self.status_message = "Error: No models to benchmark.".to_string();
+            return;
+        }
+        let prompts = match bench::load_prompts(prompts) {
+            Ok(prompts) => prompts,
+            Err(e) => {
+                self.status_message = format!("Error reading prompts: {}", e);
+                return;
+            }
+        };
+
+        self.input_buffer.clear();
+        self.focus = Focus::Conversation;
+        self.active_tab = ActiveTab::Bench;
+        self.status_message = format!("Benchmarking {} models on {} prompts...", models.len(), prompts.len());
+        self.bench = Some(BenchRun { models: models.clone(), prompts: prompts.clone(), results: Vec::new(), finished: false });
+        self.jobs.spawn(format!("Benchmark {} models", models.len()), bench::run(self.http_client.clone(), self.limiter.clone(), models, prompts, self.event_sender.clone()));
+    }
+
+    // Streams a response for `prompt` without adding anything to the conversation
+    fn start_request(&mut self, model_name: String, prompt: String, request: PromptRequest) {
+        if let Err(e) = self.usage.used(&model_name) {
+            self.notifications.push(ErrorReport::from(&e).context("Failed to save model usage"));
+        }
+        if let Err(e) = self.stats.prompted(&model_name) {
+            self.notifications.push(ErrorReport::from(&e).context("Failed to save usage statistics"));
+        }
+        self.metrics.request(&model_name);
+        self.idle.used(&model_name);
+        self.is_loading = true;
+        self.stream_started = Some(Instant::now());
+        self.streaming_model = Some(model_name.clone());
+        self.request_count += 1;
+        self.active_request = Some(self.request_count);
+        self.status_message = format!("Asking {}...", model_name);
+        self.scroll_offset = 0;
+        self.model_loading = false;
+        self.load_note = None;
+        self.check_model_loaded(model_name.clone());
+
+        // The chosen model first, then the configured fallbacks
+        let primary = Route { model: model_name, server: None };
+        let mut routes = vec![primary.clone()];
+        routes.extend(self.config.routing.fallbacks.iter().filter(|route| **route != primary).cloned());
+
+        let client = self.http_client.clone();
+        let events = ReplyEvents { request: self.request_count, sender: self.event_sender.clone() };
+        let hooks = self.config.hooks.clone();
+        let decoding = self.config.stream_decoding;
+        let limiter = self.limiter.clone();
+        let label = format!("Reply from {}", primary.model);
+        self.request_job = Some(self.jobs.spawn(label, async move {
+            // Errors reach the conversation as AppEvents; the job keeps the
+            // result for the jobs popup
+            // Wait our turn on a shared server; the slot is held while streaming
+            let _slot = limiter.acquire().await;
+            dispatch_prompt(client, hooks, decoding, routes, prompt, request, events).await
+        }));
+    }
+
+    // A cold model can take a while to load before anything streams; say so
+    // instead of looking hung
+    fn check_model_loaded(&self, model: String) {
+        let client = self.http_client.clone();
+        let event_sender = self.event_sender.clone();
+        self.jobs.spawn(format!("Check that {} is loaded", model), async move {
+            if let Ok(false) = health::is_loaded(&client, &model).await {
+                let _ = event_sender.send(AppEvent::ModelLoading(model)).await;
+            }
+        });
+    }
+
+    // The shape of the next reply; Err when the server is too old for it
+    fn reply_shape(&self, format: Option<serde_json::Value>) -> Result<ReplyShape, String> {
+        if let Some(format) = &format {
+            Feature::for_format(format).check(self.server_version)?;
+        }
+        let think = self.config.think;
+        if think.is_some() {
+            Feature::Thinking.check(self.server_version).map_err(|msg| format!("{}; remove think from the config", msg))?;
+        }
+        Ok(ReplyShape { format, num_predict: self.num_predict, think })
+    }
+
+    // Asks the model to pick up an interrupted reply, or one cut off at the
+    // num_predict limit, where it stopped; the continuation streams into the
+    // same turn.
+    fn resume_interrupted(&mut self) {
+        if self.refuse_if_read_only() {
+            return;
+        }
+        if self.is_loading {
+            self.status_message = "Wait for the current response to finish.".to_string();
+            return;
+        }
+        let Some(turn) = self.conversation.last().filter(|turn| turn.interrupted || turn.finish == Some(Finish::Length)) else {
+            self.status_message = "Nothing to resume.".to_string();
+            return;
+        };
+        // The model that wrote the partial reply finishes it
+        let model_name = turn.name.clone();
+        let partial = turn.text.to_string();
+        let question = self
+            .conversation
+            .iter()
+            .rev()
+            .find(|turn| turn.role == Role::User)
+            .map(ConversationTurn::sent_text)
+            .unwrap_or_default();
+        let shape = match self.reply_shape(None) {
+            Ok(shape) => shape,
+            Err(msg) => {
+                self.status_message = format!("Error: {}.", msg);
+                return;
+            }
+        };
+        if let Some(turn) = self.conversation.last_mut() {
+            turn.interrupted = false;
+            turn.finish = None;
+        }
+
+        let instruction = "Your previous answer was cut off. Continue it exactly where it stopped, \
+                           without repeating anything already written.";
+        let (prompt, request) = if self.chat_mode() {
+            let mut messages = self.chat_history();
+            messages.push(ChatMessage::new("user", instruction.to_string()));
+            (instruction.to_string(), PromptRequest::Chat { messages, mcp: self.tools(&model_name), shape, earlier: None })
+        } else {
+            let prompt = format!("{}\n\nQuestion:\n{}\n\nAnswer so far:\n{}", instruction, question, partial);
+            (prompt, PromptRequest::Generate { shape })
+        };
+        self.start_request(model_name, prompt, request);
+        // The continuation streams into the interrupted turn
+        if let Some(turn) = self.conversation.last_mut() {
+            turn.request = self.active_request;
+        }
+    }
+
+    // Unescapes a finished reply that arrived HTML-escaped or with :shortcode:
+    // emoji, before plugins and post-processing see it
+    fn decode_reply(&mut self) {
+        let Some(turn) = self.conversation.last_mut() else { return };
+        if !turn.is_model_reply() {
+            return;
+        }
+        if let Some(text) = entities::decode(&turn.text, self.config.decode_entities, self.config.emoji_shortcodes) {
+            turn.text = TurnText::from(text);
+        }
+    }
+
+    // Lets plugins rewrite a response once it has finished streaming
+    fn apply_incoming_plugins(&mut self) {
+        let Some(turn) = self.conversation.last_mut() else { return };
+        if !turn.is_model_reply() {
+            return;
+        }
+        match self.plugins.transform_incoming(turn.text.to_string()) {
+            Ok(text) => turn.text = TurnText::from(text),
+            Err(e) => self.status_message = format!("Error in plugin on_receive: {}", e),
+        }
+    }
+
+    // Filters the finished reply, then runs the other post-processing steps on it
+    fn postprocess_response(&mut self) {
+        let Some(turn_index) = self.conversation.len().checked_sub(1) else { return };
+        if !self.conversation[turn_index].is_model_reply() {
+            return;
+        }
+        // The other steps run once the filter has finished
+        if self.postprocess.filter && self.config.hooks.post_receive.is_some() {
+            self.run_post_receive_hook(turn_index);
+        } else {
+            self.run_postprocess_steps(turn_index);
+        }
+    }
+
+    fn run_postprocess_steps(&mut self, turn_index: usize) {
+        let Some(turn) = self.conversation.get(turn_index) else { return };
+        let reply = turn.text.clone();
+        let model = turn.name.clone();
+        let config = &self.config.postprocess;
+        let mut notes = Vec::new();
+
+        if self.postprocess.extract_code {
+            match postprocess::save_code_blocks(config, &reply) {
+                Ok(Some((dir, count))) => notes.push(format!("{} code blocks saved to {}", count, dir.display())),
+                Ok(None) => {}
+                Err(e) => notes.push(format!("error saving code: {}", e)),
+            }
+        }
+        if self.postprocess.copy {
+            notes.push(match self.clipboard.copy(&self.config.copy_format.apply(&reply)) {
+                Ok(()) => "copied".to_string(),
+                Err(e) => format!("error copying: {}", e),
+            });
+        }
+        let prompt = self.prompt_before(turn_index);
+        if self.postprocess.journal {
+            if let Err(e) = postprocess::append_journal(config, &model, prompt, &reply) {
+                notes.push(format!("error writing journal: {}", e));
+            } else {
+                notes.push("journaled".to_string());
+            }
+        }
+        if self.postprocess.note {
+            if let Err(e) = postprocess::append_note(config, &model, prompt, &reply) {
+                notes.push(format!("error appending to note: {}", e));
+            } else {
+                notes.push("noted".to_string());
+            }
+        }
+        if !notes.is_empty() {
+            self.status_message = format!("Response received ({}).", notes.join(", "));
+        }
+    }
+
+    // Asks the current model to translate the selected turn, or else the
+    // clipboard; the translation arrives as a new turn
+    fn translate(&mut self, translation: Option<Translation>) {
+        let Some(translation) = translation.or_else(|| self.last_translation.clone()) else {
+            self.status_message = "Usage: /translate [from:]<to>, e.g. /translate french or /translate de:en".to_string();
+            return;
+        };
+        let (text, source) = match self.selected_turn.and_then(|index| self.conversation.get(index)) {
+            Some(turn) => (turn.text.to_string(), format!("{}'s turn", turn.name)),
+            None => match self.clipboard.paste() {
+                Ok(text) if !text.trim().is_empty() => (text, "the clipboard".to_string()),
+                Ok(_) => {
+                    self.status_message = "Select a turn ('[' / ']') or copy some text to translate.".to_string();
+                    return;
+                }
+                Err(e) => {
+                    self.status_message = format!("Error reading the clipboard: {}", e);
+                    return;
+                }
+            },
+        };
+        let languages = match &translation.from {
+            Some(from) => format!("from {} to {}", from, translation.to),
+            None => format!("to {}", translation.to),
+        };
+        let prompt = format!(
+            "Translate the following text {}. Reply with the translation only, keeping its formatting, \
+             markdown and code blocks intact.\n\n{}",
+            languages,
+            text.trim()
+        );
+        self.last_translation = Some(translation);
+        if self.send_prompt(prompt) {
+            self.input_buffer.clear();
+            self.focus = Focus::Conversation;
+            self.status_message = format!("Translating {} {}... (/translate repeats this)", source, languages);
+        }
+    }
+
+    fn prompt_wrap_status(&self) -> String {
+        if self.prompt_wrap.is_empty() {
+            "Prompts are sent as typed.".to_string()
+        } else {
+            format!("Prompts in this conversation are wrapped ({}).", self.prompt_wrap.summary())
+        }
+    }
+
+    // The prompt a reply answers
+    fn prompt_before(&self, turn_index: usize) -> &str {
+        self.conversation[..turn_index]
+            .iter()
+            .rev()
+            .find(|turn| turn.role == Role::User)
+            .map_or("", |turn| &turn.text)
+    }
+
+    // Appends the selected exchange, or else the latest one, to the notes file.
+    // Selecting a prompt picks the reply that follows it.
+    fn append_to_note(&mut self) {
+        let reply_index = match self.selected_turn {
+            Some(index) => self.conversation.iter().skip(index).position(ConversationTurn::is_model_reply).map(|offset| index + offset),
+            None => self.conversation.iter().rposition(ConversationTurn::is_model_reply),
+        };
+        let Some(turn_index) = reply_index else {
+            self.status_message = "No reply to add to the note yet.".to_string();
+            return;
+        };
+        let turn = &self.conversation[turn_index];
+        let prompt = self.prompt_before(turn_index);
+        self.status_message = match postprocess::append_note(&self.config.postprocess, &turn.name, prompt, &turn.text) {
+            Ok(path) => format!("Exchange appended to {}.", path.display()),
+            Err(e) => format!("Error appending to note: {}", e),
+        };
+    }
+
+    fn run_post_receive_hook(&mut self, turn_index: usize) {
+        let Some(command) = self.config.hooks.post_receive.clone() else { return };
+        let text = self.conversation[turn_index].text.to_string();
+        let timeout = self.config.hooks.timeout();
+        let event_sender = self.event_sender.clone();
+        self.jobs.spawn("Post-receive hook", async move {
+            let result = hooks::run_hook(&command, &text, timeout).await;
+            let _ = event_sender.send(AppEvent::ResponseHookDone { turn_index, text, result }).await;
+        });
+    }
+
+
+    fn run_command(&mut self, command: Result<Command, String>) {
+        match command {
+            Ok(Command::File(path)) => {
+                // Replace the command text with the attachment and keep editing
+                self.input_buffer.clear();
+                self.attach_file(Path::new(&path));
+            }
+            Ok(Command::Diff { staged }) => match git::diff(staged) {
+                Ok(diff) if diff.trim().is_empty() => {
+                    self.status_message = "No changes to explain.".to_string();
+                }
+                Ok(diff) => {
+                    let prompt = format!(
+                        "Explain the following changes and review them for bugs or risky edits:\n\n{}",
+                        attachment::fenced_text("diff", &diff)
+                    );
+                    self.input_buffer.clear();
+                    self.send_prompt(prompt);
+                    self.focus = Focus::Conversation;
+                }
+                Err(e) => self.status_message = format!("Error: {}", e),
+            },
+            Ok(Command::CommitMsg) => match git::diff(true) {
+                Ok(diff) if diff.trim().is_empty() => {
+                    self.status_message = "Nothing staged. Stage changes with `git add` first.".to_string();
+                }
+                Ok(diff) => {
+                    let prompt = format!(
+                        "Write a commit message in the Conventional Commits format for the following staged changes. \
+                         Reply with the commit message only.\n\n{}",
+                        attachment::fenced_text("diff", &diff)
+                    );
+                    self.input_buffer.clear();
+                    if self.send_prompt(prompt) {
+                        self.response_action = Some(ResponseAction::CopyCommitMessage);
+                    }
+                    self.focus = Focus::Conversation;
+                }
+                Err(e) => self.status_message = format!("Error: {}", e),
+            },
+            Ok(Command::Watch { path, template }) => {
+                match FileWatch::start(Path::new(&path), template, self.event_sender.clone()) {
+                    Ok(file_watch) => {
+                        self.file_watch = Some(file_watch);
+                        self.input_buffer.clear();
+                        self.focus = Focus::Conversation;
+                        // Ask once right away, then again on every change
+                        self.run_watch_prompt();
+                    }
+                    Err(e) => self.status_message = format!("Error: {}", e),
+                }
+            }
+            Ok(Command::Unwatch) => {
+                self.input_buffer.clear();
+                self.focus = Focus::Conversation;
+                self.watch_pending_since = None;
+                self.status_message = match self.file_watch.take() {
+                    Some(file_watch) => format!("Stopped watching {}.", attachment::display_path(&file_watch.path)),
+                    None => "Not watching any file.".to_string(),
+                };
+            }
+            Ok(Command::Plugin { name, args }) if self.plugins.has_command(&name) => {
+                match self.plugins.run_command(&name, &args) {
+                    Ok(Some(prompt)) => {
+                        self.input_buffer.clear();
+                        self.send_prompt(prompt);
+                        self.focus = Focus::Conversation;
+                    }
+                    Ok(None) => {
+                        self.input_buffer.clear();
+                        self.focus = Focus::Conversation;
+                        self.status_message = format!("Ran /{}.", name);
+                    }
+                    Err(e) => self.status_message = format!("Error in /{}: {}", name, e),
+                }
+            }
+            Ok(Command::Ask { model, prompt }) => match self.resolve_model(&model) {
+                Some(model_name) => {
+                    if self.send_prompt_to(model_name, prompt) {
+                        self.input_buffer.clear();
+                        self.focus = Focus::Conversation;
+                    }
+                }
+                None => self.status_message = format!("Error: No installed model named '{}'.", model),
+            },
+            Ok(Command::Format { preset, prompt }) => {
+                let Some(model_name) = self.get_selected_model_name() else {
+                    self.status_message = "Error: No model selected.".to_string();
+                    return;
+                };
+                if self.send_formatted_prompt(model_name, prompt.clone(), Some(preset)) {
+                    self.format_check = Some(FormatCheck { preset, prompt, retries_left: self.config.format_retries });
+                    self.input_buffer.clear();
+                    self.focus = Focus::Conversation;
+                }
+            }
+            Ok(Command::Bench { models, prompts }) => self.start_bench(&models, &prompts),
+            Ok(Command::BenchExport(path)) => {
+                let Some(run) = &self.bench else {
+                    self.status_message = "Error: No benchmark results to export.".to_string();
+                    return;
+                };
+                match bench::export(run, Path::new(&path)) {
+                    Ok(()) => {
+                        self.input_buffer.clear();
+                        self.focus = Focus::Conversation;
+                        self.status_message = format!("Benchmark results written to {}.", path);
+                    }
+                    Err(e) => self.status_message = format!("Error exporting results: {}", e),
+                }
+            }
+            Ok(Command::Arena(prompt)) => self.start_arena(prompt),
+            Ok(Command::Pull(models)) => self.queue_pulls(models),
+            Ok(Command::Browse(query)) => self.open_library(query),
+            Ok(Command::Embed(texts)) => self.embed_texts(texts),
+            Ok(Command::Post { step, enabled }) => {
+                let Some(name) = step else {
+                    self.status_message = format!("Post-processing: {}", self.postprocess.summary());
+                    return;
+                };
+                let Some(step) = Step::parse(&name) else {
+                    self.status_message = "Error: Steps are code, copy, journal, note and filter.".to_string();
+                    return;
+                };
+                let flag = self.postprocess.get_mut(step);
+                *flag = enabled.unwrap_or(!*flag);
+                self.input_buffer.clear();
+                self.focus = Focus::Conversation;
+                self.status_message = format!("Post-processing: {}", self.postprocess.summary());
+            }
+            Ok(Command::Prefix(text)) => {
+                self.input_buffer.clear();
+                self.focus = Focus::Conversation;
+                self.prompt_wrap.prefix = text;
+                self.status_message = self.prompt_wrap_status();
+            }
+            Ok(Command::Suffix(text)) => {
+                self.input_buffer.clear();
+                self.focus = Focus::Conversation;
+                self.prompt_wrap.suffix = text;
+                self.status_message = self.prompt_wrap_status();
+            }
+            Ok(Command::Translate(translation)) => self.translate(translation),
+            Ok(Command::AppendToNote) => {
+                self.input_buffer.clear();
+                self.focus = Focus::Conversation;
+                self.append_to_note();
+            }
+            Ok(Command::Markdown(raw)) => {
+                self.input_buffer.clear();
+                self.focus = Focus::Conversation;
+                self.raw_markdown = raw.unwrap_or(!self.raw_markdown);
+                self.status_message = if self.raw_markdown {
+                    "Showing markdown source. Turns toggled with 'm' keep their own setting.".to_string()
+                } else {
+                    "Rendering markdown. Turns toggled with 'm' keep their own setting.".to_string()
+                };
+            }
+            Ok(Command::Layout(layout)) => {
+                self.input_buffer.clear();
+                self.focus = Focus::Conversation;
+                self.message_layout = layout.unwrap_or(self.message_layout.other());
+                self.status_message =
+                    format!("Layout: {} (set `message_layout` in the config to keep it).", self.message_layout.name());
+            }
+            Ok(Command::NumPredict(limit)) => {
+                self.input_buffer.clear();
+                self.focus = Focus::Conversation;
+                self.num_predict = limit;
+                self.status_message = match limit {
+                    Some(tokens) => format!("Replies stop after {} tokens (set `num_predict` in the config to keep it).", tokens),
+                    None => "Reply length left to the server.".to_string(),
+                };
+            }
+            Ok(Command::Theme(theme)) => {
+                self.input_buffer.clear();
+                self.focus = Focus::Conversation;
+                match theme {
+                    Some(theme) => {
+                        self.theme = theme;
+                        self.status_message = format!("Theme: {} (set `theme` in the config to keep it).", theme.name());
+                    }
+                    None => self.modals.push(Modal::ThemePicker(ThemePicker::new(self.theme))),
+                }
+            }
+            Ok(Command::Split) => {
+                self.input_buffer.clear();
+                self.focus = Focus::Conversation;
+                self.toggle_split();
+            }
+            Ok(Command::Reload) => {
+                self.input_buffer.clear();
+                self.focus = Focus::Conversation;
+                self.reload_config();
+            }
+            Ok(Command::Jobs) => {
+                self.input_buffer.clear();
+                self.focus = Focus::Conversation;
+                self.open_jobs();
+            }
+            Ok(Command::Tee(path)) => {
+                self.input_buffer.clear();
+                self.focus = Focus::Conversation;
+                let Some(path) = path else {
+                    self.status_message = match self.tee.take() {
+                        Some(tee) => format!("Stopped logging to {}.", tee.path.display()),
+                        None => "Tee is not on. Start it with /tee <path>.".to_string(),
+                    };
+                    return;
+                };
+                match Tee::open(Path::new(&path)) {
+                    Ok(tee) => {
+                        self.status_message = format!("Logging prompts and replies to {} (/tee to stop).", tee.path.display());
+                        self.tee = Some(tee);
+                    }
+                    Err(e) => self.status_message = format!("Error opening {}: {}", path, e),
+                }
+            }
+            Ok(Command::Search(query)) => {
+                self.input_buffer.clear();
+                self.focus = Focus::Conversation;
+                self.search(&query);
+            }
+            Ok(Command::Export { turns, path }) => self.export_turns(turns, path),
+            Ok(Command::Plugin { name, .. }) => {
+                self.status_message = format!("Unknown command: /{}", name);
+            }
+            Ok(Command::Clear) => {
+                self.input_buffer.clear();
+                self.focus = Focus::Conversation;
+                self.confirm_clear();
+            }
+            Ok(Command::New(name)) => {
+                self.input_buffer.clear();
+                self.focus = Focus::Conversation;
+                match name {
+                    None if !self.config.templates.is_empty() => {
+                        self.modals.push(Modal::TemplatePicker(TemplatePicker::new(&self.config.templates)))
+                    }
+                    name => self.new_conversation(name.as_deref()),
+                }
+            }
+            Err(msg) => {
+                // Keep the buffer so the command can be corrected
+                self.status_message = msg;
+            }
+        }
+    }
+
+    // Tab / Shift+Tab: the next or previous screen, wrapping around
+    fn step_tab(&mut self, step: isize) {
+        let count = ActiveTab::ALL.len() as isize;
+        let index = (self.active_tab.index() as isize + step).rem_euclid(count);
+        self.show_tab(ActiveTab::ALL[index as usize]);
+    }
+
+    fn show_tab(&mut self, tab: ActiveTab) {
+        if tab == self.active_tab || self.refuse_if_read_only() {
+            return;
+        }
+        match tab {
+            ActiveTab::Health => self.refresh_health(),
+            // Sessions saved since the last visit show up
+            ActiveTab::Sessions => {
+                let trash_days = self.config.trash_days;
+                let browser = self.session_browser.get_or_insert_with(|| SessionPicker::browser(trash_days));
+                if let SessionAction::Status(message) = browser.reload() {
+                    self.status_message = message;
+                }
+            }
+            _ => {}
+        }
+        self.active_tab = tab;
+    }
+
+    fn refresh_health(&mut self) {
+        self.health_requested = Some(Instant::now());
+        let client = self.http_client.clone();
+        let event_sender = self.event_sender.clone();
+        self.jobs.spawn("Health check", async move {
+            let report = health::check(client).await;
+            let _ = event_sender.send(AppEvent::HealthChecked(report)).await;
+        });
+    }
+
+    // Counts a failed request for the health tab and the metrics file
+    fn request_failed(&mut self, kind: &'static str) {
+        self.request_errors.record();
+        self.metrics.error(kind);
+    }
+
+    fn tick_metrics(&mut self) {
+        if !self.metrics.due(&self.config.metrics) {
+            return;
+        }
+        if let Err(e) = self.metrics.write(&self.config.metrics) {
+            self.notifications.push(ErrorReport::from(&e).context("Failed to write the metrics file"));
+        }
+    }
+
+    // Ends the request being streamed without waiting for the server;
+    // true when part of a reply arrived and was marked for resuming
+    fn interrupt_request(&mut self) -> bool {
+        self.abandon_request("interrupted");
+        match self.conversation.last_mut().filter(|turn| turn.is_model_reply()) {
+            Some(turn) => {
+                turn.interrupted = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Ends a request that didn't finish; whatever of the reply arrived is
+    // left as it is, without the steps a finished reply goes through. Events
+    // still on their way from it, its OllamaDone included, are dropped.
+    fn abandon_request(&mut self, note: &str) {
+        self.write_tee(|tee| tee.reply_end(Some(note)));
+        self.is_loading = false;
+        self.active_request = None;
+        self.request_job = None;
+        self.stream_started = None;
+        self.streaming_model = None;
+        self.pending_route_note = None;
+        self.model_loading = false;
+        self.response_action = None;
+        self.format_check = None;
+        self.validation_retry = None;
+    }
+
+    fn open_jobs(&mut self) {
+        self.modals.push(Modal::Jobs(JobList::new(self.jobs.rows())));
+    }
+
+    // Records the jobs that ended since the last tick. A crash is reported;
+    // a pull or reply whose job was cancelled or crashed never sent its own
+    // finishing event, so it is finished here.
+    fn tick_jobs(&mut self) {
+        for ended in self.jobs.poll() {
+            self.needs_redraw = true;
+            let failure = match ended.state {
+                JobState::Cancelled => None,
+                JobState::Panicked => {
+                    self.notifications.push(ErrorReport::new(ErrorCategory::Local, format!("{} crashed", ended.label)));
+                    Some("crashed".to_string())
+                }
+                _ => continue,
+            };
+            if self.request_job == Some(ended.id) && self.is_loading {
+                let resumable = self.interrupt_request();
+                self.status_message = match (&failure, resumable) {
+                    (Some(_), _) => format!("{} crashed.", ended.label),
+                    (None, true) => format!("{} cancelled. Press 'r' to resume.", ended.label),
+                    (None, false) => format!("{} cancelled.", ended.label),
+                };
+            } else if let Some(model) = self.transfers.job_ended(ended.id, failure) {
+                self.status_message = format!("Stopped the pull of {}.", model);
+                self.save_pending_pulls();
+                self.start_pulls();
+            }
+        }
+        if let Some(list) = self.modals.jobs_mut() {
+            list.refresh(self.jobs.rows());
+            self.needs_redraw = true;
+        }
+    }
+
+    // Fills the models list from the cache when the server can't be
+    // reached; false when nothing was cached for this server
+    fn show_cached_models(&mut self) -> bool {
+        let Some((models, saved_at)) = self.catalog.models() else { return false };
+        self.model_sizes = models.iter().map(|model| (model.name.clone(), model.size)).collect();
+        self.model_details = models.iter().map(|model| (model.name.clone(), model.details.clone())).collect();
+        self.models = models.iter().map(|model| model.name.clone()).collect();
+        self.capabilities = self.catalog.capabilities().clone();
+        if self.sort_models_by_size {
+            self.sort_models();
+        }
+        self.no_models = false;
+        let preferred = self.preferred_model.clone();
+        self.select_model(preferred);
+        self.models_cached = Some(saved_at);
+        self.models_retried = Some(Instant::now());
+        self.status_message = format!(
+            "Can't reach the server at {}; showing the models cached {}.",
+            endpoint::base(),
+            catalog::age(saved_at)
+        );
+        true
+    }
+
+    // While showing cached models, asks the server again now and then
+    fn tick_offline(&mut self) {
+        if self.models_cached.is_none() || self.models_retried.is_some_and(|at| at.elapsed() < OFFLINE_RETRY) {
+            return;
+        }
+        self.models_retried = Some(Instant::now());
+        self.refresh_models();
+    }
+
+    // Keeps the dashboard current while it is on screen
+    fn tick_health(&mut self) {
+        if self.active_tab == ActiveTab::Health
+            && self.health_requested.is_none_or(|at| at.elapsed() >= health::REFRESH_INTERVAL)
+        {
+            self.refresh_health();
+        }
+    }
+
+    // Probes GPU and CPU use while a response is generating
+    fn tick_resources(&mut self) {
+        if !self.is_loading || !self.resources.due() {
+            return;
+        }
+        self.resources.requested = Some(Instant::now());
+        let previous = self.resources.snapshot.as_ref().and_then(|snapshot| snapshot.cpu_times);
+        let event_sender = self.event_sender.clone();
+        self.jobs.spawn("Resource probe", async move {
+            let snapshot = resources::probe(previous).await;
+            let _ = event_sender.send(AppEvent::ResourcesProbed(snapshot)).await;
+        });
+    }
+
+    // Unloads the models prompted from here once the user has been away for
+    // `unload_after_idle_mins`; a reply still generating isn't idle
+    fn tick_idle(&mut self) {
+        if self.is_loading {
+            self.idle.touch();
+            return;
+        }
+        let minutes = self.config.unload_after_idle_mins;
+        let models = self.idle.due(Duration::from_secs(minutes * 60));
+        if models.is_empty() {
+            return;
+        }
+        for model in &models {
+            let client = self.http_client.clone();
+            let model = model.clone();
+            self.jobs.spawn(format!("Unload {}", model), async move { idle::set_loaded(&client, &model, Some(0)).await });
+        }
+        self.status_message = format!("Unloaded {} after {} idle minutes; the selected model loads again on the next key.", models.join(", "), minutes);
+        self.needs_redraw = true;
+    }
+
+    // Input after being away: loads the selected model again if it was
+    // unloaded, so it is warm by the time the prompt is sent
+    fn user_active(&mut self) {
+        self.idle.touch();
+        let Some(model) = self.get_selected_model_name() else { return };
+        if !self.idle.rewarm(&model) || self.read_only {
+            return;
+        }
+        self.status_message = format!("Loading {} again...", model);
+        let client = self.http_client.clone();
+        self.jobs.spawn(format!("Load {}", model), async move { idle::set_loaded(&client, &model, None).await });
+    }
+
+    fn tick_watch(&mut self) {
+        let Some(since) = self.watch_pending_since else { return };
+        if since.elapsed() >= WATCH_DEBOUNCE && !self.is_loading {
+            self.watch_pending_since = None;
+            self.needs_redraw = true;
+            self.run_watch_prompt();
+        }
+    }
+
+    fn tick_config_reload(&mut self) {
+        let Some(since) = self.config_pending_since else { return };
+        if since.elapsed() >= WATCH_DEBOUNCE {
+            self.config_pending_since = None;
+            self.needs_redraw = true;
+            self.reload_config();
+        }
+    }
+
+    // Applies a fresh read of the config file; a broken file leaves the
+    // current settings in place
+    fn reload_config(&mut self) {
+        let config = match config::load() {
+            Ok(config) => config,
+            Err(e) => {
+                self.notifications.push(ErrorReport::from(&e).context("Config not reloaded"));
+                return;
+            }
+        };
+        match proxy::build_client(&config.proxy) {
+            Ok(client) => self.http_client = client,
+            Err(e) => self.notifications.push(ErrorReport::from(&e).context("Proxy settings ignored")),
+        }
+        self.theme = config.theme;
+        self.true_color = config.true_color.unwrap_or_else(theme::true_color_supported);
+        self.raw_markdown = config.raw_markdown;
+        self.message_layout = config.message_layout;
+        self.num_predict = config.num_predict;
+        self.frames.set_max_fps(config.max_fps);
+        self.transfers.set_max_parallel(config.max_parallel_pulls);
+        self.limiter.reconfigure(&config.limits);
+        self.redactor = Redactor::new(&config.redact);
+        let mcp_changed = config.mcp_servers != self.config.mcp_servers;
+        self.config = config;
+        self.start_pulls();
+        self.status_message = if mcp_changed {
+            "Config reloaded. MCP server changes apply after a restart.".to_string()
+        } else {
+            "Config reloaded.".to_string()
+        };
+    }
+
+    fn run_watch_prompt(&mut self) {
+        let Some(file_watch) = &self.file_watch else { return };
+        let label = attachment::display_path(&file_watch.path);
+        match file_watch.render_prompt() {
+            Ok(prompt) => {
+                if self.send_prompt(prompt) {
+                    self.status_message = format!("Watching {}, asking the model... (/unwatch to stop)", label);
+                }
+            }
+            Err(e) => self.status_message = format!("Error reading watched file: {}", e),
+        }
+    }
+
+    // Checks a /format reply, sending it back with the problem while retries
+    // are left. Returns true if it was sent back.
+    fn check_format(&mut self) -> bool {
+        let Some(check) = self.format_check.take() else { return false };
+        let Some(reply) = self.conversation.last().filter(|turn| turn.is_model_reply()) else { return false };
+        let error = match check.preset.validate(&reply.text) {
+            Ok(()) => {
+                self.status_message = format!("Reply is valid {}.", check.preset.name());
+                return false;
+            }
+            Err(error) => error,
+        };
+        if check.retries_left == 0 {
+            self.status_message = format!("Reply is not valid {}: {}. No retries left.", check.preset.name(), error);
+            return false;
+        }
+        // The model that got it wrong tries again
+        let model_name = reply.name.clone();
+        let prompt = formats::reask_prompt(&check.prompt, &error);
+        if !self.send_formatted_prompt(model_name, prompt, Some(check.preset)) {
+            return false;
+        }
+        self.status_message = format!("Reply is not valid {} ({}), asking again...", check.preset.name(), error);
+        self.format_check = Some(FormatCheck { retries_left: check.retries_left - 1, ..check });
+        true
+    }
+
+    // Runs the configured validators on the finished reply in the background;
+    // the response action and post-processing wait for them
+    fn validate_response(&mut self) {
+        let validators = self.config.validation.validators.clone();
+        let Some(turn_index) = self.conversation.len().checked_sub(1) else { return };
+        // A reply cut short by an error would only be retried for the server's fault
+        let turn = &self.conversation[turn_index];
+        if turn.interrupted || matches!(turn.finish, Some(Finish::Error(_))) {
+            self.validation_retry = None;
+            return;
+        }
+        if validators.is_empty() || !turn.is_model_reply() {
+            self.validation_retry = None;
+            self.finish_response();
+            return;
+        }
+        let reply = self.conversation[turn_index].text.to_string();
+        let timeout = self.config.hooks.timeout();
+        let event_sender = self.event_sender.clone();
+        self.status_message = "Validating the reply...".to_string();
+        self.jobs.spawn("Validate the reply", async move {
+            let result = validation::check(validators, reply, timeout).await;
+            let _ = event_sender.send(AppEvent::ResponseValidated { turn_index, result }).await;
+        });
+    }
+
+    fn response_validated(&mut self, turn_index: usize, result: Result<Option<String>, AppError>) {
+        // Something else was sent in the meantime
+        if self.is_loading || turn_index + 1 != self.conversation.len() {
+            self.validation_retry = None;
+            return;
+        }
+        let retried = match result {
+            Ok(None) => {
+                self.validation_retry = None;
+                self.status_message = "Reply passed validation.".to_string();
+                false
+            }
+            Ok(Some(reason)) => self.retry_invalid_reply(turn_index, &reason),
+            Err(e) => {
+                self.validation_retry = None;
+                self.notifications.push(ErrorReport::from(&e).context("Validation skipped"));
+                false
+            }
+        };
+        if !retried {
+            self.finish_response();
+        }
+    }
+
+    // Asks again with the reason appended while the retry budget lasts.
+    // Returns true if the prompt was sent again.
+    fn retry_invalid_reply(&mut self, turn_index: usize, reason: &str) -> bool {
+        let retry = self.validation_retry.take();
+        let retries_left = retry.as_ref().map_or(self.config.validation.retries, |retry| retry.retries_left);
+        if retries_left == 0 {
+            self.status_message = format!("Reply kept although it failed validation: {}", reason);
+            return false;
+        }
+        // The original prompt, not an earlier retry of it
+        let prompt = match retry {
+            Some(retry) => retry.prompt,
+            None => self.prompt_before(turn_index).to_string(),
+        };
+        if prompt.is_empty() {
+            return false;
+        }
+        let model_name = self.conversation[turn_index].name.clone();
+        if !self.send_prompt_to(model_name, validation::retry_prompt(&prompt, reason)) {
+            return false;
+        }
+        self.status_message = format!("Reply failed validation, asking again ({} left): {}", retries_left - 1, reason);
+        self.validation_retry = Some(ValidationRetry { prompt, retries_left: retries_left - 1 });
+        true
+    }
+
+    fn finish_response(&mut self) {
+        if let Some(action) = self.response_action.take() {
+            self.run_response_action(action);
+        }
+        self.postprocess_response();
+    }
+
+    // Runs once the response that requested it has finished streaming
+    fn run_response_action(&mut self, action: ResponseAction) {
+        match action {
+            ResponseAction::CopyCommitMessage => {
+                let Some(reply) = self.conversation.last().filter(|turn| turn.is_model_reply()) else {
+                    self.status_message = "No commit message was generated.".to_string();
+                    return;
+                };
+                let message = git::clean_commit_message(&reply.text);
+                self.status_message = match self.clipboard.copy(&message) {
+                    Ok(()) => "Commit message copied to clipboard.".to_string(),
+                    Err(e) => format!("Error copying commit message: {}", e),
+                };
+            }
+        }
+    }
+
+    fn open_file_picker(&mut self) {
+        match FilePicker::open(Path::new(".")) {
+            Ok(picker) => self.modals.push(Modal::FilePicker(picker)),
+            Err(e) => self.status_message = format!("Error opening file picker: {}", e),
+        }
+    }
+
+    fn attach_file(&mut self, path: &Path) {
+        match attachment::attach_file(path) {
+            Ok(attachment) => {
+                // Insert at the cursor, on a line of its own
+                if !self.input_buffer.cursor_at_line_start() {
+                    self.input_buffer.insert_char('\n');
+                }
+                self.input_buffer.insert_str(&attachment.block);
+                self.status_message = if attachment.truncated {
+                    format!(
+                        "Warning: {} is larger than {} KiB and was truncated.",
+                        attachment.label,
+                        attachment::MAX_ATTACHMENT_BYTES / 1024
+                    )
+                } else {
+                    format!("Attached {}.", attachment.label)
+                };
+            }
+            Err(e) => self.status_message = format!("Error attaching file: {}", e),
+        }
+    }
+
+    // Line at which each turn starts in the conversation pane, after wrapping
+    fn turn_line_offsets(&self) -> Vec<u16> {
+        conversation_lines(self, self.conversation_width).turn_offsets
+    }
+
+    fn open_outline(&mut self) {
+        let entries = conversation_lines(self, self.conversation_width).outline;
+        match Outline::new(entries, self.scroll_offset) {
+            Some(outline) => self.modals.push(Modal::Outline(outline)),
+            None => self.status_message = "No headings in the conversation.".to_string(),
+        }
+    }
+
+    // Rows of the conversation pane as plain text, for visual selection
+    fn visual_rows(&self) -> Vec<Row> {
+        let view = conversation_lines(self, self.conversation_width);
+        view.lines
+            .iter()
+            .zip(view.continued)
+            .zip(view.margins)
+            .map(|((line, continued), (before, after))| {
+                let text: String = line.spans.iter().map(|span| span.content.as_ref()).collect();
+                let length = text.chars().count().saturating_sub(after);
+                Row { text: text.chars().take(length).collect(), gutter: layout::gutter_chars(line) + before, continued }
+            })
+            .collect()
+    }
+
+    fn start_visual(&mut self) {
+        let rows = self.visual_rows();
+        if rows.is_empty() {
+            self.status_message = "Nothing to select yet.".to_string();
+            return;
+        }
+        // Start at the top of the visible part of the conversation
+        self.visual = Some(Selection::new(&rows, (self.scroll_offset as usize).min(rows.len() - 1)));
+        self.status_message = "Visual: h/j/k/l, w/b: Extend selection, 0/$: Line start/end, y: Copy, e: Export turns, s: Summarize, a: Add to scratchpad, Esc: Cancel.".to_string();
+    }
+
+    // Dragging over the conversation selects its text, which is copied when
+    // the button is released; borders and other panes are never included
+    fn handle_mouse(&mut self, mouse: MouseEvent, conversation: Rect) {
+        // Visual selection is the mouse's own popup
+        if self.active_tab != ActiveTab::Chat || matches!(self.focus(), Focus::Popup(popup) if popup != Popup::Visual) {
+            return;
+        }
+        let text_area = layout::text_area(conversation, self.conversation_width);
+        match mouse.kind {
+            MouseEventKind::ScrollUp => self.scroll_up(3),
+            MouseEventKind::ScrollDown => self.scroll_down(3),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let inside = (text_area.left()..text_area.right()).contains(&mouse.column)
+                    && (text_area.top()..text_area.bottom()).contains(&mouse.row);
+                let rows = self.visual_rows();
+                if !inside || rows.is_empty() {
+                    return;
+                }
+                self.visual = Some(Selection::at(self.mouse_position(&rows, mouse, text_area)));
+                self.mouse_selecting = true;
+            }
+            MouseEventKind::Drag(MouseButton::Left) if self.mouse_selecting => {
+                // Dragging past the top or bottom edge scrolls
+                if mouse.row < text_area.top() {
+                    self.scroll_up(1);
+                } else if mouse.row >= text_area.bottom() {
+                    self.scroll_down(1);
+                }
+                let rows = self.visual_rows();
+                let position = self.mouse_position(&rows, mouse, text_area);
+                if let Some(selection) = self.visual.as_mut() {
+                    selection.cursor = position;
+                }
+            }
+            MouseEventKind::Up(MouseButton::Left) if self.mouse_selecting => {
+                self.mouse_selecting = false;
+                let Some(selection) = self.visual.take() else { return };
+                if selection.is_empty() {
+                    return;
+                }
+                let text = selection.extract(&self.visual_rows());
+                self.status_message = match self.clipboard.copy(&text) {
+                    Ok(()) => format!("Copied {} characters.", text.chars().count()),
+                    Err(e) => format!("Error copying: {}", e),
+                };
+            }
+            _ => {}
+        }
+    }
+
+    // Conversation row and column under the mouse, clamped to the text area
+    fn mouse_position(&self, rows: &[Row], mouse: MouseEvent, text_area: Rect) -> Position {
+        let y = mouse.row.clamp(text_area.top(), text_area.bottom().saturating_sub(1)) - text_area.top();
+        let x = mouse.column.saturating_sub(text_area.left());
+        visual::position_at(rows, self.scroll_offset as usize + y as usize, x as usize)
+    }
+
+    // Scrolls just enough to show `row` of the conversation pane
+    fn scroll_to_row(&mut self, row: usize) {
+        let row = row.min(u16::MAX as usize) as u16;
+        if row < self.scroll_offset {
+            self.scroll_offset = row;
+        } else if row >= self.scroll_offset.saturating_add(self.conversation_height) {
+            self.scroll_offset = row.saturating_sub(self.conversation_height.saturating_sub(1));
+        }
+    }
+
+    // Moves the turn selection by `delta`, scrolling the selected turn into view
+    fn select_turn(&mut self, delta: isize) {
+        if self.conversation.is_empty() {
+            return;
+        }
+        let last = self.conversation.len() - 1;
+        let next = match self.selected_turn {
+            None if delta < 0 => Some(last),
+            None => Some(0),
+            Some(i) => i.checked_add_signed(delta).filter(|&i| i <= last),
+        };
+        self.selected_turn = next;
+        if let Some(index) = next {
+            self.scroll_offset = self.turn_line_offsets()[index];
+            self.status_message = format!(
+                "Turn {}/{} selected. '>': Quote-reply, y/Y: Copy, x: Exclude, m: Raw/rendered, p: Pin, S: Summarize, z: Fold summary, d: Delete, Esc: Clear selection.",
+                index + 1,
+                self.conversation.len()
+            );
+        }
+    }
+
+    fn toggle_selected_turn_excluded(&mut self) {
+        if self.refuse_if_read_only() {
+            return;
+        }
+        let Some(turn) = self.selected_turn.and_then(|i| self.conversation.get_mut(i)) else {
+            self.status_message = "Select a turn first ('[' / ']').".to_string();
+            return;
+        };
+        turn.excluded = !turn.excluded;
+        self.status_message = if turn.excluded {
+            "Turn excluded from the context sent to the model. 'x' to include it again.".to_string()
+        } else {
+            "Turn included in the context again.".to_string()
+        };
+    }
+
+    // Copies the selected turn, or else the latest reply
+    fn yank_turn(&mut self, format: CopyFormat) {
+        let turn = match self.selected_turn {
+            Some(index) => self.conversation.get(index),
+            None => self.conversation.iter().rev().find(|turn| turn.is_model_reply()),
+        };
+        let Some(turn) = turn else {
+            self.status_message = "Nothing to copy yet.".to_string();
+            return;
+        };
+        // A folded reply is its code
+        if let Some(code) = self.selected_folded_code() {
+            self.status_message = match self.clipboard.copy(&code.code) {
+                Ok(()) => format!("Copied {} lines of code.", code.lines),
+                Err(e) => format!("Error copying: {}", e),
+            };
+            return;
+        }
+        let text = format.apply(&turn.text);
+        self.status_message = match self.clipboard.copy(&text) {
+            Ok(()) => format!("Copied {}'s turn as {}. ('Y' copies it as {})", turn.name, format.name(), format.other().name()),
+            Err(e) => format!("Error copying: {}", e),
+        };
+    }
+
+    // Adds the selected turn, or else the latest reply, to the scratchpad
+    fn add_turn_to_scratchpad(&mut self) {
+        let turn = match self.selected_turn {
+            Some(index) => self.conversation.get(index),
+            None => self.conversation.iter().rev().find(|turn| turn.is_model_reply()),
+        };
+        let Some(turn) = turn else {
+            self.status_message = "Nothing to add to the scratchpad yet.".to_string();
+            return;
+        };
+        let text = turn.text.to_string();
+        let sender = turn.name.clone();
+        self.add_to_scratchpad(&text);
+        self.status_message = format!("Added {}'s turn to the scratchpad.", sender);
+    }
+
+    fn add_to_scratchpad(&mut self, text: &str) {
+        self.scratchpad.append(text);
+        self.scratchpad.open = true;
+    }
+
+    // Puts the scratchpad ahead of the draft and continues typing there
+    fn use_scratchpad_as_context(&mut self) {
+        let Some(context) = self.scratchpad.as_context() else {
+            self.status_message = "The scratchpad is empty.".to_string();
+            return;
+        };
+        self.scratchpad.focused = false;
+        self.input_buffer.set_text(format!("{}{}", context, self.input_buffer.text()));
+        self.focus = Focus::Input;
+        self.status_message = format!("Scratchpad added to the draft as context. {}: Send.", self.send_key());
+    }
+
+    // Writes some or all of the conversation to a file, or copies it as markdown
+    fn export_turns(&mut self, turns: ExportTurns, path: Option<String>) {
+        let (range, what) = match turns {
+            ExportTurns::All => (0..self.conversation.len(), "Conversation".to_string()),
+            ExportTurns::Selected => match self.selected_turn.filter(|&index| index < self.conversation.len()) {
+                Some(index) => (index..index + 1, format!("Turn {}", index + 1)),
+                None => {
+                    self.status_message = "Select a turn first ('[' / ']'), or use --turns N-M.".to_string();
+                    return;
+                }
+            },
+            ExportTurns::Range(first, last) if last <= self.conversation.len() => {
+                (first - 1..last, if first == last { format!("Turn {}", first) } else { format!("Turns {}-{}", first, last) })
+            }
+            ExportTurns::Range(..) => {
+                self.status_message = format!("Error: The conversation has {} turns.", self.conversation.len());
+                return;
+            }
+        };
+        if range.is_empty() {
+            self.status_message = "Nothing to export yet.".to_string();
+            return;
+        }
+        let turns: Result<Vec<ConversationTurn>, AppError> = self.conversation[range]
+            .iter()
+            .map(|turn| Ok(ConversationTurn { text: self.redactor.redact_export(&turn.text)?.into(), ..turn.clone() }))
+            .collect();
+        let result = turns.and_then(|turns| match &path {
+            Some(path) => session::export(&turns, self.get_selected_model_name(), Path::new(path))
+                .map(|()| format!("{} exported to {}.", what, path)),
+            None => self.clipboard.copy(&session::to_markdown(&turns)).map(|()| format!("{} copied as markdown.", what)),
+        });
+        match result {
+            Ok(message) => {
+                self.input_buffer.clear();
+                self.focus = Focus::Conversation;
+                self.status_message = message;
+            }
+            Err(e) => self.status_message = format!("Error exporting: {}", e),
+        }
+    }
+
+    // Turns the visual selection touches, as an /export command to finish
+    // with a path (or send as is to copy them)
+    fn export_visual(&mut self, selection: &Selection) {
+        let offsets = self.turn_line_offsets();
+        let turn_at = |row: usize| offsets.partition_point(|&offset| offset as usize <= row).max(1);
+        let (start, end) = selection.range();
+        self.input_buffer.set_text(format!("/export --turns {}-{} ", turn_at(start.row), turn_at(end.row)));
+        self.focus = Focus::Input;
+        self.status_message = format!("Add a path to write a file, or {} to copy as markdown.", self.send_key());
+    }
+
+    fn renderer_for(&self, turn: &ConversationTurn) -> Renderer {
+        let default = if self.raw_markdown { Renderer::Plain } else { Renderer::Markdown };
+        turn.renderer.or_else(|| Renderer::detect(&turn.text)).unwrap_or(default)
+    }
+
+    // The code of a finished reply that is one long code block, shown folded
+    // unless unfolded; see code_fold.rs
+    fn foldable_code(&self, turn: &ConversationTurn) -> Option<CodeOnly> {
+        let streaming = turn.request.is_some() && turn.request == self.active_request;
+        if self.config.fold_code_lines == 0 || !turn.is_model_reply() || streaming || self.renderer_for(turn) != Renderer::Markdown {
+            return None;
+        }
+        code_fold::code_only(&turn.text).filter(|code| code.lines >= self.config.fold_code_lines)
+    }
+
+    // The selected turn's code while it is shown folded
+    fn selected_folded_code(&self) -> Option<CodeOnly> {
+        let turn = self.conversation.get(self.selected_turn?)?;
+        self.foldable_code(turn).filter(|_| !turn.code_expanded)
+    }
+
+    // Folds or unfolds the selected code-only reply; false when it isn't one
+    fn toggle_code_fold(&mut self) -> bool {
+        let Some(index) = self.selected_turn.filter(|&index| index < self.conversation.len()) else { return false };
+        if self.foldable_code(&self.conversation[index]).is_none() {
+            return false;
+        }
+        let turn = &mut self.conversation[index];
+        turn.code_expanded = !turn.code_expanded;
+        self.status_message = if turn.code_expanded { "Code unfolded. Enter folds it again." } else { "Code folded." }.to_string();
+        true
+    }
+
+    // Saves the selected folded code where the code post-processing step would
+    fn save_selected_code(&mut self) {
+        let Some(index) = self.selected_turn else { return };
+        let text = self.conversation[index].text.to_string();
+        self.status_message = match postprocess::save_code_blocks(&self.config.postprocess, &text) {
+            Ok(Some((dir, _))) => format!("Code saved to {}.", dir.display()),
+            Ok(None) => "No code to save.".to_string(),
+            Err(e) => format!("Error saving code: {}", e),
+        };
+    }
+
+    // Moves the selected turn on to the next renderer: markdown, plain, ANSI
+    fn cycle_selected_turn_renderer(&mut self) {
+        let Some(index) = self.selected_turn.filter(|&index| index < self.conversation.len()) else {
+            self.status_message = "Select a turn first ('[' / ']').".to_string();
+            return;
+        };
+        let renderer = self.renderer_for(&self.conversation[index]).next();
+        self.conversation[index].renderer = Some(renderer);
+        self.status_message = format!("Showing the turn as {}. 'm' for {}.", renderer.name(), renderer.next().name());
+    }
+
+    fn summarize_selected_turn(&mut self) {
+        let Some(index) = self.selected_turn.filter(|&index| index < self.conversation.len()) else {
+            self.status_message = "Select a turn to summarize first ('[' / ']').".to_string();
+            return;
+        };
+        let text = self.conversation[index].text.to_string();
+        self.summarize(index, text);
+    }
+
+    // Summarizes the selected text, attaching the summary to the turn the
+    // selection ends in
+    fn summarize_visual(&mut self, selection: &Selection, rows: &[Row]) {
+        let offsets = self.turn_line_offsets();
+        let (_, end) = selection.range();
+        let index = offsets.partition_point(|&offset| offset as usize <= end.row).saturating_sub(1);
+        self.summarize(index, selection.extract(rows));
+    }
+
+    // Asks the current model for a summary on the side; it doesn't become
+    // part of the conversation
+    fn summarize(&mut self, turn_index: usize, text: String) {
+        if self.refuse_if_read_only() {
+            return;
+        }
+        let Some(turn) = self.conversation.get(turn_index) else { return };
+        if self.is_loading && turn_index + 1 == self.conversation.len() {
+            self.status_message = "Wait for the reply to finish before summarizing it.".to_string();
+            return;
+        }
+        let Some(model) = self.get_selected_model_name() else {
+            self.status_message = "Error: No model selected.".to_string();
+            return;
+        };
+        let turn_text = turn.text.to_string();
+        let text = match self.redactor.redact_prompt(text) {
+            Ok((text, _)) => text,
+            Err(e) => {
+                self.status_message = format!("Not summarized: {}", e);
+                return;
+            }
+        };
+        let client = self.http_client.clone();
+        let limiter = self.limiter.clone();
+        let event_sender = self.event_sender.clone();
+        self.status_message = format!("Summarizing with {}...", model);
+        self.jobs.spawn(format!("Summarize with {}", model), async move {
+            let _slot = limiter.acquire().await;
+            let result = summarize::summarize(client, &model, &text).await;
+            let _ = event_sender.send(AppEvent::Summarized { turn_index, text: turn_text, result }).await;
+        });
+    }
+
+    fn attach_summary(&mut self, turn_index: usize, text: &str, result: Result<Summary, AppError>) {
+        let summary = match result {
+            Ok(summary) => summary,
+            Err(e) => {
+                self.notifications.push(ErrorReport::from(&e).context("Failed to summarize"));
+                return;
+            }
+        };
+        match self.conversation.get_mut(turn_index).filter(|turn| *turn.text == *text) {
+            Some(turn) => {
+                turn.summary = Some(summary);
+                self.status_message = format!("Summary added to turn {}. 'z' on the turn unfolds it.", turn_index + 1);
+            }
+            None => self.status_message = "The turn changed before its summary arrived; summary dropped.".to_string(),
+        }
+    }
+
+    fn toggle_summary(&mut self) {
+        let Some(summary) = self.selected_turn.and_then(|index| self.conversation.get_mut(index)).and_then(|turn| turn.summary.as_mut()) else {
+            self.status_message = "Select a turn with a summary first ('S' summarizes one).".to_string();
+            return;
+        };
+        summary.expanded = !summary.expanded;
+    }
+
+    // Pins the selected turn above the conversation, replacing any pinned
+    // one; with no selection, or on the pinned turn itself, unpins
+    fn toggle_pin(&mut self) {
+        match self.selected_turn.filter(|&index| Some(index) != self.pinned_turn) {
+            Some(index) => {
+                self.pinned_turn = Some(index);
+                self.status_message = format!("Turn {} pinned. 'p' with no turn selected to unpin.", index + 1);
+            }
+            None if self.pinned_turn.is_some() => {
+                self.pinned_turn = None;
+                self.status_message = "Unpinned.".to_string();
+            }
+            None => self.status_message = "Select a turn to pin first ('[' / ']').".to_string(),
+        }
+    }
+
+    // Pane layout, with room for the pinned turn if there is one
+    fn text_width(&self, area: Rect) -> u16 {
+        let width = layout::conversation_text_width(area, self.scratchpad.open, self.split.is_some());
+        match self.config.max_content_width {
+            0 => width,
+            max => width.min(max),
+        }
+    }
+
+    // Takes the live conversation out, leaving an empty one in its place
+    fn park_conversation(&mut self) -> ParkedConversation {
+        let parked = ParkedConversation {
+            turns: std::mem::take(&mut self.conversation),
+            draft: self.input_buffer.text().to_string(),
+            scratchpad: self.scratchpad.text().to_string(),
+            scroll_offset: std::mem::take(&mut self.scroll_offset),
+            selected_turn: self.selected_turn.take(),
+            prompt_wrap: std::mem::take(&mut self.prompt_wrap),
+            model: self.get_selected_model_name(),
+            resumed_session: self.resumed_session.take(),
+        };
+        self.input_buffer.clear();
+        self.scratchpad.editor.clear();
+        self.pinned_turn = None;
+        self.search_matches.clear();
+        // Undoing a clear would restore into the wrong side; it stays archived
+        self.cleared_conversation = None;
+        parked
+    }
+
+    fn unpark_conversation(&mut self, parked: ParkedConversation) {
+        self.conversation = parked.turns;
+        self.input_buffer.set_text(parked.draft);
+        self.scratchpad.editor.set_text(parked.scratchpad);
+        self.scroll_offset = parked.scroll_offset;
+        self.selected_turn = parked.selected_turn;
+        self.prompt_wrap = parked.prompt_wrap;
+        self.resumed_session = parked.resumed_session;
+        if parked.model.is_some() {
+            self.select_model(parked.model);
+        }
+    }
+
+    // Opens a second, empty conversation beside this one, or closes the
+    // split, archiving the unfocused side
+    fn toggle_split(&mut self) {
+        if self.refuse_if_read_only() {
+            return;
+        }
+        if self.is_loading {
+            self.status_message = "Wait for the response to finish first.".to_string();
+            return;
+        }
+        let Some(split) = self.split.take() else {
+            let current = self.park_conversation();
+            self.split = Some(SplitView::new(current));
+            self.status_message = "New conversation on the right. Tab: Next pane (each side is one), /split: Close.".to_string();
+            return;
+        };
+        let parked = split.parked;
+        if parked.turns.is_empty() {
+            self.status_message = "Split closed.".to_string();
+            return;
+        }
+        // A conversation resumed from the archive goes back there
+        let folder = parked.resumed_session.as_deref().map_or(Folder::Sessions, Folder::of);
+        match session::archive(folder, &parked.turns, &parked.draft, &parked.scratchpad, &parked.prompt_wrap, parked.model.clone()) {
+            Ok(path) => {
+                if let Some(resumed) = &parked.resumed_session {
+                    let _ = session::remove(resumed);
+                }
+                self.status_message = format!("Split closed; the other conversation was archived to {}.", path.display());
+            }
+            Err(e) => {
+                self.status_message = format!("Error archiving the other conversation, split kept open: {}", e);
+                self.split = Some(SplitView { parked, ..split });
+            }
+        }
+    }
+
+    fn switch_split_side(&mut self) {
+        if self.is_loading {
+            self.status_message = "Wait for the response to finish before switching sides.".to_string();
+            return;
+        }
+        let Some(mut split) = self.split.take() else { return };
+        let current = self.park_conversation();
+        let other = std::mem::replace(&mut split.parked, current);
+        self.unpark_conversation(other);
+        split.side = split.other_side();
+        self.split = Some(split);
+    }
+
+    fn delete_selected_turn(&mut self) {
+        if self.refuse_if_read_only() {
+            return;
+        }
+        // The reply still streaming could be the turn deleted, or its prompt
+        if self.is_loading {
+            self.status_message = "Wait for the response to finish before deleting turns.".to_string();
+            return;
+        }
+        let Some(index) = self.selected_turn.filter(|&i| i < self.conversation.len()) else {
+            self.status_message = "Select a turn first ('[' / ']').".to_string();
+            return;
+        };
+        self.conversation.remove(index);
+        self.pinned_turn = match self.pinned_turn {
+            Some(pinned) if pinned == index => None,
+            Some(pinned) if pinned > index => Some(pinned - 1),
+            pinned => pinned,
+        };
+        // Keep a nearby turn selected so several can be deleted in a row
+        self.selected_turn = if self.conversation.is_empty() {
+            None
+        } else {
+            Some(index.min(self.conversation.len() - 1))
+        };
+        self.status_message = "Turn deleted.".to_string();
+    }
+
+    // Archives the conversation to the session store and starts a fresh one
+    fn clear_conversation(&mut self) {
+        if self.refuse_if_read_only() {
+            return;
+        }
+        if self.is_loading {
+            self.status_message = "Wait for the response to finish before clearing.".to_string();
+            return;
+        }
+        if self.conversation.is_empty() {
+            self.status_message = "Conversation is already empty.".to_string();
+            return;
+        }
+
+        // The unsent draft belongs to the conversation being archived
+        let turns = std::mem::take(&mut self.conversation);
+        let draft = self.input_buffer.text().to_string();
+        self.input_buffer.clear();
+        let scratchpad = self.scratchpad.text().to_string();
+        self.scratchpad.editor.clear();
+        let folder = self.resumed_session.as_deref().map_or(Folder::Sessions, Folder::of);
+        let archive_path = match session::archive(folder, &turns, &draft, &scratchpad, &self.prompt_wrap, self.get_selected_model_name()) {
+            Ok(path) => {
+                self.status_message = format!("Conversation archived to {}. Press 'u' to undo.", path.display());
+                // The new copy supersedes the one it was resumed from
+                if let Some(resumed) = self.resumed_session.take() {
+                    let _ = session::remove(&resumed);
+                }
+                Some(path)
+            }
+            Err(e) => {
+                self.status_message = format!("Conversation cleared, but archiving failed ({}). Press 'u' to undo.", e);
+                None
+            }
+        };
+        // A new conversation starts with the configured post-processing
+        let postprocess = std::mem::replace(&mut self.postprocess, Steps::from_config(&self.config.postprocess));
+        let prompt_wrap = std::mem::take(&mut self.prompt_wrap);
+        self.cleared_conversation = Some(ClearedConversation { turns, draft, archive_path, postprocess, prompt_wrap, scratchpad });
+        self.selected_turn = None;
+        self.pinned_turn = None;
+        self.scroll_offset = 0;
+    }
+
+    // Archives the conversation, as clearing does, and starts another: blank,
+    // or seeded with the named template's system prompt and examples
+    fn new_conversation(&mut self, template: Option<&str>) {
+        let template = match template {
+            Some(name) => match templates::find(&self.config.templates, name) {
+                Some(template) => Some(template.clone()),
+                None => {
+                    self.status_message = format!("No template named '{}'.", name);
+                    return;
+                }
+            },
+            None => None,
+        };
+        let archived = !self.conversation.is_empty();
+        if archived {
+            self.clear_conversation();
+            // Read-only, or a reply still streaming
+            if !self.conversation.is_empty() {
+                return;
+            }
+        } else if self.refuse_if_read_only() {
+            return;
+        }
+        let Some(template) = template else {
+            if !archived {
+                self.status_message = "New conversation.".to_string();
+            }
+            return;
+        };
+
+        if let Some(system) = template.system.filter(|system| !system.trim().is_empty()) {
+            self.conversation.push(ConversationTurn::new(Role::SystemPrompt, "System Prompt", system));
+        }
+        for message in template.messages {
+            let mut turn = match message.role {
+                TemplateRole::User => ConversationTurn::new(Role::User, "You (example)", message.text),
+                TemplateRole::Assistant => ConversationTurn::new(Role::Assistant, "Example reply", message.text),
+            };
+            turn.example = true;
+            self.conversation.push(turn);
+        }
+        let mut status = format!("Started from template \"{}\".", template.name);
+        if let Some(model) = template.model {
+            if self.models.contains(&model) {
+                self.select_model(Some(model));
+            } else {
+                status = format!("{} Its model {} is not installed.", status, model);
+            }
+        }
+        // Clearing said how to undo; keep saying it
+        if archived {
+            status.push_str(" Press 'u' to bring the old conversation back.");
+        }
+        self.status_message = status;
+        self.focus = Focus::Input;
+    }
+
+    // Makes a saved conversation the live one again, draft included. The
+    // one it replaces is saved first, draft and all, so it can be resumed too
+    fn resume_session(&mut self, path: PathBuf, session: Session) {
+        if self.resumed_session.as_ref() == Some(&path) {
+            self.status_message = format!("\"{}\" is already open.", session.title);
+            return;
+        }
+        if self.is_loading {
+            self.status_message = "Wait for the response to finish before switching conversations.".to_string();
+            return;
+        }
+        let mut saved = String::new();
+        if !self.conversation.is_empty() || !self.input_buffer.text().is_empty() {
+            let folder = self.resumed_session.as_deref().map_or(Folder::Sessions, Folder::of);
+            let draft = self.input_buffer.text().to_string();
+            let scratchpad = self.scratchpad.text().to_string();
+            match session::archive(folder, &self.conversation, &draft, &scratchpad, &self.prompt_wrap, self.get_selected_model_name()) {
+                Ok(archived) => {
+                    if let Some(resumed) = self.resumed_session.take() {
+                        let _ = session::remove(&resumed);
+                    }
+                    saved = format!(" The previous conversation was saved to {}.", archived.display());
+                }
+                Err(e) => {
+                    self.status_message = format!("Not switched, saving the current conversation failed: {}", e);
+                    return;
+                }
+            }
+        }
+        // Undoing a clear would restore into the wrong conversation
+        self.cleared_conversation = None;
+        self.pinned_turn = None;
+        self.search_matches.clear();
+        self.scroll_offset = 0;
+        self.conversation = session.turns;
+        self.input_buffer.set_text(session.draft);
+        self.scratchpad.open = !session.scratchpad.is_empty();
+        self.scratchpad.editor.set_text(session.scratchpad);
+        self.prompt_wrap = session.prompt_wrap;
+        self.selected_turn = None;
+        if self.models.is_empty() {
+            self.preferred_model = session.model;
+        } else {
+            self.select_model(session.model);
+        }
+        self.status_message = format!("Resumed \"{}\". Press Enter to continue typing.{}", session.title, saved);
+        self.resumed_session = Some(path);
+    }
+
+    fn undo_clear(&mut self) {
+        let Some(cleared) = self.cleared_conversation.take() else {
+            self.status_message = "Nothing to undo.".to_string();
+            return;
+        };
+        // Anything typed into the new conversation is kept after the restored turns
+        let newer = std::mem::replace(&mut self.conversation, cleared.turns);
+        self.postprocess = cleared.postprocess;
+        self.prompt_wrap = cleared.prompt_wrap;
+        self.conversation.extend(newer);
+        // Bring the old draft back unless something new has been typed since
+        if self.input_buffer.text().is_empty() {
+            self.input_buffer.set_text(cleared.draft);
+        }
+        if self.scratchpad.text().is_empty() {
+            self.scratchpad.editor.set_text(cleared.scratchpad);
+        }
+        // The restored conversation is live again, so drop its archived copy
+        if let Some(path) = cleared.archive_path {
+            let _ = session::remove(&path);
+        }
+        self.status_message = "Conversation restored.".to_string();
+    }
+
+    // Starts a new prompt quoting the selected turn as a Markdown blockquote
+    fn quote_selected_turn(&mut self) {
+        let Some(turn) = self.selected_turn.and_then(|i| self.conversation.get(i)) else {
+            self.status_message = "Select a turn first ('[' / ']').".to_string();
+            return;
+        };
+
+        let mut quote = format!("> {} wrote:\n>\n", turn.name);
+        for line in turn.text.lines() {
+            if line.is_empty() {
+                quote.push_str(">\n");
+            } else {
+                quote.push_str(&format!("> {}\n", line));
+            }
+        }
+        quote.push('\n');
+
+        self.input_buffer.set_text(quote);
+        self.focus = Focus::Input;
+        self.status_message = format!("Replying to quoted turn... {}: Send, Esc: Leave (keeps draft).", self.send_key());
+    }
+
+    // The draft's {{date}}, {{file:path}}... filled in, see variables.rs
+    fn expand_variables(&mut self, text: &str) -> Result<String, String> {
+        let (clipboard, conversation) = (&mut self.clipboard, &self.conversation);
+        let (expanded, _) = variables::expand(text, |variable| match variable {
+            Variable::Date => Ok(chrono::Local::now().format("%Y-%m-%d").to_string()),
+            Variable::Cwd => std::env::current_dir().map(|cwd| cwd.display().to_string()).map_err(|e| e.to_string()),
+            Variable::Clipboard => clipboard.paste().map_err(|e| e.to_string()),
+            Variable::File(path) => attachment::attach_file(Path::new(path)).map(|file| file.block).map_err(|e| e.to_string()),
+            Variable::LastResponse => conversation
+                .iter()
+                .rev()
+                .find(|turn| turn.is_model_reply())
+                .map(|turn| turn.text.to_string())
+                .ok_or_else(|| "no reply yet".to_string()),
+        })?;
+        Ok(expanded)
+    }
+
+    // Shows the draft as it would be sent, or the draft again
+    fn toggle_input_preview(&mut self) {
+        if self.input_preview.take().is_some() {
+            return;
+        }
+        let draft = self.input_buffer.text().to_string();
+        if !variables::has_variables(&draft) {
+            self.status_message = "No variables to preview ({{date}}, {{cwd}}, {{clipboard}}, {{file:path}}, {{last_response}}).".to_string();
+            return;
+        }
+        match self.expand_variables(&draft) {
+            Ok(expanded) => self.input_preview = Some(expanded),
+            Err(e) => self.status_message = format!("Error: {}", e),
+        }
+    }
+
+    fn undo_input(&mut self) {
+        if !self.input_buffer.undo() {
+            self.status_message = "Nothing to undo.".to_string();
+        }
+    }
+
+    fn redo_input(&mut self) {
+        if !self.input_buffer.redo() {
+            self.status_message = "Nothing to redo.".to_string();
+        }
+    }
+
+    fn scroll_down(&mut self, amount: u16) {
+        self.scroll_offset = self.scroll_offset.saturating_add(amount);
+        // TODO: Clamp scroll_offset based on actual content height for more robust scrolling
+    }
+
+    fn scroll_up(&mut self, amount: u16) {
+        self.scroll_offset = self.scroll_offset.saturating_sub(amount);
+    }
+}
+
+// --- Main Application Logic ---
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    // `ollama-tui view <session-file>` opens a saved session read-only;
+    // `--resume` continues the latest one and `--session <name>` a given one.
+    // `--host` and `--keep-alive` go with any of them.
+    let mut args: Vec<String> = std::env::args().collect();
+    let endpoint_flags = match endpoint::take_flags(&mut args) {
+        Ok(flags) => flags,
+        Err(usage) => {
+            eprintln!("{}", usage);
+            std::process::exit(2);
+        }
+    };
+    // `ollama-tui batch ...` runs a file of prompts without the full UI
+    if args.get(1).is_some_and(|arg| arg == "batch") {
+        return run_batch(&args[2..], &endpoint_flags).await;
+    }
+    let resumed = match args.get(1).map(String::as_str) {
+        Some("--resume") => session::recent(Folder::Sessions, 1).map(|mut sessions| sessions.pop()).transpose(),
+        Some("--session") => match args.get(2) {
+            Some(name) => Some(session::find(name)),
+            None => {
+                eprintln!("Usage: ollama-tui --session <name>");
+                std::process::exit(2);
+            }
+        },
+        _ => None,
+    };
+    let resumed = match resumed {
+        Some(Ok(resumed)) => Some(resumed),
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        None if args.get(1).is_some_and(|arg| arg == "--resume") => {
+            eprintln!("No saved sessions to resume.");
+            std::process::exit(1);
+        }
+        None => None,
+    };
+    let viewed_session = match args.get(1).map(String::as_str) {
+        Some("view") => {
+            let Some(path) = args.get(2).map(PathBuf::from) else {
+                eprintln!("Usage: ollama-tui view <session-file>");
+                std::process::exit(2);
+            };
+            match session::load(&path) {
+                Ok(session) => Some((path, session)),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        _ => None,
+    };
+
+    let (config, config_error) = match config::load() {
+        Ok(config) => (config, None),
+        Err(e) => (Config::default(), Some(e)),
+    };
+    if let Err(e) = endpoint::init(&endpoint_flags, &config) {
+        eprintln!("Invalid server setting: {}", e);
+        std::process::exit(1);
+    }
+
+    // Setup terminal
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    // The legacy Windows console can't do bracketed paste (pastes then arrive
+    // as typed keys) and may refuse mouse capture; neither is worth failing over
+    let _ = execute!(stdout, EnableMouseCapture);
+    let _ = execute!(stdout, EnableBracketedPaste);
+    // Disambiguation, plus event types so held keys arrive marked as
+    // repeats (releases are ignored)
+    let enhanced_keys = config.keyboard_protocol && supports_keyboard_enhancement().unwrap_or(false);
+    if enhanced_keys {
+        execute!(
+            stdout,
+            PushKeyboardEnhancementFlags(
+                KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES | KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+            )
+        )?;
+    }
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    // Create communication channel and App instance
+    let (tx, rx) = mpsc::channel(queue::CAPACITY);
+    let (plugins, plugin_errors) = PluginHost::load(config::plugins_dir().as_deref());
+    let mut app = App::new(rx, tx.clone(), config, plugins);
+    app.enhanced_keys = enhanced_keys;
+    if let Some(e) = config_error {
+        app.notifications.push(ErrorReport::from(&e).context("Failed to load config, using defaults"));
+    }
+    match Scoreboard::load() {
+        Ok(scoreboard) => app.scoreboard = scoreboard,
+        Err(e) => app.notifications.push(ErrorReport::from(&e).context("Failed to load the arena scoreboard")),
+    }
+    match proxy::build_client(&app.config.proxy) {
+        Ok(client) => app.http_client = client,
+        Err(e) => app.notifications.push(ErrorReport::from(&e).context("Proxy settings ignored")),
+    }
+    match ModelUsage::load() {
+        Ok(usage) => app.usage = usage,
+        Err(e) => app.notifications.push(ErrorReport::from(&e).context("Failed to load model usage")),
+    }
+    match Catalog::load() {
+        Ok(catalog) => app.catalog = catalog,
+        Err(e) => app.notifications.push(ErrorReport::from(&e).context("Failed to load the model cache")),
+    }
+    match UsageStats::load() {
+        Ok(stats) => app.stats = stats,
+        Err(e) => app.notifications.push(ErrorReport::from(&e).context("Failed to load usage statistics")),
+    }
+    for error in plugin_errors {
+        app.notifications.push(error);
+    }
+
+    match viewed_session {
+        Some((path, session)) => {
+            // Show the saved conversation without contacting the server
+            app.read_only = true;
+            app.conversation = session.turns;
+            app.models = session.model.into_iter().collect();
+            app.selected_model_index = if app.models.is_empty() { None } else { Some(0) };
+            app.status_message = format!(
+                "Viewing {} (read-only). '/': Search, n/N: Next/previous match, /export <path>: Export, q: Quit.",
+                path.display()
+            );
+        }
+        None => {
+            match Onboarding::load() {
+                Ok(onboarding) => app.onboarding = onboarding,
+                Err(e) => app.notifications.push(ErrorReport::from(&e).context("Failed to load onboarding state")),
+            }
+            if let Err(e) = app.onboarding.count_launch() {
+                app.notifications.push(ErrorReport::from(&e).context("Failed to save onboarding state"));
+            }
+            if let Err(e) = session::purge_trash(app.config.trash_days) {
+                app.notifications.push(ErrorReport::from(&e).context("Failed to empty the session trash"));
+            }
+            match resumed {
+                Some((path, session)) => app.resume_session(path, session),
+                None if app.config.session_picker => match session::recent(Folder::Sessions, session_picker::RECENT_SESSIONS) {
+                    Ok(sessions) => {
+                        if let Some(picker) = SessionPicker::new(sessions, app.config.trash_days) {
+                            app.modals.push(Modal::SessionPicker(picker));
+                        }
+                    }
+                    Err(e) => app.notifications.push(ErrorReport::from(&e).context("Failed to list saved sessions")),
+                },
+                None => {}
+            }
+            // Without a config directory there is nothing to watch; /reload still works
+            app.config_watch = config::watch(tx.clone()).ok();
+            start_background_tasks(&app);
+            match transfers::load_pending() {
+                Ok(models) if !models.is_empty() => {
+                    let question = format!("{} pull(s) were interrupted: {}. Resume?", models.len(), models.join(", "));
+                    app.confirm(question, Confirmation::ResumePulls(models));
+                }
+                Ok(_) => {}
+                Err(e) => app.notifications.push(ErrorReport::from(&e).context("Failed to load interrupted pulls")),
+            }
+        }
+    }
+
+    // Run the main TUI loop
+    let res = run_app(&mut terminal, &mut app).await;
+
+    // Cancel whatever is still in flight, so a reply being streamed stops on
+    // the server too, and give the tasks a moment to wind down
+    if app.jobs.running() > 0 {
+        app.status_message = "Cancelling running requests...".to_string();
+        let _ = terminal.draw(|f| {
+            ui(f, &app);
+        });
+    }
+    app.jobs.shutdown(jobs::SHUTDOWN_GRACE).await;
+
+    // Restore terminal
+    if enhanced_keys {
+        execute!(terminal.backend_mut(), PopKeyboardEnhancementFlags)?;
+    }
+    disable_raw_mode()?;
+    let _ = execute!(terminal.backend_mut(), DisableMouseCapture, DisableBracketedPaste);
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+
+    // Print errors if the app loop returned an error
+    if let Err(err) = res {
+        eprintln!("TUI Error: {}", err);
+    }
+    if let Err(e) = app.catalog.save_capabilities() {
+        eprintln!("Failed to save the model cache: {}", e);
+    }
+    if let Err(e) = app.metrics.write(&app.config.metrics) {
+        eprintln!("Failed to write the metrics file: {}", e);
+    }
+
+    Ok(())
+}
+
+
+async fn run_batch(args: &[String], endpoint_flags: &endpoint::Flags) -> Result<(), Box<dyn Error>> {
+    // Scripts check the exit code, so every failure ends the process with one
+    let args = match batch::parse_args(args) {
+        Ok(args) => args,
+        Err(usage) => {
+            eprintln!("{}", usage);
+            std::process::exit(2);
+        }
+    };
+    let config = config::load().unwrap_or_else(|e| {
+        eprintln!("Failed to load config, using defaults: {}", e);
+        Config::default()
+    });
+    if let Err(e) = endpoint::init(endpoint_flags, &config) {
+        eprintln!("Invalid server setting: {}", e);
+        std::process::exit(1);
+    }
+    let client = proxy::build_client(&config.proxy).unwrap_or_else(|e| {
+        eprintln!("Proxy settings ignored: {}", e);
+        Client::new()
+    });
+    if let Err(e) = batch::run(args, config, client).await {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+// --- Initial Async Tasks ---
+fn start_background_tasks(app: &App) {
+    // Start configured MCP servers in the background
+    if !app.config.mcp_servers.is_empty() {
+        let servers = app.config.mcp_servers.clone();
+        let mcp_event_sender = app.event_sender.clone();
+        app.jobs.spawn("Connect MCP servers", async move {
+            let (manager, errors) = McpManager::connect(&servers).await;
+            let _ = mcp_event_sender.send(AppEvent::McpConnected { manager: Arc::new(manager), errors }).await;
+        });
+    }
+
+    // Ask the server which version it runs, to know what it supports
+    app.preflight(endpoint::base().to_string());
+
+    // Fetch models immediately
+    app.refresh_models();
+}
+
+
+// --- Main Event Loop ---
+async fn run_app<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+) -> Result<(), AppError> {
+
+    loop {
+        let size = terminal.size()?;
+        app.conversation_width = app.text_width(size);
+
+        // Draw only when something changed, within the FPS cap
+        if app.needs_redraw && app.frames.until_ready().is_zero() {
+            let started = Instant::now();
+            let mut conversation = app.conversation_pane;
+            terminal.draw(|f| conversation = ui(f, app))?;
+            app.conversation_pane = conversation;
+            app.conversation_height = conversation.height.saturating_sub(2);
+            app.frames.record(started);
+            app.needs_redraw = false;
+        }
+
+        // --- Handle Input Events (non-blocking) ---
+        // Poll for crossterm events with a small timeout, or until the next
+        // frame is allowed when a redraw is waiting
+        let timeout = if app.needs_redraw { app.frames.until_ready() } else { IDLE_POLL };
+        if event::poll(timeout)? {
+            // Any input may change the screen, resizes included
+            app.needs_redraw = true;
+            // If poll is true, read() is guaranteed not to block
+            let event = event::read()?;
+            if matches!(event, Event::Key(_) | Event::Paste(_) | Event::Mouse(_)) {
+                app.user_active();
+            }
+            match event {
+                // Presses, and repeats of keys meant to be held (see
+                // keys.rs); releases would otherwise type each key twice
+                Event::Key(key) => {
+                    let acts = match app.key_repeat.kind(&key) {
+                        KeyEventKind::Press => true,
+                        KeyEventKind::Repeat => {
+                            let typing = matches!(app.focus(), Focus::Input | Focus::Scratchpad);
+                            keys::repeats(&key, typing, &app.config.send_key)
+                        }
+                        KeyEventKind::Release => false,
+                    };
+                    if acts && !handle_key(app, key) {
+                        return Ok(()); // Quit
+                    }
+                }
+                // Pasted text goes into the draft as a single undo step
+                Event::Paste(text) if app.scratchpad.focused => {
+                    app.scratchpad.editor.insert_str(&text.replace("\r\n", "\n").replace('\r', "\n"));
+                }
+                Event::Paste(text) if app.focus() == Focus::Input => {
+                    app.input_buffer.insert_str(&text.replace("\r\n", "\n").replace('\r', "\n"));
+                }
+                Event::Mouse(mouse) => app.handle_mouse(mouse, app.conversation_pane),
+                // Handle terminal resize events if necessary (redraw is automatic)
+                Event::Resize(_, _) => {}
+                // Ignore other event types (Focus, Paste, etc.)
+                _ => {}
+            }
+        }
+
+        // --- Handle Async Events from Ollama tasks (non-blocking) ---
+        // Everything queued since the last iteration, up to a budget
+        for _ in 0..queue::DRAIN_BUDGET {
+            match app.event_receiver.try_recv() {
+                Ok(app_event) => {
+                    app.needs_redraw = true;
+                    app.queue.handled();
+                    handle_app_event(app, app_event);
+                }
+                // No message received from async tasks
+                Err(mpsc::error::TryRecvError::Empty) => break,
+                // Channel disconnected - critical error
+                Err(mpsc::error::TryRecvError::Disconnected) => {
+                    app.status_message = "Critical Error: Async event channel disconnected.".to_string();
+                    terminal.draw(|f| {
+                        ui(f, app);
+                    })?; // Draw final error before exiting
+                    return Err(AppError::ChannelReceive);
+                }
+            }
+        }
+        // Whatever is left, and whether senders are waiting on a full queue
+        app.queue.record_depth(app.event_receiver.len());
+
+        app.tick_watch();
+        app.tick_config_reload();
+        app.tick_jobs();
+        app.tick_metrics();
+        app.tick_health();
+        app.tick_offline();
+        app.tick_resources();
+        app.tick_idle();
+        if app.is_animating() && app.frames.since_last_frame() >= SPINNER_INTERVAL {
+            app.needs_redraw = true;
+        }
+
+        // Short sleep to prevent high CPU usage when idle; none while events
+        // are still queued
+        if app.event_receiver.is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+    } // End main loop
+}
+
+
+// Applies one event from a background task
+fn handle_app_event(app: &mut App, app_event: AppEvent) {
+    // What's left of a reply after its request was interrupted or replaced
+    if app_event.request().is_some_and(|request| app.active_request != Some(request)) {
+        return;
+    }
+    match app_event {
+        AppEvent::ModelsFetched(Ok(models)) => {
+            app.models_cached = None;
+            let cached = models
+                .iter()
+                .map(|m| CachedModel { name: m.name.clone(), size: m.size, details: m.details.clone() })
+                .collect();
+            if let Err(e) = app.catalog.store_models(cached) {
+                app.notifications.push(ErrorReport::from(&e).context("Failed to save the model cache"));
+            }
+            app.model_sizes = models.iter().map(|m| (m.name.clone(), m.size)).collect();
+            app.model_details = models.iter().map(|m| (m.name.clone(), m.details.clone())).collect();
+            app.models = models.into_iter().map(|m| m.name).collect();
+            if app.sort_models_by_size {
+                app.sort_models();
+            }
+            if let Err(e) = app.usage.seen(&app.models) {
+                app.notifications.push(ErrorReport::from(&e).context("Failed to save model usage"));
+            }
+            app.fetch_capabilities();
+            app.no_models = app.models.is_empty();
+            if !app.models.is_empty() {
+                let preferred = app.preferred_model.take();
+                app.select_model(preferred); // The first model unless another is wanted
+                app.status_message = format!(
+                    "{} models loaded. Select: Up/Down, Chat: Enter (then {} to send)",
+                    app.models.len(),
+                    app.send_key()
+                );
+            } else {
+                app.status_message = "No models installed. Press 1-3 to pull a starter, b to browse.".to_string();
+            }
+        }
+        AppEvent::ModelDeleted { model, result } => match result {
+            Ok(()) => {
+                let selected = app.get_selected_model_name();
+                app.models.retain(|name| *name != model);
+                app.no_models = app.models.is_empty();
+                app.model_sizes.remove(&model);
+                app.model_details.remove(&model);
+                app.capabilities.remove(&model);
+                app.select_model(selected);
+                app.status_message = match app.usage.forget(&model) {
+                    Ok(()) => format!("Deleted {}.", model),
+                    Err(e) => format!("Deleted {}, but saving model usage failed: {}", model, e),
+                };
+            }
+            Err(e) => {
+                app.request_failed("delete");
+                app.notifications.push(ErrorReport::from(&e).context(&format!("Failed to delete {}", model)));
+            }
+        },
+        AppEvent::PullProgress { model, progress } => {
+            if let Some((job, line)) = app.transfers.progress(&model, progress) {
+                app.jobs.set_progress(job, line);
+            }
+        }
+        AppEvent::PullFinished { model, result } => {
+            match &result {
+                Ok(()) => {
+                    app.metrics.pulled(true);
+                    app.status_message = format!("Pulled {}.", model);
+                    app.refresh_models();
+                }
+                Err(e) => {
+                    app.request_failed("pull");
+                    app.metrics.pulled(false);
+                    app.status_message = format!("Error pulling {}: {}", model, e);
+                }
+            }
+            if app.transfers.finish(&model, result) {
+                app.status_message = format!("{} failed digest verification; pulling it again.", model);
+            }
+            app.save_pending_pulls();
+            app.start_pulls();
+        }
+        AppEvent::LibraryFetched(result) => match (result, app.modals.library_mut()) {
+            (Ok(models), Some(browser)) => {
+                browser.set_index(models, app.config.library_url.clone().unwrap_or_default());
+            }
+            (Err(e), Some(_)) => {
+                app.status_message = format!("Error fetching the library index, showing the bundled one: {}", e);
+            }
+            // Closed before the index arrived
+            (_, None) => {}
+        },
+        AppEvent::Embedded { model, texts, result } => match result {
+            Ok(vectors) => {
+                app.status_message = format!("Embedded {} text(s) with {}.", texts.len(), model);
+                app.playground.add(model, texts, vectors);
+            }
+            Err(e) => {
+                app.request_failed("embed");
+                app.status_message = format!("Error embedding with {}: {}", model, e);
+            }
+        },
+        AppEvent::CapabilitiesFetched { model, capabilities } => {
+            app.catalog.store_capabilities(&model, &capabilities);
+            app.capabilities.insert(model, capabilities);
+        }
+        AppEvent::BenchResult(result) => {
+            if let Some(error) = &result.error {
+                app.request_failed("bench");
+                app.status_message = format!("Benchmark error on {}: {}", result.model, error);
+            }
+            if let Some(run) = app.bench.as_mut() {
+                run.results.push(result);
+            }
+        }
+        AppEvent::ArenaChunk { side, text } => {
+            if let Some(arena) = app.arena.as_mut() {
+                arena.replies[side].push_str(&text);
+            }
+        }
+        AppEvent::ArenaDone { side, error } => {
+            if let Some(arena) = app.arena.as_mut() {
+                arena.finished[side] = true;
+                if let Some(error) = error {
+                    arena.replies[side].push_str(&format!("\n\n[Error: {}]", error));
+                    app.request_errors.record();
+                    app.metrics.error("arena");
+                }
+                if arena.is_finished() {
+                    app.status_message = "Both answers are in. Vote: a = A is better, b = B is better, t = tie.".to_string();
+                }
+            }
+        }
+        AppEvent::BenchFinished => {
+            if let Some(run) = app.bench.as_mut() {
+                run.finished = true;
+            }
+            app.status_message = "Benchmark finished. Export with /bench export <path.csv|.json>.".to_string();
+        }
+        AppEvent::HealthChecked(report) => {
+            if report.version.is_some() {
+                app.server_version = report.version;
+            }
+            if app.server_latency.as_ref().is_none_or(|(server, _)| server == endpoint::base()) {
+                app.server_latency = Some((endpoint::base().to_string(), report.latency));
+            }
+            app.health = Some(report);
+        }
+        AppEvent::PreflightDone(preflight) => {
+            // Feature checks are against the default server only
+            if let (true, Ok(version)) = (preflight.server == endpoint::base(), &preflight.version) {
+                app.server_version = Some(*version);
+                if let Some(mcp) = app.mcp.as_ref().filter(|mcp| mcp.tool_count() > 0) {
+                    if let Err(msg) = Feature::Tools.check(Some(*version)) {
+                        app.status_message = format!("{} MCP tools not offered: {}.", mcp.tool_count(), msg);
+                    }
+                }
+            }
+            // Old servers lack /api/version; carry on without feature checks
+            app.server_latency = Some((preflight.server, preflight.latency));
+        }
+        AppEvent::ModelsFetched(Err(e)) => {
+            app.request_failed("models");
+            // Still down; the cached list stays up without another report
+            if let Some(saved_at) = app.models_cached {
+                app.status_message = format!("Still can't reach the server; showing the models cached {}.", catalog::age(saved_at));
+                return;
+            }
+            if app.models.is_empty() && app.show_cached_models() {
+                return;
+            }
+            app.status_message = "Error fetching models.".to_string();
+            app.notifications.push(ErrorReport::from(&e).context("Failed to fetch models"));
+        }
+        AppEvent::ModelLoading(model) => {
+            // Only while the request it was checked for still waits on its first token
+            let answered = app.conversation.last().is_some_and(|turn| turn.request.is_some() && turn.request == app.active_request);
+            if app.is_loading && app.streaming_model.as_ref() == Some(&model) && !answered {
+                app.model_loading = true;
+                app.status_message = format!("Loading {} into memory…", model);
+            }
+        }
+        AppEvent::OllamaChunk { request, text: chunk } => {
+            // Append chunk to the last conversation turn if it's from the model
+            let model_name = app.streaming_model.clone().unwrap_or_else(|| "Model".to_string());
+            if app.model_loading {
+                app.model_loading = false;
+                app.status_message = format!("Asking {}...", model_name);
+            }
+            app.write_tee(|tee| tee.chunk(&model_name, &chunk));
+            match app.conversation.last_mut().filter(|turn| turn.request == Some(request)) {
+                Some(last_turn) => last_turn.text.push_str(&chunk), // Append to this request's reply
+                None => {
+                    // First chunk, or the first after a tool call: start a new Model turn
+                    let first_token = !app.conversation.iter().any(|turn| turn.request == Some(request));
+                    if let Some(started) = app.stream_started.filter(|_| first_token) {
+                        app.metrics.first_token(&model_name, started.elapsed());
+                    }
+                    let mut turn = ConversationTurn::new(Role::Assistant, model_name, chunk);
+                    turn.request = Some(request);
+                    turn.routed = app.pending_route_note.take();
+                    app.conversation.push(turn);
+                }
+            }
+            if let (Some(job), Some(turn)) = (app.request_job, app.conversation.last()) {
+                app.jobs.set_progress(job, format!("{} received", health::format_bytes(turn.text.len() as u64)));
+            }
+            // TODO: Implement auto-scrolling logic if desired
+        }
+        AppEvent::Generated { generation, .. } => {
+            let model = app.streaming_model.clone().unwrap_or_default();
+            app.recent_generations.push(generation);
+            app.metrics.generated(&model, &generation);
+            if let Some(turn) = app.streaming_turn_mut() {
+                // A continued reply counts what came before too
+                turn.tokens = Some(turn.tokens.unwrap_or(0) + generation.eval_count).filter(|tokens| *tokens > 0);
+            }
+            let load = Duration::from_nanos(generation.load_duration);
+            app.load_note = (load >= LOAD_NOTE_THRESHOLD).then_some(load);
+            if let Err(e) = app.stats.generated(&model, &generation) {
+                app.notifications.push(ErrorReport::from(&e).context("Failed to save usage statistics"));
+            }
+        }
+        AppEvent::OllamaDone { .. } => {
+            app.write_tee(|tee| tee.reply_end(None));
+            // Mark loading as finished, update status
+            app.is_loading = false;
+            app.request_job = None;
+            app.stream_started = None;
+            app.streaming_model = None;
+            app.active_request = None;
+            app.pending_route_note = None;
+            app.model_loading = false;
+            let loaded = app.load_note.take().map(|load| format!(" Model loaded in {:.1}s.", load.as_secs_f64())).unwrap_or_default();
+            app.status_message = format!("Response received.{} Press 'Enter' to type ({} to send).", loaded, app.send_key());
+            if app.conversation.last().is_some_and(|turn| turn.finish == Some(Finish::Length)) {
+                app.status_message = format!("Response cut off at the token limit.{} Press 'C' to continue it.", loaded);
+            }
+            app.decode_reply();
+            app.apply_incoming_plugins();
+            // A reply sent back for another try skips the rest
+            if !app.check_format() {
+                app.validate_response();
+            }
+        }
+        AppEvent::ResponseValidated { turn_index, result } => app.response_validated(turn_index, result),
+        AppEvent::OllamaError { report: err_msg, .. } => {
+            app.request_failed("reply");
+            // Part of the reply may be on screen already; say it stopped short
+            if let Some(turn) = app.streaming_turn_mut() {
+                turn.finish = Some(Finish::Error(err_msg.message.clone()));
+            }
+            app.abandon_request("failed");
+            app.status_message = "Error occurred.".to_string();
+            app.notifications.push(err_msg);
+        }
+        AppEvent::StreamDecodeError { report, .. } => app.notifications.push(report),
+        AppEvent::Rerouted { route, reason, .. } => {
+            app.request_failed("reroute");
+            let tried = app.streaming_model.replace(route.model.clone()).unwrap_or_default();
+            let note = match &route.server {
+                Some(server) => format!("fallback for {} via {}", tried, server),
+                None => format!("fallback for {}", tried),
+            };
+            app.status_message = format!("{} failed ({}), asking {}...", tried, reason, route.model);
+            app.pending_route_note = Some(note);
+            let server = route.base_url().to_string();
+            if app.server_latency.as_ref().is_none_or(|(current, _)| *current != server) {
+                app.preflight(server);
+            }
+        }
+        AppEvent::DoneReason { reason, .. } => {
+            if let Some(turn) = app.streaming_turn_mut() {
+                turn.finish = Some(Finish::from_reason(&reason));
+            }
+        }
+        AppEvent::OllamaInterrupted { message: err_msg, .. } => {
+            app.request_failed("interrupted");
+            if app.interrupt_request() {
+                app.status_message = format!("Connection lost mid-response ({}). Press 'r' to resume.", err_msg);
+            } else {
+                // Nothing arrived yet, so there is nothing to resume
+                app.status_message = "Error occurred.".to_string();
+                app.notifications.push(err_msg);
+            }
+        }
+        AppEvent::PromptRewritten { prompt, .. } => {
+            // Show what was actually sent after the pre-send hook
+            if let Some(turn) = app.conversation.iter_mut().rev().find(|turn| turn.role == Role::User) {
+                turn.text = TurnText::from(prompt);
+                // The hook saw the prompt already wrapped
+                turn.wrap = None;
+            }
+        }
+        AppEvent::ResponseHookDone { turn_index, text, result } => {
+            // Cleared, deleted or regenerated while the hook ran
+            let Some(turn) = app.conversation.get_mut(turn_index).filter(|turn| *turn.text == *text) else {
+                app.status_message = "The reply changed while the post-receive hook ran; its result was dropped.".to_string();
+                return;
+            };
+            match result {
+                Ok(HookOutcome::Unchanged) => app.run_postprocess_steps(turn_index),
+                Ok(HookOutcome::Replaced(text)) => {
+                    turn.text = TurnText::from(text);
+                    app.run_postprocess_steps(turn_index);
+                }
+                Err(e) => app.status_message = format!("Post-receive hook error: {}", e),
+            }
+        }
+        AppEvent::McpConnected { manager, errors } => {
+            for error in errors {
+                app.notifications.push(error);
+            }
+            app.status_message = match Feature::Tools.check(app.server_version) {
+                Ok(()) => format!("{} MCP tools available.", manager.tool_count()),
+                Err(msg) => format!("{} MCP tools not offered: {}.", manager.tool_count(), msg),
+            };
+            app.mcp = Some(manager);
+        }
+        AppEvent::ToolCall { name, arguments, .. } => {
+            let shown = serde_json::to_string_pretty(&arguments).unwrap_or_default();
+            let mut turn = ConversationTurn::new(
+                Role::System,
+                "Tool Call",
+                format!("`{}`\n{}", name, attachment::fenced_block("json", &shown)),
+            );
+            turn.tool_call = Some(ToolCall { function: ToolCallFunction { name: name.clone(), arguments } });
+            app.conversation.push(turn);
+            app.status_message = format!("Running tool {}...", name);
+        }
+        AppEvent::ToolResult { name, output, is_error, .. } => {
+            let text = if is_error {
+                format!("`{}` failed: {}", name, output)
+            } else {
+                format!("`{}` returned:\n{}", name, attachment::fenced_text("", &output))
+            };
+            app.conversation.push(ConversationTurn::new(Role::Tool, "Tool Result", text));
+        }
+        AppEvent::WatchedFileChanged => {
+            if app.file_watch.is_some() {
+                // Restart the debounce window on every event
+                app.watch_pending_since = Some(Instant::now());
+            }
+        }
+        AppEvent::ConfigFileChanged => app.config_pending_since = Some(Instant::now()),
+        AppEvent::ResourcesProbed(snapshot) => app.resources.snapshot = Some(snapshot),
+        AppEvent::Summarized { turn_index, text, result } => app.attach_summary(turn_index, &text, result),
+    }
+}
+
+// Hands a key to whatever has focus; false when it asks to quit
+fn handle_key(app: &mut App, key: KeyEvent) -> bool {
+    let focus = app.focus();
+    // Help opens over whatever is open, short of an error
+    if key.code == KeyCode::F(1) && !matches!(focus, Focus::Popup(Popup::Error | Popup::Help)) {
+        app.modals.push(Modal::Help);
+        return true;
+    }
+    match focus {
+        Focus::Popup(popup) => handle_popup_key(app, popup, key),
+        Focus::Scratchpad => match app.scratchpad.handle_key(key, &app.config.send_key) {
+            ScratchpadAction::None => {}
+            ScratchpadAction::Leave => {
+                app.scratchpad.focused = false;
+                app.status_message = "Scratchpad kept. 'e' to edit it again, 'w' to hide it.".to_string();
+            }
+            ScratchpadAction::UseAsContext => app.use_scratchpad_as_context(),
+        },
+        Focus::Input => handle_input_key(app, key),
+        // Screens with keys of their own see them first
+        Focus::Models | Focus::Conversation => {
+            if !handle_tab_key(app, key) {
+                return handle_pane_key(app, key);
+            }
+        }
+    }
+    true
+}
+
+// The top popup takes every key until it closes
+fn handle_popup_key(app: &mut App, popup: Popup, key: KeyEvent) {
+    match popup {
+        Popup::Help => {
+            if matches!(key.code, KeyCode::Esc | KeyCode::F(1) | KeyCode::Char('?') | KeyCode::Char('q')) {
+                app.modals.pop();
+            }
+        }
+        // Enter dismisses one error, Esc all of them
+        Popup::Error => match key.code {
+            KeyCode::Enter => app.notifications.dismiss(),
+            KeyCode::Esc => app.notifications.dismiss_all(),
+            _ => {}
+        },
+        // The session picker shown on launch
+        Popup::SessionPicker => {
+            let Some(Modal::SessionPicker(picker)) = app.modals.top_mut() else { return };
+            match picker.handle_key(key) {
+                SessionAction::None => {}
+                SessionAction::Close => {
+                    app.modals.pop();
+                }
+                SessionAction::Status(message) => app.status_message = message,
+                SessionAction::Resume(path, session) => {
+                    app.modals.pop();
+                    app.resume_session(path, *session);
+                }
+            }
+        }
+        Popup::FilePicker => {
+            let Some(Modal::FilePicker(picker)) = app.modals.top_mut() else { return };
+            match picker.handle_key(key) {
+                PickerAction::None => {}
+                PickerAction::Close => {
+                    app.modals.pop();
+                }
+                PickerAction::Picked(path) => {
+                    app.modals.pop();
+                    app.attach_file(&path);
+                }
+            }
+        }
+        Popup::Jobs => {
+            let Some(Modal::Jobs(list)) = app.modals.top_mut() else { return };
+            match list.handle_key(key) {
+                JobAction::None => {}
+                JobAction::Close => {
+                    app.modals.pop();
+                }
+                JobAction::Cancel(id) => {
+                    if app.jobs.cancel(id) {
+                        app.status_message = format!("Cancelling job #{}...", id);
+                    }
+                }
+            }
+        }
+        Popup::Outline => {
+            let Some(Modal::Outline(outline)) = app.modals.top_mut() else { return };
+            match outline.handle_key(key) {
+                OutlineAction::None => {}
+                OutlineAction::Close => {
+                    app.modals.pop();
+                }
+                OutlineAction::Jump(line) => {
+                    app.modals.pop();
+                    app.scroll_offset = line;
+                }
+            }
+        }
+        Popup::Library => {
+            let Some(Modal::Library(browser)) = app.modals.top_mut() else { return };
+            match browser.handle_key(key) {
+                BrowserAction::None => {}
+                BrowserAction::Close => {
+                    app.modals.pop();
+                }
+                BrowserAction::Pull(model) => {
+                    app.modals.pop();
+                    app.queue_pulls(vec![model]);
+                }
+            }
+        }
+        // The theme picker previews as it moves
+        Popup::ThemePicker => {
+            let Some(Modal::ThemePicker(picker)) = app.modals.top_mut() else { return };
+            match picker.handle_key(key) {
+                ThemeAction::Preview(theme) => app.theme = theme,
+                ThemeAction::Keep(theme) => {
+                    app.theme = theme;
+                    app.modals.pop();
+                    app.status_message = format!("Theme: {} (set `theme` in the config to keep it).", theme.name());
+                }
+                ThemeAction::Revert(theme) => {
+                    app.theme = theme;
+                    app.modals.pop();
+                }
+            }
+        }
+        Popup::TemplatePicker => {
+            let Some(Modal::TemplatePicker(picker)) = app.modals.top_mut() else { return };
+            match picker.handle_key(key) {
+                TemplateAction::None => {}
+                TemplateAction::Close => {
+                    app.modals.pop();
+                }
+                TemplateAction::Start(name) => {
+                    app.modals.pop();
+                    app.new_conversation(name.as_deref());
+                }
+            }
+        }
+        Popup::Visual => {
+            let Some(mut selection) = app.visual.take() else { return };
+            let rows = app.visual_rows();
+            match selection.handle_key(key, &rows) {
+                VisualAction::None => {
+                    app.scroll_to_row(selection.cursor.row);
+                    app.visual = Some(selection);
+                }
+                VisualAction::Exit => app.status_message = "Selection cancelled.".to_string(),
+                VisualAction::Export => app.export_visual(&selection),
+                VisualAction::Summarize => app.summarize_visual(&selection, &rows),
+                VisualAction::AddToScratchpad => {
+                    let text = selection.extract(&rows);
+                    app.add_to_scratchpad(&text);
+                    app.status_message = format!("Added {} characters to the scratchpad.", text.chars().count());
+                }
+                VisualAction::Yank => {
+                    let text = selection.extract(&rows);
+                    app.status_message = match app.clipboard.copy(&text) {
+                        Ok(()) => format!("Copied {} characters.", text.chars().count()),
+                        Err(e) => format!("Error copying: {}", e),
+                    };
+                }
+            }
+        }
+        // A pending y/n question takes the next key, whatever it is
+        Popup::Confirmation => {
+            let Some(Modal::Confirm { action: confirmation, .. }) = app.modals.pop() else { return };
+            if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                match confirmation {
+                    Confirmation::ClearConversation => app.clear_conversation(),
+                    Confirmation::DeleteModel(model) => app.delete_model(model),
+                    Confirmation::ResumePulls(models) => app.queue_pulls(models),
+                }
+            } else {
+                if let Confirmation::ResumePulls(_) = confirmation {
+                    // Forget them rather than asking again next time
+                    app.save_pending_pulls();
+                }
+                app.status_message = "Cancelled.".to_string();
+            }
+        }
+    }
+}
+
+// Keys outside the draft; the models list and the conversation each have
+// their own j/k, everything else works from both
+fn handle_pane_key(app: &mut App, key: KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Char('l') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.confirm_clear();
+        }
+        // Panes on the chat screen, screens everywhere else
+        KeyCode::Tab if app.active_tab == ActiveTab::Chat => app.cycle_focus(true),
+        KeyCode::BackTab if app.active_tab == ActiveTab::Chat => app.cycle_focus(false),
+        KeyCode::Tab => app.step_tab(1),
+        KeyCode::BackTab => app.step_tab(-1),
+        KeyCode::F(12) => app.frames.show_stats = !app.frames.show_stats,
+        KeyCode::F(11) => {
+            app.resources.enabled = !app.resources.enabled;
+            app.status_message = if app.resources.enabled {
+                "GPU and CPU use will show while a response generates (F11 to hide).".to_string()
+            } else {
+                "Resource overlay off.".to_string()
+            };
+        }
+        KeyCode::Char('u') => app.undo_clear(),
+        KeyCode::Char('?') => app.modals.push(Modal::Help),
+        KeyCode::Char('J') => app.open_jobs(),
+        KeyCode::Char('r') if app.active_tab == ActiveTab::Health => app.refresh_health(),
+        KeyCode::Char('a') if app.active_tab == ActiveTab::Arena => app.vote_arena(Vote::A),
+        KeyCode::Char('b') if app.active_tab == ActiveTab::Arena => app.vote_arena(Vote::B),
+        KeyCode::Char('t') if app.active_tab == ActiveTab::Arena => app.vote_arena(Vote::Tie),
+        KeyCode::Char('j') if app.active_tab == ActiveTab::Transfers => app.transfers.select(1),
+        KeyCode::Char('k') if app.active_tab == ActiveTab::Transfers => app.transfers.select(-1),
+        KeyCode::Char('c') if app.active_tab == ActiveTab::Transfers => app.cancel_pull(),
+        KeyCode::Char(c) if app.showing_empty_state() && empty_state::starter_for_key(c).is_some() => {
+            app.queue_pulls(empty_state::starter_for_key(c).into_iter().map(str::to_string).collect());
+        }
+        KeyCode::Char('b') if app.showing_empty_state() => app.open_library(String::new()),
+        KeyCode::Char('R') if app.showing_empty_state() => {
+            app.status_message = "Checking the server for models...".to_string();
+            app.refresh_models();
+        }
+        KeyCode::Char('H') if app.onboarding.active() => {
+            app.status_message = match app.onboarding.dismiss() {
+                Ok(()) => "Hints hidden for good.".to_string(),
+                Err(e) => format!("Hints hidden, but saving that failed: {}", e),
+            };
+        }
+        KeyCode::Char('r') | KeyCode::Char('C') => app.resume_interrupted(),
+        KeyCode::Char('k') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.input_buffer.clear();
+            app.status_message = "Draft cleared.".to_string();
+        }
+        KeyCode::Char('q') => return false, // Quit
+        KeyCode::Char('j') | KeyCode::Down if app.model_keys() && !app.models.is_empty() => {
+            let i = app.selected_model_index.unwrap_or(0);
+            let next = if i >= app.models.len() - 1 { 0 } else { i + 1 };
+            app.selected_model_index = Some(next);
+        },
+        KeyCode::Char('k') | KeyCode::Up if app.model_keys() && !app.models.is_empty() => {
+            let i = app.selected_model_index.unwrap_or(0);
+            let prev = if i == 0 { app.models.len() - 1 } else { i - 1 };
+            app.selected_model_index = Some(prev);
+        },
+        KeyCode::Char('j') | KeyCode::Down if app.active_tab == ActiveTab::Chat && app.focus == Focus::Conversation => {
+            app.scroll_down(1)
+        }
+        KeyCode::Char('k') | KeyCode::Up if app.active_tab == ActiveTab::Chat && app.focus == Focus::Conversation => {
+            app.scroll_up(1)
+        }
+        KeyCode::Enter if app.toggle_code_fold() => {}
+        KeyCode::Enter => app.start_editing(),
+        KeyCode::Char('[') => app.select_turn(-1),
+        KeyCode::Char(']') => app.select_turn(1),
+        KeyCode::Char('>') => app.quote_selected_turn(),
+        KeyCode::Char('/') => {
+            app.input_buffer.set_text("/search ".to_string());
+            app.focus = Focus::Input;
+            app.status_message = format!("Type the text to find, then {}.", app.send_key());
+        }
+        KeyCode::Char('n') => app.next_search_match(1),
+        KeyCode::Char('N') => app.next_search_match(-1),
+        KeyCode::Char('o') => app.open_outline(),
+        KeyCode::Char('v') if app.active_tab == ActiveTab::Chat => app.start_visual(),
+        KeyCode::Char('x') => app.toggle_selected_turn_excluded(),
+        KeyCode::Char('s') if app.model_keys() => app.toggle_model_sort(),
+        KeyCode::Char('s') if app.selected_folded_code().is_some() => app.save_selected_code(),
+        KeyCode::Char('D') if app.model_keys() => app.confirm_delete_model(),
+        KeyCode::Char('p') => app.toggle_pin(),
+        KeyCode::Char('S') => app.summarize_selected_turn(),
+        KeyCode::Char('w') => app.scratchpad.open = !app.scratchpad.open,
+        KeyCode::Char('e') => {
+            app.scratchpad.open = true;
+            app.scratchpad.focused = true;
+            app.status_message = format!("Editing the scratchpad... {}: Use as context, Esc: Leave.", app.send_key());
+        }
+        KeyCode::Char('A') => app.add_turn_to_scratchpad(),
+        KeyCode::Char('z') => app.toggle_summary(),
+        KeyCode::Char('m') => app.cycle_selected_turn_renderer(),
+        KeyCode::Char('y') => app.yank_turn(app.config.copy_format),
+        KeyCode::Char('Y') => app.yank_turn(app.config.copy_format.other()),
+        KeyCode::Char('d') => app.delete_selected_turn(),
+        KeyCode::Esc => app.selected_turn = None,
+        KeyCode::PageDown => app.scroll_down(10), // Scroll faster
+        KeyCode::PageUp => app.scroll_up(10),   // Scroll faster
+        _ => {} // Ignore other keys in Normal mode
+    }
+    true
+}
+
+fn handle_input_key(app: &mut App, key: KeyEvent) {
+    // Any other key goes back to the draft and acts on it
+    if key.code != KeyCode::Char('p') || key.modifiers != KeyModifiers::CONTROL {
+        app.input_preview = None;
+    }
+    match (key.code, key.modifiers) {
+        // The configured send key (Ctrl+D unless changed), or
+        // Ctrl+Enter where the terminal can tell it apart from Enter
+        _ if app.config.send_key.matches(&key) => {
+            app.submit_prompt(); // Handles state change and status message
+        }
+        (KeyCode::Enter, modifiers) if modifiers.contains(KeyModifiers::CONTROL) => {
+            app.submit_prompt();
+        }
+        // Ctrl+O opens the file picker to attach a file
+        (KeyCode::Char('o'), KeyModifiers::CONTROL) => {
+            app.open_file_picker();
+        }
+        // Ctrl+P shows the draft with its variables filled in
+        (KeyCode::Char('p'), KeyModifiers::CONTROL) => app.toggle_input_preview(),
+        // Enter (and Shift+Enter) inserts a newline
+         (KeyCode::Enter, _) => {
+            app.input_buffer.insert_char('\n');
+        }
+        // Regular character input (handle Shift implicitly)
+        (KeyCode::Char(c), modifier) if modifier == KeyModifiers::NONE || modifier == KeyModifiers::SHIFT => {
+            app.input_buffer.insert_char(c);
+        }
+        // Backspace/Delete remove the character before/after the cursor
+        (KeyCode::Backspace, _) => {
+            app.input_buffer.backspace();
+        }
+        (KeyCode::Delete, _) => {
+            app.input_buffer.delete();
+        }
+        // Cursor navigation
+        (KeyCode::Left, _) => app.input_buffer.move_left(),
+        (KeyCode::Right, _) => app.input_buffer.move_right(),
+        (KeyCode::Up, _) => app.input_buffer.move_up(),
+        (KeyCode::Down, _) => app.input_buffer.move_down(),
+        (KeyCode::Home, _) => app.input_buffer.move_home(),
+        (KeyCode::End, _) => app.input_buffer.move_end(),
+        // Escape leaves editing but keeps the draft
+        (KeyCode::Esc, _) => {
+            app.focus = Focus::Conversation;
+            app.status_message = "Draft kept. Press 'Enter' to continue typing, Ctrl+K to discard it.".to_string();
+        }
+        // Tab leaves the draft for the next pane, like Esc
+        (KeyCode::Tab, _) => app.cycle_focus(true),
+        (KeyCode::BackTab, _) => app.cycle_focus(false),
+        // Undo / redo edits to the draft
+        (KeyCode::Char('z'), KeyModifiers::CONTROL) => app.undo_input(),
+        (KeyCode::Char('y'), KeyModifiers::CONTROL) => app.redo_input(),
+        // Ctrl+U deletes back to the start of the line
+        (KeyCode::Char('u'), KeyModifiers::CONTROL) => {
+            app.input_buffer.delete_to_line_start();
+        }
+        // Ctrl+K explicitly discards the draft
+        (KeyCode::Char('k'), KeyModifiers::CONTROL) => {
+            app.input_buffer.clear();
+            app.status_message = "Draft cleared.".to_string();
+        }
+        _ => {} // Ignore other keys/modifiers in Editing mode
+    }
+}
 
-        // --- Handle Input Events (non-blocking) ---
-        // Poll for crossterm events with a small timeout
-        if event::poll(Duration::from_millis(50))? {
-            // If poll is true, read() is guaranteed not to block
-            match event::read()? {
-                // Process only key press events
-                Event::Key(key) if key.kind == KeyEventKind::Press => {
-                    match app.input_mode {
-                        InputMode::Normal => match key.code {
-                            KeyCode::Char('q') => return Ok(()), // Quit
-                            KeyCode::Char('j') | KeyCode::Down => {
-                                if !app.models.is_empty() {
-                                    let i = app.selected_model_index.unwrap_or(0);
-                                    let next = if i >= app.models.len() - 1 { 0 } else { i + 1 };
-                                    app.selected_model_index = Some(next);
-                                }
-                            },
-                            KeyCode::Char('k') | KeyCode::Up => {
-                                if !app.models.is_empty() {
-                                    let i = app.selected_model_index.unwrap_or(0);
-                                    let prev = if i == 0 { app.models.len() - 1 } else { i - 1 };
-                                    app.selected_model_index = Some(prev);
-                                }
-                            },
-                            KeyCode::Enter => {
-                                if app.selected_model_index.is_some() {
-                                    app.input_mode = InputMode::Editing;
-                                    app.status_message = "Editing prompt... Enter: Newline, Ctrl+S: Send, Esc: Cancel.".to_string();
-                                } else {
-                                    app.status_message = "Select a model first (Up/Down keys).".to_string();
-                                }
-                            }
-                            KeyCode::PageDown => app.scroll_down(10), // Scroll faster
-                            KeyCode::PageUp => app.scroll_up(10),   // Scroll faster
-                            _ => {} // Ignore other keys in Normal mode
-                        },
-                        InputMode::Editing => match (key.code, key.modifiers) {
-                            // Use Ctrl+S to send the prompt
-                            (KeyCode::Char('s'), KeyModifiers::CONTROL) => {
-                                app.submit_prompt(); // Handles state change and status message
-                            }
-                            // Enter key inserts a newline
-                             (KeyCode::Enter, _) => {
-                                app.input_buffer.push('\n');
-                            }
-                            // Regular character input (handle Shift implicitly)
-                            (KeyCode::Char(c), modifier) if modifier == KeyModifiers::NONE || modifier == KeyModifiers::SHIFT => {
-                                app.input_buffer.push(c);
-                            }
-                            // Backspace removes the last character
-                            (KeyCode::Backspace, _) => {
-                                app.input_buffer.pop();
-                            }
-                            // Escape cancels editing and clears the buffer
-                            (KeyCode::Esc, _) => {
-                                app.input_mode = InputMode::Normal;
-                                app.input_buffer.clear(); // Clear buffer on cancel
-                                app.status_message = "Input cancelled. Press 'Enter' to start typing again.".to_string();
-                            }
-                            _ => {} // Ignore other keys/modifiers in Editing mode
-                        }
-                    }
-                }
-                // Handle terminal resize events if necessary (redraw is automatic)
-                Event::Resize(_, _) => {}
-                // Ignore other event types (Mouse, Focus, Paste, etc.)
-                _ => {}
+// Alt+1..9 picks a screen from either pane; the Models and Sessions tabs add
+// keys of their own. True when the key was used
+fn handle_tab_key(app: &mut App, key: KeyEvent) -> bool {
+    if let KeyCode::Char(c @ '1'..='9') = key.code {
+        if key.modifiers.contains(KeyModifiers::ALT) {
+            if let Some(&tab) = ActiveTab::ALL.get(c as usize - '1' as usize) {
+                app.show_tab(tab);
             }
+            return true;
         }
-
-        // --- Handle Async Events from Ollama tasks (non-blocking) ---
-        match app.event_receiver.try_recv() {
-            Ok(app_event) => {
-                 // Process received AppEvent
-                 match app_event {
-                    AppEvent::ModelsFetched(Ok(models)) => {
-                        app.models = models;
-                        if !app.models.is_empty() {
-                            app.selected_model_index = Some(0); // Select first model
-                            app.status_message = format!(
-                                "{} models loaded. Select: Up/Down, Chat: Enter (then Ctrl+S to send)",
-                                app.models.len()
-                            );
-                        } else {
-                            app.status_message = "No models found on Ollama server.".to_string();
-                        }
-                    }
-                    AppEvent::ModelsFetched(Err(e)) => {
-                        // Display error fetching models
-                        app.status_message = format!("Error fetching models: {}", e);
-                        // Optionally add to conversation log
-                        app.conversation.push(ConversationTurn {
-                            sender: "System Error".to_string(),
-                            text: format!("Failed to fetch models: {}", e)
-                        });
-                    }
-                    AppEvent::OllamaChunk(chunk) => {
-                        // Append chunk to the last conversation turn if it's from the model
-                        let model_name = app.get_selected_model_name().unwrap_or_else(|| "Model".to_string());
-                        if let Some(last_turn) = app.conversation.last_mut() {
-                            if last_turn.sender == model_name {
-                                last_turn.text.push_str(&chunk); // Append to existing model response
-                            } else {
-                                // Last turn was from User or Error, start new Model turn
-                                app.conversation.push(ConversationTurn {
-                                    sender: model_name,
-                                    text: chunk,
-                                });
-                            }
-                        } else {
-                            // Conversation is empty, start the first Model turn
-                            app.conversation.push(ConversationTurn {
-                                sender: model_name,
-                                text: chunk,
-                            });
-                        }
-                        // TODO: Implement auto-scrolling logic if desired
-                    }
-                    AppEvent::OllamaDone => {
-                        // Mark loading as finished, update status
-                        app.is_loading = false;
-                        app.status_message = "Response received. Press 'Enter' to type (Ctrl+S to send).".to_string();
-                    }
-                    AppEvent::OllamaError(err_msg) => {
-                        // Mark loading finished, display error
-                        app.is_loading = false;
-                        // Add error to conversation for visibility
-                        app.conversation.push(ConversationTurn {
-                            sender: "Error".to_string(),
-                            text: err_msg.clone()
-                        });
-                        // Update status bar
-                        app.status_message = format!("Error occurred: {}", err_msg);
-                    }
+    }
+    match app.active_tab {
+        ActiveTab::Models => match key.code {
+            KeyCode::Enter if app.selected_model_index.is_some() => {
+                app.show_tab(ActiveTab::Chat);
+                if let Some(model) = app.get_selected_model_name() {
+                    app.status_message = format!("Chatting with {}. Press Enter to type.", model);
                 }
+                true
+            }
+            KeyCode::Char('b') => {
+                app.open_library(String::new());
+                true
             }
-            // No message received from async tasks
-            Err(mpsc::error::TryRecvError::Empty) => {}
-            // Channel disconnected - critical error
-            Err(mpsc::error::TryRecvError::Disconnected) => {
-                app.status_message = "Critical Error: Async event channel disconnected.".to_string();
-                terminal.draw(|f| ui(f, app))?; // Draw final error before exiting
-                return Err(AppError::ChannelReceive);
+            KeyCode::Char('R') => {
+                app.status_message = "Checking the server for models...".to_string();
+                app.refresh_models();
+                true
             }
+            _ => false,
+        },
+        // Everything but switching screens and quitting goes to the list
+        ActiveTab::Sessions if !matches!(key.code, KeyCode::Tab | KeyCode::BackTab | KeyCode::Char('q')) => {
+            let Some(browser) = app.session_browser.as_mut() else { return false };
+            match browser.handle_key(key) {
+                SessionAction::None => {}
+                SessionAction::Close => app.show_tab(ActiveTab::Chat),
+                SessionAction::Status(message) => app.status_message = message,
+                SessionAction::Resume(path, session) => {
+                    app.show_tab(ActiveTab::Chat);
+                    app.resume_session(path, *session);
+                }
+            }
+            true
         }
-
-        // Short sleep to prevent high CPU usage when idle
-        tokio::time::sleep(Duration::from_millis(10)).await;
-
-    } // End main loop
+        _ => false,
+    }
 }
 
-
 // --- UI Drawing Logic ---
-// Takes immutable borrow of App as state changes happen in run_app loop
-fn ui(f: &mut Frame, app: &App) {
-    // Main layout: Models List | Right Pane
-    let main_chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(25), Constraint::Percentage(75)].as_ref())
-        .split(f.size());
+// Takes immutable borrow of App as state changes happen in run_app loop;
+// returns where the conversation pane went
+fn ui(f: &mut Frame, app: &App) -> Rect {
+    let view = conversation_lines(app, app.text_width(f.size()));
+    let pinned = pinned_rows(app, &view);
+    let panes = layout::panes(
+        f.size(),
+        pinned.as_ref().map(|rows| rows.len().min(u16::MAX as usize) as u16),
+        app.scratchpad.open,
+        app.split.as_ref().map(|split| split.side),
+        app.active_tab == ActiveTab::Chat,
+    );
+
+    // --- Tab Bar ---
+    let titles: Vec<Line> = ActiveTab::ALL
+        .iter()
+        .enumerate()
+        .map(|(index, tab)| Line::from(format!("{} {}", index + 1, tab.title())))
+        .collect();
+    let tabs = Tabs::new(titles)
+        .select(app.active_tab.index())
+        .style(Style::default().fg(Color::DarkGray))
+        .highlight_style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD));
+    f.render_widget(tabs, panes.tabs);
 
     // --- Left side: Models List ---
+    // Inside the borders and the highlight symbol
+    let row_width = panes.models.width.saturating_sub(4) as usize;
      let model_items: Vec<ListItem> = app
         .models
         .iter()
-        .map(|m| ListItem::new(m.as_str())) // Creates ListItem<'a> borrowing from app.models
+        .map(|m| {
+            // Name, details and badges, e.g.
+            // "llava:7b  (4.5 GB, Q4_0, 7B) [vision] [idle 40d]"
+            let summary = app.model_details.get(m).cloned().unwrap_or_default().summary(app.model_sizes.get(m).copied());
+            let mut after = Vec::new();
+            if !summary.is_empty() {
+                after.push(Span::styled(format!("  {}", summary), Style::default().fg(Color::DarkGray)));
+            }
+            if let Some(capabilities) = app.capabilities.get(m) {
+                after.extend(capabilities::badges(capabilities));
+            }
+            if app.is_cleanup_candidate(m) {
+                let idle = app.usage.idle_days(m).unwrap_or_default();
+                after.push(Span::styled(format!(" [idle {}d]", idle), Style::default().fg(Color::Red)));
+            }
+            // The name gives way to the details down to two thirds of the
+            // row; past that the details are cut at the edge instead
+            let after_width: usize = after.iter().map(Span::width).sum();
+            let name_floor = (row_width * 2 / 3).max(MIN_MODEL_NAME_WIDTH);
+            let name = installed::fit_name(m, row_width.saturating_sub(after_width).max(name_floor));
+            let mut spans = vec![Span::raw(name)];
+            spans.extend(after);
+            ListItem::new(Line::from(spans))
+        })
         .collect();
 
+    // Footer: disk used by all models and how many look unused
+    let total_size: u64 = app.model_sizes.values().sum();
+    let idle = app.models.iter().filter(|m| app.is_cleanup_candidate(m)).count();
+    let footer = if idle > 0 {
+        format!(" {} on disk, {} idle, s: Sort, D: Delete ", health::format_bytes(total_size), idle)
+    } else {
+        format!(" {} on disk, s: Sort ", health::format_bytes(total_size))
+    };
+    // Cached models while the server is down; the Models tab says how old
+    let mut models_block = Block::default().borders(Borders::ALL).title_bottom(footer);
+    models_block = match app.models_cached {
+        Some(_) => models_block.title(Line::styled(" Models (offline) ", Style::default().fg(Color::Yellow))),
+        None => models_block.title(" Models (j/k) "),
+    };
+    if app.focus() == Focus::Models {
+        models_block = models_block.border_style(Style::default().fg(Color::Cyan));
+    }
+
     let models_list = List::new(model_items) // List holds Vec<ListItem<'a>>
-        .block(Block::default().borders(Borders::ALL).title(" Models (j/k) "))
+        .block(models_block)
         .highlight_style(
             Style::default()
                 .add_modifier(Modifier::BOLD)
@@ -417,92 +4680,154 @@ fn ui(f: &mut Frame, app: &App) {
     let mut list_state = ListState::default();
     list_state.select(app.selected_model_index);
 
-    f.render_stateful_widget(models_list, main_chunks[0], &mut list_state);
-
-    // --- Right side: Conversation, Input, Status ---
-    let right_chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints(
-            [
-                Constraint::Min(1),        // Conversation area
-                Constraint::Length(7),     // Input area height
-                Constraint::Length(1),     // Status bar height
-            ]
-            .as_ref(),
-        )
-        .split(main_chunks[1]);
+    f.render_stateful_widget(models_list, panes.models, &mut list_state);
 
-    // --- Conversation Area ---
-    // Build the content for the conversation paragraph
-    let mut conversation_content: Vec<Line> = Vec::new();
-    for turn in &app.conversation { // Borrow each turn
-         let prefix_style = match turn.sender.as_str() {
-            "You" => Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
-            "Error" | "System Error" => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
-            _ => Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
-        };
-         // Create Line<'a> borrowing from turn.sender
-         let prefix = Line::styled(format!("{}: ", turn.sender), prefix_style);
-         conversation_content.push(prefix);
+    // --- Pinned Turn ---
+    if let (Some(rows), Some(area)) = (pinned, panes.pinned) {
+        let sender = app.pinned_turn.and_then(|index| app.conversation.get(index)).map_or("", |turn| turn.name.as_str());
+        let pinned_block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow))
+            .title(format!(" Pinned: {} (p: Unpin, or pin the selected turn instead) ", sender));
+        f.render_widget(pinned_block, area);
+        f.render_widget(Paragraph::new(view.lines[rows].to_vec()), layout::text_area(area, app.conversation_width));
+    }
 
-         // Render the turn's text using Markdown
-         // render_markdown returns Vec<Line<'a>> borrowing from turn.text
-         conversation_content.extend(render_markdown(&turn.text));
+    // --- Conversation Area ---
+    let (conversation_lines, conversation_title) = match &app.visual {
+        Some(selection) => (
+            view.lines
+                .into_iter()
+                .zip(view.margins)
+                .enumerate()
+                .map(|(row, (line, margins))| selection.highlight(row, line, margins))
+                .collect(),
+            " Conversation (Visual: w/b/j/k: Extend, y: Copy, Esc: Cancel) ",
+        ),
+        None => (view.lines, " Conversation (j/k, PgUp/PgDn: Scroll, [/]: Select turn, v: Select text, Tab: Next pane) "),
+    };
+    let mut conversation_block = Block::default().borders(Borders::ALL).title(conversation_title);
+    // The live side of a split is marked even while another pane has focus
+    if app.split.is_some() || app.focus() == Focus::Conversation {
+        conversation_block = conversation_block.border_style(Style::default().fg(Color::Cyan));
+    }
+    // The block is drawn on its own so the text can be centered inside it
+    let conversation_paragraph = Paragraph::new(conversation_lines) // Takes Vec<Line<'_>>
+        .scroll((app.scroll_offset, 0)); // Apply vertical scroll offset
 
-         // Add spacing between turns
-         conversation_content.push(Line::from("")); // Creates Line<'static>
+    match app.active_tab {
+        ActiveTab::Chat if app.showing_empty_state() => empty_state::render(f, panes.conversation),
+        ActiveTab::Chat => {
+            f.render_widget(conversation_block, panes.conversation);
+            f.render_widget(conversation_paragraph, layout::text_area(panes.conversation, app.conversation_width));
+        }
+        ActiveTab::Models => {
+            let models: Vec<installed::ModelRow> = app
+                .models
+                .iter()
+                .map(|name| installed::ModelRow {
+                    name,
+                    size: app.model_sizes.get(name).copied(),
+                    capabilities: app.capabilities.get(name).map_or(&[], Vec::as_slice),
+                    idle_days: app.usage.idle_days(name),
+                    idle: app.is_cleanup_candidate(name),
+                })
+                .collect();
+            let cached = app.models_cached.map(catalog::age);
+            installed::render(f, panes.conversation, &models, app.selected_model_index, cached.as_deref());
+        }
+        ActiveTab::Sessions => {
+            if let Some(browser) = &app.session_browser {
+                browser.render(f, panes.conversation);
+            }
+        }
+        ActiveTab::Health => health::render(f, panes.conversation, app.health.as_ref(), &app.request_errors),
+        ActiveTab::Bench => bench::render(f, panes.conversation, app.bench.as_ref()),
+        ActiveTab::Arena => arena::render(f, panes.conversation, app.arena.as_ref(), &app.scoreboard),
+        ActiveTab::Transfers => transfers::render(f, panes.conversation, &app.transfers),
+        ActiveTab::Embeddings => embeddings::render(f, panes.conversation, &app.playground),
+        ActiveTab::Stats => stats::render(f, panes.conversation, &app.stats),
     }
 
-    let conversation_paragraph = Paragraph::new(conversation_content) // Takes Vec<Line<'_>>
-        .block(Block::default().borders(Borders::ALL).title(" Conversation (PgUp/PgDn) "))
-        .wrap(Wrap { trim: false }) // Don't trim whitespace on wrapped lines
-        .scroll((app.scroll_offset, 0)); // Apply vertical scroll offset
+    // --- Other Side of a Split ---
+    if let (Some(split), Some(area), true) = (&app.split, panes.other, app.active_tab == ActiveTab::Chat) {
+        let parked = &split.parked;
+        let width = app.text_width(f.size());
+        let other = render_turns(app, &parked.turns, parked.selected_turn, false, width);
+        let title = match &parked.model {
+            Some(model) => format!(" Other conversation: {} (Tab: Go there) ", model),
+            None => " Other conversation (Tab: Go there) ".to_string(),
+        };
+        let other_block = Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::DarkGray)).title(title);
+        f.render_widget(other_block, area);
+        let other_paragraph = Paragraph::new(other.lines).scroll((parked.scroll_offset, 0));
+        f.render_widget(other_paragraph, layout::text_area(area, width));
+    }
 
-    f.render_widget(conversation_paragraph, right_chunks[0]);
+    // --- Scratchpad ---
+    if let Some(area) = panes.scratchpad {
+        if let Some((x, y)) = app.scratchpad.render(f, area, &app.send_key()) {
+            f.set_cursor(x, y);
+        }
+    }
 
     // --- Input Area ---
-    let input_title = match app.input_mode {
-        InputMode::Editing => " Input (Enter: Newline, Ctrl+S: Send, Esc: Cancel) ",
-        InputMode::Normal => " Input (Press Enter to type) ",
+    let editing = app.focus == Focus::Input;
+    let input_title = if app.input_preview.is_some() {
+        " Input, variables filled in (Ctrl+P: Back to the draft) ".to_string()
+    } else if editing {
+        format!(" Input (Enter: Newline, {}: Send, Ctrl+O: Attach, Ctrl+K: Clear, Esc: Leave) ", app.send_key())
+    } else {
+        " Input (Press Enter to type) ".to_string()
     };
-    let input_block_style = match app.input_mode {
-        InputMode::Editing => Style::default().fg(Color::Yellow), // Highlight border
-        InputMode::Normal => Style::default(),
+    let input_block_style = if editing {
+        Style::default().fg(Color::Yellow) // Highlight border
+    } else {
+        Style::default()
     };
-    let input_block = Block::default()
+    let mut input_block = Block::default()
         .borders(Borders::ALL)
         .title(input_title)
         .border_style(input_block_style);
+    if !app.prompt_wrap.is_empty() {
+        input_block = input_block.title_bottom(Span::styled(
+            format!(" {} ", app.prompt_wrap.summary()),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
 
-    // Create the input paragraph, borrowing from app.input_buffer
-    let input_paragraph = Paragraph::new(app.input_buffer.as_str()) // Creates Paragraph<'a>
-        .block(input_block)
-        .wrap(Wrap { trim: false }); // Wrap long input lines
+    // Live word / token counter, warning when the request may overflow the context
+    if editing {
+        let draft_tokens = tokens::estimate_tokens(app.input_buffer.text());
+        let total_tokens = app.draft_token_estimate();
+        let mut counter = format!(" {} words / ~{} tokens ", tokens::word_count(app.input_buffer.text()), draft_tokens);
+        let mut counter_style = Style::default();
+        if total_tokens > app.config.context_window {
+            counter.push_str(&format!(
+                "⚠ ~{} in request > {} ctx, {} ",
+                total_tokens,
+                app.config.context_window,
+                app.config.context_trim.name()
+            ));
+            counter_style = counter_style.fg(Color::Red).add_modifier(Modifier::BOLD);
+        }
+        input_block = input_block.title(
+            block::Title::from(Span::styled(counter, counter_style)).alignment(Alignment::Right),
+        );
+    }
 
-    f.render_widget(input_paragraph, right_chunks[1]);
+    // The editor draws its own gutter, wrapping and scrolling
+    let cursor = match &app.input_preview {
+        Some(preview) => {
+            f.render_widget(Paragraph::new(preview.as_str()).block(input_block).wrap(Wrap { trim: false }), panes.input);
+            None
+        }
+        None => app.input_buffer.render(f, panes.input, input_block),
+    };
 
     // Set cursor position visually only when editing
-    if let InputMode::Editing = app.input_mode {
-        let input_area = right_chunks[1];
-        let buffer_char_count = app.input_buffer.chars().count();
-        // Calculate width inside borders, ensure it's at least 1
-        let input_width = input_area.width.saturating_sub(2).max(1);
-
-        // Estimate row/col (doesn't perfectly handle complex wrapping)
-        let estimated_row = buffer_char_count as u16 / input_width;
-        let estimated_col = buffer_char_count as u16 % input_width;
-
-        // Calculate absolute screen coordinates
-        let cursor_x = input_area.x + 1 + estimated_col;
-        let cursor_y = (input_area.y + 1 + estimated_row)
-                           .min(input_area.bottom().saturating_sub(1)); // Clamp Y within box
-
-         // Clamp X within box
-         let max_cursor_x = input_area.right().saturating_sub(1);
-         let clamped_cursor_x = cursor_x.min(max_cursor_x);
-
-        // Set the terminal cursor
-        f.set_cursor(clamped_cursor_x, cursor_y);
+    if let (true, Some((cursor_x, cursor_y))) = (editing, cursor) {
+        f.set_cursor(cursor_x, cursor_y);
     }
 
     // --- Status Bar ---
@@ -514,32 +4839,351 @@ fn ui(f: &mut Frame, app: &App) {
         Style::default().bg(Color::DarkGray).fg(Color::White)
     };
 
-    // Create status bar paragraph, borrowing from app.status_message
-    let status_bar = Paragraph::new(app.status_message.as_str()) // Creates Paragraph<'a>
+    let status_spans = status_line::render(&app.config.status_format, |name| status_value(app, name));
+    let status_bar = Paragraph::new(Line::from(status_spans))
         .style(status_style);
-    f.render_widget(status_bar, right_chunks[2]);
+    // Sparklines on the right when there is room for them
+    let mut status_area = panes.status;
+    if !app.recent_generations.is_empty() && status_area.width >= 3 * stats::SPARKLINES_WIDTH {
+        status_area.width -= stats::SPARKLINES_WIDTH;
+        let sparklines = Rect { x: status_area.right(), width: stats::SPARKLINES_WIDTH, ..status_area };
+        app.recent_generations.render(f, sparklines, status_style);
+    }
+    f.render_widget(status_bar, status_area);
+
+    if app.resources.enabled && app.is_loading {
+        resources::render(f, panes.conversation, app.resources.snapshot.as_ref());
+    }
+
+    // --- Onboarding Hints ---
+    if app.onboarding.active() {
+        onboarding::render(f, &onboarding_hints(app, &panes));
+    }
+
+    // --- Popups ---
+    app.modals.render(f);
+    app.notifications.render(f, modal::centered_rect(60, 30, f.size()));
+    app.frames.render(f, f.size(), &app.queue.summary());
+    if app.frames.show_stats {
+        trace::render(f, f.size());
+    }
+    app.theme.apply(f.buffer_mut());
+    if !app.true_color {
+        theme::downgrade_rgb(f.buffer_mut());
+    }
+    panes.conversation
+}
+
+// At most one hint per pane, for what the user is doing right now; the
+// input pane is too short for a box, so its hints sit just above it
+fn onboarding_hints(app: &App, panes: &layout::Panes) -> Vec<Hint> {
+    let busy = matches!(app.focus(), Focus::Popup(_));
+    if busy || app.active_tab != ActiveTab::Chat || app.showing_empty_state() {
+        return Vec::new();
+    }
+    let mut hints = Vec::new();
+    if app.models.len() > 1 && app.focus == Focus::Models {
+        hints.push(Hint { anchor: panes.models, text: "j/k: Pick a model, Tab: Next pane".to_string() });
+    }
+    let text = match app.focus {
+        Focus::Input => format!("{} sends, Enter adds a newline, Esc leaves", app.send_key()),
+        _ if app.conversation.is_empty() => "Press Enter to start typing".to_string(),
+        _ => "[ and ]: Select a turn, y: Copy it, v: Select text".to_string(),
+    };
+    hints.push(Hint { anchor: panes.conversation, text });
+    hints
+}
+
+// Lines of the pinned turn within the conversation, without its sender
+// prefix and the blank line after it; only the chat view shows it
+fn pinned_rows(app: &App, view: &ConversationView) -> Option<std::ops::Range<usize>> {
+    if app.active_tab != ActiveTab::Chat {
+        return None;
+    }
+    let index = app.pinned_turn.filter(|&index| index < app.conversation.len())?;
+    let start = view.turn_offsets[index] as usize + 1;
+    let end = view.turn_offsets.get(index + 1).map_or(view.lines.len(), |&next| next as usize).saturating_sub(1);
+    Some(start..end.max(start))
+}
+
+// Every line of the conversation pane, already wrapped to `width`, with the
+// line at which each turn and heading starts
+struct ConversationView<'a> {
+    lines: Vec<Line<'a>>,
+    // Per line: whether it was wrapped onto from the line before
+    continued: Vec<bool>,
+    // Per line: columns of bubble padding before and after the text
+    margins: Vec<(usize, usize)>,
+    turn_offsets: Vec<u16>,
+    outline: Vec<OutlineEntry>,
+}
+
+fn conversation_lines(app: &App, width: u16) -> ConversationView<'_> {
+    render_turns(app, &app.conversation, app.selected_turn, true, width)
+}
+
+// `live` is false for the parked side of a split, which never streams
+fn render_turns<'a>(app: &'a App, turns: &'a [ConversationTurn], selected_turn: Option<usize>, live: bool, width: u16) -> ConversationView<'a> {
+    let mut conversation_content: Vec<Line> = Vec::new();
+    let mut continued = Vec::new();
+    let mut margins = Vec::new();
+    let mut offsets = Vec::with_capacity(turns.len());
+    let mut outline = Vec::new();
+    let spinner = app.spinner_frame().filter(|_| live);
+    // The model turn still being generated, if any
+    let streaming_turn = spinner
+        .and(turns.last())
+        .filter(|turn| turn.request.is_some() && turn.request == app.active_request)
+        .map(|_| turns.len() - 1);
+    for (index, turn) in turns.iter().enumerate() { // Borrow each turn
+         let mut prefix_style = match turn.role {
+            Role::User => Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+            Role::System | Role::Tool => Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+            Role::Error => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            Role::SystemPrompt => Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            Role::Assistant => Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        };
+         // Highlight the selected turn's header
+         if selected_turn == Some(index) {
+             prefix_style = prefix_style.add_modifier(Modifier::REVERSED);
+         }
+         let excluded_style = Style::default().fg(Color::DarkGray).add_modifier(Modifier::CROSSED_OUT);
+         if turn.excluded {
+             prefix_style = prefix_style.patch(excluded_style);
+         }
+         // Create Line<'a> borrowing from turn.name
+         let is_streaming = streaming_turn == Some(index);
+         let mut prefix = match spinner.filter(|_| is_streaming) {
+             Some(frame) => Line::styled(format!("{} {}: ", frame, turn.name), prefix_style),
+             None => Line::styled(format!("{}: ", turn.name), prefix_style),
+         };
+         if let Some(note) = &turn.routed {
+             prefix.spans.push(Span::styled(format!("({}) ", note), Style::default().fg(Color::DarkGray)));
+         }
+         if turn.wrap.is_some() {
+             prefix.spans.push(Span::styled("(wrapped) ", Style::default().fg(Color::DarkGray)));
+         }
+         if turn.interrupted {
+             prefix.spans.push(Span::styled("(interrupted, 'r' to resume)", Style::default().fg(Color::Yellow)));
+         } else if let Some(finish) = &turn.finish {
+             prefix.spans.push(finish.marker(turn.tokens));
+         }
+         let mut turn_lines = vec![prefix];
+
+         // Render the turn's text, usually as markdown
+         // Lines borrow from turn.text; only its unfinished tail is re-parsed
+         let folded = app.foldable_code(turn).filter(|_| !turn.code_expanded);
+         let RenderedMarkdown { mut lines, headings } = match app.renderer_for(turn) {
+             _ if folded.is_some() => RenderedMarkdown {
+                 lines: folded.iter().map(CodeOnly::folded_line).collect(),
+                 headings: Vec::new(),
+             },
+             Renderer::Plain => render_raw(&turn.text),
+             Renderer::Ansi => RenderedMarkdown { lines: renderer::ansi_lines(&turn.text), headings: Vec::new() },
+             Renderer::Markdown if is_streaming => turn.text.render_streaming(),
+             Renderer::Markdown => turn.text.render(),
+         };
+         if turn.excluded {
+             // Strike through every span so its own colours don't win
+             for line in &mut lines {
+                 for span in &mut line.spans {
+                     span.style = span.style.patch(excluded_style);
+                 }
+             }
+         }
+         if is_streaming {
+             // Subtle background on the growing turn, with a blinking cursor at its end
+             let streaming_style = Style::default().bg(Color::Rgb(30, 30, 40));
+             let cursor_visible = app.stream_started.is_some_and(|t| t.elapsed().as_millis() / 500 % 2 == 0);
+             if let Some(last) = lines.last_mut() {
+                 last.spans.push(Span::styled(if cursor_visible { "▍" } else { " " }, Style::default().fg(Color::Yellow)));
+             }
+             lines = lines.into_iter().map(|line| line.patch_style(streaming_style)).collect();
+         }
+         turn_lines.extend(lines);
+
+         // A folded summary shows only its first line
+         if let Some(summary) = &turn.summary {
+             let style = Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC);
+             if summary.expanded {
+                 turn_lines.push(Line::styled("▾ Summary ('z' to fold):", style));
+                 turn_lines.extend(summary.text.lines().map(|line| Line::styled(format!("  {}", line), style)));
+             } else {
+                 let first = summary.text.lines().next().unwrap_or("");
+                 turn_lines.push(Line::styled(format!("▸ Summary: {} ('z' to unfold)", first), style));
+             }
+         }
+
+         offsets.push(conversation_content.len().min(u16::MAX as usize) as u16);
+         // Your prompts as bubbles, on the right
+         if app.message_layout == MessageLayout::Bubbles && turn.role == Role::User {
+             let header = turn_lines.remove(0);
+             let top = conversation_content.len();
+             let bubble = bubbles::right(header, turn_lines, width);
+             for heading in headings {
+                 let line = top + bubble.starts.get(heading.line + 1).copied().unwrap_or_default();
+                 outline.push(OutlineEntry { turn: index, level: heading.level, title: heading.title, line: line.min(u16::MAX as usize) as u16 });
+             }
+             conversation_content.extend(bubble.rows);
+             continued.extend(bubble.continued);
+             margins.extend(bubble.margins);
+             // Spacing between turns
+             conversation_content.push(Line::from(""));
+             continued.push(false);
+             margins.push((0, 0));
+             continue;
+         }
+         // Add spacing between turns
+         turn_lines.push(Line::from("")); // Creates Line<'static>
+         // Wrapped one line at a time so each heading's final position is known
+         let mut headings = headings.into_iter().peekable();
+         for (line_index, line) in turn_lines.into_iter().enumerate() {
+             // turn_lines starts with the sender prefix
+             if let Some(heading) = headings.next_if(|heading| heading.line + 1 == line_index) {
+                 outline.push(OutlineEntry {
+                     turn: index,
+                     level: heading.level,
+                     title: heading.title,
+                     line: conversation_content.len().min(u16::MAX as usize) as u16,
+                 });
+             }
+             let rows = layout::wrap_line(line, width);
+             continued.extend((0..rows.len()).map(|row| row > 0));
+             margins.extend(rows.iter().map(|_| (0, 0)));
+             conversation_content.extend(rows);
+         }
+    }
+    // Waiting for the first token: show a placeholder turn with the spinner
+    if let (Some(frame), None) = (spinner, streaming_turn) {
+        let model_name = app.get_selected_model_name().unwrap_or_else(|| "Model".to_string());
+        let mut placeholder = Line::styled(
+            format!("{} {}: ", frame, model_name),
+            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        );
+        if app.model_loading {
+            let waited = app.stream_started.map_or(0, |started| started.elapsed().as_secs());
+            placeholder.spans.push(Span::styled(
+                format!("loading model into memory… {}s", waited),
+                Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+            ));
+        }
+        conversation_content.push(placeholder);
+        continued.push(false);
+        margins.push((0, 0));
+    }
+
+    ConversationView { lines: conversation_content, continued, margins, turn_offsets: offsets, outline }
 }
 
+// The value of a status line variable, None when it has none right now
+fn status_value(app: &App, name: &str) -> Option<Span<'static>> {
+    let text = match name {
+        "status" => Some(app.status_message.clone()).filter(|status| !status.is_empty()),
+        "mode" => Some(if app.focus == Focus::Input { "EDITING" } else { "NORMAL" }.to_string()),
+        "focus" => Some(app.focus().name().to_string()),
+        "api" => Some(if app.chat_mode() { "chat" } else { "generate" }.to_string()),
+        "model" => app.get_selected_model_name(),
+        "host" => {
+            let server = app.server_latency.as_ref().map_or(endpoint::base(), |(server, _)| server.as_str());
+            Some(server.trim_start_matches("http://").trim_start_matches("https://").to_string())
+        }
+        "latency" => return app.server_latency.as_ref().map(|(server, latency)| latency_span(server, *latency)),
+        "version" => app.server_version.map(|version| format!("Ollama {}", version)),
+        // Requests held back by the configured limits
+        "queued" => Some(app.limiter.waiting()).filter(|waiting| *waiting > 0).map(|waiting| format!("{} queued", waiting)),
+        "ctx_used" => Some(app.draft_token_estimate().to_string()),
+        "ctx_max" => Some(app.config.context_window.to_string()),
+        "tps" => app.recent_generations.latest_speed().map(|speed| format!("{:.1}", speed)),
+        "turns" => Some(app.conversation.len().to_string()),
+        "plugins" => Some(app.plugins.status_segments()).filter(|segments| !segments.is_empty()).map(|segments| segments.join(" | ")),
+        _ => None,
+    };
+    text.map(Span::raw)
+}
+
+// Round trip to the server in use: green is local-fast, yellow noticeable,
+// red slow enough to explain sluggish streaming
+fn latency_span(server: &str, latency: Option<Duration>) -> Span<'static> {
+    let via = if server == endpoint::base() { String::new() } else { format!(" via {}", server) };
+    let Some(latency) = latency else {
+        return Span::styled(format!("unreachable{}", via), Style::default().bg(Color::Red).fg(Color::White));
+    };
+    let color = match latency.as_millis() {
+        0..=99 => Color::Green,
+        100..=399 => Color::Yellow,
+        _ => Color::Red,
+    };
+    Span::styled(format!("{} ms{}", latency.as_millis(), via), Style::default().bg(color).fg(Color::Black))
+}
 
 // --- Markdown Renderer ---
 // Takes a string slice with lifetime 'a and returns Lines borrowing from it
-fn render_markdown<'a>(markdown_input: &'a str) -> Vec<Line<'a>> {
+// A markdown heading: its level, its text and the index of its rendered line
+#[derive(Clone)]
+struct Heading {
+    level: usize,
+    title: String,
+    line: usize,
+}
+
+struct RenderedMarkdown<'a> {
+    lines: Vec<Line<'a>>,
+    headings: Vec<Heading>,
+}
+
+// Markdown source shown verbatim, one line per source line
+fn render_raw(text: &str) -> RenderedMarkdown<'_> {
+    let raw_style = Style::default().fg(Color::Gray).bg(Color::Rgb(30, 30, 30));
+    let mut lines: Vec<Line> = text.lines().map(|line| Line::styled(line, raw_style)).collect();
+    if lines.is_empty() {
+        lines.push(Line::raw(""));
+    }
+    RenderedMarkdown { lines, headings: Vec::new() }
+}
+
+// Gutter bar colours for blockquotes, by nesting depth
+const QUOTE_COLORS: [Color; 4] = [Color::Yellow, Color::Blue, Color::Magenta, Color::Green];
+
+fn render_markdown<'a>(markdown_input: &'a str) -> RenderedMarkdown<'a> {
     let mut options = Options::empty();
     options.insert(Options::ENABLE_STRIKETHROUGH);
-    let parser = Parser::new_ext(markdown_input, options);
+    options.insert(Options::ENABLE_FOOTNOTES);
+    // Math is moved out of the markdown's way first; see math.rs
+    let source = math::isolate(markdown_input);
+    let parser = Parser::new_ext(&source, options);
 
     let mut lines: Vec<Line<'a>> = Vec::new();
     let mut current_spans: Vec<Span<'a>> = Vec::new();
     let mut current_style = Style::default().fg(Color::Cyan); // Base style for model text
     let mut list_stack: Vec<Option<u64>> = Vec::new();
+    let mut headings: Vec<Heading> = Vec::new();
+    // First line of each open blockquote, innermost last
+    let mut quote_starts: Vec<usize> = Vec::new();
+    // Footnotes and reference-link targets, numbered in order of first use and
+    // listed under the turn; keyed by "^label" or the link destination
+    let mut references: Vec<(String, Vec<Line<'a>>)> = Vec::new();
+    let reference_number = |references: &mut Vec<(String, Vec<Line<'a>>)>, key: String| {
+        match references.iter().position(|(existing, _)| *existing == key) {
+            Some(index) => index + 1,
+            None => {
+                references.push((key, Vec::new()));
+                references.len()
+            }
+        }
+    };
+    let reference_marker_style = Style::default().fg(Color::LightBlue);
+    let mut pending_link_reference: Option<usize> = None;
+    let mut footnote_start: Option<(usize, String)> = None;
     let mut in_code_block = false;
+    let mut in_math_block = false;
+    let math_style = Style::default().fg(Color::LightGreen).add_modifier(Modifier::ITALIC);
     let code_block_style = Style::default().bg(Color::Rgb(40, 40, 40)).fg(Color::White);
     let inline_code_style = Style::default().bg(Color::Rgb(50, 50, 50)).fg(Color::Yellow).add_modifier(Modifier::ITALIC);
 
     // Helper closure to push completed lines
     let push_current_line = |lines: &mut Vec<Line<'a>>, current_spans: &mut Vec<Span<'a>>| {
         if !current_spans.is_empty() {
-            lines.push(Line::from(current_spans.drain(..).collect::<Vec<_>>()));
+            lines.push(Line::from(std::mem::take(current_spans)));
         }
     };
 
@@ -550,17 +5194,26 @@ fn render_markdown<'a>(markdown_input: &'a str) -> Vec<Line<'a>> {
                     MDTag::Paragraph => {
                         // Reset style for new paragraph if needed (e.g., after blockquote)
                         current_style = Style::default().fg(Color::Cyan);
+                        if !quote_starts.is_empty() {
+                            current_style = current_style.add_modifier(Modifier::ITALIC);
+                        }
                     }
                     MDTag::Heading(level, _, _) => {
                         push_current_line(&mut lines, &mut current_spans); // Finish previous line
+                        // The heading's line is the next one pushed; its title fills in as text arrives
+                        headings.push(Heading { level: level as usize, title: String::new(), line: lines.len() });
                         current_style = Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD);
                         let prefix = "#".repeat(level as usize) + " ";
                         current_spans.push(Span::styled(prefix, current_style));
                     }
                     MDTag::BlockQuote => {
                         push_current_line(&mut lines, &mut current_spans);
-                        current_style = Style::default().fg(Color::Yellow).add_modifier(Modifier::ITALIC);
-                        current_spans.push(Span::styled("> ", current_style));
+                        quote_starts.push(lines.len());
+                    }
+                    MDTag::CodeBlock(CodeBlockKind::Fenced(language)) if language.as_ref() == math::FENCE_LANGUAGE => {
+                        push_current_line(&mut lines, &mut current_spans);
+                        in_code_block = true;
+                        in_math_block = true;
                     }
                     MDTag::CodeBlock(_) => {
                         push_current_line(&mut lines, &mut current_spans);
@@ -579,7 +5232,7 @@ fn render_markdown<'a>(markdown_input: &'a str) -> Vec<Line<'a>> {
                              Some(&Some(start)) => { // Ordered list
                                  // Simple count - may be incorrect for complex lists
                                  let count = lines.iter().filter(|line| {
-                                     line.spans.first().map_or(false, |span| span.content.starts_with(&indent) && span.content.contains(". "))
+                                     line.spans.first().is_some_and(|span| span.content.starts_with(&indent) && span.content.contains(". "))
                                  }).count() as u64;
                                  format!("{}. ", start.saturating_add(count))
                              }
@@ -598,8 +5251,18 @@ fn render_markdown<'a>(markdown_input: &'a str) -> Vec<Line<'a>> {
                     MDTag::Strikethrough => {
                         current_style = current_style.add_modifier(Modifier::CROSSED_OUT);
                     }
-                    MDTag::Link(_, _dest, _) => { // Mark dest as unused
+                    MDTag::Link(link_type, dest, title) => {
                         current_style = current_style.fg(Color::Blue).add_modifier(Modifier::UNDERLINED);
+                        // Reference-style links get a numbered marker and their target listed below
+                        if matches!(link_type, LinkType::Reference | LinkType::Collapsed | LinkType::Shortcut) {
+                            let number = reference_number(&mut references, dest.to_string());
+                            let target = &mut references[number - 1].1;
+                            if target.is_empty() {
+                                let text = if title.is_empty() { dest.to_string() } else { format!("{} \"{}\"", dest, title) };
+                                target.push(Line::styled(text, Style::default().fg(Color::Blue)));
+                            }
+                            pending_link_reference = Some(number);
+                        }
                     }
                      MDTag::Image(_, _, _) => { // Placeholder for images
                          current_spans.push(Span::styled("[Image]", Style::default().fg(Color::DarkGray)));
@@ -612,17 +5275,43 @@ fn render_markdown<'a>(markdown_input: &'a str) -> Vec<Line<'a>> {
                      }
                      // Ignore table tags
                     MDTag::Table(_) | MDTag::TableHead | MDTag::TableRow | MDTag::TableCell => {}
-                    // Ignore footnotes
-                    MDTag::FootnoteDefinition(_) => {}
+                    // The definition is rendered as usual, then moved to the references
+                    MDTag::FootnoteDefinition(label) => {
+                        push_current_line(&mut lines, &mut current_spans);
+                        footnote_start = Some((lines.len(), label.to_string()));
+                    }
                 }
             }
             MDEvent::End(tag) => {
                 match tag {
-                    MDTag::Paragraph | MDTag::Heading(_,_,_) | MDTag::Item | MDTag::BlockQuote => {
+                    MDTag::Heading(_, _, _) => {
+                        let title = current_spans.iter().skip(1).map(|span| span.content.as_ref()).collect::<String>();
+                        if let Some(heading) = headings.last_mut() {
+                            heading.title = title.trim().to_string();
+                        }
+                        push_current_line(&mut lines, &mut current_spans);
+                    }
+                    MDTag::BlockQuote => {
+                        push_current_line(&mut lines, &mut current_spans);
+                        // A gutter bar down the whole quote; outer quotes end later and
+                        // so put their bars in front, each depth in its own colour
+                        let depth = quote_starts.len();
+                        let start = quote_starts.pop().unwrap_or(lines.len());
+                        let bar_style = Style::default().fg(QUOTE_COLORS[(depth - 1) % QUOTE_COLORS.len()]);
+                        for line in &mut lines[start..] {
+                            line.spans.insert(0, Span::styled(layout::QUOTE_GUTTER, bar_style));
+                        }
+                    }
+                    MDTag::Paragraph | MDTag::Item => {
                         push_current_line(&mut lines, &mut current_spans);
                         // Reset style might be needed if nested (e.g., end blockquote)
                         // Base style is reset implicitly at start of paragraph usually
                     }
+                    MDTag::CodeBlock(_) if in_math_block => {
+                        push_current_line(&mut lines, &mut current_spans);
+                        in_code_block = false;
+                        in_math_block = false;
+                    }
                     MDTag::CodeBlock(_) => {
                         push_current_line(&mut lines, &mut current_spans); // Push last line of code
                         in_code_block = false;
@@ -650,16 +5339,32 @@ fn render_markdown<'a>(markdown_input: &'a str) -> Vec<Line<'a>> {
                         if current_style.fg == Some(Color::Blue) {
                            current_style = current_style.fg(Color::Cyan);
                         }
+                        if let Some(number) = pending_link_reference.take() {
+                            current_spans.push(Span::styled(format!("[{}]", number), reference_marker_style));
+                        }
                     }
                     MDTag::Image(_, _, _) => {} // No style changes for image placeholder end
                     // Ignored tags
                     MDTag::Table(_) | MDTag::TableHead | MDTag::TableRow | MDTag::TableCell => {}
-                    MDTag::FootnoteDefinition(_) => {}
+                    MDTag::FootnoteDefinition(_) => {
+                        push_current_line(&mut lines, &mut current_spans);
+                        if let Some((start, label)) = footnote_start.take() {
+                            let number = reference_number(&mut references, format!("^{}", label));
+                            references[number - 1].1 = lines.drain(start..).collect();
+                        }
+                    }
                 }
             }
             MDEvent::Text(text) => {
                 // text is Cow<'a, str>
-                if in_code_block {
+                if in_math_block {
+                    // Display math, indented; \\ line breaks become new lines
+                    for math_line in math::to_unicode(&text).lines() {
+                        push_current_line(&mut lines, &mut current_spans);
+                        current_spans.push(Span::styled(format!("    {}", math_line), math_style));
+                        push_current_line(&mut lines, &mut current_spans);
+                    }
+                } else if in_code_block {
                      // Preserve line breaks within code blocks
                     for (i, code_line) in text.lines().enumerate() {
                         if i > 0 { // Push previous line if multi-line text event
@@ -684,10 +5389,17 @@ fn render_markdown<'a>(markdown_input: &'a str) -> Vec<Line<'a>> {
             }
             MDEvent::Code(text) => { // Inline code `code`
                 // text is Cow<'a, str>
-                current_spans.push(Span::styled(text.to_string(), inline_code_style));
+                match text.strip_prefix(math::INLINE_MARKER) {
+                    Some(latex) => current_spans.push(Span::styled(math::to_unicode(latex), math_style)),
+                    None => current_spans.push(Span::styled(text.to_string(), inline_code_style)),
+                }
             }
-            MDEvent::Html(_) | MDEvent::FootnoteReference(_) => {
-                 // Ignored HTML and footnotes
+            MDEvent::FootnoteReference(label) => {
+                let number = reference_number(&mut references, format!("^{}", label));
+                current_spans.push(Span::styled(format!("[{}]", number), reference_marker_style));
+            }
+            MDEvent::Html(_) => {
+                 // Ignored HTML
             }
             MDEvent::SoftBreak => {
                 // Usually treat as a space in Markdown rendering
@@ -714,62 +5426,163 @@ fn render_markdown<'a>(markdown_input: &'a str) -> Vec<Line<'a>> {
     // Push any remaining spans after the loop finishes
     push_current_line(&mut lines, &mut current_spans);
 
+    if !references.is_empty() {
+        lines.push(Line::styled("References", Style::default().fg(Color::DarkGray).add_modifier(Modifier::BOLD)));
+        for (index, (_, mut body)) in references.into_iter().enumerate() {
+            if body.is_empty() {
+                body.push(Line::styled("(not defined)", Style::default().fg(Color::DarkGray)));
+            }
+            for (line_index, mut line) in body.into_iter().enumerate() {
+                let label = if line_index == 0 { format!("[{}] ", index + 1) } else { "    ".to_string() };
+                line.spans.insert(0, Span::styled(label, reference_marker_style));
+                lines.push(line);
+            }
+        }
+    }
+
     // Ensure at least one line is returned, even if empty, for consistent spacing
     if lines.is_empty() {
         lines.push(Line::raw(""));
     }
 
-    lines // Return Vec<Line<'a>>
+    RenderedMarkdown { lines, headings }
 }
 
 
 // --- Async Ollama API Functions ---
-async fn fetch_models(client: Client) -> Result<Vec<String>, AppError> {
-    let url = format!("{}/api/tags", OLLAMA_BASE_URL);
-    let response = client.get(&url)
-        .timeout(Duration::from_secs(15))
-        .send().await
-        .map_err(AppError::ApiRequest)?;
+async fn fetch_models(client: Client) -> Result<Vec<ModelInfo>, AppError> {
+    let url = format!("{}/api/tags", endpoint::base());
+    let (trace, response) = trace::send(client.get(&url)
+        .timeout(Duration::from_secs(15))).await?;
 
     if response.status().is_success() {
-        let tags_response: OllamaTagsResponse = response.json().await?;
-        Ok(tags_response.models.into_iter().map(|m| m.name).collect())
+        let tags_response: OllamaTagsResponse = response.json().await.map_err(|e| trace.tag(e.into()))?;
+        Ok(tags_response.models)
     } else {
         let status = response.status();
         let err_text = response.text().await.unwrap_or_else(|_| "Failed to read error body".to_string());
-        Err(AppError::ApiResponse(format!("Status {} - {}", status, err_text)))
+        Err(trace.tag(AppError::ApiResponse(format!("Status {} - {}", status, err_text))))
+    }
+}
+
+// Runs the pre-send hook (if any) before streaming the response
+async fn dispatch_prompt(
+    client: Client,
+    hooks: HooksConfig,
+    decoding: StreamDecoding,
+    routes: Vec<Route>,
+    prompt: String,
+    request: PromptRequest,
+    events: ReplyEvents,
+) -> Result<(), AppError> {
+    let prompt = match &hooks.pre_send {
+        Some(command) => match hooks::run_hook(command, &prompt, hooks.timeout()).await {
+            Ok(HookOutcome::Unchanged) => prompt,
+            Ok(HookOutcome::Replaced(rewritten)) => {
+                let _ = events.sender.send(AppEvent::PromptRewritten { request: events.request, prompt: rewritten.clone() }).await;
+                rewritten
+            }
+            Err(e) => {
+                // Vetoed: report it and finish without contacting the server
+                let _ = events.sender.send(AppEvent::OllamaError { request: events.request, report: ErrorReport::from(&e).context("Prompt not sent") }).await;
+                let _ = events.sender.send(AppEvent::OllamaDone { request: events.request }).await;
+                return Err(e);
+            }
+        },
+        None => prompt,
+    };
+    let request = match request {
+        PromptRequest::Chat { mut messages, mcp, shape, earlier } => {
+            // The history ends with this prompt; use the hook's version of it
+            if let Some(last) = messages.last_mut() {
+                last.content = prompt.clone();
+            }
+            // Without a summary the trimmed turns are just left out
+            if let (Some(earlier), Some(route)) = (earlier, routes.first()) {
+                let result = context_trim::summarize_into(client.clone(), &route.model, &earlier, &mut messages).await;
+                let turn_index = earlier.turn_index;
+                let _ = events.sender.send(AppEvent::Summarized { turn_index, text: earlier.turn_text, result }).await;
+            }
+            PromptRequest::Chat { messages, mcp, shape, earlier: None }
+        }
+        request => request,
+    };
+
+    // Try each route until one accepts the request
+    let mut last_error = None;
+    for (index, route) in routes.into_iter().enumerate() {
+        if let Some(AppError::Unavailable(reason, _)) = &last_error {
+            let _ = events.sender.send(AppEvent::Rerouted { request: events.request, route: route.clone(), reason: reason.clone() }).await;
+        }
+        let result = match &request {
+            PromptRequest::Generate { shape } => {
+                stream_ollama_response(client.clone(), &route, decoding, prompt.clone(), shape, events.clone()).await
+            }
+            PromptRequest::Chat { messages, mcp, shape, .. } => {
+                chat::stream_chat_response(
+                    client.clone(),
+                    &route,
+                    decoding,
+                    messages.clone(),
+                    mcp.clone(),
+                    shape.clone(),
+                    events.clone(),
+                )
+                .await
+            }
+        };
+        match result {
+            Err(AppError::Unavailable(reason, category)) => {
+                last_error = Some(AppError::Unavailable(format!("{} (route {}): {}", route.model, index + 1, reason), category));
+            }
+            result => return result,
+        }
     }
+
+    let error = last_error.unwrap_or_else(|| AppError::Unavailable("No model to send to".to_string(), ErrorCategory::Config));
+    let _ = events.sender.send(AppEvent::OllamaError { request: events.request, report: ErrorReport::from(&error) }).await;
+    let _ = events.sender.send(AppEvent::OllamaDone { request: events.request }).await;
+    Err(error)
+}
+
+// Failures worth retrying elsewhere: unknown model, rate limiting or a struggling server
+fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::NOT_FOUND
+        || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        || status.is_server_error()
 }
 
 async fn stream_ollama_response(
     client: Client,
-    model_name: String,
+    route: &Route,
+    decoding: StreamDecoding,
     prompt: String,
-    event_sender: mpsc::Sender<AppEvent>,
+    shape: &ReplyShape,
+    events: ReplyEvents,
 ) -> Result<(), AppError> { // Return AppError for internal task errors (like channel send fail)
 
-    let url = format!("{}/api/generate", OLLAMA_BASE_URL);
+    let url = format!("{}/api/generate", route.base_url());
     let request_body = OllamaGenerateRequest {
-        model: model_name,
+        model: route.model.clone(),
         prompt,
+        format: shape.format.clone(),
+        options: shape.options(),
+        think: shape.think,
         stream: true,
+        keep_alive: endpoint::keep_alive(),
     };
 
     // Send request and handle potential client-side errors
-    let response_result = client.post(&url)
+    let response_result = trace::send(client.post(&url)
         .json(&request_body)
-        .timeout(Duration::from_secs(300)) // Long timeout for generation
-        .send().await;
+        .timeout(Duration::from_secs(300))) // Long timeout for generation
+        .await;
 
-    let response = match response_result {
-         Ok(resp) => resp,
+    let (trace, response) = match response_result {
+         Ok(sent) => sent,
          Err(e) => {
-             // Report request error via channel
-             let err_msg = format!("Request Error: {}", e);
-             // Use ?.await notation for cleaner error handling on send
-             let _ = event_sender.send(AppEvent::OllamaError(err_msg.clone())).await; // Ignore send error here? Or handle?
-             let _ = event_sender.send(AppEvent::OllamaDone).await; // Signal done regardless
-             return Err(AppError::ApiRequest(e)); // Propagate original error if needed by caller context (though usually handled via event)
+             // The caller reports it once no other route is left
+             return Err(AppError::unavailable(e));
          }
      };
 
@@ -777,11 +5590,14 @@ async fn stream_ollama_response(
     if !response.status().is_success() {
         let status = response.status();
         let err_text = response.text().await.unwrap_or_else(|_| "Failed to read error body".to_string());
-        let err_msg = format!("API Error: Status {} - {}", status, err_text);
+        let err_msg = trace.tag_message(format!("API Error: Status {} - {}", status, err_text));
+        if is_retryable_status(status) {
+            return Err(AppError::Unavailable(err_msg, ErrorCategory::Server));
+        }
         // Send API error and Done signal via channel
-        let send_err = event_sender.send(AppEvent::OllamaError(err_msg)).await
+        let send_err = events.sender.send(AppEvent::OllamaError { request: events.request, report: ErrorReport::new(ErrorCategory::Server, err_msg) }).await
              .map_err(|e| AppError::ChannelSend(format!("Failed to send API error: {}", e))); // Convert channel error to AppError
-        let send_done = event_sender.send(AppEvent::OllamaDone).await
+        let send_done = events.sender.send(AppEvent::OllamaDone { request: events.request }).await
              .map_err(|e| AppError::ChannelSend(format!("Failed to send done after API error: {}", e)));
         // Return the first channel send error encountered, or Ok if both sends succeeded
         return send_err.and(send_done);
@@ -789,107 +5605,234 @@ async fn stream_ollama_response(
 
     // Process the successful stream response
     let mut stream = response.bytes_stream();
-    let mut buffer = String::new(); // Buffer for partial JSON lines
+    let mut decoder = NdjsonDecoder::<OllamaGenerateChunk>::new(decoding);
 
     while let Some(item_result) = stream.next().await {
         match item_result {
             Ok(chunk_bytes) => {
-                // Append bytes to buffer, handling potential UTF-8 errors lossily
-                buffer.push_str(&String::from_utf8_lossy(&chunk_bytes));
-
-                // Process complete lines delimited by newline
-                while let Some(newline_pos) = buffer.find('\n') {
-                    // Extract line and remove from buffer
-                    let line = buffer.drain(..=newline_pos).collect::<String>();
-                    let trimmed_line = line.trim();
-
-                    if trimmed_line.is_empty() { continue; } // Skip empty lines
-
-                    // Attempt to parse the line as an Ollama chunk
-                    match serde_json::from_str::<OllamaGenerateChunk>(trimmed_line) {
-                        Ok(chunk) => {
-                            // Send the response part via channel
-                            if event_sender.send(AppEvent::OllamaChunk(chunk.response)).await.is_err() {
-                                eprintln!("Error: Failed to send Ollama chunk to main loop. Stopping stream.");
-                                // Report channel error
-                                let _ = event_sender.send(AppEvent::OllamaError("Channel closed during streaming".to_string())).await;
-                                return Err(AppError::ChannelSend("Failed to send chunk".to_string()));
-                            }
-
-                            // Check if this chunk signals the end
-                            if chunk.done {
-                                // Send the final done signal
-                                if event_sender.send(AppEvent::OllamaDone).await.is_err() {
-                                     eprintln!("Error: Failed to send Ollama done signal.");
-                                     // *** FIX IS HERE *** Changed AppEvent to AppError
-                                     return Err(AppError::ChannelSend("Failed to send done signal".to_string()));
-                                }
-                                return Ok(()); // Stream finished successfully
-                            }
-                        }
-                        Err(e) => {
-                            // Report JSON decoding errors
-                            let error_msg = format!("JSON Decode Error: '{}' on line: '{}'", e, trimmed_line);
-                            eprintln!("{}", error_msg); // Log locally
-                            // Send error via channel but continue processing stream
-                            if event_sender.send(AppEvent::OllamaError(error_msg)).await.is_err() {
-                                eprintln!("Error: Failed to send JSON decode error to main loop.");
-                                // If sending error fails, the channel is likely broken, stop the task
-                                return Err(AppError::ChannelSend("Failed to send decode error".to_string()));
-                            }
-                        }
+                for decoded in decoder.push(&chunk_bytes) {
+                    if handle_generate_chunk(decoded, &events).await? {
+                        trace.record("done");
+                        return Ok(()); // Stream finished successfully
                     }
-                } // End while processing lines
+                }
             }
             Err(e) => {
                  // Error reading from the byte stream itself
-                 let error_msg = format!("Stream Read Error: {}", e);
-                 eprintln!("{}", error_msg);
-                 // Report error via channel
-                 if event_sender.send(AppEvent::OllamaError(error_msg)).await.is_err() {
-                      eprintln!("Error: Failed to send stream read error to main loop.");
-                      // Even if send fails, proceed to send Done signal if possible
-                 }
-                 // Signal done because the stream stopped
-                 let _ = event_sender.send(AppEvent::OllamaDone).await;
-                 // Return Ok as the error was reported via channel. Task itself didn't fail due to channel send.
-                 // Or could return a specific AppError indicating stream read failure.
-                 return Ok(());
+                 let error_msg = trace.tag_message(format!("Stream Read Error: {}", e));
+                 // Keep the partial text and let the user resume it
+                 let _ = events.sender.send(AppEvent::OllamaInterrupted { request: events.request, message: error_msg.clone() }).await;
+                 return Err(AppError::Interrupted(error_msg));
             }
         }
     } // End while reading stream chunks
 
     // --- Stream Ended Naturally ---
 
-     // Process any remaining data in the buffer after the stream closes
-    let final_data = buffer.trim();
-    if !final_data.is_empty() {
-         match serde_json::from_str::<OllamaGenerateChunk>(final_data) {
-             Ok(chunk) => {
-                 // Send final chunk content
-                 if event_sender.send(AppEvent::OllamaChunk(chunk.response)).await.is_err() {
-                      eprintln!("Error: Failed to send final Ollama chunk to main loop.");
-                      // Proceed to send Done signal anyway
-                 }
-                 // Check chunk.done here? Maybe, but stream ending is the main signal.
-             }
-             Err(e) => {
-                 // Report final buffer decoding error
-                 let error_msg = format!("Final Buffer Decode Error: '{}' on data: '{}'", e, final_data);
-                 eprintln!("{}", error_msg);
-                  if event_sender.send(AppEvent::OllamaError(error_msg)).await.is_err(){
-                       eprintln!("Error: Failed to send final buffer decode error to main loop.");
-                  }
-             }
-         }
+    // Process any remaining data in the buffer after the stream closes
+    for decoded in decoder.finish() {
+        if handle_generate_chunk(decoded, &events).await? {
+            return Ok(());
+        }
+    }
+
+    // No chunk said done, so the server went away mid-response
+    let error_msg = trace.tag_message("Connection closed before the response finished".to_string());
+    let _ = events.sender.send(AppEvent::OllamaInterrupted { request: events.request, message: error_msg.clone() }).await;
+    Err(AppError::Interrupted(error_msg))
+}
+
+// Forwards one decoded chunk; returns true once the final chunk has been handled
+async fn handle_generate_chunk(
+    decoded: Result<OllamaGenerateChunk, String>,
+    events: &ReplyEvents,
+) -> Result<bool, AppError> {
+    match decoded {
+        Ok(chunk) => {
+            // Send the response part via channel
+            if events.sender.send(AppEvent::OllamaChunk { request: events.request, text: chunk.response }).await.is_err() {
+                return Err(AppError::ChannelSend("Failed to send chunk".to_string()));
+            }
+            // Check if this chunk signals the end
+            if chunk.done {
+                if let Some(reason) = chunk.done_reason {
+                    let _ = events.sender.send(AppEvent::DoneReason { request: events.request, reason }).await;
+                }
+                let _ = events.sender.send(AppEvent::Generated { request: events.request, generation: chunk.generation }).await;
+                events.sender.send(AppEvent::OllamaDone { request: events.request }).await
+                    .map_err(|_| AppError::ChannelSend("Failed to send done signal".to_string()))?;
+            }
+            Ok(chunk.done)
+        }
+        Err(error_msg) => {
+            // Report decoding errors but carry on with the stream
+            events.sender.send(AppEvent::StreamDecodeError { request: events.request, report: ErrorReport::new(ErrorCategory::Parse, error_msg) }).await
+                .map_err(|_| AppError::ChannelSend("Failed to send decode error".to_string()))?;
+            Ok(false)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App {
+        let (tx, rx) = mpsc::channel(8);
+        App::new(rx, tx, Config::default(), PluginHost::load(None).0)
+    }
+
+    // What start_request sets up, without touching the server or the usage files
+    fn start(app: &mut App, model: &str, prompt: &str) -> u64 {
+        app.conversation.push(ConversationTurn::user(prompt));
+        app.request_count += 1;
+        app.active_request = Some(app.request_count);
+        app.is_loading = true;
+        app.streaming_model = Some(model.to_string());
+        app.request_count
+    }
+
+    fn chunk(app: &mut App, request: u64, text: &str) {
+        handle_app_event(app, AppEvent::OllamaChunk { request, text: text.to_string() });
+    }
+
+    fn replies(app: &App) -> Vec<(String, String)> {
+        app.conversation
+            .iter()
+            .filter(|turn| turn.role == Role::Assistant)
+            .map(|turn| (turn.name.clone(), turn.text.to_string()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn adjacent_replies_from_one_model_stay_apart() {
+        let mut app = app();
+        let first = start(&mut app, "llama3", "one");
+        chunk(&mut app, first, "A");
+        chunk(&mut app, first, "B");
+        handle_app_event(&mut app, AppEvent::OllamaDone { request: first });
+        // A second reply right after the first, without a prompt turn between
+        // them, as after a failed request is retried
+        app.request_count += 1;
+        app.active_request = Some(app.request_count);
+        app.is_loading = true;
+        app.streaming_model = Some("llama3".to_string());
+        let second = app.request_count;
+        chunk(&mut app, second, "C");
+        handle_app_event(&mut app, AppEvent::OllamaDone { request: second });
+
+        assert_eq!(replies(&app), [("llama3".to_string(), "AB".to_string()), ("llama3".to_string(), "C".to_string())]);
+        assert!(!app.is_loading);
+    }
+
+    #[tokio::test]
+    async fn chunks_after_a_tool_call_start_a_new_turn() {
+        let mut app = app();
+        let request = start(&mut app, "llama3", "weather?");
+        chunk(&mut app, request, "Let me check.");
+        handle_app_event(&mut app, AppEvent::ToolCall { request, name: "weather".to_string(), arguments: serde_json::json!({}) });
+        handle_app_event(
+            &mut app,
+            AppEvent::ToolResult { request, name: "weather".to_string(), output: "sunny".to_string(), is_error: false },
+        );
+        chunk(&mut app, request, "It's sunny.");
+        handle_app_event(&mut app, AppEvent::OllamaDone { request });
+
+        assert_eq!(replies(&app), [("llama3".to_string(), "Let me check.".to_string()), ("llama3".to_string(), "It's sunny.".to_string())]);
+        assert_eq!(app.conversation.last().map(|turn| turn.request), Some(Some(request)));
+    }
+
+    #[tokio::test]
+    async fn tool_results_follow_their_call_in_the_history() {
+        let mut app = app();
+        let request = start(&mut app, "llama3", "weather?");
+        handle_app_event(&mut app, AppEvent::ToolCall { request, name: "weather".to_string(), arguments: serde_json::json!({}) });
+        handle_app_event(
+            &mut app,
+            AppEvent::ToolResult { request, name: "weather".to_string(), output: "sunny".to_string(), is_error: false },
+        );
+        chunk(&mut app, request, "It's sunny.");
+        handle_app_event(&mut app, AppEvent::OllamaDone { request });
+
+        let roles = |app: &App| app.chat_history().iter().map(|message| message.role.clone()).collect::<Vec<_>>();
+        assert_eq!(roles(&app), ["user", "assistant", "tool", "assistant"]);
+        assert_eq!(app.chat_history()[1].tool_calls.len(), 1);
+
+        // Without its call, the result would be malformed history
+        app.conversation[1].excluded = true;
+        assert_eq!(roles(&app), ["user", "assistant"]);
     }
 
-    // Always send the Done signal when the stream ends, unless already sent (e.g., chunk.done was true)
-    // Note: The logic above returns early if chunk.done is true and Done is sent successfully.
-    if event_sender.send(AppEvent::OllamaDone).await.is_err() {
-        eprintln!("Error: Failed to send final Ollama done signal to main loop.");
-        return Err(AppError::ChannelSend("Failed to send final done signal".to_string()));
+    #[tokio::test]
+    async fn events_from_an_interrupted_request_are_dropped() {
+        let mut app = app();
+        let old = start(&mut app, "llama3", "one");
+        chunk(&mut app, old, "partial");
+        assert!(app.interrupt_request());
+        let new = start(&mut app, "llama3", "two");
+
+        // Still queued from the old request
+        chunk(&mut app, old, " stale");
+        handle_app_event(&mut app, AppEvent::DoneReason { request: old, reason: "stop".to_string() });
+        handle_app_event(&mut app, AppEvent::OllamaDone { request: old });
+        assert_eq!(app.active_request, Some(new));
+        assert!(app.is_loading);
+
+        chunk(&mut app, new, "fresh");
+        handle_app_event(&mut app, AppEvent::OllamaDone { request: new });
+        assert_eq!(replies(&app), [("llama3".to_string(), "partial".to_string()), ("llama3".to_string(), "fresh".to_string())]);
+        assert!(app.conversation[1].interrupted);
+        assert_eq!(app.conversation[1].finish, None);
+        assert!(!app.is_loading);
+    }
+
+    #[tokio::test]
+    async fn a_failed_reply_is_not_post_processed() {
+        let mut app = app();
+        app.config.decode_entities = true;
+        let request = start(&mut app, "llama3", "one");
+        chunk(&mut app, request, "a &amp;");
+        // A bad line doesn't stop the stream
+        let report = |message: &str| ErrorReport::new(ErrorCategory::Parse, message.to_string());
+        handle_app_event(&mut app, AppEvent::StreamDecodeError { request, report: report("bad line") });
+        assert!(app.is_loading);
+        chunk(&mut app, request, " b");
+
+        // Every failure is followed by a Done
+        handle_app_event(&mut app, AppEvent::OllamaError { request, report: report("Server Error: out of memory") });
+        handle_app_event(&mut app, AppEvent::OllamaDone { request });
+        assert_eq!(replies(&app), [("llama3".to_string(), "a &amp; b".to_string())]);
+        assert_eq!(app.conversation[1].finish, Some(Finish::Error("Server Error: out of memory".to_string())));
+        assert_eq!(app.status_message, "Error occurred.");
+        assert_eq!(app.active_request, None);
+        assert!(!app.is_loading);
+    }
+
+    #[tokio::test]
+    async fn a_failed_reply_is_not_validated() {
+        let mut app = app();
+        app.config.validation.validators = vec![validation::Validator::Json];
+        let request = start(&mut app, "llama3", "json please");
+        chunk(&mut app, request, "{\"a\":");
+        handle_app_event(
+            &mut app,
+            AppEvent::OllamaError { request, report: ErrorReport::new(ErrorCategory::Server, "Status 500".to_string()) },
+        );
+        handle_app_event(&mut app, AppEvent::OllamaDone { request });
+        app.validate_response();
+        assert_eq!(app.status_message, "Error occurred.");
+        assert!(app.validation_retry.is_none());
     }
 
-    Ok(()) // Task completed successfully
+    #[tokio::test]
+    async fn a_hook_result_for_a_changed_turn_is_dropped() {
+        let mut app = app();
+        let request = start(&mut app, "llama3", "one");
+        chunk(&mut app, request, "first");
+        // Regenerated while the hook ran on "first"
+        app.conversation[1].text = TurnText::from("second".to_string());
+        let result = Ok(HookOutcome::Replaced("hooked".to_string()));
+        handle_app_event(&mut app, AppEvent::ResponseHookDone { turn_index: 1, text: "first".to_string(), result });
+        assert_eq!(app.conversation[1].text.to_string(), "second");
+    }
 }
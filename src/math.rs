@@ -0,0 +1,611 @@
+use std::borrow::Cow;
+
+// --- LaTeX Math ---
+// Models write math as $...$, \(...\), $$...$$ or \[...\]. Before markdown
+// parsing these are moved into constructs the parser leaves alone (a ```math
+// block for display math, inline code tagged with INLINE_MARKER otherwise),
+// and the renderer turns their LaTeX into Unicode.
+
+// Invisible character starting the inline code that holds inline math
+pub const INLINE_MARKER: char = '\u{2061}';
+pub const FENCE_LANGUAGE: &str = "math";
+
+// Rewrites math delimiters outside code; borrows when there is no math
+pub fn isolate(source: &str) -> Cow<'_, str> {
+    if !source.contains('$') && !source.contains("\\(") && !source.contains("\\[") {
+        return Cow::Borrowed(source);
+    }
+    let mut out = String::with_capacity(source.len());
+    let mut in_fence = false;
+    let mut rest = source;
+    while !rest.is_empty() {
+        let line_end = rest.find('\n').map_or(rest.len(), |i| i + 1);
+        let trimmed = rest[..line_end].trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            out.push_str(&rest[..line_end]);
+            rest = &rest[line_end..];
+        } else if in_fence {
+            out.push_str(&rest[..line_end]);
+            rest = &rest[line_end..];
+        } else {
+            let consumed = isolate_line(rest, &mut out);
+            rest = &rest[consumed..];
+        }
+    }
+    Cow::Owned(out)
+}
+
+// Copies one line of `text` into `out`, rewriting any math on it. Display
+// math may run over several lines; returns the number of bytes consumed.
+fn isolate_line(text: &str, out: &mut String) -> usize {
+    let mut pos = 0;
+    loop {
+        let rest = &text[pos..];
+        let Some(c) = rest.chars().next() else { return pos };
+        if c == '\n' {
+            out.push('\n');
+            return pos + 1;
+        }
+        let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+
+        if c == '`' {
+            // Inline code is copied untouched, up to the matching backtick run
+            let run = line.len() - line.trim_start_matches('`').len();
+            let end = line[run..].find(&line[..run]).map_or(run, |i| run + i + run);
+            out.push_str(&line[..end]);
+            pos += end;
+        } else if let Some((open, close, display)) = delimiters(line) {
+            let scope = &rest[..math_scope(rest)];
+            match scope[open.len()..].find(close) {
+                Some(len) if !rest[open.len()..open.len() + len].trim().is_empty() => {
+                    let latex = rest[open.len()..open.len() + len].trim();
+                    // Display math only gets its own block when it starts the line
+                    let line_start = out.rfind('\n').map_or(0, |i| i + 1);
+                    if display && out[line_start..].trim().is_empty() {
+                        out.truncate(line_start);
+                        out.push_str(&format!("```{}\n{}\n```\n", FENCE_LANGUAGE, latex));
+                        pos += open.len() + len + close.len();
+                        // The fence took the line break; drop the original one
+                        if text[pos..].starts_with('\n') {
+                            pos += 1;
+                        }
+                        return pos;
+                    }
+                    push_inline(out, latex);
+                    pos += open.len() + len + close.len();
+                }
+                _ => {
+                    out.push_str(open);
+                    pos += open.len();
+                }
+            }
+        } else if c == '$' {
+            match inline_dollar_len(line) {
+                Some(len) => {
+                    push_inline(out, &line[1..len - 1]);
+                    pos += len;
+                }
+                None => {
+                    out.push('$');
+                    pos += 1;
+                }
+            }
+        } else if c == '\\' && line.len() > 1 {
+            // An escaped character, e.g. \$, is never a delimiter
+            let len = 1 + line[1..].chars().next().map_or(0, char::len_utf8);
+            out.push_str(&line[..len]);
+            pos += len;
+        } else {
+            out.push(c);
+            pos += c.len_utf8();
+        }
+    }
+}
+
+// How far math opened at the start of `text` may run: up to a blank line or
+// a code fence, so a stray delimiter can't swallow the code after it
+fn math_scope(text: &str) -> usize {
+    let mut end = text.find('\n').map_or(text.len(), |i| i + 1);
+    while end < text.len() {
+        let line_end = text[end..].find('\n').map_or(text.len(), |i| end + i + 1);
+        let line = text[end..line_end].trim();
+        if line.is_empty() || line.starts_with("```") || line.starts_with("~~~") {
+            break;
+        }
+        end = line_end;
+    }
+    end
+}
+
+// Delimiters that can span lines: (open, close, display)
+fn delimiters(line: &str) -> Option<(&'static str, &'static str, bool)> {
+    [("$$", "$$", true), ("\\[", "\\]", true), ("\\(", "\\)", false)]
+        .into_iter()
+        .find(|(open, _, _)| line.starts_with(open))
+}
+
+// Length of a $...$ span at the start of `line`. Like pandoc, the opening $
+// must be followed and the closing $ preceded by a non-space, and the closing
+// $ must not be followed by a digit, so "$5 and $10" stays text.
+fn inline_dollar_len(line: &str) -> Option<usize> {
+    let first = line[1..].chars().next()?;
+    if first.is_whitespace() || first == '$' {
+        return None;
+    }
+    let mut escaped = false;
+    for (i, c) in line.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '$' if i > 1 => {
+                let before = line[..i].chars().next_back()?;
+                let after = line[i + 1..].chars().next();
+                if !before.is_whitespace() && !after.is_some_and(|a| a.is_ascii_digit()) {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn push_inline(out: &mut String, latex: &str) {
+    // Single-line, backtick-free so it stays one inline code span
+    let latex = latex.replace('`', "'").replace('\n', " ");
+    out.push_str(&format!("`{}{}`", INLINE_MARKER, latex));
+}
+
+// --- LaTeX to Unicode ---
+// Covers what models commonly write: Greek letters, operators and relations,
+// super/subscripts, \frac, \sqrt, \mathbb and text commands. Anything
+// unknown is kept as written.
+pub fn to_unicode(latex: &str) -> String {
+    let chars: Vec<char> = latex.chars().collect();
+    let mut pos = 0;
+    let mut out = String::new();
+    while pos < chars.len() {
+        out.push_str(&convert_token(&chars, &mut pos));
+    }
+    // Collapse the spacing LaTeX ignores, line by line
+    out.lines().map(|line| line.split_whitespace().collect::<Vec<_>>().join(" ")).collect::<Vec<_>>().join("\n")
+}
+
+fn convert_token(chars: &[char], pos: &mut usize) -> String {
+    let c = chars[*pos];
+    *pos += 1;
+    match c {
+        '\\' => convert_command(chars, pos),
+        '{' => {
+            let inner = take_group(chars, pos);
+            to_unicode(&inner)
+        }
+        '^' | '_' => {
+            let arg = convert_argument(chars, pos);
+            let mapped: Option<String> = arg
+                .chars()
+                .map(|ch| if c == '^' { superscript(ch) } else { subscript(ch) })
+                .collect();
+            match mapped {
+                Some(mapped) if !arg.is_empty() => mapped,
+                _ if arg.chars().count() == 1 => format!("{}{}", c, arg),
+                _ => format!("{}({})", c, arg),
+            }
+        }
+        // Alignment points in aligned environments
+        '&' => String::new(),
+        '~' => " ".to_string(),
+        _ => c.to_string(),
+    }
+}
+
+// Contents of a {...} group whose '{' was just consumed
+fn take_group(chars: &[char], pos: &mut usize) -> String {
+    let mut depth = 1;
+    let mut group = String::new();
+    while *pos < chars.len() {
+        let c = chars[*pos];
+        *pos += 1;
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            _ => {}
+        }
+        group.push(c);
+    }
+    group
+}
+
+// The converted next argument: a group, a command or a single character
+fn convert_argument(chars: &[char], pos: &mut usize) -> String {
+    while chars.get(*pos).is_some_and(|c| c.is_whitespace()) {
+        *pos += 1;
+    }
+    if *pos >= chars.len() {
+        return String::new();
+    }
+    convert_token(chars, pos).trim().to_string()
+}
+
+// The next argument as raw text, for \text and friends
+fn raw_argument(chars: &[char], pos: &mut usize) -> String {
+    if chars.get(*pos) == Some(&'{') {
+        *pos += 1;
+        take_group(chars, pos)
+    } else {
+        convert_argument(chars, pos)
+    }
+}
+
+fn convert_command(chars: &[char], pos: &mut usize) -> String {
+    let start = *pos;
+    while chars.get(*pos).is_some_and(|c| c.is_ascii_alphabetic()) {
+        *pos += 1;
+    }
+    if *pos == start {
+        // A single-character command such as \, \{ or \\
+        let Some(&c) = chars.get(*pos) else { return "\\".to_string() };
+        *pos += 1;
+        return match c {
+            '\\' => "\n".to_string(),
+            ',' | ';' | ':' | ' ' => " ".to_string(),
+            '!' => String::new(),
+            '|' => "‖".to_string(),
+            _ => c.to_string(),
+        };
+    }
+    let name: String = chars[start..*pos].iter().collect();
+    match name.as_str() {
+        "frac" | "dfrac" | "tfrac" => {
+            let numerator = convert_argument(chars, pos);
+            let denominator = convert_argument(chars, pos);
+            format!("{}/{}", wrap_operand(&numerator), wrap_operand(&denominator))
+        }
+        "sqrt" => {
+            let index = if chars.get(*pos) == Some(&'[') {
+                let end = chars[*pos..].iter().position(|c| *c == ']').map_or(chars.len(), |i| *pos + i);
+                let index: String = chars[*pos + 1..end].iter().collect();
+                *pos = (end + 1).min(chars.len());
+                Some(to_unicode(&index))
+            } else {
+                None
+            };
+            let radicand = wrap_operand(&convert_argument(chars, pos));
+            match index.as_deref() {
+                None | Some("2") => format!("√{}", radicand),
+                Some("3") => format!("∛{}", radicand),
+                Some("4") => format!("∜{}", radicand),
+                Some(n) => {
+                    let sup: Option<String> = n.chars().map(superscript).collect();
+                    format!("{}√{}", sup.unwrap_or_else(|| format!("({})", n)), radicand)
+                }
+            }
+        }
+        "text" | "textrm" | "textit" | "textbf" | "mathrm" | "operatorname" => raw_argument(chars, pos),
+        "mathbf" | "mathit" | "mathsf" | "boldsymbol" | "vec" | "overline" | "hat" | "bar" | "tilde" => {
+            convert_argument(chars, pos)
+        }
+        "mathbb" => {
+            let letters = raw_argument(chars, pos);
+            letters.chars().map(|c| double_struck(c).unwrap_or(c)).collect()
+        }
+        "begin" | "end" => {
+            raw_argument(chars, pos);
+            String::new()
+        }
+        "left" | "right" | "big" | "Big" | "bigg" | "Bigg" | "displaystyle" | "limits" => String::new(),
+        // Function names like \sin are written upright, as themselves
+        _ if OPERATOR_NAMES.contains(&name.as_str()) => name,
+        _ => match symbol(&name) {
+            Some(symbol) => symbol.to_string(),
+            None => format!("\\{}", name),
+        },
+    }
+}
+
+// Parenthesizes compound operands so a/b stays unambiguous
+fn wrap_operand(operand: &str) -> String {
+    if operand.chars().all(|c| c.is_alphanumeric() || c == '.') || operand.chars().count() == 1 {
+        operand.to_string()
+    } else {
+        format!("({})", operand)
+    }
+}
+
+const OPERATOR_NAMES: [&str; 29] = [
+    "sin", "cos", "tan", "cot", "sec", "csc", "arcsin", "arccos", "arctan", "sinh", "cosh", "tanh", "log", "ln",
+    "exp", "lim", "max", "min", "sup", "inf", "det", "gcd", "arg", "deg", "dim", "ker", "mod", "bmod", "Pr",
+];
+
+fn symbol(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "alpha" => "α",
+        "beta" => "β",
+        "gamma" => "γ",
+        "delta" => "δ",
+        "epsilon" => "ϵ",
+        "varepsilon" => "ε",
+        "zeta" => "ζ",
+        "eta" => "η",
+        "theta" => "θ",
+        "vartheta" => "ϑ",
+        "iota" => "ι",
+        "kappa" => "κ",
+        "lambda" => "λ",
+        "mu" => "μ",
+        "nu" => "ν",
+        "xi" => "ξ",
+        "pi" => "π",
+        "varpi" => "ϖ",
+        "rho" => "ρ",
+        "varrho" => "ϱ",
+        "sigma" => "σ",
+        "varsigma" => "ς",
+        "tau" => "τ",
+        "upsilon" => "υ",
+        "phi" => "ϕ",
+        "varphi" => "φ",
+        "chi" => "χ",
+        "psi" => "ψ",
+        "omega" => "ω",
+        "Gamma" => "Γ",
+        "Delta" => "Δ",
+        "Theta" => "Θ",
+        "Lambda" => "Λ",
+        "Xi" => "Ξ",
+        "Pi" => "Π",
+        "Sigma" => "Σ",
+        "Upsilon" => "Υ",
+        "Phi" => "Φ",
+        "Psi" => "Ψ",
+        "Omega" => "Ω",
+        "times" => "×",
+        "cdot" => "·",
+        "div" => "÷",
+        "pm" => "±",
+        "mp" => "∓",
+        "ast" => "∗",
+        "star" => "⋆",
+        "circ" => "∘",
+        "bullet" => "•",
+        "oplus" => "⊕",
+        "otimes" => "⊗",
+        "leq" | "le" => "≤",
+        "geq" | "ge" => "≥",
+        "neq" | "ne" => "≠",
+        "ll" => "≪",
+        "gg" => "≫",
+        "approx" => "≈",
+        "equiv" => "≡",
+        "sim" => "∼",
+        "simeq" => "≃",
+        "cong" => "≅",
+        "propto" => "∝",
+        "infty" => "∞",
+        "partial" => "∂",
+        "nabla" => "∇",
+        "sum" => "∑",
+        "prod" => "∏",
+        "coprod" => "∐",
+        "int" => "∫",
+        "iint" => "∬",
+        "iiint" => "∭",
+        "oint" => "∮",
+        "to" | "rightarrow" => "→",
+        "leftarrow" | "gets" => "←",
+        "leftrightarrow" => "↔",
+        "Rightarrow" | "implies" => "⇒",
+        "Leftarrow" => "⇐",
+        "Leftrightarrow" | "iff" => "⇔",
+        "mapsto" => "↦",
+        "uparrow" => "↑",
+        "downarrow" => "↓",
+        "in" => "∈",
+        "notin" => "∉",
+        "ni" => "∋",
+        "subset" => "⊂",
+        "subseteq" => "⊆",
+        "supset" => "⊃",
+        "supseteq" => "⊇",
+        "cup" => "∪",
+        "cap" => "∩",
+        "setminus" => "∖",
+        "emptyset" | "varnothing" => "∅",
+        "forall" => "∀",
+        "exists" => "∃",
+        "nexists" => "∄",
+        "neg" | "lnot" => "¬",
+        "land" | "wedge" => "∧",
+        "lor" | "vee" => "∨",
+        "ldots" | "dots" => "…",
+        "cdots" => "⋯",
+        "vdots" => "⋮",
+        "ddots" => "⋱",
+        "angle" => "∠",
+        "perp" => "⊥",
+        "parallel" => "∥",
+        "mid" => "∣",
+        "degree" => "°",
+        "prime" => "′",
+        "hbar" => "ℏ",
+        "ell" => "ℓ",
+        "Re" => "ℜ",
+        "Im" => "ℑ",
+        "aleph" => "ℵ",
+        "langle" => "⟨",
+        "rangle" => "⟩",
+        "lfloor" => "⌊",
+        "rfloor" => "⌋",
+        "lceil" => "⌈",
+        "rceil" => "⌉",
+        "quad" => "  ",
+        "qquad" => "    ",
+        _ => return None,
+    })
+}
+
+fn superscript(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '⁰',
+        '1' => '¹',
+        '2' => '²',
+        '3' => '³',
+        '4' => '⁴',
+        '5' => '⁵',
+        '6' => '⁶',
+        '7' => '⁷',
+        '8' => '⁸',
+        '9' => '⁹',
+        '+' => '⁺',
+        '-' | '−' => '⁻',
+        '=' => '⁼',
+        '(' => '⁽',
+        ')' => '⁾',
+        'a' => 'ᵃ',
+        'b' => 'ᵇ',
+        'c' => 'ᶜ',
+        'd' => 'ᵈ',
+        'e' => 'ᵉ',
+        'f' => 'ᶠ',
+        'g' => 'ᵍ',
+        'h' => 'ʰ',
+        'i' => 'ⁱ',
+        'j' => 'ʲ',
+        'k' => 'ᵏ',
+        'l' => 'ˡ',
+        'm' => 'ᵐ',
+        'n' => 'ⁿ',
+        'o' => 'ᵒ',
+        'p' => 'ᵖ',
+        'r' => 'ʳ',
+        's' => 'ˢ',
+        't' => 'ᵗ',
+        'u' => 'ᵘ',
+        'v' => 'ᵛ',
+        'w' => 'ʷ',
+        'x' => 'ˣ',
+        'y' => 'ʸ',
+        'z' => 'ᶻ',
+        'T' => 'ᵀ',
+        '′' => '′',
+        '*' | '∗' => '*',
+        _ => return None,
+    })
+}
+
+fn subscript(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '₀',
+        '1' => '₁',
+        '2' => '₂',
+        '3' => '₃',
+        '4' => '₄',
+        '5' => '₅',
+        '6' => '₆',
+        '7' => '₇',
+        '8' => '₈',
+        '9' => '₉',
+        '+' => '₊',
+        '-' | '−' => '₋',
+        '=' => '₌',
+        '(' => '₍',
+        ')' => '₎',
+        'a' => 'ₐ',
+        'e' => 'ₑ',
+        'h' => 'ₕ',
+        'i' => 'ᵢ',
+        'j' => 'ⱼ',
+        'k' => 'ₖ',
+        'l' => 'ₗ',
+        'm' => 'ₘ',
+        'n' => 'ₙ',
+        'o' => 'ₒ',
+        'p' => 'ₚ',
+        'r' => 'ᵣ',
+        's' => 'ₛ',
+        't' => 'ₜ',
+        'u' => 'ᵤ',
+        'v' => 'ᵥ',
+        'x' => 'ₓ',
+        _ => return None,
+    })
+}
+
+fn double_struck(c: char) -> Option<char> {
+    Some(match c {
+        'N' => 'ℕ',
+        'Z' => 'ℤ',
+        'Q' => 'ℚ',
+        'R' => 'ℝ',
+        'C' => 'ℂ',
+        'P' => 'ℙ',
+        'H' => 'ℍ',
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inline(latex: &str) -> String {
+        format!("`{}{}`", INLINE_MARKER, latex)
+    }
+
+    #[test]
+    fn text_without_math_is_borrowed() {
+        assert!(matches!(isolate("No math here."), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn inline_math_becomes_marked_code() {
+        assert_eq!(isolate("Area is $\\pi r^2$."), format!("Area is {}.", inline("\\pi r^2")));
+        assert_eq!(isolate("so \\(x + 1\\) holds"), format!("so {} holds", inline("x + 1")));
+        // Prices are not math
+        assert_eq!(isolate("Costs $5 and $10."), "Costs $5 and $10.");
+        assert_eq!(isolate("Escaped \\$x$ stays"), "Escaped \\$x$ stays");
+    }
+
+    #[test]
+    fn display_math_gets_its_own_block() {
+        assert_eq!(isolate("$$\na + b\n$$\nafter"), "```math\na + b\n```\nafter");
+        assert_eq!(isolate("\\[x = 1\\]\n"), "```math\nx = 1\n```\n");
+        // Not at the start of a line, it stays inline
+        assert_eq!(isolate("so $$x$$ holds"), format!("so {} holds", inline("x")));
+    }
+
+    #[test]
+    fn code_is_left_alone() {
+        let fenced = "```sh\necho $HOME $x$\n```\n";
+        assert_eq!(isolate(fenced), fenced);
+        assert_eq!(isolate("Run `echo $a$` now"), "Run `echo $a$` now");
+    }
+
+    #[test]
+    fn unclosed_delimiters_stop_at_code_and_paragraphs() {
+        let shell = "Use $$ to get the PID.\n```sh\necho $$\n```";
+        assert_eq!(isolate(shell), shell);
+        let call = "Call f\\(x\n```\ny\\)\n```";
+        assert_eq!(isolate(call), call);
+        let paragraphs = "Costs $$ here.\n\nAnd $$ there.";
+        assert_eq!(isolate(paragraphs), paragraphs);
+    }
+
+    #[test]
+    fn latex_becomes_unicode() {
+        assert_eq!(to_unicode("\\alpha^2 + \\beta_{10}"), "α² + β₁₀");
+        assert_eq!(to_unicode("\\frac{1}{2} \\leq \\frac{a+b}{c}"), "1/2 ≤ (a+b)/c");
+        assert_eq!(to_unicode("\\sqrt{x} \\sqrt[3]{y} \\sqrt[n]{z}"), "√x ∛y ⁿ√z");
+        assert_eq!(to_unicode("x \\in \\mathbb{R}"), "x ∈ ℝ");
+        assert_eq!(to_unicode("\\sin x + \\text{if } y"), "sin x + if y");
+        // No superscript for q, so it is spelled out
+        assert_eq!(to_unicode("x^q y^{qq}"), "x^q y^(qq)");
+        assert_eq!(to_unicode("\\unknown{1}"), "\\unknown1");
+    }
+}
@@ -0,0 +1,204 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{collections::HashMap, process::Stdio, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines},
+    process::{Child, ChildStdin, ChildStdout, Command},
+    sync::Mutex,
+};
+
+use crate::AppError;
+
+// --- MCP (Model Context Protocol) Client ---
+// Talks JSON-RPC 2.0 over a server's stdin/stdout, one message per line.
+const PROTOCOL_VERSION: &str = "2024-11-05";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct McpServerConfig {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct McpTool {
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(rename = "inputSchema", default)]
+    input_schema: Value,
+}
+
+#[derive(Debug)]
+struct McpConnection {
+    // Kept so the server is killed when the connection is dropped
+    _child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+    next_id: u64,
+}
+
+impl McpConnection {
+    async fn spawn(config: &McpServerConfig) -> Result<Self, AppError> {
+        let mut child = Command::new(&config.command)
+            .args(&config.args)
+            .envs(&config.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null()) // Anything written to stderr would corrupt the TUI
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| AppError::Mcp(format!("{}: failed to start `{}`: {}", config.name, config.command, e)))?;
+
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = BufReader::new(child.stdout.take().expect("stdout is piped")).lines();
+        Ok(McpConnection { _child: child, stdin, stdout, next_id: 1 })
+    }
+
+    async fn send(&mut self, message: &Value) -> Result<(), AppError> {
+        let mut line = message.to_string();
+        line.push('\n');
+        self.stdin.write_all(line.as_bytes()).await?;
+        self.stdin.flush().await?;
+        Ok(())
+    }
+
+    async fn notify(&mut self, method: &str) -> Result<(), AppError> {
+        self.send(&json!({ "jsonrpc": "2.0", "method": method })).await
+    }
+
+    async fn request(&mut self, method: &str, params: Value) -> Result<Value, AppError> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(&json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))
+            .await?;
+
+        let response = tokio::time::timeout(REQUEST_TIMEOUT, async {
+            // Skip notifications and anything else until our response arrives
+            while let Some(line) = self.stdout.next_line().await? {
+                let Ok(message) = serde_json::from_str::<Value>(&line) else { continue };
+                if message.get("id").and_then(Value::as_u64) == Some(id) {
+                    return Ok(message);
+                }
+            }
+            Err(AppError::Mcp("server closed the connection".to_string()))
+        })
+        .await
+        .map_err(|_| AppError::Mcp(format!("`{}` timed out", method)))??;
+
+        if let Some(error) = response.get("error") {
+            let message = error.get("message").and_then(Value::as_str).unwrap_or("unknown error");
+            return Err(AppError::Mcp(format!("`{}` failed: {}", method, message)));
+        }
+        Ok(response.get("result").cloned().unwrap_or(Value::Null))
+    }
+}
+
+#[derive(Debug)]
+pub struct McpManager {
+    connections: Vec<Mutex<McpConnection>>,
+    // Each tool alongside the index of the connection that provides it
+    tools: Vec<(usize, McpTool)>,
+}
+
+impl McpManager {
+    // Starts every configured server; servers that fail are reported and skipped
+    pub async fn connect(configs: &[McpServerConfig]) -> (Self, Vec<String>) {
+        let mut manager = McpManager { connections: Vec::new(), tools: Vec::new() };
+        let mut errors = Vec::new();
+
+        for config in configs {
+            match Self::connect_one(config).await {
+                Ok((connection, tools)) => {
+                    let index = manager.connections.len();
+                    manager.connections.push(Mutex::new(connection));
+                    for tool in tools {
+                        // First server to register a name wins
+                        if manager.tools.iter().any(|(_, t)| t.name == tool.name) {
+                            errors.push(format!("{}: tool `{}` is already provided by another server", config.name, tool.name));
+                        } else {
+                            manager.tools.push((index, tool));
+                        }
+                    }
+                }
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+        (manager, errors)
+    }
+
+    async fn connect_one(config: &McpServerConfig) -> Result<(McpConnection, Vec<McpTool>), AppError> {
+        let mut connection = McpConnection::spawn(config).await?;
+        connection
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": { "name": "ollama-tui", "version": env!("CARGO_PKG_VERSION") },
+                }),
+            )
+            .await?;
+        connection.notify("notifications/initialized").await?;
+
+        let result = connection.request("tools/list", json!({})).await?;
+        let tools: Vec<McpTool> = serde_json::from_value(result.get("tools").cloned().unwrap_or_else(|| json!([])))?;
+        Ok((connection, tools))
+    }
+
+    pub fn tool_count(&self) -> usize {
+        self.tools.len()
+    }
+
+    // Tool definitions in the shape /api/chat expects
+    pub fn ollama_tools(&self) -> Vec<Value> {
+        self.tools
+            .iter()
+            .map(|(_, tool)| {
+                json!({
+                    "type": "function",
+                    "function": {
+                        "name": tool.name,
+                        "description": tool.description,
+                        "parameters": tool.input_schema,
+                    }
+                })
+            })
+            .collect()
+    }
+
+    // Calls a tool and flattens its text content into a single string
+    pub async fn call_tool(&self, name: &str, arguments: Value) -> Result<String, AppError> {
+        let Some((index, _)) = self.tools.iter().find(|(_, tool)| tool.name == name) else {
+            return Err(AppError::Mcp(format!("unknown tool `{}`", name)));
+        };
+
+        let result = self.connections[*index]
+            .lock()
+            .await
+            .request("tools/call", json!({ "name": name, "arguments": arguments }))
+            .await?;
+
+        let text = result
+            .get("content")
+            .and_then(Value::as_array)
+            .map(|content| {
+                content
+                    .iter()
+                    .filter_map(|item| item.get("text").and_then(Value::as_str))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .unwrap_or_default();
+
+        if result.get("isError").and_then(Value::as_bool).unwrap_or(false) {
+            Err(AppError::Mcp(format!("`{}` returned an error: {}", name, text)))
+        } else {
+            Ok(text)
+        }
+    }
+}
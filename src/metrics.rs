@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    fs,
+    path::Path,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{config::MetricsConfig, stats::Generation, AppError};
+
+// --- Metrics Export ---
+// Counters for this run (requests, tokens, latencies and errors) written to
+// `metrics.path` on quit and, with `interval_secs`, while running. The
+// Prometheus format suits node_exporter's textfile collector; JSON is for
+// anything else. The file is replaced in one rename, so a scrape never sees
+// half of it. Counters start from zero every launch, which Prometheus treats
+// as a counter reset.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricsFormat {
+    #[default]
+    Prometheus,
+    Json,
+}
+
+#[derive(Serialize, Default)]
+struct ModelMetrics {
+    requests: u64,
+    prompt_tokens: u64,
+    generated_tokens: u64,
+    generation_seconds: f64,
+    load_seconds: f64,
+    // Sent until the first token arrived
+    first_token_seconds_sum: f64,
+    first_token_count: u64,
+    // Whole responses, as the server timed them
+    response_seconds_sum: f64,
+    response_count: u64,
+}
+
+#[derive(Serialize)]
+pub struct Metrics {
+    start_time_seconds: u64,
+    models: BTreeMap<String, ModelMetrics>,
+    // By what failed, e.g. "reply", "pull", "interrupted"
+    errors: BTreeMap<&'static str, u64>,
+    pulls_done: u64,
+    pulls_failed: u64,
+    #[serde(skip)]
+    written: Option<Instant>,
+    // Only the first of a run of failed writes is reported
+    #[serde(skip)]
+    failing: bool,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics {
+            start_time_seconds: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs()),
+            models: BTreeMap::new(),
+            errors: BTreeMap::new(),
+            pulls_done: 0,
+            pulls_failed: 0,
+            written: None,
+            failing: false,
+        }
+    }
+}
+
+impl Metrics {
+    pub fn request(&mut self, model: &str) {
+        self.model(model).requests += 1;
+    }
+
+    pub fn first_token(&mut self, model: &str, latency: Duration) {
+        let metrics = self.model(model);
+        metrics.first_token_seconds_sum += latency.as_secs_f64();
+        metrics.first_token_count += 1;
+    }
+
+    pub fn generated(&mut self, model: &str, generation: &Generation) {
+        let metrics = self.model(model);
+        metrics.prompt_tokens += generation.prompt_eval_count;
+        metrics.generated_tokens += generation.eval_count;
+        metrics.generation_seconds += generation.eval_duration as f64 / 1e9;
+        metrics.load_seconds += generation.load_duration as f64 / 1e9;
+        metrics.response_seconds_sum += generation.total_duration as f64 / 1e9;
+        metrics.response_count += 1;
+    }
+
+    pub fn error(&mut self, kind: &'static str) {
+        *self.errors.entry(kind).or_default() += 1;
+    }
+
+    pub fn pulled(&mut self, ok: bool) {
+        if ok {
+            self.pulls_done += 1;
+        } else {
+            self.pulls_failed += 1;
+        }
+    }
+
+    fn model(&mut self, model: &str) -> &mut ModelMetrics {
+        self.models.entry(model.to_string()).or_default()
+    }
+
+    // Whether a periodic write is due
+    pub fn due(&self, config: &MetricsConfig) -> bool {
+        config.path.is_some()
+            && config.interval_secs > 0
+            && self.written.is_none_or(|at| at.elapsed() >= Duration::from_secs(config.interval_secs))
+    }
+
+    // Writes the snapshot if a path is configured; an error is returned only
+    // for the first of consecutive failures
+    pub fn write(&mut self, config: &MetricsConfig) -> Result<(), AppError> {
+        let Some(path) = &config.path else { return Ok(()) };
+        self.written = Some(Instant::now());
+        let text = match config.format {
+            MetricsFormat::Prometheus => self.prometheus(),
+            MetricsFormat::Json => serde_json::to_string_pretty(self)?,
+        };
+        match replace_file(path, &text) {
+            Ok(()) => {
+                self.failing = false;
+                Ok(())
+            }
+            Err(_) if self.failing => Ok(()),
+            Err(e) => {
+                self.failing = true;
+                Err(e.into())
+            }
+        }
+    }
+
+    fn prometheus(&self) -> String {
+        let mut out = String::new();
+        let per_model = |out: &mut String, name: &str, kind: &str, help: &str, value: &dyn Fn(&ModelMetrics) -> f64| {
+            header(out, name, kind, help);
+            for (model, metrics) in &self.models {
+                let _ = writeln!(out, "{}{{model=\"{}\"}} {}", name, escape(model), value(metrics));
+            }
+        };
+        per_model(&mut out, "ollama_tui_requests_total", "counter", "Prompts sent.", &|m| m.requests as f64);
+        per_model(&mut out, "ollama_tui_prompt_tokens_total", "counter", "Prompt tokens evaluated.", &|m| m.prompt_tokens as f64);
+        per_model(&mut out, "ollama_tui_generated_tokens_total", "counter", "Tokens generated.", &|m| m.generated_tokens as f64);
+        per_model(&mut out, "ollama_tui_generation_seconds_total", "counter", "Time spent generating tokens.", &|m| {
+            m.generation_seconds
+        });
+        per_model(&mut out, "ollama_tui_load_seconds_total", "counter", "Time spent loading models.", &|m| m.load_seconds);
+
+        header(&mut out, "ollama_tui_first_token_seconds", "summary", "Time from sending a prompt to its first token.");
+        for (model, metrics) in &self.models {
+            let model = escape(model);
+            let _ = writeln!(out, "ollama_tui_first_token_seconds_sum{{model=\"{}\"}} {}", model, metrics.first_token_seconds_sum);
+            let _ = writeln!(out, "ollama_tui_first_token_seconds_count{{model=\"{}\"}} {}", model, metrics.first_token_count);
+        }
+        header(&mut out, "ollama_tui_response_seconds", "summary", "Whole responses, as timed by the server.");
+        for (model, metrics) in &self.models {
+            let model = escape(model);
+            let _ = writeln!(out, "ollama_tui_response_seconds_sum{{model=\"{}\"}} {}", model, metrics.response_seconds_sum);
+            let _ = writeln!(out, "ollama_tui_response_seconds_count{{model=\"{}\"}} {}", model, metrics.response_count);
+        }
+
+        header(&mut out, "ollama_tui_errors_total", "counter", "Failed requests, by what failed.");
+        for (kind, count) in &self.errors {
+            let _ = writeln!(out, "ollama_tui_errors_total{{kind=\"{}\"}} {}", kind, count);
+        }
+        header(&mut out, "ollama_tui_pulls_total", "counter", "Finished model pulls.");
+        let _ = writeln!(out, "ollama_tui_pulls_total{{result=\"done\"}} {}", self.pulls_done);
+        let _ = writeln!(out, "ollama_tui_pulls_total{{result=\"failed\"}} {}", self.pulls_failed);
+        header(&mut out, "ollama_tui_start_time_seconds", "gauge", "When this run started, in seconds since the epoch.");
+        let _ = writeln!(out, "ollama_tui_start_time_seconds {}", self.start_time_seconds);
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+// Label values escape backslashes, quotes and newlines
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+// Written beside the target and renamed over it; the textfile collector
+// skips the hidden temporary file
+fn replace_file(path: &Path, text: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let temp = path.with_file_name(format!(".{}.tmp", name));
+    fs::write(&temp, text)?;
+    fs::rename(&temp, path)
+}
@@ -0,0 +1,186 @@
+use ratatui::{prelude::*, widgets::*};
+
+use crate::{
+    file_picker::FilePicker, focus::Popup, jobs::JobList, library::LibraryBrowser, outline::Outline, session_picker::SessionPicker,
+    templates::TemplatePicker, theme::ThemePicker, Confirmation,
+};
+
+// --- Modals ---
+// Popups open on a stack: the top one is drawn last, over a cleared centered
+// area, and takes every key until it closes, handing keys back to the one
+// beneath or to the focused pane. Esc closes any of them; pickers may do
+// something first, like the theme picker putting the old theme back.
+pub enum Modal {
+    SessionPicker(SessionPicker),
+    FilePicker(FilePicker),
+    Outline(Outline),
+    Library(LibraryBrowser),
+    ThemePicker(ThemePicker),
+    // Blank or a template, for /new
+    TemplatePicker(TemplatePicker),
+    // Background jobs ('J' or /jobs)
+    Jobs(JobList),
+    // 'y' runs the action, any other key cancels it
+    Confirm { question: String, action: Confirmation },
+    // F1 (or '?' outside the draft)
+    Help,
+}
+
+const HELP: [(&str, &str); 21] = [
+    ("Tab / Shift+Tab", "next / previous pane (screen, off the chat)"),
+    ("Alt+1..9", "go to a screen"),
+    ("Enter", "start typing, or unfold the selected code reply"),
+    ("Ctrl+D", "send (the send_key setting)"),
+    ("Esc", "leave the draft or selection, close a popup"),
+    ("j / k", "pick a model, or scroll the conversation"),
+    ("[ / ]", "select the previous / next turn"),
+    ("y / Y", "copy the selected turn"),
+    ("r / C", "continue an interrupted or truncated reply"),
+    ("v", "select text"),
+    ("/", "search the conversation"),
+    ("o", "outline of the conversation"),
+    ("m", "next renderer for the selected turn"),
+    ("p", "pin the selected turn"),
+    ("e / w", "edit / show the scratchpad"),
+    ("Ctrl+L", "clear the conversation (u: undo)"),
+    ("Ctrl+O", "attach a file while typing"),
+    ("Ctrl+P", "preview the draft with {{date}}, {{file:path}}... filled in"),
+    ("J", "jobs running in the background"),
+    ("F11 / F12", "resource overlay / frame timings"),
+    ("q", "quit"),
+];
+
+impl Modal {
+    pub fn kind(&self) -> Popup {
+        match self {
+            Modal::SessionPicker(_) => Popup::SessionPicker,
+            Modal::FilePicker(_) => Popup::FilePicker,
+            Modal::Outline(_) => Popup::Outline,
+            Modal::Library(_) => Popup::Library,
+            Modal::ThemePicker(_) => Popup::ThemePicker,
+            Modal::TemplatePicker(_) => Popup::TemplatePicker,
+            Modal::Jobs(_) => Popup::Jobs,
+            Modal::Confirm { .. } => Popup::Confirmation,
+            Modal::Help => Popup::Help,
+        }
+    }
+
+    fn area(&self, screen: Rect) -> Rect {
+        match self {
+            Modal::SessionPicker(_) | Modal::Jobs(_) => centered_rect(70, 70, screen),
+            Modal::FilePicker(_) | Modal::Outline(_) | Modal::Help => centered_rect(60, 70, screen),
+            Modal::Library(_) => centered_rect(80, 80, screen),
+            Modal::ThemePicker(_) => centered_rect(30, 30, screen),
+            Modal::TemplatePicker(_) => centered_rect(50, 40, screen),
+            // The question, a blank line and the keys, inside the border
+            Modal::Confirm { .. } => {
+                let area = centered_rect(50, 100, screen);
+                let height = 5.min(area.height);
+                Rect { y: area.y + (area.height - height) / 2, height, ..area }
+            }
+        }
+    }
+
+    fn render(&self, f: &mut Frame, area: Rect) {
+        match self {
+            Modal::SessionPicker(picker) => picker.render(f, area),
+            Modal::FilePicker(picker) => picker.render(f, area),
+            Modal::Outline(outline) => outline.render(f, area),
+            Modal::Library(browser) => browser.render(f, area),
+            Modal::ThemePicker(picker) => picker.render(f, area),
+            Modal::TemplatePicker(picker) => picker.render(f, area),
+            Modal::Jobs(list) => list.render(f, area),
+            Modal::Confirm { question, .. } => {
+                let text = vec![
+                    Line::raw(question.as_str()),
+                    Line::raw(""),
+                    Line::styled("y: Yes, any other key: No", Style::default().fg(Color::DarkGray)),
+                ];
+                let block = Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::Yellow));
+                f.render_widget(Paragraph::new(text).block(block).wrap(Wrap { trim: false }), area);
+            }
+            Modal::Help => {
+                let lines: Vec<Line> = HELP
+                    .iter()
+                    .map(|(keys, action)| {
+                        Line::from(vec![
+                            Span::styled(format!("{:<17}", keys), Style::default().fg(Color::Cyan)),
+                            Span::raw(*action),
+                        ])
+                    })
+                    .collect();
+                let block = Block::default().borders(Borders::ALL).title(" Keys ").title_bottom(" Esc: Close ");
+                f.render_widget(Paragraph::new(lines).block(block), area);
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Modals {
+    stack: Vec<Modal>,
+}
+
+impl Modals {
+    pub fn push(&mut self, modal: Modal) {
+        self.stack.push(modal);
+    }
+
+    pub fn pop(&mut self) -> Option<Modal> {
+        self.stack.pop()
+    }
+
+    pub fn top(&self) -> Option<&Modal> {
+        self.stack.last()
+    }
+
+    pub fn top_mut(&mut self) -> Option<&mut Modal> {
+        self.stack.last_mut()
+    }
+
+    // The library browser, even with help open over it, for the index
+    // arriving after it was opened
+    pub fn library_mut(&mut self) -> Option<&mut LibraryBrowser> {
+        self.stack.iter_mut().rev().find_map(|modal| match modal {
+            Modal::Library(browser) => Some(browser),
+            _ => None,
+        })
+    }
+
+    // The jobs popup, wherever it is on the stack, to keep it current
+    pub fn jobs_mut(&mut self) -> Option<&mut JobList> {
+        self.stack.iter_mut().find_map(|modal| match modal {
+            Modal::Jobs(list) => Some(list),
+            _ => None,
+        })
+    }
+
+    // Bottom to top, each over a cleared area
+    pub fn render(&self, f: &mut Frame) {
+        for modal in &self.stack {
+            let area = modal.area(f.size());
+            f.render_widget(Clear, area);
+            modal.render(f, area);
+        }
+    }
+}
+
+// Returns a rectangle of the given percentage size centered inside `area`
+pub fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(area);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(vertical[1])[1]
+}
@@ -0,0 +1,181 @@
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+use std::marker::PhantomData;
+
+// --- Streaming NDJSON Decoder ---
+// Ollama streams one JSON object per line. A network read can end in the
+// middle of a multi-byte character, so bytes are buffered raw and only
+// complete lines/objects are decoded; a partial sequence waits for the next
+// read instead of turning into '�'.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamDecoding {
+    // Every line must be a whole object; each bad line is reported
+    #[default]
+    Strict,
+    // Objects are picked out wherever they start, newlines or not, and only
+    // repeated failures are reported. For proxies that mangle chunk boundaries.
+    Lossy,
+}
+
+// Bad objects in a row that lossy mode skips before reporting
+const LOSSY_FAILURE_LIMIT: usize = 3;
+
+enum Decoded<T> {
+    Item(T),
+    // Ollama reports failures mid-stream as {"error": "..."}
+    ServerError(String),
+    Mismatch(String),
+}
+
+fn decode_value<T: DeserializeOwned>(value: Value) -> Decoded<T> {
+    if let Some(error) = value.get("error").and_then(Value::as_str) {
+        return Decoded::ServerError(format!("Server Error: {}", error));
+    }
+    match serde_json::from_value(value.clone()) {
+        Ok(item) => Decoded::Item(item),
+        Err(e) => Decoded::Mismatch(format!("Unexpected chunk: '{}' in {}", e, value)),
+    }
+}
+
+pub struct NdjsonDecoder<T> {
+    mode: StreamDecoding,
+    buffer: Vec<u8>,
+    failures: usize,
+    _item: PhantomData<T>,
+}
+
+impl<T: DeserializeOwned> NdjsonDecoder<T> {
+    pub fn new(mode: StreamDecoding) -> Self {
+        NdjsonDecoder { mode, buffer: Vec::new(), failures: 0, _item: PhantomData }
+    }
+
+    // Returns every complete item in the buffer; errors are meant for the user
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<Result<T, String>> {
+        self.buffer.extend_from_slice(bytes);
+        self.decode(false)
+    }
+
+    // Decodes whatever is left once the stream has closed
+    pub fn finish(&mut self) -> Vec<Result<T, String>> {
+        self.decode(true)
+    }
+
+    fn decode(&mut self, at_end: bool) -> Vec<Result<T, String>> {
+        match self.mode {
+            StreamDecoding::Strict => self.decode_lines(at_end),
+            StreamDecoding::Lossy => self.decode_objects(at_end),
+        }
+    }
+
+    fn decode_lines(&mut self, at_end: bool) -> Vec<Result<T, String>> {
+        let mut items = Vec::new();
+        while let Some(newline_pos) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=newline_pos).collect();
+            items.extend(Self::decode_line(&line));
+        }
+        if at_end {
+            let rest = std::mem::take(&mut self.buffer);
+            items.extend(Self::decode_line(&rest));
+        }
+        items
+    }
+
+    fn decode_line(line: &[u8]) -> Option<Result<T, String>> {
+        let trimmed = line.trim_ascii();
+        if trimmed.is_empty() {
+            return None;
+        }
+        // Parsed as bytes, so bad UTF-8 is reported rather than replaced with '�'
+        Some(match serde_json::from_slice::<Value>(trimmed) {
+            Ok(value) => match decode_value(value) {
+                Decoded::Item(item) => Ok(item),
+                Decoded::ServerError(msg) | Decoded::Mismatch(msg) => Err(msg),
+            },
+            Err(e) => Err(format!("JSON Decode Error: '{}' on line: '{}'", e, String::from_utf8_lossy(trimmed))),
+        })
+    }
+
+    fn decode_objects(&mut self, at_end: bool) -> Vec<Result<T, String>> {
+        let mut items = Vec::new();
+        let mut start = 0;
+        loop {
+            // Objects start at '{'; anything before one is noise
+            match self.buffer[start..].iter().position(|b| *b == b'{') {
+                Some(offset) => start += offset,
+                None => {
+                    start = self.buffer.len();
+                    break;
+                }
+            }
+            let mut values = serde_json::Deserializer::from_slice(&self.buffer[start..]).into_iter::<Value>();
+            let failure = match values.next() {
+                Some(Ok(value)) => {
+                    start += values.byte_offset();
+                    match decode_value(value) {
+                        Decoded::Item(item) => {
+                            self.failures = 0;
+                            items.push(Ok(item));
+                            continue;
+                        }
+                        Decoded::ServerError(msg) => {
+                            items.push(Err(msg));
+                            continue;
+                        }
+                        Decoded::Mismatch(msg) => msg,
+                    }
+                }
+                // The rest of this object hasn't arrived yet
+                Some(Err(e)) if e.is_eof() && !at_end => break,
+                Some(Err(e)) => {
+                    // Resync from the next '{'
+                    start += 1;
+                    e.to_string()
+                }
+                None => break,
+            };
+            self.failures += 1;
+            if self.failures >= LOSSY_FAILURE_LIMIT {
+                self.failures = 0;
+                items.push(Err(format!("Stream decoding keeps failing, latest error: {}", failure)));
+            }
+        }
+        self.buffer.drain(..start);
+        items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Chunk {
+        response: String,
+    }
+
+    // Feeds `stream` in reads of `size` bytes, cutting through characters
+    fn decode_in_reads(mode: StreamDecoding, stream: &[u8], size: usize) -> Vec<Result<String, String>> {
+        let mut decoder = NdjsonDecoder::<Chunk>::new(mode);
+        let mut items: Vec<_> = stream.chunks(size).flat_map(|read| decoder.push(read)).collect();
+        items.extend(decoder.finish());
+        items.into_iter().map(|item| item.map(|chunk| chunk.response)).collect()
+    }
+
+    #[test]
+    fn characters_split_across_reads_are_kept_whole() {
+        let stream = "{\"response\":\"日本\"}\n{\"response\":\"🦙 é\"}\n".as_bytes();
+        for mode in [StreamDecoding::Strict, StreamDecoding::Lossy] {
+            for size in 1..stream.len() {
+                let items = decode_in_reads(mode, stream, size);
+                assert_eq!(items, [Ok("日本".to_string()), Ok("🦙 é".to_string())], "{:?} in reads of {}", mode, size);
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_utf8_is_reported_not_replaced() {
+        let items = decode_in_reads(StreamDecoding::Strict, b"{\"response\":\"\xff\"}\n", 4);
+        assert!(matches!(items.as_slice(), [Err(_)]));
+    }
+}
@@ -0,0 +1,103 @@
+use ratatui::{prelude::*, widgets::*};
+use std::collections::VecDeque;
+
+use crate::{AppError, ErrorCategory};
+
+// --- Error Notifications ---
+// Errors are shown in a modal instead of the conversation, so they never end
+// up in the history sent to the model.
+// Older errors are dropped beyond this
+const MAX_PENDING: usize = 20;
+
+// One error as the modal shows it: what went wrong and, when the kind of
+// failure is known, what to try
+#[derive(Debug, Clone)]
+pub struct ErrorReport {
+    pub message: String,
+    category: Option<ErrorCategory>,
+    suggestion: Option<String>,
+}
+
+impl ErrorReport {
+    pub fn new(category: ErrorCategory, message: String) -> Self {
+        let suggestion = category.suggestion(&message);
+        ErrorReport { message, category: Some(category), suggestion }
+    }
+
+    // Puts what was being done in front, e.g. "Failed to fetch models: …"
+    pub fn context(mut self, context: &str) -> Self {
+        self.message = format!("{}: {}", context, self.message);
+        self
+    }
+}
+
+impl From<&AppError> for ErrorReport {
+    fn from(error: &AppError) -> Self {
+        ErrorReport::new(error.category(), error.to_string())
+    }
+}
+
+// Messages from the app itself, with nothing to categorize
+impl From<String> for ErrorReport {
+    fn from(message: String) -> Self {
+        ErrorReport { message, category: None, suggestion: None }
+    }
+}
+
+impl From<&str> for ErrorReport {
+    fn from(message: &str) -> Self {
+        ErrorReport::from(message.to_string())
+    }
+}
+
+pub struct Notifications {
+    pending: VecDeque<ErrorReport>,
+}
+
+impl Notifications {
+    pub fn new() -> Self {
+        Notifications { pending: VecDeque::new() }
+    }
+
+    pub fn push(&mut self, message: impl Into<ErrorReport>) {
+        if self.pending.len() == MAX_PENDING {
+            self.pending.pop_front();
+        }
+        self.pending.push_back(message.into());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    // Removes the error currently shown
+    pub fn dismiss(&mut self) {
+        self.pending.pop_front();
+    }
+
+    pub fn dismiss_all(&mut self) {
+        self.pending.clear();
+    }
+
+    pub fn render(&self, f: &mut Frame, area: Rect) {
+        let Some(message) = self.pending.front() else { return };
+        let more = match self.pending.len() - 1 {
+            0 => String::new(),
+            n => format!(", {} more", n),
+        };
+        let heading = message.category.map_or("Error", ErrorCategory::label);
+        let title = format!(" {}{} (Enter: Dismiss, Esc: Dismiss all) ", heading, more);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Red))
+            .title(title);
+        let mut text = Text::raw(message.message.as_str());
+        if let Some(suggestion) = &message.suggestion {
+            text.lines.push(Line::raw(""));
+            text.lines.push(Line::styled(format!("Try: {}", suggestion), Style::default().fg(Color::Yellow)));
+        }
+        let paragraph = Paragraph::new(text).block(block).wrap(Wrap { trim: false });
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+    }
+}
@@ -0,0 +1,83 @@
+use ratatui::{prelude::*, widgets::*};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+
+use crate::AppError;
+
+// --- Onboarding Hints ---
+// For the first few launches, short key hints are drawn next to the pane they
+// are about, changing with what the user is doing. 'H' hides them for good.
+// Launches are counted in <data dir>/ollama-tui/onboarding.json.
+const HINT_LAUNCHES: u32 = 5;
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Onboarding {
+    launches: u32,
+    dismissed: bool,
+}
+
+fn onboarding_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("ollama-tui").join("onboarding.json"))
+}
+
+impl Onboarding {
+    // A missing file means this is the first launch
+    pub fn load() -> Result<Self, AppError> {
+        let Some(path) = onboarding_path() else {
+            return Ok(Onboarding::default());
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Onboarding::default()),
+            Err(e) => Err(AppError::Io(e)),
+        }
+    }
+
+    fn save(&self) -> Result<(), AppError> {
+        let path = onboarding_path().ok_or_else(|| AppError::Session("No data directory available".to_string()))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn count_launch(&mut self) -> Result<(), AppError> {
+        self.launches = self.launches.saturating_add(1);
+        self.save()
+    }
+
+    pub fn dismiss(&mut self) -> Result<(), AppError> {
+        self.dismissed = true;
+        self.save()
+    }
+
+    pub fn active(&self) -> bool {
+        !self.dismissed && self.launches <= HINT_LAUNCHES
+    }
+}
+
+// A hint and the pane it belongs to
+pub struct Hint {
+    pub anchor: Rect,
+    pub text: String,
+}
+
+// Draws each hint in a small box over the bottom-right corner of its pane
+pub fn render(f: &mut Frame, hints: &[Hint]) {
+    for hint in hints {
+        let inner = hint.anchor.inner(Margin { horizontal: 1, vertical: 1 });
+        let width = (hint.text.chars().count() as u16 + 4).min(inner.width);
+        if width < 8 || inner.height < 3 {
+            continue;
+        }
+        let rect = Rect::new(inner.right() - width, inner.bottom() - 3, width, 3);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Magenta))
+            .title(" Hint (H: hide) ");
+        let text = Paragraph::new(format!(" {}", hint.text)).block(block).style(Style::default().fg(Color::White));
+        f.render_widget(Clear, rect);
+        f.render_widget(text, rect);
+    }
+}
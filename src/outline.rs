@@ -0,0 +1,83 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+// --- Outline Popup ('o' in normal mode) ---
+// Headings of every turn, for jumping around long responses
+const MAX_LEVEL: usize = 3;
+
+pub struct OutlineEntry {
+    pub turn: usize,
+    pub level: usize,
+    pub title: String,
+    // Line of the heading in the wrapped conversation pane
+    pub line: u16,
+}
+
+pub struct Outline {
+    entries: Vec<OutlineEntry>,
+    selected: usize,
+}
+
+pub enum OutlineAction {
+    None,
+    Close,
+    Jump(u16),
+}
+
+impl Outline {
+    // None when there are no H1-H3 headings; starts on the last heading at or
+    // above `scroll_offset`
+    pub fn new(entries: Vec<OutlineEntry>, scroll_offset: u16) -> Option<Self> {
+        let entries: Vec<OutlineEntry> = entries.into_iter().filter(|entry| entry.level <= MAX_LEVEL).collect();
+        if entries.is_empty() {
+            return None;
+        }
+        let selected = entries.iter().rposition(|entry| entry.line <= scroll_offset).unwrap_or(0);
+        Some(Outline { entries, selected })
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> OutlineAction {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('o') => return OutlineAction::Close,
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.selected = (self.selected + 1) % self.entries.len();
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.selected = self.selected.checked_sub(1).unwrap_or(self.entries.len() - 1);
+            }
+            KeyCode::Enter => return OutlineAction::Jump(self.entries[self.selected].line),
+            _ => {}
+        }
+        OutlineAction::None
+    }
+
+    pub fn render(&self, f: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:>3} ", entry.turn + 1), Style::default().fg(Color::DarkGray)),
+                    Span::raw("  ".repeat(entry.level - 1)),
+                    Span::styled(entry.title.as_str(), Style::default().fg(Color::Magenta)),
+                ]))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Yellow))
+                    .title(" Outline (turn, heading) ")
+                    .title_bottom(" Enter: jump, Esc: close "),
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD).bg(Color::Blue))
+            .highlight_symbol("> ");
+
+        let mut state = ListState::default();
+        state.select(Some(self.selected));
+
+        f.render_stateful_widget(list, area, &mut state);
+    }
+}
@@ -0,0 +1,185 @@
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag};
+
+use crate::math;
+
+// --- Markdown to Plain Text ---
+// What "copy as plain text" puts on the clipboard: the reply as it reads on
+// screen, without markdown syntax. Lists keep their markers, code blocks lose
+// their fences, links show their target after the text, math is Unicode and
+// table cells are tab-separated so they paste into spreadsheets.
+pub fn from_markdown(markdown: &str) -> String {
+    let source = math::isolate(markdown);
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_FOOTNOTES);
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_TASKLISTS);
+
+    let mut writer = PlainWriter::default();
+    for event in Parser::new_ext(&source, options) {
+        writer.event(event);
+    }
+    writer.out.trim_end().to_string()
+}
+
+#[derive(Default)]
+struct PlainWriter {
+    out: String,
+    quote_depth: usize,
+    // Next number of each open list; None for bullet lists
+    list_stack: Vec<Option<u64>>,
+    list_just_started: bool,
+    // Text follows a list marker or footnote label on the same line
+    item_start: bool,
+    // Text of the open code block, and whether it holds display math
+    code_block: Option<(String, bool)>,
+    // Where each open link's text starts, and its target
+    links: Vec<(usize, String)>,
+    first_in_row: bool,
+}
+
+impl PlainWriter {
+    // Indentation of continuation lines at the current nesting
+    fn indent(&self) -> usize {
+        (self.quote_depth + self.list_stack.len()) * 2
+    }
+
+    fn newline(&mut self, indent: usize) {
+        let trimmed = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed);
+        self.out.push('\n');
+        self.out.push_str(&" ".repeat(indent));
+    }
+
+    // Blank line between blocks; a single line break inside lists
+    fn block_break(&mut self) {
+        if self.item_start {
+            self.item_start = false;
+            return;
+        }
+        if self.out.is_empty() {
+            return;
+        }
+        if self.list_stack.is_empty() {
+            self.newline(0);
+        }
+        self.newline(self.indent());
+    }
+
+    fn event(&mut self, event: Event) {
+        if let Some((code, _)) = self.code_block.as_mut() {
+            match event {
+                Event::Text(text) => code.push_str(&text),
+                Event::End(Tag::CodeBlock(_)) => {
+                    let (mut code, is_math) = self.code_block.take().unwrap_or_default();
+                    if is_math {
+                        code = math::to_unicode(&code);
+                    }
+                    for (index, line) in code.trim_end_matches('\n').lines().enumerate() {
+                        if index > 0 {
+                            self.newline(self.indent());
+                        }
+                        self.out.push_str(line);
+                    }
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        // Anything written after a list marker ends the "same line" state
+        if matches!(event, Event::Text(_) | Event::Code(_) | Event::Html(_) | Event::FootnoteReference(_)) {
+            self.item_start = false;
+        }
+        match event {
+            Event::Start(tag) => match tag {
+                Tag::Paragraph | Tag::Heading(..) => self.block_break(),
+                Tag::BlockQuote => {
+                    self.quote_depth += 1;
+                    self.block_break();
+                }
+                Tag::CodeBlock(kind) => {
+                    self.block_break();
+                    self.item_start = false;
+                    let is_math = matches!(&kind, CodeBlockKind::Fenced(language) if language.as_ref() == math::FENCE_LANGUAGE);
+                    self.code_block = Some((String::new(), is_math));
+                }
+                Tag::List(start) => {
+                    self.block_break();
+                    self.item_start = false;
+                    self.list_stack.push(start);
+                    self.list_just_started = true;
+                }
+                Tag::Item => {
+                    if !self.list_just_started {
+                        self.newline(self.indent() - 2);
+                    }
+                    self.list_just_started = false;
+                    let marker = match self.list_stack.last_mut() {
+                        Some(Some(number)) => {
+                            *number += 1;
+                            format!("{}. ", *number - 1)
+                        }
+                        _ => "- ".to_string(),
+                    };
+                    self.out.push_str(&marker);
+                    self.item_start = true;
+                }
+                Tag::Table(_) => {
+                    self.block_break();
+                    self.first_in_row = true;
+                }
+                Tag::TableHead | Tag::TableRow => {
+                    if !self.first_in_row {
+                        self.newline(self.indent());
+                    }
+                    self.first_in_row = true;
+                }
+                Tag::TableCell => {
+                    if !self.first_in_row {
+                        self.out.push('\t');
+                    }
+                    self.first_in_row = false;
+                }
+                Tag::Link(_, dest, _) => self.links.push((self.out.len(), dest.to_string())),
+                Tag::Image(..) => self.out.push_str("[Image: "),
+                Tag::FootnoteDefinition(label) => {
+                    self.block_break();
+                    self.out.push_str(&format!("[{}]: ", label));
+                    self.item_start = true;
+                }
+                Tag::Emphasis | Tag::Strong | Tag::Strikethrough => {}
+            },
+            Event::End(tag) => match tag {
+                Tag::BlockQuote => self.quote_depth -= 1,
+                Tag::List(_) => {
+                    self.list_stack.pop();
+                    self.list_just_started = false;
+                }
+                Tag::TableHead | Tag::TableRow => self.first_in_row = false,
+                Tag::Link(..) => {
+                    if let Some((start, dest)) = self.links.pop() {
+                        // Autolinks and links written as their own URL aren't repeated
+                        if !dest.is_empty() && self.out[start..] != dest {
+                            self.out.push_str(&format!(" ({})", dest));
+                        }
+                    }
+                }
+                Tag::Image(..) => self.out.push(']'),
+                _ => {}
+            },
+            Event::Text(text) | Event::Html(text) => self.out.push_str(&text),
+            Event::Code(text) => match text.strip_prefix(math::INLINE_MARKER) {
+                Some(latex) => self.out.push_str(&math::to_unicode(latex)),
+                None => self.out.push_str(&text),
+            },
+            Event::FootnoteReference(label) => self.out.push_str(&format!("[{}]", label)),
+            Event::SoftBreak | Event::HardBreak => self.newline(self.indent()),
+            Event::Rule => {
+                self.block_break();
+                self.out.push_str("----------");
+            }
+            Event::TaskListMarker(checked) => self.out.push_str(if checked { "[x] " } else { "[ ] " }),
+        }
+    }
+}
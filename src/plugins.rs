@@ -0,0 +1,134 @@
+use mlua::{Function, Lua, Table, Value};
+use std::{fs, path::Path};
+
+use crate::AppError;
+
+// --- Lua Plugins ---
+// Every *.lua file in <config dir>/ollama-tui/plugins is run at startup and
+// can use the global `ollama` table:
+//
+//   ollama.register_command(name, function(args) return prompt_or_nil end)
+//   ollama.on_send(function(prompt) return new_prompt_or_nil end)
+//   ollama.on_receive(function(response) return new_response_or_nil end)
+//   ollama.add_status_segment(function() return text end)
+const COMMANDS: &str = "ollama_tui.commands";
+const ON_SEND: &str = "ollama_tui.on_send";
+const ON_RECEIVE: &str = "ollama_tui.on_receive";
+const STATUS_SEGMENTS: &str = "ollama_tui.status_segments";
+
+pub struct PluginHost {
+    lua: Lua,
+}
+
+fn plugin_error(e: mlua::Error) -> AppError {
+    AppError::Plugin(e.to_string())
+}
+
+// Registers a Rust function that appends a Lua callback to a registry list
+fn list_registrar(lua: &Lua, key: &'static str) -> mlua::Result<Function> {
+    lua.create_function(move |lua, callback: Function| {
+        let list: Table = lua.named_registry_value(key)?;
+        list.push(callback)
+    })
+}
+
+impl PluginHost {
+    fn new() -> mlua::Result<Self> {
+        let lua = Lua::new();
+        for key in [COMMANDS, ON_SEND, ON_RECEIVE, STATUS_SEGMENTS] {
+            lua.set_named_registry_value(key, lua.create_table()?)?;
+        }
+
+        let api = lua.create_table()?;
+        api.set(
+            "register_command",
+            lua.create_function(|lua, (name, callback): (String, Function)| {
+                let commands: Table = lua.named_registry_value(COMMANDS)?;
+                commands.set(name, callback)
+            })?,
+        )?;
+        api.set("on_send", list_registrar(&lua, ON_SEND)?)?;
+        api.set("on_receive", list_registrar(&lua, ON_RECEIVE)?)?;
+        api.set("add_status_segment", list_registrar(&lua, STATUS_SEGMENTS)?)?;
+        lua.globals().set("ollama", api)?;
+
+        Ok(PluginHost { lua })
+    }
+
+    // Loads every plugin in `dir`, returning the host and any per-plugin errors
+    pub fn load(dir: Option<&Path>) -> (Self, Vec<String>) {
+        let host = PluginHost::new().expect("failed to initialise the Lua runtime");
+        let mut errors = Vec::new();
+
+        let Some(entries) = dir.and_then(|dir| fs::read_dir(dir).ok()) else {
+            return (host, errors);
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "lua"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+            let result = fs::read_to_string(&path)
+                .map_err(AppError::Io)
+                .and_then(|source| host.lua.load(source).set_name(&name).exec().map_err(plugin_error));
+            if let Err(e) = result {
+                errors.push(format!("Failed to load plugin '{}': {}", name, e));
+            }
+        }
+        (host, errors)
+    }
+
+    pub fn has_command(&self, name: &str) -> bool {
+        self.lua
+            .named_registry_value::<Table>(COMMANDS)
+            .and_then(|commands| commands.contains_key(name))
+            .unwrap_or(false)
+    }
+
+    // Runs a plugin command; a returned string is sent as a prompt
+    pub fn run_command(&self, name: &str, args: &str) -> Result<Option<String>, AppError> {
+        let commands: Table = self.lua.named_registry_value(COMMANDS).map_err(plugin_error)?;
+        let callback: Function = commands.get(name).map_err(plugin_error)?;
+        callback.call(args).map_err(plugin_error)
+    }
+
+    // Passes text through each registered transform in turn; nil keeps it unchanged
+    fn transform(&self, key: &str, mut text: String) -> Result<String, AppError> {
+        let transforms: Table = self.lua.named_registry_value(key).map_err(plugin_error)?;
+        for callback in transforms.sequence_values::<Function>() {
+            let callback = callback.map_err(plugin_error)?;
+            if let Some(rewritten) = callback.call::<Option<String>>(text.as_str()).map_err(plugin_error)? {
+                text = rewritten;
+            }
+        }
+        Ok(text)
+    }
+
+    pub fn transform_outgoing(&self, text: String) -> Result<String, AppError> {
+        self.transform(ON_SEND, text)
+    }
+
+    pub fn transform_incoming(&self, text: String) -> Result<String, AppError> {
+        self.transform(ON_RECEIVE, text)
+    }
+
+    // Collects the text of every status segment; failing segments show as "?"
+    pub fn status_segments(&self) -> Vec<String> {
+        let Ok(segments) = self.lua.named_registry_value::<Table>(STATUS_SEGMENTS) else {
+            return Vec::new();
+        };
+        segments
+            .sequence_values::<Function>()
+            .filter_map(|callback| callback.ok())
+            .map(|callback| match callback.call::<Value>(()) {
+                Ok(Value::Nil) => String::new(),
+                Ok(value) => value.to_string().unwrap_or_else(|_| "?".to_string()),
+                Err(_) => "?".to_string(),
+            })
+            .filter(|segment| !segment.is_empty())
+            .collect()
+    }
+}
@@ -0,0 +1,199 @@
+use chrono::Local;
+use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag};
+use std::{
+    fmt::Write as _,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+use crate::{config::PostprocessConfig, AppError};
+
+// --- Response Post-Processing ---
+// Steps run on every finished response, switchable per conversation
+#[derive(Clone, Copy, Debug)]
+pub enum Step {
+    ExtractCode,
+    Copy,
+    Journal,
+    Note,
+    Filter,
+}
+
+impl Step {
+    pub const ALL: [Step; 5] = [Step::Filter, Step::ExtractCode, Step::Copy, Step::Journal, Step::Note];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Step::ExtractCode => "code",
+            Step::Copy => "copy",
+            Step::Journal => "journal",
+            Step::Note => "note",
+            Step::Filter => "filter",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Step> {
+        Step::ALL.into_iter().find(|step| step.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Steps {
+    pub extract_code: bool,
+    pub copy: bool,
+    pub journal: bool,
+    pub note: bool,
+    pub filter: bool,
+}
+
+impl Steps {
+    pub fn from_config(config: &PostprocessConfig) -> Self {
+        Steps {
+            extract_code: config.extract_code,
+            copy: config.copy_to_clipboard,
+            journal: config.journal,
+            note: config.note,
+            filter: config.filter,
+        }
+    }
+
+    pub fn get_mut(&mut self, step: Step) -> &mut bool {
+        match step {
+            Step::ExtractCode => &mut self.extract_code,
+            Step::Copy => &mut self.copy,
+            Step::Journal => &mut self.journal,
+            Step::Note => &mut self.note,
+            Step::Filter => &mut self.filter,
+        }
+    }
+
+    // e.g. "filter:on code:off copy:off journal:on note:off"
+    pub fn summary(mut self) -> String {
+        Step::ALL
+            .into_iter()
+            .map(|step| format!("{}:{}", step.name(), if *self.get_mut(step) { "on" } else { "off" }))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+fn data_subdir(configured: &Option<PathBuf>, name: &str) -> Result<PathBuf, AppError> {
+    configured
+        .clone()
+        .or_else(|| dirs::data_dir().map(|dir| dir.join("ollama-tui").join(name)))
+        .ok_or_else(|| AppError::Session("No data directory available".to_string()))
+}
+
+// Fenced code blocks in a markdown reply, with their language tag
+fn code_blocks(markdown: &str) -> Vec<(String, String)> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(String, String)> = None;
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(language))) => {
+                current = Some((language.split_whitespace().next().unwrap_or("").to_string(), String::new()));
+            }
+            Event::Text(text) => {
+                if let Some((_, code)) = current.as_mut() {
+                    code.push_str(&text);
+                }
+            }
+            Event::End(Tag::CodeBlock(_)) => blocks.extend(current.take()),
+            _ => {}
+        }
+    }
+    blocks
+}
+
+fn extension_for(language: &str) -> &'static str {
+    match language.to_lowercase().as_str() {
+        "rust" | "rs" => "rs",
+        "python" | "py" => "py",
+        "javascript" | "js" => "js",
+        "typescript" | "ts" => "ts",
+        "bash" | "sh" | "shell" | "zsh" => "sh",
+        "go" => "go",
+        "c" => "c",
+        "cpp" | "c++" => "cpp",
+        "java" => "java",
+        "json" => "json",
+        "toml" => "toml",
+        "yaml" | "yml" => "yaml",
+        "html" => "html",
+        "css" => "css",
+        "sql" => "sql",
+        "markdown" | "md" => "md",
+        _ => "txt",
+    }
+}
+
+// Saves each fenced code block as its own file, returning the directory used
+pub fn save_code_blocks(config: &PostprocessConfig, reply: &str) -> Result<Option<(PathBuf, usize)>, AppError> {
+    let blocks = code_blocks(reply);
+    if blocks.is_empty() {
+        return Ok(None);
+    }
+    let dir = data_subdir(&config.code_dir, "scratch")?;
+    fs::create_dir_all(&dir)?;
+    let stamp = Local::now().format("%Y%m%d-%H%M%S");
+    for (index, (language, code)) in blocks.iter().enumerate() {
+        let path = dir.join(format!("{}-{}.{}", stamp, index + 1, extension_for(language)));
+        fs::write(path, code)?;
+    }
+    Ok(Some((dir, blocks.len())))
+}
+
+// Appends the exchange to today's journal file
+pub fn append_journal(config: &PostprocessConfig, model: &str, prompt: &str, reply: &str) -> Result<PathBuf, AppError> {
+    let dir = data_subdir(&config.journal_dir, "journal")?;
+    fs::create_dir_all(&dir)?;
+    let now = Local::now();
+    let path = dir.join(format!("{}.md", now.format("%Y-%m-%d")));
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    write!(
+        file,
+        "## {} - {}\n\n**Prompt**\n\n{}\n\n**Response**\n\n{}\n\n",
+        now.format("%H:%M"),
+        model,
+        prompt.trim(),
+        reply.trim()
+    )?;
+    Ok(path)
+}
+
+// The configured notes file for today, with a leading ~ expanded
+pub fn notes_path(config: &PostprocessConfig) -> Result<PathBuf, AppError> {
+    let pattern = config
+        .notes_file
+        .as_deref()
+        .ok_or_else(|| AppError::Config("Set postprocess.notes_file to the markdown file for notes".to_string()))?;
+    let mut name = String::new();
+    write!(name, "{}", Local::now().format(pattern))
+        .map_err(|_| AppError::Config(format!("Bad date pattern in notes_file `{}`", pattern)))?;
+    Ok(match name.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().map_or_else(|| PathBuf::from(&name), |home| home.join(rest)),
+        None => PathBuf::from(name),
+    })
+}
+
+// Appends the exchange to the notes file under a timestamped header
+pub fn append_note(config: &PostprocessConfig, model: &str, prompt: &str, reply: &str) -> Result<PathBuf, AppError> {
+    let path = notes_path(config)?;
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    // A blank line between this and whatever the note already holds
+    let separator = if file.metadata()?.len() > 0 { "\n" } else { "" };
+    write!(
+        file,
+        "{}### {} - {}\n\n> {}\n\n{}\n\n",
+        separator,
+        Local::now().format("%Y-%m-%d %H:%M"),
+        model,
+        prompt.trim().replace('\n', "\n> "),
+        reply.trim()
+    )?;
+    Ok(path)
+}
@@ -0,0 +1,49 @@
+use reqwest::{Client, NoProxy, Proxy};
+use std::env;
+
+use crate::{config::ProxyConfig, endpoint, AppError};
+
+// --- HTTP(S) and SOCKS Proxies ---
+// An explicit `[proxy] url` wins; otherwise the usual environment variables
+// are honoured. Either way the Ollama server (local, or from OLLAMA_HOST) is
+// never proxied, along with NO_PROXY and the configured `no_proxy` hosts.
+const LOCAL_HOSTS: &str = "localhost,127.0.0.1,::1";
+
+fn env_var(names: &[&str]) -> Option<String> {
+    names.iter().find_map(|name| env::var(name).ok()).filter(|value| !value.is_empty())
+}
+
+fn no_proxy_list(config: &ProxyConfig) -> Option<NoProxy> {
+    let mut hosts = vec![LOCAL_HOSTS.to_string(), endpoint::host_name().to_string()];
+    hosts.extend(env_var(&["NO_PROXY", "no_proxy"]));
+    hosts.extend(config.no_proxy.iter().cloned());
+    NoProxy::from_string(&hosts.join(","))
+}
+
+pub fn build_client(config: &ProxyConfig) -> Result<Client, AppError> {
+    let invalid = |e: reqwest::Error| AppError::Config(format!("Invalid proxy: {}", e));
+    let mut proxies = Vec::new();
+    match &config.url {
+        // http://, https://, socks5:// or socks5h:// (DNS through the proxy)
+        Some(url) => proxies.push(Proxy::all(url).map_err(invalid)?),
+        None => {
+            if let Some(url) = env_var(&["HTTP_PROXY", "http_proxy"]) {
+                proxies.push(Proxy::http(url).map_err(invalid)?);
+            }
+            if let Some(url) = env_var(&["HTTPS_PROXY", "https_proxy"]) {
+                proxies.push(Proxy::https(url).map_err(invalid)?);
+            }
+            if let Some(url) = env_var(&["ALL_PROXY", "all_proxy"]) {
+                proxies.push(Proxy::all(url).map_err(invalid)?);
+            }
+        }
+    }
+
+    // Replaces reqwest's own environment lookup, which has no way to exempt
+    // the local server
+    let mut builder = Client::builder().no_proxy();
+    for proxy in proxies {
+        builder = builder.proxy(proxy.no_proxy(no_proxy_list(config)));
+    }
+    builder.build().map_err(AppError::ApiRequest)
+}
@@ -0,0 +1,46 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+// --- Event Queue Backpressure ---
+// Background tasks talk to the UI through one bounded channel. Nothing is
+// ever dropped: when it is full, senders wait (a streaming reply simply reads
+// from the socket more slowly) until the UI catches up. The UI drains the
+// queue every loop iteration, but handles at most DRAIN_BUDGET events before
+// drawing and reading keys again, so a flood of chunks can't freeze input.
+pub const CAPACITY: usize = 100;
+pub const DRAIN_BUDGET: usize = 500;
+
+// For the F12 readout
+#[derive(Default)]
+pub struct QueueStats {
+    // When each event of the last second was handled
+    recent: VecDeque<Instant>,
+    // Most events seen waiting at once
+    peak: usize,
+    // Loop iterations that found the queue full, i.e. with senders waiting
+    full: u64,
+}
+
+impl QueueStats {
+    pub fn handled(&mut self) {
+        let now = Instant::now();
+        self.recent.push_back(now);
+        while self.recent.front().is_some_and(|at| now.duration_since(*at) > Duration::from_secs(1)) {
+            self.recent.pop_front();
+        }
+    }
+
+    // Events still waiting after this iteration's drain
+    pub fn record_depth(&mut self, depth: usize) {
+        self.peak = self.peak.max(depth);
+        if depth >= CAPACITY {
+            self.full += 1;
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        format!("{} ev/s | queue peak {}/{} | full {}", self.recent.len(), self.peak, CAPACITY, self.full)
+    }
+}
@@ -0,0 +1,78 @@
+use regex::{NoExpand, Regex};
+use serde::Deserialize;
+
+use crate::{config::RedactConfig, AppError};
+
+// --- Redaction ---
+// Each rule's matches are replaced with a visible "[redacted:<name>]", in
+// prompts before they are sent and/or in exports. Add your own rules for
+// internal hostnames, customer IDs and the like.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct RedactRule {
+    pub name: String,
+    pub pattern: String,
+}
+
+impl RedactRule {
+    fn new(name: &str, pattern: &str) -> Self {
+        RedactRule { name: name.to_string(), pattern: pattern.to_string() }
+    }
+}
+
+// Used when the config lists no rules of its own
+pub fn default_rules() -> Vec<RedactRule> {
+    vec![
+        RedactRule::new(
+            "private-key",
+            r"-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?-----END [A-Z ]*PRIVATE KEY-----",
+        ),
+        RedactRule::new(
+            "api-key",
+            r"\b(?:sk-[A-Za-z0-9_-]{20,}|gh[pousr]_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{22,}|AKIA[0-9A-Z]{16}|xox[abprs]-[A-Za-z0-9-]{10,})",
+        ),
+        RedactRule::new("email", r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b"),
+    ]
+}
+
+pub struct Redactor {
+    // A rule that doesn't compile keeps everything from being redacted, so
+    // nothing goes out unredacted by mistake
+    rules: Result<Vec<(String, Regex)>, String>,
+    prompts: bool,
+    exports: bool,
+}
+
+impl Redactor {
+    pub fn new(config: &RedactConfig) -> Self {
+        let rules = config
+            .rules
+            .iter()
+            .map(|rule| {
+                Regex::new(&rule.pattern)
+                    .map(|regex| (rule.name.clone(), regex))
+                    .map_err(|e| format!("Bad redaction rule `{}`: {}", rule.name, e))
+            })
+            .collect();
+        Redactor { rules, prompts: config.prompts, exports: config.exports }
+    }
+
+    // The text with every match replaced, and how many there were
+    pub fn redact(&self, text: &str) -> Result<(String, usize), AppError> {
+        let rules = self.rules.as_ref().map_err(|e| AppError::Config(e.clone()))?;
+        let mut text = text.to_string();
+        let mut count = 0;
+        for (name, regex) in rules {
+            count += regex.find_iter(&text).count();
+            text = regex.replace_all(&text, NoExpand(&format!("[redacted:{}]", name))).into_owned();
+        }
+        Ok((text, count))
+    }
+
+    pub fn redact_prompt(&self, prompt: String) -> Result<(String, usize), AppError> {
+        if self.prompts { self.redact(&prompt) } else { Ok((prompt, 0)) }
+    }
+
+    pub fn redact_export(&self, text: &str) -> Result<String, AppError> {
+        if self.exports { self.redact(text).map(|(text, _)| text) } else { Ok(text.to_string()) }
+    }
+}
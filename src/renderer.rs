@@ -0,0 +1,176 @@
+use ratatui::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// --- Turn Renderers ('m' cycles them on the selected turn) ---
+// Markdown suits most replies, but some models print ANSI colours or ASCII
+// art, which markdown mangles: escapes show up literally and art loses its
+// spacing to emphasis and list markers. Turns without a renderer of their
+// own get one picked from their text.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Renderer {
+    Markdown,
+    // The text as it is, monospace, nothing interpreted
+    Plain,
+    // ANSI colour and style codes applied, other escapes dropped
+    Ansi,
+}
+
+// Lines made mostly of these look like drawing, not prose
+const ART_CHARS: &str = "/\\|_-=+*#<>()[]{}^~.'`:;o";
+const ART_LINES: usize = 3;
+
+impl Renderer {
+    pub fn name(self) -> &'static str {
+        match self {
+            Renderer::Markdown => "markdown",
+            Renderer::Plain => "plain",
+            Renderer::Ansi => "ANSI",
+        }
+    }
+
+    pub fn next(self) -> Renderer {
+        match self {
+            Renderer::Markdown => Renderer::Plain,
+            Renderer::Plain => Renderer::Ansi,
+            Renderer::Ansi => Renderer::Markdown,
+        }
+    }
+
+    // A renderer the text clearly needs, if any: escape codes call for ANSI,
+    // a run of lines drawn with symbols for plain
+    pub fn detect(text: &str) -> Option<Renderer> {
+        if text.contains("\x1b[") {
+            return Some(Renderer::Ansi);
+        }
+        let mut run = 0;
+        let mut in_fence = false;
+        for line in text.lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                // Art inside a code block renders fine as markdown
+                in_fence = !in_fence;
+                run = 0;
+                continue;
+            }
+            run = if !in_fence && is_art(trimmed) { run + 1 } else { 0 };
+            if run >= ART_LINES {
+                return Some(Renderer::Plain);
+            }
+        }
+        None
+    }
+}
+
+fn is_art(line: &str) -> bool {
+    let visible = line.chars().filter(|c| !c.is_whitespace()).count();
+    let drawn = line.chars().filter(|c| ART_CHARS.contains(*c)).count();
+    // A markdown rule or table row is not art
+    let markdown = line.chars().all(|c| c == '-' || c == '*' || c == '_') || (line.starts_with('|') && line.ends_with('|'));
+    visible >= 3 && drawn * 10 >= visible * 6 && !markdown
+}
+
+// --- ANSI Escapes ---
+// SGR codes (ESC [ … m) become span styles; cursor movement and any other
+// control sequence is dropped, since it has no meaning inside a pane.
+pub fn ansi_lines(text: &str) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut style = Style::default();
+    for source in text.lines() {
+        let mut spans: Vec<Span<'static>> = Vec::new();
+        let mut current = String::new();
+        let mut chars = source.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\x1b' => {
+                    if !current.is_empty() {
+                        spans.push(Span::styled(std::mem::take(&mut current), style));
+                    }
+                    match chars.next() {
+                        // CSI: parameters, then a final byte in @..~
+                        Some('[') => {
+                            let mut params = String::new();
+                            for c in chars.by_ref() {
+                                if ('@'..='~').contains(&c) {
+                                    if c == 'm' {
+                                        style = apply_sgr(style, &params);
+                                    }
+                                    break;
+                                }
+                                params.push(c);
+                            }
+                        }
+                        // OSC (titles, hyperlinks): up to BEL or ESC \
+                        Some(']') => {
+                            while let Some(c) = chars.next() {
+                                if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                                    break;
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                '\t' => current.push_str("    "),
+                c if c.is_control() => {}
+                c => current.push(c),
+            }
+        }
+        if !current.is_empty() {
+            spans.push(Span::styled(current, style));
+        }
+        lines.push(Line::from(spans));
+    }
+    if lines.is_empty() {
+        lines.push(Line::raw(""));
+    }
+    lines
+}
+
+fn apply_sgr(mut style: Style, params: &str) -> Style {
+    let codes: Vec<u16> = params.split(';').map(|code| code.parse().unwrap_or(0)).collect();
+    let mut codes = codes.into_iter();
+    while let Some(code) = codes.next() {
+        style = match code {
+            0 => Style::default(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            5 => style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style.add_modifier(Modifier::REVERSED),
+            9 => style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style.remove_modifier(Modifier::ITALIC),
+            24 => style.remove_modifier(Modifier::UNDERLINED),
+            25 => style.remove_modifier(Modifier::SLOW_BLINK),
+            27 => style.remove_modifier(Modifier::REVERSED),
+            29 => style.remove_modifier(Modifier::CROSSED_OUT),
+            30..=37 => style.fg(Color::Indexed((code - 30) as u8)),
+            90..=97 => style.fg(Color::Indexed((code - 90 + 8) as u8)),
+            40..=47 => style.bg(Color::Indexed((code - 40) as u8)),
+            100..=107 => style.bg(Color::Indexed((code - 100 + 8) as u8)),
+            39 => Style { fg: None, ..style },
+            49 => Style { bg: None, ..style },
+            38 => match extended_color(&mut codes) {
+                Some(color) => style.fg(color),
+                None => style,
+            },
+            48 => match extended_color(&mut codes) {
+                Some(color) => style.bg(color),
+                None => style,
+            },
+            _ => style,
+        };
+    }
+    style
+}
+
+// The rest of a 38/48 code: 5;n for the 256-colour palette, 2;r;g;b for RGB
+fn extended_color(codes: &mut impl Iterator<Item = u16>) -> Option<Color> {
+    match codes.next()? {
+        5 => Some(Color::Indexed(codes.next()? as u8)),
+        2 => Some(Color::Rgb(codes.next()? as u8, codes.next()? as u8, codes.next()? as u8)),
+        _ => None,
+    }
+}
@@ -0,0 +1,182 @@
+use ratatui::{prelude::*, widgets::*};
+use serde_json::Value;
+use std::{
+    fs,
+    process::Stdio,
+    time::{Duration, Instant},
+};
+use tokio::process::Command;
+
+use crate::health::format_bytes;
+
+// --- GPU/CPU Overlay (F11) ---
+// While a response is generating, GPU memory and utilization (from
+// nvidia-smi or rocm-smi) and CPU/RAM use (from /proc, Linux only) are
+// probed every few seconds, to answer "is it even using the GPU?"
+pub const PROBE_INTERVAL: Duration = Duration::from_secs(2);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug)]
+struct Gpu {
+    name: String,
+    used: u64,
+    total: u64,
+    utilization: Option<u32>,
+}
+
+// Cumulative CPU time from /proc/stat: (idle, total) in ticks
+pub type CpuTimes = (u64, u64);
+
+#[derive(Debug)]
+pub struct Snapshot {
+    gpus: Vec<Gpu>,
+    // Percent busy since the previous probe; None on the first one
+    cpu: Option<f64>,
+    pub cpu_times: Option<CpuTimes>,
+    // (used, total) in bytes
+    ram: Option<(u64, u64)>,
+}
+
+#[derive(Default)]
+pub struct Resources {
+    pub enabled: bool,
+    pub snapshot: Option<Snapshot>,
+    pub requested: Option<Instant>,
+}
+
+impl Resources {
+    pub fn due(&self) -> bool {
+        self.enabled && self.requested.is_none_or(|at| at.elapsed() >= PROBE_INTERVAL)
+    }
+}
+
+async fn run(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(PROBE_TIMEOUT, output).await.ok()?.ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// One line per GPU: name, memory used and total (MiB), utilization (%)
+async fn nvidia_gpus() -> Option<Vec<Gpu>> {
+    let output = run(
+        "nvidia-smi",
+        &["--query-gpu=name,memory.used,memory.total,utilization.gpu", "--format=csv,noheader,nounits"],
+    )
+    .await?;
+    let mib = |field: &str| field.trim().parse::<u64>().ok().map(|mib| mib * 1024 * 1024);
+    Some(
+        output
+            .lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split(',').collect();
+                let [name, used, total, utilization] = fields.as_slice() else { return None };
+                Some(Gpu {
+                    name: name.trim().to_string(),
+                    used: mib(used)?,
+                    total: mib(total)?,
+                    utilization: utilization.trim().parse().ok(),
+                })
+            })
+            .collect(),
+    )
+}
+
+// {"card0": {"GPU use (%)": "12", "VRAM Total Memory (B)": "...", "VRAM Total Used Memory (B)": "..."}}
+async fn rocm_gpus() -> Option<Vec<Gpu>> {
+    let output = run("rocm-smi", &["--showuse", "--showmeminfo", "vram", "--json"]).await?;
+    let cards: serde_json::Map<String, Value> = serde_json::from_str(&output).ok()?;
+    let number = |card: &Value, key: &str| card.get(key)?.as_str()?.trim().parse::<u64>().ok();
+    Some(
+        cards
+            .iter()
+            .filter(|(name, _)| name.starts_with("card"))
+            .filter_map(|(name, card)| {
+                Some(Gpu {
+                    name: name.clone(),
+                    used: number(card, "VRAM Total Used Memory (B)")?,
+                    total: number(card, "VRAM Total Memory (B)")?,
+                    utilization: number(card, "GPU use (%)").map(|percent| percent as u32),
+                })
+            })
+            .collect(),
+    )
+}
+
+fn cpu_times() -> Option<CpuTimes> {
+    let stat = fs::read_to_string("/proc/stat").ok()?;
+    let ticks: Vec<u64> = stat.lines().next()?.split_whitespace().skip(1).filter_map(|n| n.parse().ok()).collect();
+    // user nice system idle iowait ...
+    let idle = ticks.get(3)? + ticks.get(4).unwrap_or(&0);
+    Some((idle, ticks.iter().sum()))
+}
+
+fn ram() -> Option<(u64, u64)> {
+    let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+    let field = |name: &str| {
+        let line = meminfo.lines().find(|line| line.starts_with(name))?;
+        line.split_whitespace().nth(1)?.parse::<u64>().ok().map(|kib| kib * 1024)
+    };
+    let (total, available) = (field("MemTotal:")?, field("MemAvailable:")?);
+    Some((total.saturating_sub(available), total))
+}
+
+// CPU use is measured against `previous`, the times from the last probe
+pub async fn probe(previous: Option<CpuTimes>) -> Snapshot {
+    let gpus = match nvidia_gpus().await {
+        Some(gpus) => gpus,
+        None => rocm_gpus().await.unwrap_or_default(),
+    };
+    let cpu_times = cpu_times();
+    let cpu = previous.zip(cpu_times).and_then(|((idle_before, total_before), (idle, total))| {
+        let elapsed = total.checked_sub(total_before).filter(|&ticks| ticks > 0)?;
+        let busy = elapsed.saturating_sub(idle.saturating_sub(idle_before));
+        Some(busy as f64 * 100.0 / elapsed as f64)
+    });
+    Snapshot { gpus, cpu, cpu_times, ram: ram() }
+}
+
+// Top-right corner of `area`, below the frame readout's row
+pub fn render(f: &mut Frame, area: Rect, snapshot: Option<&Snapshot>) {
+    let mut lines = Vec::new();
+    match snapshot {
+        None => lines.push(Line::from("Probing...")),
+        Some(snapshot) => {
+            if snapshot.gpus.is_empty() {
+                lines.push(Line::styled("No GPU found (nvidia-smi, rocm-smi)", Style::default().fg(Color::Yellow)));
+            }
+            for gpu in &snapshot.gpus {
+                let utilization = gpu.utilization.map_or("?".to_string(), |percent| format!("{}%", percent));
+                lines.push(Line::from(format!(
+                    "{}: {}, VRAM {} / {}",
+                    gpu.name,
+                    utilization,
+                    format_bytes(gpu.used),
+                    format_bytes(gpu.total)
+                )));
+            }
+            let cpu = snapshot.cpu.map_or("?".to_string(), |percent| format!("{:.0}%", percent));
+            let ram = snapshot
+                .ram
+                .map_or("?".to_string(), |(used, total)| format!("{} / {}", format_bytes(used), format_bytes(total)));
+            lines.push(Line::from(format!("CPU: {}, RAM {}", cpu, ram)));
+        }
+    }
+    let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 4;
+    let height = lines.len() as u16 + 2;
+    if area.width < width || area.height < height + 1 {
+        return;
+    }
+    let rect = Rect::new(area.right() - width, area.y + 1, width, height);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::DarkGray))
+        .padding(Padding::horizontal(1))
+        .title(" Resources (F11) ");
+    f.render_widget(Clear, rect);
+    f.render_widget(Paragraph::new(lines).block(block).style(Style::default().fg(Color::White)), rect);
+}
@@ -0,0 +1,94 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{prelude::*, widgets::*};
+
+use crate::{editor::InputEditor, keys::KeyBinding};
+
+// --- Scratchpad ('w' shows it, 'e' edits it) ---
+// A third pane beside the conversation for snippets worth keeping. 'A' adds
+// the selected turn and 'a' a visual selection; the send key puts the whole
+// pad into the draft as context. It is saved with the conversation.
+pub struct Scratchpad {
+    pub editor: InputEditor,
+    pub open: bool,
+    // Keys go to the pad instead of the rest of the UI
+    pub focused: bool,
+}
+
+pub enum ScratchpadAction {
+    None,
+    Leave,
+    UseAsContext,
+}
+
+impl Scratchpad {
+    pub fn new() -> Self {
+        Scratchpad { editor: InputEditor::new(), open: false, focused: false }
+    }
+
+    pub fn text(&self) -> &str {
+        self.editor.text()
+    }
+
+    // Adds a snippet at the end, a blank line apart from the one before
+    pub fn append(&mut self, snippet: &str) {
+        let mut text = self.editor.text().trim_end().to_string();
+        if !text.is_empty() {
+            text.push_str("\n\n");
+        }
+        text.push_str(snippet.trim());
+        self.editor.set_text(text);
+    }
+
+    // What goes into the draft, ahead of the question
+    pub fn as_context(&self) -> Option<String> {
+        let notes = self.editor.text().trim();
+        (!notes.is_empty()).then(|| format!("Context from my notes:\n\n{}\n\n", notes))
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent, send_key: &KeyBinding) -> ScratchpadAction {
+        match (key.code, key.modifiers) {
+            _ if send_key.matches(&key) => return ScratchpadAction::UseAsContext,
+            (KeyCode::Esc, _) => return ScratchpadAction::Leave,
+            (KeyCode::Char('z'), KeyModifiers::CONTROL) => {
+                self.editor.undo();
+            }
+            (KeyCode::Char('y'), KeyModifiers::CONTROL) => {
+                self.editor.redo();
+            }
+            (KeyCode::Char('k'), KeyModifiers::CONTROL) => self.editor.clear(),
+            (KeyCode::Char('u'), KeyModifiers::CONTROL) => self.editor.delete_to_line_start(),
+            (KeyCode::Char(c), modifiers) if modifiers == KeyModifiers::NONE || modifiers == KeyModifiers::SHIFT => {
+                self.editor.insert_char(c);
+            }
+            (KeyCode::Enter, _) => self.editor.insert_char('\n'),
+            (KeyCode::Backspace, _) => self.editor.backspace(),
+            (KeyCode::Delete, _) => self.editor.delete(),
+            (KeyCode::Left, _) => self.editor.move_left(),
+            (KeyCode::Right, _) => self.editor.move_right(),
+            (KeyCode::Up, _) => self.editor.move_up(),
+            (KeyCode::Down, _) => self.editor.move_down(),
+            (KeyCode::Home, _) => self.editor.move_home(),
+            (KeyCode::End, _) => self.editor.move_end(),
+            _ => {}
+        }
+        ScratchpadAction::None
+    }
+
+    // Returns the cursor position while the pad has focus
+    pub fn render(&self, f: &mut Frame, area: Rect, send_key: &str) -> Option<(u16, u16)> {
+        let block = if self.focused {
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow))
+                .title(" Scratchpad ")
+                .title_bottom(format!(" {}: Use as context, Ctrl+K: Clear, Esc: Leave ", send_key))
+        } else {
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Scratchpad (e: Edit, w: Hide) ")
+                .title_bottom(" A: Add selected turn ")
+        };
+        let cursor = self.editor.render(f, area, block);
+        cursor.filter(|_| self.focused)
+    }
+}
@@ -0,0 +1,220 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::{AppError, ConversationTurn, PromptWrap, Role};
+
+// --- Session Store ---
+// Conversations are saved as JSON files under <data dir>/ollama-tui/sessions.
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+    pub title: String,
+    pub model: Option<String>,
+    pub saved_at: DateTime<Local>,
+    pub turns: Vec<ConversationTurn>,
+    // Unsent input, restored when the conversation is reopened
+    #[serde(default)]
+    pub draft: String,
+    #[serde(default)]
+    pub scratchpad: String,
+    // The conversation's /prefix and /suffix
+    #[serde(default)]
+    pub prompt_wrap: PromptWrap,
+}
+
+pub fn sessions_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("ollama-tui").join("sessions"))
+}
+
+// Archived sessions are left out of the launch picker; trashed ones are
+// deleted for good once they've been there for `trash_days`
+#[derive(Clone, Copy, PartialEq)]
+pub enum Folder {
+    Sessions,
+    Archive,
+    Trash,
+}
+
+impl Folder {
+    fn dir(self) -> Option<PathBuf> {
+        let dir = sessions_dir()?;
+        Some(match self {
+            Folder::Sessions => dir,
+            Folder::Archive => dir.join("archive"),
+            Folder::Trash => dir.join("trash"),
+        })
+    }
+
+    // The folder a session file is in, going by its parent directory
+    pub fn of(path: &Path) -> Folder {
+        [Folder::Archive, Folder::Trash]
+            .into_iter()
+            .find(|folder| folder.dir().is_some_and(|dir| path.parent() == Some(dir.as_path())))
+            .unwrap_or(Folder::Sessions)
+    }
+}
+
+// Uses the start of the first prompt as the title
+fn title_for(turns: &[ConversationTurn]) -> String {
+    let first_prompt = turns
+        .iter()
+        .find(|turn| turn.role == Role::User && !turn.example)
+        .and_then(|turn| turn.text.lines().find(|line| !line.trim().is_empty()))
+        .unwrap_or("Untitled");
+    let mut title: String = first_prompt.trim().chars().take(60).collect();
+    if first_prompt.trim().chars().count() > 60 {
+        title.push('…');
+    }
+    title
+}
+
+// Writes the conversation to a new timestamped session file in `folder`
+pub fn archive(
+    folder: Folder,
+    turns: &[ConversationTurn],
+    draft: &str,
+    scratchpad: &str,
+    prompt_wrap: &PromptWrap,
+    model: Option<String>,
+) -> Result<PathBuf, AppError> {
+    let dir = folder.dir().ok_or_else(|| AppError::Session("No data directory available".to_string()))?;
+    fs::create_dir_all(&dir)?;
+
+    let saved_at = Local::now();
+    let path = dir.join(format!("{}.json", saved_at.format("%Y%m%d-%H%M%S%.3f")));
+    let session = Session {
+        title: title_for(turns),
+        model,
+        saved_at,
+        turns: turns.to_vec(),
+        draft: draft.to_string(),
+        scratchpad: scratchpad.to_string(),
+        prompt_wrap: prompt_wrap.clone(),
+    };
+    fs::write(&path, serde_json::to_string_pretty(&session)?)?;
+    Ok(path)
+}
+
+pub fn load(path: &Path) -> Result<Session, AppError> {
+    let contents = fs::read_to_string(path)?;
+    serde_json::from_str(&contents)
+        .map_err(|e| AppError::Session(format!("{} is not a saved session: {}", path.display(), e)))
+}
+
+// Writes the turns as JSON for a .json path, markdown otherwise
+pub fn export(turns: &[ConversationTurn], model: Option<String>, path: &Path) -> Result<(), AppError> {
+    let is_json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let contents = if is_json {
+        let session = Session {
+            title: title_for(turns),
+            model,
+            saved_at: Local::now(),
+            turns: turns.to_vec(),
+            draft: String::new(),
+            scratchpad: String::new(),
+            prompt_wrap: PromptWrap::default(),
+        };
+        serde_json::to_string_pretty(&session)?
+    } else {
+        to_markdown(turns)
+    };
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+pub fn to_markdown(turns: &[ConversationTurn]) -> String {
+    let mut markdown = format!("# {}\n\n", title_for(turns));
+    for turn in turns {
+        markdown.push_str(&format!("**{}:**\n\n{}\n\n", turn.name, turn.text.trim()));
+    }
+    markdown
+}
+
+// Session files, newest first: their names start with the time they were saved
+fn session_files(folder: Folder) -> Result<Vec<PathBuf>, AppError> {
+    let Some(dir) = folder.dir() else {
+        return Ok(Vec::new());
+    };
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(AppError::Io(e)),
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort_by(|a, b| b.cmp(a));
+    Ok(files)
+}
+
+// The latest `limit` sessions; files that don't parse are left out
+pub fn recent(folder: Folder, limit: usize) -> Result<Vec<(PathBuf, Session)>, AppError> {
+    Ok(session_files(folder)?
+        .into_iter()
+        .filter_map(|path| load(&path).ok().map(|session| (path, session)))
+        .take(limit)
+        .collect())
+}
+
+// A session by path, file name (with or without .json) or, failing those,
+// the newest one whose title contains `name`; archived sessions count too
+pub fn find(name: &str) -> Result<(PathBuf, Session), AppError> {
+    let path = Path::new(name);
+    if path.is_file() {
+        return Ok((path.to_path_buf(), load(path)?));
+    }
+    let mut files = session_files(Folder::Sessions)?;
+    files.extend(session_files(Folder::Archive)?);
+    let stem = name.trim_end_matches(".json");
+    if let Some(path) = files.iter().find(|path| path.file_stem().is_some_and(|file| file == stem)) {
+        return Ok((path.clone(), load(path)?));
+    }
+    let needle = name.to_lowercase();
+    files
+        .into_iter()
+        .filter_map(|path| load(&path).ok().map(|session| (path, session)))
+        .find(|(_, session)| session.title.to_lowercase().contains(&needle))
+        .ok_or_else(|| AppError::Session(format!("No saved session matches '{}'", name)))
+}
+
+pub fn remove(path: &Path) -> Result<(), AppError> {
+    fs::remove_file(path)?;
+    Ok(())
+}
+
+// Moves a session file into `folder`, returning its new path
+pub fn move_to(path: &Path, folder: Folder) -> Result<PathBuf, AppError> {
+    let dir = folder.dir().ok_or_else(|| AppError::Session("No data directory available".to_string()))?;
+    let name = path.file_name().ok_or_else(|| AppError::Session(format!("{} is not a session file", path.display())))?;
+    fs::create_dir_all(&dir)?;
+    let target = dir.join(name);
+    fs::rename(path, &target)?;
+    // The purge counts from when it was trashed, not when it was saved
+    if folder == Folder::Trash {
+        fs::File::options().write(true).open(&target)?.set_modified(SystemTime::now())?;
+    }
+    Ok(target)
+}
+
+// Deletes sessions trashed more than `days` ago; 0 keeps them forever
+pub fn purge_trash(days: u32) -> Result<usize, AppError> {
+    if days == 0 {
+        return Ok(0);
+    }
+    let Some(cutoff) = SystemTime::now().checked_sub(Duration::from_secs(days as u64 * 24 * 60 * 60)) else {
+        return Ok(0);
+    };
+    let mut purged = 0;
+    for path in session_files(Folder::Trash)? {
+        if fs::metadata(&path)?.modified()? < cutoff {
+            fs::remove_file(&path)?;
+            purged += 1;
+        }
+    }
+    Ok(purged)
+}
@@ -0,0 +1,159 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+use std::path::PathBuf;
+
+use crate::session::{self, Folder, Session};
+
+// --- Session Picker (on launch, and the Sessions tab) ---
+// Lists the most recent saved conversations to pick one up again; Esc starts
+// a new conversation instead. Tab switches to the archive and the trash, 'a'
+// archives (or brings back) a conversation and 'd' moves it to the trash.
+// On the Sessions tab, where Tab switches screens, 'f' switches folders and
+// Esc goes back to the chat.
+pub const RECENT_SESSIONS: usize = 20;
+
+pub struct SessionPicker {
+    folder: Folder,
+    sessions: Vec<(PathBuf, Session)>,
+    selected: usize,
+    trash_days: u32,
+    // Drawn as the Sessions tab rather than a popup
+    tab: bool,
+}
+
+pub enum SessionAction {
+    None,
+    Close,
+    Resume(PathBuf, Box<Session>),
+    // The outcome of archiving, trashing or restoring, for the status bar
+    Status(String),
+}
+
+impl SessionPicker {
+    // None when there is nothing to resume
+    pub fn new(sessions: Vec<(PathBuf, Session)>, trash_days: u32) -> Option<Self> {
+        (!sessions.is_empty()).then_some(SessionPicker { folder: Folder::Sessions, sessions, selected: 0, trash_days, tab: false })
+    }
+
+    // For the Sessions tab, filled in by reload()
+    pub fn browser(trash_days: u32) -> Self {
+        SessionPicker { folder: Folder::Sessions, sessions: Vec::new(), selected: 0, trash_days, tab: true }
+    }
+
+    // Lists the current folder again, keeping the selection where it can
+    pub fn reload(&mut self) -> SessionAction {
+        let selected = self.selected;
+        let action = self.show(self.folder);
+        self.selected = selected.min(self.sessions.len().saturating_sub(1));
+        action
+    }
+
+    fn show(&mut self, folder: Folder) -> SessionAction {
+        self.folder = folder;
+        self.selected = 0;
+        match session::recent(folder, RECENT_SESSIONS) {
+            Ok(sessions) => {
+                self.sessions = sessions;
+                SessionAction::None
+            }
+            Err(e) => {
+                self.sessions.clear();
+                SessionAction::Status(format!("Error listing sessions: {}", e))
+            }
+        }
+    }
+
+    // Moves the selected session to `folder` and drops it from the list
+    fn move_selected(&mut self, folder: Folder, done: &str) -> SessionAction {
+        let Some((path, session)) = self.sessions.get(self.selected) else {
+            return SessionAction::None;
+        };
+        match session::move_to(path, folder) {
+            Ok(_) => {
+                let message = format!("{} \"{}\".", done, session.title);
+                self.sessions.remove(self.selected);
+                self.selected = self.selected.min(self.sessions.len().saturating_sub(1));
+                SessionAction::Status(message)
+            }
+            Err(e) => SessionAction::Status(format!("Error: {}", e)),
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> SessionAction {
+        let count = self.sessions.len();
+        match (key.code, self.folder) {
+            (KeyCode::Esc, _) => return SessionAction::Close,
+            (KeyCode::Tab | KeyCode::Char('f'), Folder::Sessions) => return self.show(Folder::Archive),
+            (KeyCode::Tab | KeyCode::Char('f'), Folder::Archive) => return self.show(Folder::Trash),
+            (KeyCode::Tab | KeyCode::Char('f'), Folder::Trash) => return self.show(Folder::Sessions),
+            (KeyCode::Char('j') | KeyCode::Down, _) if count > 0 => {
+                self.selected = (self.selected + 1) % count;
+            }
+            (KeyCode::Char('k') | KeyCode::Up, _) if count > 0 => {
+                self.selected = self.selected.checked_sub(1).unwrap_or(count - 1);
+            }
+            (KeyCode::Enter, Folder::Sessions | Folder::Archive) if count > 0 => {
+                let (path, session) = self.sessions.swap_remove(self.selected);
+                return SessionAction::Resume(path, Box::new(session));
+            }
+            (KeyCode::Char('a'), Folder::Sessions) => return self.move_selected(Folder::Archive, "Archived"),
+            (KeyCode::Char('a'), Folder::Archive) => return self.move_selected(Folder::Sessions, "Unarchived"),
+            (KeyCode::Char('d'), Folder::Sessions | Folder::Archive) => return self.move_selected(Folder::Trash, "Moved to the trash:"),
+            (KeyCode::Char('r'), Folder::Trash) => return self.move_selected(Folder::Sessions, "Restored"),
+            _ => {}
+        }
+        SessionAction::None
+    }
+
+    pub fn render(&self, f: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .sessions
+            .iter()
+            .map(|(_, session)| {
+                ListItem::new(vec![
+                    Line::styled(session.title.as_str(), Style::default().add_modifier(Modifier::BOLD)),
+                    Line::styled(
+                        format!(
+                            "    {}, {} turns, {}",
+                            session.model.as_deref().unwrap_or("no model"),
+                            session.turns.len(),
+                            session.saved_at.format("%Y-%m-%d %H:%M")
+                        ),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ])
+            })
+            .collect();
+
+        let (title, hints) = match self.folder {
+            Folder::Sessions if self.tab => (" Sessions ".to_string(), "Enter: Resume, a: Archive, d: Trash, f: Archive/Trash"),
+            Folder::Sessions => (" Resume a conversation? ".to_string(), "Enter: Resume, a: Archive, d: Trash, Tab: Archive/Trash"),
+            Folder::Archive if self.tab => (" Archived conversations ".to_string(), "Enter: Resume, a: Unarchive, d: Trash, f: Trash"),
+            Folder::Archive => (" Archived conversations ".to_string(), "Enter: Resume, a: Unarchive, d: Trash, Tab: Trash"),
+            Folder::Trash if self.trash_days > 0 => (format!(" Trash (deleted after {} days) ", self.trash_days), "r: Restore"),
+            Folder::Trash => (" Trash ".to_string(), "r: Restore"),
+        };
+        let hints = match (self.folder, self.tab) {
+            (Folder::Trash, true) => format!(" {}, f: Recent, Esc: Back to the chat ", hints),
+            (Folder::Trash, false) => format!(" {}, Tab: Recent, Esc: Start a new one ", hints),
+            (_, true) => format!(" {}, Esc: Back to the chat ", hints),
+            (_, false) => format!(" {}, Esc: Start a new one ", hints),
+        };
+        let border = if self.tab { Color::Reset } else { Color::Yellow };
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(border))
+                    .title(title)
+                    .title_bottom(hints),
+            )
+            .highlight_style(Style::default().bg(Color::Blue))
+            .highlight_symbol("> ");
+
+        let mut state = ListState::default();
+        state.select((!self.sessions.is_empty()).then_some(self.selected));
+
+        f.render_stateful_widget(list, area, &mut state);
+    }
+}
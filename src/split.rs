@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+
+use crate::{layout::Side, ConversationTurn, PromptWrap};
+
+// --- Split View (/split) ---
+// A second conversation beside the first, e.g. a coding chat next to a docs
+// chat. Only the focused side is live: Tab parks its state here and brings
+// the other side's back, so streaming, undo and the rest keep working on a
+// single conversation.
+pub struct SplitView {
+    pub side: Side,
+    pub parked: ParkedConversation,
+}
+
+#[derive(Default)]
+pub struct ParkedConversation {
+    pub turns: Vec<ConversationTurn>,
+    pub draft: String,
+    pub scratchpad: String,
+    pub scroll_offset: u16,
+    pub selected_turn: Option<usize>,
+    pub prompt_wrap: PromptWrap,
+    pub model: Option<String>,
+    pub resumed_session: Option<PathBuf>,
+}
+
+impl SplitView {
+    // The new, empty conversation goes on the right and takes focus
+    pub fn new(current: ParkedConversation) -> Self {
+        SplitView { side: Side::Right, parked: current }
+    }
+
+    pub fn other_side(&self) -> Side {
+        match self.side {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use chrono::{Duration as Days, Local};
+use ratatui::{prelude::*, widgets::*};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fs,
+    path::PathBuf,
+};
+
+use crate::AppError;
+
+// --- Usage Statistics ---
+// Prompts, generated tokens and generation time per model and day, kept in
+// <data dir>/ollama-tui/stats.json and charted on the Stats tab.
+const CHART_DAYS: i64 = 14;
+
+// Timings from the final chunk of a generation; durations are nanoseconds
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(default)]
+pub struct Generation {
+    pub prompt_eval_count: u64,
+    pub eval_count: u64,
+    pub eval_duration: u64,
+    pub total_duration: u64,
+    // Time spent loading the model into memory first, if it wasn't already
+    pub load_duration: u64,
+}
+
+impl Generation {
+    fn tokens_per_sec(&self) -> Option<f64> {
+        (self.eval_duration > 0).then(|| self.eval_count as f64 / (self.eval_duration as f64 / 1e9))
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+struct Tally {
+    prompts: u64,
+    tokens: u64,
+    eval_ms: u64,
+    total_ms: u64,
+}
+
+impl Tally {
+    fn add(&mut self, other: Tally) {
+        self.prompts += other.prompts;
+        self.tokens += other.tokens;
+        self.eval_ms += other.eval_ms;
+        self.total_ms += other.total_ms;
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct UsageStats {
+    // "YYYY-MM-DD" -> model -> tally
+    days: BTreeMap<String, HashMap<String, Tally>>,
+}
+
+fn stats_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("ollama-tui").join("stats.json"))
+}
+
+impl UsageStats {
+    // A missing file means nothing has been tracked yet
+    pub fn load() -> Result<Self, AppError> {
+        let Some(path) = stats_path() else {
+            return Ok(UsageStats::default());
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(UsageStats::default()),
+            Err(e) => Err(AppError::Io(e)),
+        }
+    }
+
+    fn save(&self) -> Result<(), AppError> {
+        let path = stats_path().ok_or_else(|| AppError::Session("No data directory available".to_string()))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    fn today(&mut self, model: &str) -> &mut Tally {
+        let day = Local::now().format("%Y-%m-%d").to_string();
+        self.days.entry(day).or_default().entry(model.to_string()).or_default()
+    }
+
+    pub fn prompted(&mut self, model: &str) -> Result<(), AppError> {
+        self.today(model).prompts += 1;
+        self.save()
+    }
+
+    pub fn generated(&mut self, model: &str, generation: &Generation) -> Result<(), AppError> {
+        self.today(model).add(Tally {
+            prompts: 0,
+            tokens: generation.eval_count,
+            eval_ms: generation.eval_duration / 1_000_000,
+            total_ms: generation.total_duration / 1_000_000,
+        });
+        self.save()
+    }
+
+    // All-time totals per model, most tokens first
+    fn per_model(&self) -> Vec<(&str, Tally)> {
+        let mut totals: HashMap<&str, Tally> = HashMap::new();
+        for models in self.days.values() {
+            for (model, tally) in models {
+                totals.entry(model).or_default().add(*tally);
+            }
+        }
+        let mut totals: Vec<_> = totals.into_iter().collect();
+        totals.sort_by(|a, b| b.1.tokens.cmp(&a.1.tokens).then(a.0.cmp(b.0)));
+        totals
+    }
+
+    // Tokens generated on each of the last CHART_DAYS days, oldest first
+    fn per_day(&self) -> Vec<(String, u64)> {
+        let today = Local::now().date_naive();
+        (0..CHART_DAYS)
+            .rev()
+            .map(|ago| {
+                let day = today - Days::days(ago);
+                let tokens = self
+                    .days
+                    .get(&day.format("%Y-%m-%d").to_string())
+                    .map_or(0, |models| models.values().map(|tally| tally.tokens).sum());
+                (day.format("%m-%d").to_string(), tokens)
+            })
+            .collect()
+    }
+}
+
+fn format_ms(ms: u64) -> String {
+    match ms / 1000 {
+        secs if secs >= 3600 => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+        secs if secs >= 60 => format!("{}m {}s", secs / 60, secs % 60),
+        secs => format!("{}.{}s", secs, ms % 1000 / 100),
+    }
+}
+
+pub fn render(f: &mut Frame, area: Rect, stats: &UsageStats) {
+    let block = Block::default().borders(Borders::ALL).title(" Usage Statistics (Tab: Next view) ");
+    let totals = stats.per_model();
+    if totals.is_empty() {
+        f.render_widget(Paragraph::new("No prompts sent yet.").block(block), area);
+        return;
+    }
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let rows = Layout::vertical([Constraint::Percentage(50), Constraint::Min(3)]).split(inner);
+    let charts = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).split(rows[0]);
+
+    // The latest days that fit, bars being 5 wide with a gap of 1
+    let days = stats.per_day();
+    let fit = (charts[0].width.saturating_sub(2) as usize + 1) / 6;
+    let day_bars: Vec<(&str, u64)> =
+        days.iter().skip(days.len().saturating_sub(fit)).map(|(label, tokens)| (label.as_str(), *tokens)).collect();
+    let by_day = BarChart::default()
+        .block(Block::default().borders(Borders::ALL).title(" Tokens per day "))
+        .data(day_bars.as_slice())
+        .bar_width(5)
+        .bar_gap(1)
+        .bar_style(Style::default().fg(Color::Cyan))
+        .value_style(Style::default().fg(Color::Black).bg(Color::Cyan));
+    f.render_widget(by_day, charts[0]);
+
+    let model_bars: Vec<(&str, u64)> = totals.iter().map(|(model, tally)| (*model, tally.tokens)).collect();
+    let by_model = BarChart::default()
+        .block(Block::default().borders(Borders::ALL).title(" Tokens per model "))
+        .data(model_bars.as_slice())
+        .bar_width(10)
+        .bar_gap(1)
+        .bar_style(Style::default().fg(Color::Green))
+        .value_style(Style::default().fg(Color::Black).bg(Color::Green));
+    f.render_widget(by_model, charts[1]);
+
+    let header = Row::new(["Model", "Prompts", "Tokens", "Avg tok/s", "Time"]).style(Style::default().add_modifier(Modifier::BOLD));
+    let table_rows = totals.iter().map(|(model, tally)| {
+        let speed = (tally.eval_ms > 0).then(|| tally.tokens as f64 / (tally.eval_ms as f64 / 1000.0));
+        Row::new([
+            model.to_string(),
+            tally.prompts.to_string(),
+            tally.tokens.to_string(),
+            speed.map_or("-".to_string(), |speed| format!("{:.1}", speed)),
+            format_ms(tally.total_ms),
+        ])
+    });
+    let widths = [Constraint::Min(20), Constraint::Length(9), Constraint::Length(10), Constraint::Length(10), Constraint::Length(9)];
+    let table = Table::new(table_rows, widths)
+        .header(header)
+        .block(Block::default().borders(Borders::ALL).title(" All time "));
+    f.render_widget(table, rows[1]);
+}
+
+// --- Recent Generations ---
+// The last few generations as sparklines in the status bar, so a sudden drop
+// in speed (e.g. a model spilling over to the CPU) stands out
+const RECENT_GENERATIONS: usize = 10;
+// Speed label, its sparkline, latency label, its sparkline
+const WIDTHS: [u16; 4] = [12, RECENT_GENERATIONS as u16, 7, RECENT_GENERATIONS as u16];
+pub const SPARKLINES_WIDTH: u16 = WIDTHS[0] + WIDTHS[1] + WIDTHS[2] + WIDTHS[3];
+
+#[derive(Default)]
+pub struct RecentGenerations {
+    samples: VecDeque<Generation>,
+}
+
+impl RecentGenerations {
+    pub fn push(&mut self, generation: Generation) {
+        // Servers too old to report timings
+        if generation.total_duration == 0 {
+            return;
+        }
+        if self.samples.len() == RECENT_GENERATIONS {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(generation);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn latest_speed(&self) -> Option<f64> {
+        self.samples.back()?.tokens_per_sec()
+    }
+
+    // The latest speed turns yellow once it falls below half the best one
+    pub fn render(&self, f: &mut Frame, area: Rect, style: Style) {
+        let Some(latest) = self.samples.back() else { return };
+        let speeds: Vec<u64> = self.samples.iter().map(|g| g.tokens_per_sec().map_or(0, |speed| speed.round() as u64)).collect();
+        let latencies: Vec<u64> = self.samples.iter().map(|g| g.total_duration / 1_000_000).collect();
+        let best = speeds.iter().copied().max().unwrap_or(0);
+        let speed = latest.tokens_per_sec().unwrap_or(0.0);
+        let speed_style = if (speed * 2.0) < best as f64 { style.fg(Color::Yellow) } else { style };
+
+        let areas = Layout::horizontal(WIDTHS.map(Constraint::Length)).split(area);
+        // Sparklines leave the cells above short bars untouched
+        f.render_widget(Block::default().style(style), area);
+        f.render_widget(Paragraph::new(format!(" {:>5.1} tok/s", speed)).style(speed_style), areas[0]);
+        f.render_widget(Sparkline::default().data(&speeds).style(style.fg(Color::Cyan)), areas[1]);
+        f.render_widget(Paragraph::new(format!(" {:>5.1}s", latest.total_duration as f64 / 1e9)).style(style), areas[2]);
+        f.render_widget(Sparkline::default().data(&latencies).style(style.fg(Color::Magenta)), areas[3]);
+    }
+}
@@ -0,0 +1,72 @@
+use ratatui::text::Span;
+
+// --- Status Line Format ---
+// The status bar is laid out by `status_format` in the config, e.g.
+// "{mode} | {model} | {host} | {ctx_used}/{ctx_max} | {tps} tok/s". Sections
+// are separated by "|"; a section whose variables have no value right now
+// (no reply yet for {tps}, nothing queued for {queued}) is left out along
+// with its separator. "{{" and "}}" are literal braces, and an unknown
+// variable is shown as written so typos are easy to spot.
+pub const DEFAULT_FORMAT: &str = "{status} | {version} | {queued} | {plugins} | {latency}";
+
+pub const VARIABLES: [&str; 14] = [
+    "status", "mode", "focus", "api", "model", "host", "latency", "version", "queued", "ctx_used", "ctx_max", "tps",
+    "turns", "plugins",
+];
+
+pub fn render(format: &str, value: impl Fn(&str) -> Option<Span<'static>>) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    for section in format.split('|') {
+        let Some(rendered) = render_section(section.trim(), &value) else { continue };
+        if rendered.is_empty() {
+            continue;
+        }
+        if !spans.is_empty() {
+            spans.push(Span::raw(" | "));
+        }
+        spans.extend(rendered);
+    }
+    spans
+}
+
+// None when one of the section's variables has no value
+fn render_section(section: &str, value: &impl Fn(&str) -> Option<Span<'static>>) -> Option<Vec<Span<'static>>> {
+    let mut spans = Vec::new();
+    let mut literal = String::new();
+    let mut chars = section.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.next_if_eq(&'{').is_some() => literal.push('{'),
+            '}' if chars.next_if_eq(&'}').is_some() => literal.push('}'),
+            '{' => {
+                let mut name = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    name.push(c);
+                }
+                if !closed || !VARIABLES.contains(&name.as_str()) {
+                    literal.push('{');
+                    literal.push_str(&name);
+                    if closed {
+                        literal.push('}');
+                    }
+                    continue;
+                }
+                let span = value(&name)?;
+                if !literal.is_empty() {
+                    spans.push(Span::raw(std::mem::take(&mut literal)));
+                }
+                spans.push(span);
+            }
+            c => literal.push(c),
+        }
+    }
+    if !literal.is_empty() {
+        spans.push(Span::raw(literal));
+    }
+    Some(spans)
+}
@@ -0,0 +1,42 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+
+use crate::{endpoint, trace, AppError};
+
+// --- Turn Summaries ('S', or 's' in visual mode) ---
+// The selected turn or text goes to the current model with a summarizing
+// instruction, outside the conversation; the summary is attached to the turn
+// and shown folded to one line until 'z' unfolds it.
+const INSTRUCTION: &str = "Summarize the following text in a few sentences. Reply with the summary only.";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Summary {
+    pub text: String,
+    #[serde(skip)]
+    pub expanded: bool,
+}
+
+#[derive(Deserialize)]
+struct GenerateResponse {
+    response: String,
+}
+
+pub async fn summarize(client: Client, model: &str, text: &str) -> Result<Summary, AppError> {
+    let prompt = format!("{}\n\n{}", INSTRUCTION, text.trim());
+    let (trace, response) = trace::send(
+        client
+            .post(format!("{}/api/generate", endpoint::base()))
+            .json(&endpoint::with_keep_alive(json!({ "model": model, "prompt": prompt, "stream": false })))
+            .timeout(Duration::from_secs(300)),
+    )
+    .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let err_text = response.text().await.unwrap_or_default();
+        return Err(trace.tag(AppError::ApiResponse(format!("Status {} - {}", status, err_text))));
+    }
+    let generated: GenerateResponse = response.json().await.map_err(|e| trace.tag(e.into()))?;
+    Ok(Summary { text: generated.response.trim().to_string(), expanded: false })
+}
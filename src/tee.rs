@@ -0,0 +1,85 @@
+use chrono::Local;
+use serde_json::json;
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::AppError;
+
+// --- Tee Mode ---
+// Mirrors prompts and streamed replies into a log file as they happen, so a
+// crash loses nothing. A .jsonl path gets one JSON record per event; any
+// other path gets a plain-text transcript.
+pub struct Tee {
+    pub path: PathBuf,
+    file: File,
+    jsonl: bool,
+    // Model whose reply is being written, if one is open
+    reply_from: Option<String>,
+}
+
+impl Tee {
+    pub fn open(path: &Path) -> Result<Self, AppError> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let jsonl = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("jsonl"));
+        Ok(Tee { path: path.to_path_buf(), file, jsonl, reply_from: None })
+    }
+
+    // Writes and flushes straight away; nothing is buffered in memory
+    fn write(&mut self, text: &str) -> Result<(), AppError> {
+        self.file.write_all(text.as_bytes())?;
+        self.file.flush()?;
+        Ok(())
+    }
+
+    fn record(&mut self, kind: &str, sender: &str, text: &str) -> Result<(), AppError> {
+        let record = json!({
+            "time": Local::now().to_rfc3339(),
+            "type": kind,
+            "sender": sender,
+            "text": text,
+        });
+        self.write(&format!("{}\n", record))
+    }
+
+    pub fn prompt(&mut self, text: &str) -> Result<(), AppError> {
+        if self.jsonl {
+            self.record("prompt", "You", text)
+        } else {
+            self.write(&format!("You: {}\n\n", text))
+        }
+    }
+
+    // Starts a reply the first time a model's chunk arrives
+    pub fn chunk(&mut self, model: &str, text: &str) -> Result<(), AppError> {
+        if self.reply_from.as_deref() != Some(model) {
+            self.reply_end(None)?;
+            self.reply_from = Some(model.to_string());
+            if self.jsonl {
+                self.record("reply_start", model, "")?;
+            } else {
+                self.write(&format!("{}: ", model))?;
+            }
+        }
+        if self.jsonl {
+            self.record("chunk", model, text)
+        } else {
+            self.write(text)
+        }
+    }
+
+    // Closes the open reply, if any; `note` marks replies that didn't finish normally
+    pub fn reply_end(&mut self, note: Option<&str>) -> Result<(), AppError> {
+        let Some(model) = self.reply_from.take() else { return Ok(()) };
+        if self.jsonl {
+            self.record("reply_end", &model, note.unwrap_or(""))
+        } else {
+            match note {
+                Some(note) => self.write(&format!("\n[{}]\n\n", note)),
+                None => self.write("\n\n"),
+            }
+        }
+    }
+}
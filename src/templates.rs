@@ -0,0 +1,108 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+use serde::Deserialize;
+
+// --- Conversation Templates ---
+// Starter conversations from the config, picked with /new: a system prompt
+// and a few example exchanges for few-shot prompting, so a recurring setup
+// doesn't have to be pasted again every time.
+//
+//   [[templates]]
+//   name = "commit"
+//   description = "Commit messages from diffs"
+//   model = "qwen2.5-coder"
+//   system = "You write one-line commit messages."
+//   messages = [
+//     { role = "user", text = "diff --git a/x b/x ..." },
+//     { role = "assistant", text = "Fix off-by-one in pager" },
+//   ]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Template {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    // Sent first as the "system" message
+    #[serde(default)]
+    pub system: Option<String>,
+    // Selected when the conversation starts, if it is installed
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub messages: Vec<TemplateMessage>,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct TemplateMessage {
+    pub role: TemplateRole,
+    pub text: String,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateRole {
+    User,
+    Assistant,
+}
+
+// The template named `name`, ignoring case
+pub fn find<'a>(templates: &'a [Template], name: &str) -> Option<&'a Template> {
+    templates.iter().find(|template| template.name.eq_ignore_ascii_case(name))
+}
+
+// --- Template Picker ---
+// "Blank" first, then the templates in config order
+pub struct TemplatePicker {
+    entries: Vec<(String, String)>,
+    selected: usize,
+}
+
+pub enum TemplateAction {
+    None,
+    Close,
+    // None for a blank conversation
+    Start(Option<String>),
+}
+
+impl TemplatePicker {
+    pub fn new(templates: &[Template]) -> Self {
+        let mut entries = vec![("Blank".to_string(), "An empty conversation".to_string())];
+        entries.extend(templates.iter().map(|template| (template.name.clone(), template.description.clone())));
+        TemplatePicker { entries, selected: 0 }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> TemplateAction {
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => self.selected = (self.selected + 1) % self.entries.len(),
+            KeyCode::Up | KeyCode::Char('k') => self.selected = (self.selected + self.entries.len() - 1) % self.entries.len(),
+            KeyCode::Enter if self.selected == 0 => return TemplateAction::Start(None),
+            KeyCode::Enter => return TemplateAction::Start(Some(self.entries[self.selected].0.clone())),
+            KeyCode::Esc | KeyCode::Char('q') => return TemplateAction::Close,
+            _ => {}
+        }
+        TemplateAction::None
+    }
+
+    pub fn render(&self, f: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .entries
+            .iter()
+            .map(|(name, description)| {
+                ListItem::new(Line::from(vec![
+                    Span::raw(name.as_str()),
+                    Span::styled(format!("  {}", description), Style::default().fg(Color::DarkGray)),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(" New conversation ")
+                    .title_bottom(" j/k: Select, Enter: Start, Esc: Cancel "),
+            )
+            .highlight_style(Style::default().bg(Color::Blue))
+            .highlight_symbol("> ");
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        f.render_stateful_widget(list, area, &mut state);
+    }
+}
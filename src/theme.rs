@@ -0,0 +1,210 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{buffer::Buffer, prelude::*, widgets::*};
+use serde::Deserialize;
+use std::env;
+
+// --- Themes ---
+// The UI is drawn with the default palette and then recoloured cell by cell,
+// so every view picks up the theme without knowing about it. The colourblind
+// variants use the Okabe-Ito palette, which keeps errors (red) and your own
+// turns (green) apart; monochrome drops colour for bold, italic and reverse.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    #[default]
+    Default,
+    Deuteranopia,
+    Protanopia,
+    HighContrast,
+    Monochrome,
+}
+
+const THEMES: [Theme; 5] = [Theme::Default, Theme::Deuteranopia, Theme::Protanopia, Theme::HighContrast, Theme::Monochrome];
+
+// Okabe-Ito colours
+const ORANGE: Color = Color::Rgb(230, 159, 0);
+const SKY_BLUE: Color = Color::Rgb(86, 180, 233);
+const BLUE: Color = Color::Rgb(0, 114, 178);
+const YELLOW: Color = Color::Rgb(240, 228, 66);
+const VERMILLION: Color = Color::Rgb(213, 94, 0);
+const PURPLE: Color = Color::Rgb(204, 121, 167);
+
+impl Theme {
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Default => "default",
+            Theme::Deuteranopia => "deuteranopia",
+            Theme::Protanopia => "protanopia",
+            Theme::HighContrast => "high-contrast",
+            Theme::Monochrome => "monochrome",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        THEMES.into_iter().find(|theme| theme.name() == name)
+    }
+
+    pub fn names() -> String {
+        THEMES.map(Theme::name).join(", ")
+    }
+
+    // The replacement for one colour, foreground or background
+    fn recolour(self, color: Color) -> Color {
+        match (self, color) {
+            // Reds look dark to protanopes, so errors get a brighter orange
+            (Theme::Deuteranopia, Color::Red) => VERMILLION,
+            (Theme::Protanopia, Color::Red) => ORANGE,
+            (Theme::Deuteranopia | Theme::Protanopia, Color::LightRed) => YELLOW,
+            (Theme::Deuteranopia | Theme::Protanopia, Color::Green) => BLUE,
+            (Theme::Deuteranopia | Theme::Protanopia, Color::LightGreen) => SKY_BLUE,
+            (Theme::Deuteranopia | Theme::Protanopia, Color::Magenta) => PURPLE,
+            (Theme::HighContrast, Color::Red) => Color::LightRed,
+            (Theme::HighContrast, Color::Green) => Color::LightGreen,
+            (Theme::HighContrast, Color::Yellow) => Color::LightYellow,
+            (Theme::HighContrast, Color::Blue) => Color::LightBlue,
+            (Theme::HighContrast, Color::Cyan) => Color::LightCyan,
+            (Theme::HighContrast, Color::Magenta) => Color::LightMagenta,
+            (Theme::HighContrast, Color::DarkGray) => Color::Gray,
+            (Theme::HighContrast, Color::Gray) => Color::White,
+            _ => color,
+        }
+    }
+
+    // What a colour stands for once colour is gone
+    fn monochrome_modifier(color: Color, background: bool) -> Modifier {
+        match color {
+            Color::Reset => Modifier::empty(),
+            // Status bars, selections and highlighted cells
+            _ if background => match color {
+                Color::Red | Color::LightRed => Modifier::REVERSED | Modifier::BOLD,
+                Color::Rgb(..) | Color::Black => Modifier::empty(),
+                _ => Modifier::REVERSED,
+            },
+            Color::Red | Color::LightRed => Modifier::BOLD | Modifier::ITALIC,
+            Color::Green | Color::Yellow | Color::Magenta | Color::LightGreen | Color::LightYellow => Modifier::BOLD,
+            Color::DarkGray | Color::Blue | Color::LightBlue => Modifier::ITALIC,
+            _ => Modifier::empty(),
+        }
+    }
+
+    pub fn apply(self, buffer: &mut Buffer) {
+        if self == Theme::Default {
+            return;
+        }
+        for cell in buffer.content.iter_mut() {
+            if self == Theme::Monochrome {
+                let modifier = Theme::monochrome_modifier(cell.fg, false) | Theme::monochrome_modifier(cell.bg, true);
+                cell.modifier.insert(modifier);
+                cell.fg = Color::Reset;
+                cell.bg = Color::Reset;
+            } else {
+                cell.fg = self.recolour(cell.fg);
+                cell.bg = match (self, cell.bg) {
+                    // Near-black code and streaming backgrounds wash out the text
+                    (Theme::HighContrast, Color::Rgb(..)) => Color::Black,
+                    (_, bg) => self.recolour(bg),
+                };
+            }
+        }
+    }
+}
+
+// --- Colour Depth ---
+// Code blocks and the streaming turn use RGB backgrounds, which the legacy
+// Windows console (conhost) can't show. Windows Terminal sets WT_SESSION;
+// elsewhere terminals without COLORTERM still handle RGB well enough.
+pub fn true_color_supported() -> bool {
+    let colorterm = env::var("COLORTERM").unwrap_or_default();
+    if colorterm == "truecolor" || colorterm == "24bit" {
+        return true;
+    }
+    !cfg!(windows) || env::var_os("WT_SESSION").is_some()
+}
+
+// The basic colours with their usual RGB values
+const BASIC_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+fn nearest_basic(r: u8, g: u8, b: u8) -> Color {
+    let distance = |(br, bg, bb): (u8, u8, u8)| {
+        let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2);
+        d(r, br) + d(g, bg) + d(b, bb)
+    };
+    BASIC_COLORS.iter().min_by_key(|(_, rgb)| distance(*rgb)).map_or(Color::Reset, |(color, _)| *color)
+}
+
+pub fn downgrade_rgb(buffer: &mut Buffer) {
+    for cell in buffer.content.iter_mut() {
+        if let Color::Rgb(r, g, b) = cell.fg {
+            cell.fg = nearest_basic(r, g, b);
+        }
+        if let Color::Rgb(r, g, b) = cell.bg {
+            cell.bg = nearest_basic(r, g, b);
+        }
+    }
+}
+
+// --- Theme Picker ('/theme') ---
+// Moving through the list previews each theme on the whole UI at once
+pub struct ThemePicker {
+    selected: usize,
+    // Put back when the picker is cancelled
+    original: Theme,
+}
+
+pub enum ThemeAction {
+    Preview(Theme),
+    Keep(Theme),
+    Revert(Theme),
+}
+
+impl ThemePicker {
+    pub fn new(current: Theme) -> Self {
+        let selected = THEMES.iter().position(|theme| *theme == current).unwrap_or(0);
+        ThemePicker { selected, original: current }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> ThemeAction {
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => self.selected = (self.selected + 1) % THEMES.len(),
+            KeyCode::Up | KeyCode::Char('k') => self.selected = (self.selected + THEMES.len() - 1) % THEMES.len(),
+            KeyCode::Enter => return ThemeAction::Keep(THEMES[self.selected]),
+            KeyCode::Esc => return ThemeAction::Revert(self.original),
+            _ => {}
+        }
+        ThemeAction::Preview(THEMES[self.selected])
+    }
+
+    pub fn render(&self, f: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = THEMES.iter().map(|theme| ListItem::new(theme.name())).collect();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Yellow))
+                    .title(" Theme ")
+                    .title_bottom(" Up/Down: Preview, Enter: Keep, Esc: Cancel "),
+            )
+            .highlight_style(Style::default().bg(Color::Blue))
+            .highlight_symbol("> ");
+        let mut state = ListState::default();
+        state.select(Some(self.selected));
+        f.render_stateful_widget(list, area, &mut state);
+    }
+}
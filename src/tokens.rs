@@ -0,0 +1,10 @@
+// --- Token Estimates ---
+// A rough heuristic (about four characters per token for English text and
+// code); good enough to warn before a prompt overflows the context window.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+pub fn word_count(text: &str) -> usize {
+    text.split_whitespace().count()
+}
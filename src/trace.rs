@@ -0,0 +1,152 @@
+use chrono::Local;
+use ratatui::{prelude::*, widgets::*};
+use reqwest::{header::HeaderValue, Method, RequestBuilder, Response, Url};
+use std::{
+    collections::VecDeque,
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    sync::{Mutex, OnceLock},
+    time::Instant,
+};
+use uuid::Uuid;
+
+use crate::AppError;
+
+// --- Request Tracing IDs ---
+// Every API request gets a random UUID, sent to the server as X-Request-Id,
+// appended to <data dir>/ollama-tui/requests.log and listed in the F12 debug
+// overlay. Errors shown in the UI end with "[request <id>]", the first eight
+// hex digits of the UUID, so a bug report can be matched to its log lines.
+const HEADER: &str = "x-request-id";
+// Requests listed in the debug overlay
+const RECENT: usize = 8;
+// requests.log is moved to requests.log.old once it grows past this
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+
+struct Entry {
+    id: Uuid,
+    request: String,
+    outcome: String,
+}
+
+struct TraceLog {
+    // None when there is no data directory or the file can't be opened
+    file: Option<File>,
+    recent: VecDeque<Entry>,
+}
+
+impl TraceLog {
+    fn write(&mut self, id: Uuid, text: &str) {
+        if let Some(file) = self.file.as_mut() {
+            let _ = writeln!(file, "{} {} {}", Local::now().to_rfc3339(), id, text);
+        }
+    }
+}
+
+fn open_log() -> Option<File> {
+    let dir = dirs::data_dir()?.join("ollama-tui");
+    fs::create_dir_all(&dir).ok()?;
+    let path = dir.join("requests.log");
+    if fs::metadata(&path).is_ok_and(|metadata| metadata.len() > MAX_LOG_BYTES) {
+        let _ = fs::rename(&path, dir.join("requests.log.old"));
+    }
+    OpenOptions::new().create(true).append(true).open(path).ok()
+}
+
+// Shared by every task that talks to a server; opened on the first request
+fn log() -> &'static Mutex<TraceLog> {
+    static LOG: OnceLock<Mutex<TraceLog>> = OnceLock::new();
+    LOG.get_or_init(|| Mutex::new(TraceLog { file: open_log(), recent: VecDeque::new() }))
+}
+
+// One request, from sending it until its outcome is known
+pub struct Trace {
+    id: Uuid,
+    started: Instant,
+}
+
+impl Trace {
+    fn start(method: &Method, url: &Url) -> Self {
+        let trace = Trace { id: Uuid::new_v4(), started: Instant::now() };
+        let request = format!("{} {}", method, url);
+        let mut log = log().lock().unwrap_or_else(|e| e.into_inner());
+        log.write(trace.id, &request);
+        log.recent.push_front(Entry { id: trace.id, request, outcome: "...".to_string() });
+        log.recent.truncate(RECENT);
+        trace
+    }
+
+    // Logs how the request went and updates its overlay entry
+    pub fn record(&self, outcome: &str) {
+        let outcome = format!("{} ({} ms)", outcome, self.started.elapsed().as_millis());
+        let mut log = log().lock().unwrap_or_else(|e| e.into_inner());
+        log.write(self.id, &outcome);
+        if let Some(entry) = log.recent.iter_mut().find(|entry| entry.id == self.id) {
+            entry.outcome = outcome;
+        }
+    }
+
+    // Records a failure and ties it to this request
+    pub fn tag(&self, error: AppError) -> AppError {
+        self.record(&format!("failed: {}", error));
+        AppError::Traced { id: self.to_string(), source: Box::new(error) }
+    }
+
+    // Same for failures that travel as plain messages
+    pub fn tag_message(&self, message: String) -> String {
+        self.record(&format!("failed: {}", message));
+        format!("{} [request {}]", message, self)
+    }
+}
+
+// The short form shown in the UI
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let id = self.id.simple().to_string();
+        f.write_str(&id[..8])
+    }
+}
+
+// Sends `request` with a new ID; a failure to get any response comes back tagged
+pub async fn send(request: RequestBuilder) -> Result<(Trace, Response), AppError> {
+    let (client, request) = request.build_split();
+    let mut request = request?;
+    let trace = Trace::start(request.method(), request.url());
+    if let Ok(value) = HeaderValue::from_str(&trace.id.to_string()) {
+        request.headers_mut().insert(HEADER, value);
+    }
+    match client.execute(request).await {
+        Ok(response) => {
+            trace.record(&response.status().to_string());
+            Ok((trace, response))
+        }
+        Err(e) => Err(trace.tag(AppError::ApiRequest(e))),
+    }
+}
+
+// The latest requests under the FPS readout, newest first
+pub fn render(f: &mut Frame, area: Rect) {
+    let log = log().lock().unwrap_or_else(|e| e.into_inner());
+    if log.recent.is_empty() {
+        return;
+    }
+    let lines: Vec<Line> = log
+        .recent
+        .iter()
+        .map(|entry| {
+            let id = entry.id.simple().to_string();
+            Line::from(vec![
+                Span::styled(format!("{} ", &id[..8]), Style::default().fg(Color::Cyan)),
+                Span::raw(format!("{} {}", entry.request, entry.outcome)),
+            ])
+        })
+        .collect();
+    let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 2;
+    let width = width.min(area.width);
+    let height = (lines.len() as u16 + 2).min(area.height.saturating_sub(1));
+    let rect = Rect::new(area.right() - width, area.y + 1.min(area.height), width, height);
+    let block = Block::default().borders(Borders::ALL).title(" Requests (F12) ");
+    f.render_widget(Clear, rect);
+    f.render_widget(Paragraph::new(lines).block(block).style(Style::default().bg(Color::Black)), rect);
+}
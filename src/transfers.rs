@@ -0,0 +1,348 @@
+use futures::StreamExt;
+use ratatui::{prelude::*, widgets::*};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+use tokio::sync::mpsc;
+
+use crate::{
+    endpoint,
+    health::format_bytes,
+    jobs::JobId,
+    ndjson::{NdjsonDecoder, StreamDecoding},
+    trace, AppError, AppEvent,
+};
+
+// --- Model Pulls ('/pull') ---
+// Pulls wait in a queue and run a few at a time, like a download manager.
+// Progress comes from /api/pull's stream, one object per layer update.
+// Unfinished pulls are kept in <data dir>/ollama-tui/pulls.json so they can be
+// resumed after a crash or quit; Ollama keeps the partial layers and carries
+// on where it stopped.
+const SPEED_WINDOW: Duration = Duration::from_secs(5);
+// Pulls failing digest verification are queued again this many times
+const MAX_DIGEST_RETRIES: u32 = 2;
+const BAR_WIDTH: usize = 20;
+
+#[derive(Deserialize, Debug)]
+pub struct PullProgress {
+    pub status: String,
+    pub digest: Option<String>,
+    pub total: Option<u64>,
+    pub completed: Option<u64>,
+}
+
+#[derive(Debug, PartialEq)]
+enum PullState {
+    Queued,
+    Active,
+    // Whether the server reported verifying the layer digests
+    Done { verified: bool },
+    Failed(String),
+    Cancelled,
+}
+
+struct Transfer {
+    model: String,
+    state: PullState,
+    // Latest status line from the server, e.g. "pulling 6a0746a1ec1a"
+    status: String,
+    // (completed, total) bytes of each layer
+    layers: HashMap<String, (u64, u64)>,
+    // Recent (time, bytes done) readings for the speed
+    samples: VecDeque<(Instant, u64)>,
+    job: Option<JobId>,
+    verifying: bool,
+    digest_retries: u32,
+}
+
+impl Transfer {
+    fn bytes(&self) -> (u64, u64) {
+        self.layers.values().fold((0, 0), |(done, total), (d, t)| (done + d, total + t))
+    }
+
+    // Bytes per second over the last few seconds
+    fn speed(&self) -> Option<f64> {
+        let (first, last) = (self.samples.front()?, self.samples.back()?);
+        let elapsed = last.0.duration_since(first.0).as_secs_f64();
+        (elapsed > 0.0).then(|| last.1.saturating_sub(first.1) as f64 / elapsed)
+    }
+}
+
+pub struct Transfers {
+    items: Vec<Transfer>,
+    selected: usize,
+    max_parallel: usize,
+}
+
+impl Transfers {
+    pub fn new(max_parallel: usize) -> Self {
+        Transfers { items: Vec::new(), selected: 0, max_parallel: max_parallel.max(1) }
+    }
+
+    // Pulls already running keep going when the limit drops
+    pub fn set_max_parallel(&mut self, max_parallel: usize) {
+        self.max_parallel = max_parallel.max(1);
+    }
+
+    // False if the model is already queued or downloading
+    pub fn queue(&mut self, model: &str) -> bool {
+        let pending = self
+            .items
+            .iter()
+            .any(|item| item.model == model && matches!(item.state, PullState::Queued | PullState::Active));
+        if pending {
+            return false;
+        }
+        self.items.push(Transfer {
+            model: model.to_string(),
+            state: PullState::Queued,
+            status: "queued".to_string(),
+            layers: HashMap::new(),
+            samples: VecDeque::new(),
+            job: None,
+            verifying: false,
+            digest_retries: 0,
+        });
+        true
+    }
+
+    // Models still queued or downloading, as saved for the next launch
+    fn pending(&self) -> Vec<&str> {
+        self.items
+            .iter()
+            .filter(|item| matches!(item.state, PullState::Queued | PullState::Active))
+            .map(|item| item.model.as_str())
+            .collect()
+    }
+
+    pub fn save_pending(&self) -> Result<(), AppError> {
+        let path = pending_path().ok_or_else(|| AppError::Session("No data directory available".to_string()))?;
+        let pending = self.pending();
+        if pending.is_empty() {
+            return match fs::remove_file(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(AppError::Io(e)),
+                _ => Ok(()),
+            };
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(&pending)?)?;
+        Ok(())
+    }
+
+    // Marks as many queued pulls active as there are free slots and returns them
+    pub fn start_ready(&mut self) -> Vec<String> {
+        let active = self.items.iter().filter(|item| item.state == PullState::Active).count();
+        let mut started = Vec::new();
+        for item in self.items.iter_mut().filter(|item| item.state == PullState::Queued) {
+            if active + started.len() >= self.max_parallel {
+                break;
+            }
+            item.state = PullState::Active;
+            item.status = "starting".to_string();
+            started.push(item.model.clone());
+        }
+        started
+    }
+
+    fn active_mut(&mut self, model: &str) -> Option<&mut Transfer> {
+        self.items.iter_mut().find(|item| item.model == model && item.state == PullState::Active)
+    }
+
+    pub fn set_job(&mut self, model: &str, job: JobId) {
+        if let Some(item) = self.active_mut(model) {
+            item.job = Some(job);
+        }
+    }
+
+    // Updates the pull and returns its job with a line for the jobs popup
+    pub fn progress(&mut self, model: &str, progress: PullProgress) -> Option<(JobId, String)> {
+        let item = self.active_mut(model)?;
+        // "verifying sha256 digest" comes once every layer is downloaded
+        if progress.status.starts_with("verifying") {
+            item.verifying = true;
+        }
+        item.status = progress.status;
+        if let (Some(digest), Some(total)) = (progress.digest, progress.total) {
+            item.layers.insert(digest, (progress.completed.unwrap_or(0), total));
+            let now = Instant::now();
+            let (done, _) = item.bytes();
+            item.samples.push_back((now, done));
+            while item.samples.front().is_some_and(|(at, _)| now.duration_since(*at) > SPEED_WINDOW) {
+                item.samples.pop_front();
+            }
+        }
+        let (done, total) = item.bytes();
+        let line = match (done * 100).checked_div(total) {
+            Some(percent) => format!("{}% of {}", percent, format_bytes(total)),
+            None => item.status.clone(),
+        };
+        Some((item.job?, line))
+    }
+
+    // A pull whose job was cancelled from the jobs popup, or crashed, before
+    // it could report finishing; returns its model
+    pub fn job_ended(&mut self, job: JobId, failure: Option<String>) -> Option<String> {
+        let item = self.items.iter_mut().find(|item| item.state == PullState::Active && item.job == Some(job))?;
+        item.job = None;
+        item.samples.clear();
+        item.state = match failure {
+            Some(e) => PullState::Failed(e),
+            None => PullState::Cancelled,
+        };
+        Some(item.model.clone())
+    }
+
+    // True when a failed digest check put the pull back in the queue
+    pub fn finish(&mut self, model: &str, result: Result<(), String>) -> bool {
+        let Some(item) = self.active_mut(model) else { return false };
+        item.job = None;
+        item.samples.clear();
+        match result {
+            Ok(()) => item.state = PullState::Done { verified: item.verifying },
+            Err(e) if e.contains("digest") && item.digest_retries < MAX_DIGEST_RETRIES => {
+                item.digest_retries += 1;
+                item.verifying = false;
+                item.layers.clear();
+                item.state = PullState::Queued;
+                item.status = format!("digest mismatch, retry {} of {}", item.digest_retries, MAX_DIGEST_RETRIES);
+                return true;
+            }
+            Err(e) => item.state = PullState::Failed(e),
+        }
+        false
+    }
+
+    // Stops the selected pull, or drops it from the queue; returns its model
+    // and the job to abort
+    pub fn cancel_selected(&mut self) -> Option<(String, Option<JobId>)> {
+        let item = self.items.get_mut(self.selected)?;
+        if !matches!(item.state, PullState::Queued | PullState::Active) {
+            return None;
+        }
+        item.state = PullState::Cancelled;
+        item.samples.clear();
+        Some((item.model.clone(), item.job.take()))
+    }
+
+    pub fn select(&mut self, delta: isize) {
+        if !self.items.is_empty() {
+            self.selected = self.selected.saturating_add_signed(delta).min(self.items.len() - 1);
+        }
+    }
+}
+
+fn pending_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("ollama-tui").join("pulls.json"))
+}
+
+// Pulls left unfinished by the last run
+pub fn load_pending() -> Result<Vec<String>, AppError> {
+    let Some(path) = pending_path() else {
+        return Ok(Vec::new());
+    };
+    match fs::read_to_string(&path) {
+        Ok(contents) => Ok(serde_json::from_str(&contents)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(AppError::Io(e)),
+    }
+}
+
+// Streams one pull's progress as PullProgress events, then PullFinished
+pub async fn pull(client: Client, decoding: StreamDecoding, model: String, event_sender: mpsc::Sender<AppEvent>) {
+    let result = async {
+        let (trace, response) = trace::send(
+            client.post(format!("{}/api/pull", endpoint::base())).json(&json!({ "model": model, "stream": true })),
+        )
+        .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(trace.tag(AppError::ApiResponse(format!("Status {} - {}", status, body))));
+        }
+
+        let mut stream = response.bytes_stream();
+        let mut decoder = NdjsonDecoder::<PullProgress>::new(decoding);
+        while let Some(bytes) = stream.next().await {
+            for progress in decoder.push(&bytes.map_err(|e| trace.tag(e.into()))?) {
+                let progress = progress.map_err(|e| trace.tag(AppError::ApiResponse(e)))?;
+                let success = progress.status == "success";
+                let _ = event_sender.send(AppEvent::PullProgress { model: model.clone(), progress }).await;
+                if success {
+                    trace.record("done");
+                    return Ok(());
+                }
+            }
+        }
+        Err(trace.tag(AppError::Interrupted("Connection closed before the pull finished".to_string())))
+    }
+    .await;
+    let result = result.map_err(|e| e.to_string());
+    let _ = event_sender.send(AppEvent::PullFinished { model, result }).await;
+}
+
+fn progress_bar(done: u64, total: u64) -> String {
+    let filled = if total == 0 { 0 } else { (done as f64 / total as f64 * BAR_WIDTH as f64) as usize };
+    let filled = filled.min(BAR_WIDTH);
+    format!("[{}{}]", "#".repeat(filled), ".".repeat(BAR_WIDTH - filled))
+}
+
+pub fn render(f: &mut Frame, area: Rect, transfers: &Transfers) {
+    let title = format!(
+        " Transfers, up to {} at a time (j/k: Select, c: Cancel, Tab: Next view) ",
+        transfers.max_parallel
+    );
+    let block = Block::default().borders(Borders::ALL).title(title);
+    if transfers.items.is_empty() {
+        let help = "No pulls yet.\n\n/pull <model> [model...]   download models from the registry";
+        f.render_widget(Paragraph::new(help).block(block), area);
+        return;
+    }
+
+    let items: Vec<ListItem> = transfers
+        .items
+        .iter()
+        .map(|item| {
+            let (done, total) = item.bytes();
+            let (detail, style) = match &item.state {
+                PullState::Queued => (item.status.clone(), Style::default().fg(Color::DarkGray)),
+                PullState::Active => {
+                    let mut detail = format!("{} / {}", format_bytes(done), format_bytes(total));
+                    if let Some(speed) = item.speed() {
+                        detail.push_str(&format!("  {}/s", format_bytes(speed as u64)));
+                    }
+                    detail.push_str(&format!("  {}", item.status));
+                    (detail, Style::default().fg(Color::Yellow))
+                }
+                PullState::Done { verified } => {
+                    let check = if *verified { ", digest verified" } else { "" };
+                    (format!("done, {}{}", format_bytes(total), check), Style::default().fg(Color::Green))
+                }
+                PullState::Failed(e) => (format!("failed: {}", e), Style::default().fg(Color::Red)),
+                PullState::Cancelled => ("cancelled".to_string(), Style::default().fg(Color::DarkGray)),
+            };
+            let percent = (done * 100).checked_div(total).unwrap_or(0);
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:<30} ", item.model), Style::default().add_modifier(Modifier::BOLD)),
+                Span::styled(format!("{} {:>3}% ", progress_bar(done, total), percent), style),
+                Span::styled(detail, style),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().bg(Color::Blue))
+        .highlight_symbol("> ");
+    let mut state = ListState::default();
+    state.select(Some(transfers.selected));
+    f.render_stateful_widget(list, area, &mut state);
+}
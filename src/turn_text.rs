@@ -0,0 +1,259 @@
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::ops::Deref;
+
+use crate::{render_markdown, Heading, RenderedMarkdown};
+
+// --- Chunked Turn Text ---
+// A reply is split into finalized blocks, whose rendered lines are cached, and
+// a hot tail that is still growing. Only the tail is re-parsed each frame, so
+// very long streamed replies stay responsive.
+// A block closes at a blank line followed by an unindented line outside a
+// code fence; markdown never carries state across such a boundary, except
+// for footnote and link reference definitions, so text that has any is
+// always rendered whole.
+#[derive(Clone, Default)]
+pub struct TurnText {
+    text: String,
+    // text[..finalized] is rendered into `rendered`
+    finalized: usize,
+    rendered: Vec<Line<'static>>,
+    rendered_headings: Vec<Heading>,
+    // Scan state for the complete lines after `finalized`
+    scanned: usize,
+    in_fence: bool,
+    after_blank: bool,
+    has_definitions: bool,
+}
+
+impl TurnText {
+    pub fn push_str(&mut self, chunk: &str) {
+        self.text.push_str(chunk);
+        self.finalize_blocks();
+    }
+
+    fn finalize_blocks(&mut self) {
+        let mut boundary = None;
+        while let Some(len) = self.text[self.scanned..].find('\n') {
+            let line = &self.text[self.scanned..self.scanned + len];
+            if !self.in_fence && self.after_blank && !line.is_empty() && !line.starts_with(char::is_whitespace) {
+                boundary = Some(self.scanned);
+            }
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                self.in_fence = !self.in_fence;
+            } else if !self.in_fence && trimmed.starts_with('[') && trimmed.contains("]:") {
+                self.has_definitions = true;
+            }
+            self.after_blank = line.trim().is_empty();
+            self.scanned += len + 1;
+        }
+        if let Some(end) = boundary {
+            let block = render_markdown(&self.text[self.finalized..end]);
+            let first_line = self.rendered.len();
+            self.rendered_headings.extend(
+                block.headings.into_iter().map(|heading| Heading { line: first_line + heading.line, ..heading }),
+            );
+            self.rendered.extend(block.lines.into_iter().map(into_owned));
+            self.finalized = end;
+        }
+    }
+
+    // Cached lines for the finalized blocks plus a fresh render of the tail
+    pub(crate) fn render(&self) -> RenderedMarkdown<'_> {
+        self.render_with(false)
+    }
+
+    // The same, with the tail's unfinished constructs shown verbatim
+    pub(crate) fn render_streaming(&self) -> RenderedMarkdown<'_> {
+        self.render_with(true)
+    }
+
+    fn render_with(&self, streaming: bool) -> RenderedMarkdown<'_> {
+        if self.has_definitions {
+            return render_stable(&self.text, streaming);
+        }
+        let tail = render_stable(&self.text[self.finalized..], streaming);
+        let mut lines: Vec<Line<'_>> = self.rendered.iter().map(borrow_line).collect();
+        let mut headings = self.rendered_headings.clone();
+        headings.extend(tail.headings.into_iter().map(|heading| Heading { line: lines.len() + heading.line, ..heading }));
+        lines.extend(tail.lines);
+        RenderedMarkdown { lines, headings }
+    }
+}
+
+// --- Streaming Stabilization ---
+// Mid-stream, a construct whose closing half hasn't arrived yet (a ``` fence,
+// `code`, **, *, ~~ or a link) would flash the wrong style until it closes.
+// From the line that opens it, the text is shown as typed instead, and is
+// rendered as markdown once it is complete.
+const CODE: usize = 0;
+const STRONG: usize = 1;
+const EMPHASIS: usize = 2;
+const STRIKE: usize = 3;
+const LINK: usize = 4;
+
+fn render_stable(text: &str, streaming: bool) -> RenderedMarkdown<'_> {
+    if !streaming {
+        return render_markdown(text);
+    }
+    let (settled, unsettled) = text.split_at(settled_len(text));
+    let mut rendered = render_markdown(settled);
+    if unsettled.is_empty() {
+        return rendered;
+    }
+    if settled.is_empty() {
+        // render_markdown gives an empty line for empty text
+        rendered.lines.clear();
+    }
+    // Lines of one paragraph are joined with spaces, as rendered markdown does
+    let style = Style::default().fg(Color::Cyan);
+    let in_fence = is_fence(unsettled);
+    let mut after_blank = settled.is_empty() || settled.ends_with("\n\n");
+    for line in unsettled.lines() {
+        match rendered.lines.last_mut() {
+            Some(last) if !in_fence && !after_blank && !starts_block(line) => {
+                last.spans.push(Span::styled(" ", style));
+                last.spans.push(Span::styled(line.trim_start(), style));
+            }
+            _ => rendered.lines.push(Line::styled(line, style)),
+        }
+        after_blank = line.trim().is_empty();
+    }
+    rendered
+}
+
+// Byte offset where the unsettled text starts, or the text's length
+fn settled_len(text: &str) -> usize {
+    let mut fence_start = None;
+    let mut open = [None; 5];
+    let mut line_start = 0;
+    let mut end = text.len();
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end_matches('\n');
+        if is_fence(content) {
+            fence_start = if fence_start.is_some() { None } else { Some(line_start) };
+            open = [None; 5];
+        } else if fence_start.is_some() {
+            // Code; nothing to scan
+        } else if content.trim().is_empty() {
+            // Inline markup never crosses a blank line
+            open = [None; 5];
+        } else if !line.ends_with('\n') && content.trim().chars().all(|c| c == '-' || c == '=') {
+            // A lone "-" is a bullet, a rule or a heading underline to be
+            end = line_start;
+        } else {
+            scan_inline(content, line_start, &mut open);
+        }
+        line_start += line.len();
+    }
+    fence_start.or(open.iter().flatten().min().copied()).unwrap_or(end)
+}
+
+// Tracks which inline delimiters are open, and from which line
+fn scan_inline(line: &str, line_start: usize, open: &mut [Option<usize>; 5]) {
+    let toggle = |open: &mut [Option<usize>; 5], slot: usize| {
+        open[slot] = if open[slot].is_some() { None } else { Some(line_start) };
+    };
+    let bytes = line.as_bytes();
+    let indent = line.len() - line.trim_start().len();
+    // A bullet's "* " is not emphasis
+    let mut i = if line[indent..].starts_with("* ") { indent + 2 } else { 0 };
+    while i < bytes.len() {
+        let next = bytes.get(i + 1).copied();
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'`' => {
+                toggle(open, CODE);
+                while bytes.get(i + 1) == Some(&b'`') {
+                    i += 1;
+                }
+            }
+            _ if open[CODE].is_some() => {}
+            b'*' if next == Some(b'*') => {
+                toggle(open, STRONG);
+                i += 1;
+            }
+            // A "*" with spaces on both sides is just an asterisk
+            b'*' if !((i == 0 || bytes[i - 1] == b' ') && next.is_none_or(|b| b == b' ')) => toggle(open, EMPHASIS),
+            b'~' if next == Some(b'~') => {
+                toggle(open, STRIKE);
+                i += 1;
+            }
+            b'[' if open[LINK].is_none() => open[LINK] = Some(line_start),
+            // "](" keeps the link open until its ")"
+            b']' if next != Some(b'(') => open[LINK] = None,
+            b')' => open[LINK] = None,
+            _ => {}
+        }
+        i += 1;
+    }
+}
+
+fn is_fence(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("```") || trimmed.starts_with("~~~")
+}
+
+// Lines that begin a block of their own instead of continuing a paragraph
+fn starts_block(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    let numbered = trimmed.trim_start_matches(|c: char| c.is_ascii_digit());
+    ["#", ">", "- ", "* ", "+ ", "|"].iter().any(|marker| trimmed.starts_with(marker))
+        || (numbered.len() < trimmed.len() && (numbered.starts_with(". ") || numbered.starts_with(") ")))
+}
+
+fn into_owned(line: Line<'_>) -> Line<'static> {
+    let spans: Vec<Span<'static>> =
+        line.spans.into_iter().map(|span| Span::styled(span.content.into_owned(), span.style)).collect();
+    let mut owned = Line::from(spans);
+    owned.style = line.style;
+    owned.alignment = line.alignment;
+    owned
+}
+
+fn borrow_line<'a>(line: &'a Line<'static>) -> Line<'a> {
+    let spans: Vec<Span<'a>> = line.spans.iter().map(|span| Span::styled(span.content.as_ref(), span.style)).collect();
+    let mut borrowed = Line::from(spans);
+    borrowed.style = line.style;
+    borrowed.alignment = line.alignment;
+    borrowed
+}
+
+impl Deref for TurnText {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl From<String> for TurnText {
+    fn from(text: String) -> Self {
+        let mut turn_text = TurnText::default();
+        turn_text.push_str(&text);
+        turn_text
+    }
+}
+
+impl From<TurnText> for String {
+    fn from(turn_text: TurnText) -> Self {
+        turn_text.text
+    }
+}
+
+// Saved sessions keep the text as a plain string
+impl Serialize for TurnText {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.text)
+    }
+}
+
+impl<'de> Deserialize<'de> for TurnText {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(TurnText::from)
+    }
+}
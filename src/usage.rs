@@ -0,0 +1,102 @@
+use chrono::{DateTime, Local};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{collections::HashMap, fs, path::PathBuf, time::Duration};
+
+use crate::{endpoint, trace, AppError};
+
+// --- Model Usage and Cleanup ---
+// When each installed model was first seen and last prompted, kept in
+// <data dir>/ollama-tui/usage.json. Ollama doesn't track this itself, so a
+// model counts as idle from the day this app first saw it.
+#[derive(Serialize, Deserialize, Clone, Copy)]
+struct UsageRecord {
+    first_seen: DateTime<Local>,
+    last_used: Option<DateTime<Local>>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct ModelUsage {
+    models: HashMap<String, UsageRecord>,
+}
+
+fn usage_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("ollama-tui").join("usage.json"))
+}
+
+impl ModelUsage {
+    // A missing file means nothing has been tracked yet
+    pub fn load() -> Result<Self, AppError> {
+        let Some(path) = usage_path() else {
+            return Ok(ModelUsage::default());
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(ModelUsage::default()),
+            Err(e) => Err(AppError::Io(e)),
+        }
+    }
+
+    fn save(&self) -> Result<(), AppError> {
+        let path = usage_path().ok_or_else(|| AppError::Session("No data directory available".to_string()))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    // Starts tracking installed models not seen before
+    pub fn seen(&mut self, models: &[String]) -> Result<(), AppError> {
+        let now = Local::now();
+        let mut changed = false;
+        for model in models {
+            if !self.models.contains_key(model) {
+                self.models.insert(model.clone(), UsageRecord { first_seen: now, last_used: None });
+                changed = true;
+            }
+        }
+        if changed {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    pub fn used(&mut self, model: &str) -> Result<(), AppError> {
+        let now = Local::now();
+        let record = self.models.entry(model.to_string()).or_insert(UsageRecord { first_seen: now, last_used: None });
+        record.last_used = Some(now);
+        self.save()
+    }
+
+    pub fn forget(&mut self, model: &str) -> Result<(), AppError> {
+        if self.models.remove(model).is_some() {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    // Whole days since the model was last used, or first seen if never
+    pub fn idle_days(&self, model: &str) -> Option<i64> {
+        let record = self.models.get(model)?;
+        let since = record.last_used.unwrap_or(record.first_seen);
+        Some((Local::now() - since).num_days())
+    }
+}
+
+pub async fn delete_model(client: Client, model: &str) -> Result<(), AppError> {
+    let (trace, response) = trace::send(
+        client
+            .delete(format!("{}/api/delete", endpoint::base()))
+            .json(&json!({ "model": model }))
+            .timeout(Duration::from_secs(30)),
+    )
+    .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(trace.tag(AppError::ApiResponse(format!("Status {} - {}", status, body))));
+    }
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+use std::{process::Stdio, time::Duration};
+use tokio::io::AsyncWriteExt;
+
+use crate::{formats, hooks, AppError};
+
+// --- Response Validation ---
+// Every finished reply goes through the configured validators in order. The
+// first one it fails sends the prompt back with the reason appended, until
+// the retry budget runs out; only then do post-processing steps run.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Validator {
+    // The reply must contain a match, or with `reject` must not
+    Regex {
+        pattern: String,
+        #[serde(default)]
+        reject: bool,
+    },
+    // The reply must parse as JSON, code fences aside
+    Json,
+    // Gets the reply on stdin; a non-zero exit fails it, with its output as the reason
+    Command { command: String },
+}
+
+// Ok(None) when the reply passes, Ok(Some(reason)) when it fails; an error
+// means a validator itself is broken, e.g. a pattern that doesn't compile
+pub async fn check(validators: Vec<Validator>, reply: String, timeout: Duration) -> Result<Option<String>, AppError> {
+    for validator in validators {
+        let failure = match validator {
+            Validator::Regex { pattern, reject } => {
+                let regex = Regex::new(&pattern).map_err(|e| AppError::Config(format!("Bad validator pattern `{}`: {}", pattern, e)))?;
+                match (regex.is_match(&reply), reject) {
+                    (false, false) => Some(format!("it doesn't match the pattern `{}`", pattern)),
+                    (true, true) => Some(format!("it matches the forbidden pattern `{}`", pattern)),
+                    _ => None,
+                }
+            }
+            Validator::Json => serde_json::from_str::<Value>(formats::strip_fence(reply.trim()))
+                .err()
+                .map(|e| format!("it is not valid JSON ({})", e)),
+            Validator::Command { command } => run_command(&command, &reply, timeout).await?,
+        };
+        if failure.is_some() {
+            return Ok(failure);
+        }
+    }
+    Ok(None)
+}
+
+async fn run_command(command: &str, reply: &str, timeout: Duration) -> Result<Option<String>, AppError> {
+    let mut child = hooks::shell_command(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| AppError::Hook(format!("Failed to start validator `{}`: {}", command, e)))?;
+
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let reply = reply.to_string();
+    let output = async move {
+        let _ = stdin.write_all(reply.as_bytes()).await;
+        drop(stdin);
+        child.wait_with_output().await
+    };
+    let output = tokio::time::timeout(timeout, output)
+        .await
+        .map_err(|_| AppError::Hook(format!("Validator `{}` timed out after {}s", command, timeout.as_secs())))??;
+    if output.status.success() {
+        return Ok(None);
+    }
+    // Compilers and linters tend to explain themselves on stderr
+    let explanation = [output.stderr, output.stdout]
+        .iter()
+        .map(|bytes| String::from_utf8_lossy(bytes).trim().to_string())
+        .find(|text| !text.is_empty())
+        .unwrap_or_else(|| output.status.to_string());
+    Ok(Some(format!("`{}` failed:\n{}", command, explanation)))
+}
+
+// The prompt again, with why the last reply was turned down
+pub fn retry_prompt(prompt: &str, reason: &str) -> String {
+    format!("{}\n\nYour previous reply was rejected because {}. Answer again, fixing that.", prompt, reason)
+}
@@ -0,0 +1,73 @@
+// --- Prompt Variables ---
+// Placeholders in the draft, filled in when it is sent (Ctrl+P previews the
+// result first):
+//   {{date}}           today, as 2024-05-01
+//   {{cwd}}            the working directory
+//   {{clipboard}}      the system clipboard's text
+//   {{file:path}}      a file, fenced like /file attaches it
+//   {{last_response}}  the latest reply in the conversation
+// Anything else in double braces is sent as written.
+pub enum Variable<'a> {
+    Date,
+    Cwd,
+    Clipboard,
+    File(&'a str),
+    LastResponse,
+}
+
+impl<'a> Variable<'a> {
+    fn parse(name: &'a str) -> Option<Variable<'a>> {
+        match name.trim() {
+            "date" => Some(Variable::Date),
+            "cwd" => Some(Variable::Cwd),
+            "clipboard" => Some(Variable::Clipboard),
+            "last_response" => Some(Variable::LastResponse),
+            name => name.strip_prefix("file:").map(str::trim).filter(|path| !path.is_empty()).map(Variable::File),
+        }
+    }
+}
+
+// Whether `text` has anything to expand
+pub fn has_variables(text: &str) -> bool {
+    placeholders(text).next().is_some()
+}
+
+// `text` with every variable replaced by `value`, and how many there were.
+// The first variable that can't be filled in is the error.
+pub fn expand(text: &str, mut value: impl FnMut(Variable) -> Result<String, String>) -> Result<(String, usize), String> {
+    let mut expanded = String::with_capacity(text.len());
+    let mut count = 0;
+    let mut copied = 0;
+    for (start, end, variable) in placeholders(text) {
+        let placeholder = &text[start..end];
+        let replacement = value(variable).map_err(|e| format!("{}: {}", placeholder, e))?;
+        expanded.push_str(&text[copied..start]);
+        expanded.push_str(&replacement);
+        copied = end;
+        count += 1;
+    }
+    expanded.push_str(&text[copied..]);
+    Ok((expanded, count))
+}
+
+// Byte range and variable of each known placeholder
+fn placeholders(text: &str) -> impl Iterator<Item = (usize, usize, Variable<'_>)> {
+    let mut position = 0;
+    std::iter::from_fn(move || {
+        while let Some(open) = text[position..].find("{{").map(|offset| position + offset) {
+            let Some(close) = text[open + 2..].find("}}").map(|offset| open + 2 + offset) else {
+                position = text.len();
+                return None;
+            };
+            match Variable::parse(&text[open + 2..close]) {
+                Some(variable) => {
+                    position = close + 2;
+                    return Some((open, close + 2, variable));
+                }
+                // "{{{date}}" still finds the variable one brace on
+                None => position = open + 1,
+            }
+        }
+        None
+    })
+}
@@ -0,0 +1,135 @@
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use crate::{endpoint, trace, AppError};
+
+// --- Server Version and Capabilities ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct OllamaVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl OllamaVersion {
+    const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        OllamaVersion { major, minor, patch }
+    }
+
+    // Accepts "0.5.7", "v0.5.7" and pre-releases like "0.6.0-rc1"
+    pub fn parse(text: &str) -> Option<Self> {
+        let core = text.trim().trim_start_matches('v');
+        let core = core.split(['-', '+']).next()?;
+        let mut parts = core.split('.').map(|part| part.parse::<u32>());
+        let major = parts.next()?.ok()?;
+        let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+        let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+        Some(OllamaVersion::new(major, minor, patch))
+    }
+}
+
+impl fmt::Display for OllamaVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+// Features that only newer servers provide
+#[derive(Debug, Clone, Copy)]
+pub enum Feature {
+    ChatEndpoint,
+    Tools,
+    // format: "json"
+    JsonMode,
+    // format: a JSON schema
+    StructuredOutputs,
+    // The request's think field
+    Thinking,
+}
+
+impl Feature {
+    // What a request's format field needs
+    pub fn for_format(format: &Value) -> Feature {
+        if format.is_object() { Feature::StructuredOutputs } else { Feature::JsonMode }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Feature::ChatEndpoint => "Chat mode (/api/chat)",
+            Feature::Tools => "Tool calling",
+            Feature::JsonMode => "JSON mode",
+            Feature::StructuredOutputs => "Structured outputs",
+            Feature::Thinking => "The think option",
+        }
+    }
+
+    fn min_version(self) -> OllamaVersion {
+        match self {
+            Feature::ChatEndpoint => OllamaVersion::new(0, 1, 14),
+            Feature::Tools => OllamaVersion::new(0, 3, 0),
+            Feature::JsonMode => OllamaVersion::new(0, 1, 9),
+            Feature::StructuredOutputs => OllamaVersion::new(0, 5, 0),
+            Feature::Thinking => OllamaVersion::new(0, 9, 0),
+        }
+    }
+
+    // Err explains what is needed; an unknown version is given the benefit of the doubt
+    pub fn check(self, server: Option<OllamaVersion>) -> Result<(), String> {
+        match server {
+            Some(version) if version < self.min_version() => Err(format!(
+                "{} requires Ollama >= {} (server is {})",
+                self.name(),
+                self.min_version(),
+                version
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct VersionResponse {
+    version: String,
+}
+
+pub async fn fetch_version(client: Client) -> Result<OllamaVersion, AppError> {
+    fetch_version_from(&client, endpoint::base()).await
+}
+
+async fn fetch_version_from(client: &Client, base_url: &str) -> Result<OllamaVersion, AppError> {
+    let url = format!("{}/api/version", base_url);
+    let (trace, response) = trace::send(client.get(&url).timeout(Duration::from_secs(15))).await?;
+    if !response.status().is_success() {
+        return Err(trace.tag(AppError::ApiResponse(format!("Status {} from /api/version", response.status()))));
+    }
+    let body: VersionResponse = response.json().await.map_err(|e| trace.tag(e.into()))?;
+    OllamaVersion::parse(&body.version)
+        .ok_or_else(|| trace.tag(AppError::ApiResponse(format!("Unrecognised server version '{}'", body.version))))
+}
+
+// --- Connection Pre-flight ---
+// Sent on startup and whenever a fallback route moves to another server, so
+// the status bar can show how far away the server is
+#[derive(Debug)]
+pub struct Preflight {
+    pub server: String,
+    // Round trip of /api/version; None when the server couldn't be reached
+    pub latency: Option<Duration>,
+    pub version: Result<OllamaVersion, AppError>,
+}
+
+pub async fn preflight(client: Client, server: String) -> Preflight {
+    let started = Instant::now();
+    let version = fetch_version_from(&client, &server).await;
+    // Any answer, even an error status from an old server, is a round trip
+    let latency = match version.as_ref().map_err(AppError::untraced) {
+        Err(AppError::ApiRequest(_)) => None,
+        _ => Some(started.elapsed()),
+    };
+    Preflight { server, latency, version }
+}
@@ -0,0 +1,226 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, text::{Line, Span}};
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::layout;
+
+// --- Visual Selection ('v' in normal mode) ---
+// Character-wise selection over the conversation as drawn. Positions are
+// (row, column) in the wrapped rows, columns counted in characters.
+#[derive(Clone, Copy, PartialEq, PartialOrd, Eq, Ord)]
+pub struct Position {
+    pub row: usize,
+    pub column: usize,
+}
+
+// A row's text as drawn, and what it takes to map it back to the text
+pub struct Row {
+    pub text: String,
+    // Leading characters that are blockquote gutter bars or a bubble's
+    // padding, not text; padding after the text is left out of `text`
+    pub gutter: usize,
+    // Wrapped from the end of the previous row rather than a new line
+    pub continued: bool,
+}
+
+pub struct Selection {
+    anchor: Position,
+    pub cursor: Position,
+}
+
+pub enum VisualAction {
+    None,
+    Exit,
+    Yank,
+    // The whole turns the selection touches
+    Export,
+    // Summarized onto the turn the selection ends in
+    Summarize,
+    AddToScratchpad,
+}
+
+impl Selection {
+    // Starts on the first character of `row` after any gutter
+    pub fn new(rows: &[Row], row: usize) -> Self {
+        let start = Position { row, column: rows.get(row).map_or(0, |row| row.gutter) };
+        Selection { anchor: start, cursor: start }
+    }
+
+    // Starts a selection at a single position, e.g. where the mouse was pressed
+    pub fn at(position: Position) -> Self {
+        Selection { anchor: position, cursor: position }
+    }
+
+    // A click rather than a drag
+    pub fn is_empty(&self) -> bool {
+        self.anchor == self.cursor
+    }
+
+    // Start and end of the selection, both inclusive
+    pub fn range(&self) -> (Position, Position) {
+        (self.anchor.min(self.cursor), self.anchor.max(self.cursor))
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent, rows: &[Row]) -> VisualAction {
+        if rows.is_empty() {
+            return VisualAction::Exit;
+        }
+        let row_len = |row: usize| rows[row].text.chars().count();
+        let cursor = &mut self.cursor;
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('v') => return VisualAction::Exit,
+            KeyCode::Char('y') => return VisualAction::Yank,
+            KeyCode::Char('e') => return VisualAction::Export,
+            KeyCode::Char('s') => return VisualAction::Summarize,
+            KeyCode::Char('a') => return VisualAction::AddToScratchpad,
+            KeyCode::Char('j') | KeyCode::Down => cursor.row = (cursor.row + 1).min(rows.len() - 1),
+            KeyCode::Char('k') | KeyCode::Up => cursor.row = cursor.row.saturating_sub(1),
+            KeyCode::Char('l') | KeyCode::Right => cursor.column += 1,
+            KeyCode::Char('h') | KeyCode::Left => cursor.column = cursor.column.min(row_len(cursor.row)).saturating_sub(1),
+            KeyCode::Char('0') | KeyCode::Home => cursor.column = rows[cursor.row].gutter,
+            KeyCode::Char('$') | KeyCode::End => cursor.column = row_len(cursor.row).saturating_sub(1),
+            KeyCode::Char('w') => *cursor = next_word(rows, *cursor),
+            KeyCode::Char('b') => *cursor = previous_word(rows, *cursor),
+            _ => {}
+        }
+        cursor.row = cursor.row.min(rows.len() - 1);
+        // Never on a gutter bar, nor past the end of the row
+        let last = row_len(cursor.row).saturating_sub(1);
+        cursor.column = cursor.column.clamp(rows[cursor.row].gutter.min(last), last);
+        VisualAction::None
+    }
+
+    // The selected text without gutters, with wrapped rows joined back into
+    // the lines they came from
+    pub fn extract(&self, rows: &[Row]) -> String {
+        let (start, end) = self.range();
+        let mut text = String::new();
+        for (index, row) in rows.iter().enumerate().take(end.row + 1).skip(start.row) {
+            if index > start.row && !row.continued {
+                text.push('\n');
+            }
+            let from = if index == start.row { start.column } else { 0 }.max(row.gutter);
+            let to = if index == end.row { end.column + 1 } else { usize::MAX };
+            text.extend(row.text.chars().skip(from).take(to.saturating_sub(from)));
+        }
+        text
+    }
+
+    // Reverses the selected part of row `index`, leaving the `margins` of a
+    // bubble (padding before and after the text) alone
+    pub fn highlight<'a>(&self, index: usize, line: Line<'a>, margins: (usize, usize)) -> Line<'a> {
+        let (start, end) = self.range();
+        if index < start.row || index > end.row {
+            return line;
+        }
+        let from = if index == start.row { start.column } else { 0 }.max(layout::gutter_chars(&line) + margins.0);
+        let text_end = line.spans.iter().map(|span| span.content.chars().count()).sum::<usize>().saturating_sub(margins.1);
+        let to = if index == end.row { end.column + 1 } else { usize::MAX }.min(text_end);
+        let selected = Style::default().add_modifier(Modifier::REVERSED);
+
+        let mut spans = Vec::new();
+        let mut column = 0;
+        for span in line.spans {
+            let len = span.content.chars().count();
+            let (span_from, span_to) = (from.clamp(column, column + len) - column, to.clamp(column, column + len) - column);
+            if span_from == span_to {
+                spans.push(span);
+            } else {
+                let chars: Vec<char> = span.content.chars().collect();
+                for (range, style) in [
+                    (0..span_from, span.style),
+                    (span_from..span_to, span.style.patch(selected)),
+                    (span_to..len, span.style),
+                ] {
+                    if !range.is_empty() {
+                        spans.push(Span::styled(chars[range].iter().collect::<String>(), style));
+                    }
+                }
+            }
+            column += len;
+        }
+        // Show the cursor on empty rows too
+        if column == 0 {
+            spans.push(Span::styled(" ", selected));
+        }
+        let mut highlighted = Line::from(spans);
+        highlighted.style = line.style;
+        highlighted.alignment = line.alignment;
+        highlighted
+    }
+}
+
+// --- Mouse Selection ---
+// The character under terminal column `cell` of `row`, counting display
+// width so wide characters map back to the right character
+pub fn position_at(rows: &[Row], row: usize, cell: usize) -> Position {
+    let row = row.min(rows.len().saturating_sub(1));
+    let Some(text) = rows.get(row) else {
+        return Position { row, column: 0 };
+    };
+    let mut width = 0;
+    let mut column = 0;
+    for c in text.text.chars() {
+        width += c.width().unwrap_or(0);
+        if width > cell {
+            break;
+        }
+        column += 1;
+    }
+    let last = text.text.chars().count().saturating_sub(1);
+    Position { row, column: column.clamp(text.gutter.min(last), last) }
+}
+
+// Start of the next word, moving down rows as needed
+fn next_word(rows: &[Row], from: Position) -> Position {
+    let mut position = from;
+    // Skip the rest of the current word first
+    let mut in_word = char_at(rows, from).is_some_and(|c| !c.is_whitespace());
+    loop {
+        let chars: Vec<char> = rows[position.row].text.chars().collect();
+        while position.column < chars.len() {
+            let is_word = !chars[position.column].is_whitespace();
+            if is_word && !in_word {
+                return position;
+            }
+            in_word = is_word;
+            position.column += 1;
+        }
+        if position.row + 1 >= rows.len() {
+            return from;
+        }
+        position = Position { row: position.row + 1, column: rows[position.row + 1].gutter };
+        in_word = false;
+    }
+}
+
+// Start of the current or previous word, moving up rows as needed
+fn previous_word(rows: &[Row], from: Position) -> Position {
+    let mut row = from.row;
+    let mut chars: Vec<char> = rows[row].text.chars().collect();
+    let mut column = from.column.min(chars.len());
+    loop {
+        // Back over whitespace, then to the start of the word before it
+        let gutter = rows[row].gutter;
+        while column > gutter && chars[column - 1].is_whitespace() {
+            column -= 1;
+        }
+        if column > gutter {
+            while column > gutter && !chars[column - 1].is_whitespace() {
+                column -= 1;
+            }
+            return Position { row, column };
+        }
+        if row == 0 {
+            return Position { row: 0, column: gutter };
+        }
+        row -= 1;
+        chars = rows[row].text.chars().collect();
+        column = chars.len();
+    }
+}
+
+fn char_at(rows: &[Row], position: Position) -> Option<char> {
+    rows.get(position.row)?.text.chars().nth(position.column)
+}
@@ -0,0 +1,55 @@
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+
+use crate::{attachment, AppError, AppEvent};
+
+// Placeholder in a /watch template replaced by the file's current contents
+pub const FILE_PLACEHOLDER: &str = "{{file}}";
+
+// --- File Watch Mode (/watch) ---
+pub struct FileWatch {
+    pub path: PathBuf,
+    template: String,
+    // Dropping the watcher stops the notifications
+    _watcher: notify::RecommendedWatcher,
+}
+
+impl FileWatch {
+    // Watches the parent directory rather than the file itself, so editors
+    // that save by replacing the file are still picked up.
+    pub fn start(path: &Path, template: String, event_sender: mpsc::Sender<AppEvent>) -> Result<Self, AppError> {
+        let path = path.canonicalize()?;
+        if !path.is_file() {
+            return Err(AppError::Watch(format!("{} is not a file", path.display())));
+        }
+        let dir = path.parent().unwrap_or(Path::new("/")).to_path_buf();
+
+        let target = path.clone();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            let Ok(event) = res else { return };
+            let relevant = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+                && event.paths.iter().any(|p| p == &target);
+            if relevant {
+                // Runs on notify's own thread, so block rather than await
+                let _ = event_sender.blocking_send(AppEvent::WatchedFileChanged);
+            }
+        })
+        .map_err(|e| AppError::Watch(e.to_string()))?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(|e| AppError::Watch(e.to_string()))?;
+
+        Ok(FileWatch { path, template, _watcher: watcher })
+    }
+
+    // Fills the template with the file's current contents
+    pub fn render_prompt(&self) -> Result<String, AppError> {
+        let attachment = attachment::attach_file(&self.path)?;
+        if self.template.contains(FILE_PLACEHOLDER) {
+            Ok(self.template.replace(FILE_PLACEHOLDER, &attachment.block))
+        } else {
+            Ok(format!("{}\n\n{}", self.template, attachment.block))
+        }
+    }
+}